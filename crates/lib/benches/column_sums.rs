@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use votery::formats::{Binary, Cardinal, VoteFormat};
+
+const VOTERS: usize = 1_000_000;
+const CANDIDATES: usize = 8;
+
+// The loop `column_sums` replaced, indexing every element.
+fn naive_binary(data: &Binary) -> Vec<usize> {
+    let c = data.candidates;
+    let mut score = vec![0; c];
+    for i in 0..data.voters {
+        for (j, &v) in data.votes[(i * c)..((i + 1) * c)].iter().enumerate() {
+            if v {
+                score[j] += 1;
+            }
+        }
+    }
+    score
+}
+
+fn naive_cardinal(data: &Cardinal) -> Vec<usize> {
+    let mut sum = vec![0; data.candidates()];
+    for vote in data.iter() {
+        for (i, &v) in vote.iter().enumerate() {
+            sum[i] += v;
+        }
+    }
+    sum
+}
+
+fn binary(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut votes = Binary::new(CANDIDATES);
+    votes.generate_uniform(&mut rng, VOTERS);
+
+    let mut group = c.benchmark_group("binary");
+    group.bench_function("naive", |b| b.iter(|| naive_binary(black_box(&votes))));
+    group.bench_function("column_sums", |b| b.iter(|| black_box(&votes).column_sums()));
+    group.finish();
+}
+
+fn cardinal(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut votes = Cardinal::new(CANDIDATES, 0, 5);
+    votes.generate_uniform(&mut rng, VOTERS);
+
+    let mut group = c.benchmark_group("cardinal");
+    group.bench_function("naive", |b| b.iter(|| naive_cardinal(black_box(&votes))));
+    group.bench_function("column_sums", |b| b.iter(|| black_box(&votes).column_sums()));
+    group.finish();
+}
+
+// The same sums on the GPU, which only pays off for very large electorates.
+#[cfg(feature = "gpu")]
+fn gpu(c: &mut Criterion) {
+    let Ok(gpu) = votery::gpu::GpuTally::new() else {
+        return;
+    };
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut binary = Binary::new(CANDIDATES);
+    binary.generate_uniform(&mut rng, VOTERS);
+    let mut cardinal = Cardinal::new(CANDIDATES, 0, 5);
+    cardinal.generate_uniform(&mut rng, VOTERS);
+
+    let mut group = c.benchmark_group("gpu");
+    group.bench_function("binary", |b| b.iter(|| gpu.approval_sums(black_box(&binary))));
+    group.bench_function("cardinal", |b| b.iter(|| gpu.cardinal_sums(black_box(&cardinal))));
+    group.finish();
+}
+
+#[cfg(not(feature = "gpu"))]
+criterion_group!(benches, binary, cardinal);
+#[cfg(feature = "gpu")]
+criterion_group!(benches, binary, cardinal, gpu);
+criterion_main!(benches);
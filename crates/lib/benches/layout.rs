@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use votery::formats::soc::StrictOrdersComplete;
+
+const VOTERS: usize = 1_000_000;
+const CANDIDATES: usize = 8;
+
+fn layout(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut rows = StrictOrdersComplete::new(CANDIDATES);
+    rows.generate_uniform(&mut rng, VOTERS);
+    let columns = rows.to_column_major();
+    let borda: Vec<usize> = (0..CANDIDATES).rev().collect();
+
+    let mut group = c.benchmark_group("first_preferences");
+    group.bench_function("row_major", |b| b.iter(|| black_box(&rows).first_preferences()));
+    group.bench_function("column_major", |b| b.iter(|| black_box(&columns).first_preferences()));
+    group.finish();
+
+    let mut group = c.benchmark_group("borda");
+    group.bench_function("row_major", |b| b.iter(|| black_box(&rows).positional_score(&borda)));
+    group.bench_function("column_major", |b| {
+        b.iter(|| black_box(&columns).positional_score(&borda))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, layout);
+criterion_main!(benches);
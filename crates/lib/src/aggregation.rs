@@ -0,0 +1,157 @@
+//! Rank aggregation, combining many partial rankings into a single consensus
+//! ranking.
+//!
+//! Unlike the voting methods in [`methods`](crate::methods), these are meant
+//! for top-k lists, e.g. search results or survey answers, where every list
+//! only ranks its first few items and every item should get a position in the
+//! result. Every function returns a complete ranking, where items ranked
+//! equally are ordered by index.
+
+use crate::formats::{orders::Rank, soi::StrictOrdersIncomplete};
+
+/// Aggregate by Borda score. In a list of length `k`, the first item gets `k`
+/// points, the second `k - 1` and so on, while unranked items get no points.
+pub fn top_k_borda(data: &StrictOrdersIncomplete) -> Rank {
+    let mut score = vec![0; data.candidates];
+    for list in data {
+        for (i, &c) in list.iter().enumerate() {
+            score[c] += list.len() - i;
+        }
+    }
+    order_by_key(data.candidates, |c| std::cmp::Reverse(score[c]))
+}
+
+/// Aggregate by median position, the footrule heuristic. An item not in a list
+/// of length `k` is treated as if it was at position `k`, right after the
+/// list. The result approximates the ranking minimizing the total Spearman
+/// footrule distance to every list.
+pub fn footrule_median(data: &StrictOrdersIncomplete) -> Rank {
+    let n = data.candidates;
+    let mut positions: Vec<Vec<usize>> = vec![Vec::with_capacity(data.voters()); n];
+    for list in data {
+        let mut pos = vec![list.len(); n];
+        for (i, &c) in list.iter().enumerate() {
+            pos[c] = i;
+        }
+        for (c, p) in pos.into_iter().enumerate() {
+            positions[c].push(p);
+        }
+    }
+    // Twice the median, so even lengths don't need fractions
+    let median: Vec<usize> = positions
+        .iter_mut()
+        .map(|p| {
+            p.sort_unstable();
+            match p.len() {
+                0 => 0,
+                l if l % 2 == 0 => p[l / 2 - 1] + p[l / 2],
+                l => 2 * p[l / 2],
+            }
+        })
+        .collect();
+    order_by_key(n, |c| median[c])
+}
+
+/// Aggregate using the MC4 Markov chain of Dwork et al. From the current item
+/// `p`, pick an item `q` uniformly at random, and move to `q` if more lists
+/// rank `q` above `p` than `p` above `q`. Items are ranked by their probability
+/// in the stationary distribution of the chain.
+///
+/// A list ranks an item above every unranked item. With probability `damping`,
+/// the chain instead jumps to a uniformly random item, which makes the
+/// stationary distribution unique. Values like 0.05 are common.
+pub fn mc4(data: &StrictOrdersIncomplete, damping: f64) -> Result<Rank, &'static str> {
+    if !(0.0..=1.0).contains(&damping) {
+        return Err("Damping has to be between 0 and 1");
+    }
+    let n = data.candidates;
+    if n == 0 {
+        return Ok(Rank::new(0, Vec::new()));
+    }
+
+    // above[p * n + q] is the number of lists ranking `p` above `q`
+    let mut above = vec![0usize; n * n];
+    let mut ranked = vec![false; n];
+    for list in data {
+        ranked.fill(false);
+        for (i, &p) in list.iter().enumerate() {
+            for &q in &list[(i + 1)..] {
+                above[p * n + q] += 1;
+            }
+            ranked[p] = true;
+        }
+        for &p in list {
+            for q in (0..n).filter(|&q| !ranked[q]) {
+                above[p * n + q] += 1;
+            }
+        }
+    }
+
+    let jump = damping / n as f64;
+    let step = (1.0 - damping) / n as f64;
+    let mut dist = vec![1.0 / n as f64; n];
+    let mut new = vec![0.0; n];
+    for _ in 0..10_000 {
+        new.fill(jump);
+        for p in 0..n {
+            let mut stay = 1.0 - damping;
+            for q in (0..n).filter(|&q| above[q * n + p] > above[p * n + q]) {
+                new[q] += dist[p] * step;
+                stay -= step;
+            }
+            new[p] += dist[p] * stay;
+        }
+        let diff: f64 = dist.iter().zip(&new).map(|(a, b)| (a - b).abs()).sum();
+        std::mem::swap(&mut dist, &mut new);
+        if diff < 1e-12 {
+            break;
+        }
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| dist[b].total_cmp(&dist[a]));
+    Ok(Rank::new(n, order))
+}
+
+fn order_by_key<K: Ord, F: FnMut(usize) -> K>(candidates: usize, mut key: F) -> Rank {
+    let mut order: Vec<usize> = (0..candidates).collect();
+    order.sort_by_key(|&c| key(c));
+    Rank::new(candidates, order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> StrictOrdersIncomplete {
+        let mut lists = StrictOrdersIncomplete::new(5);
+        for s in ["0,1,2", "1,0,3", "1,2", "4,1,0"] {
+            assert!(lists.add_from_str(s));
+        }
+        lists
+    }
+
+    #[test]
+    fn borda() {
+        // Scores: 0: 3 + 2 + 1, 1: 2 + 3 + 2 + 2, 2: 1 + 1, 3: 1, 4: 3
+        let res = top_k_borda(&example());
+        assert_eq!(res.as_ref().order(), [1, 0, 4, 2, 3]);
+    }
+
+    #[test]
+    fn footrule() {
+        // Positions: 0: [0, 1, 2, 2], 1: [1, 0, 0, 1], 2: [2, 3, 1, 3],
+        // 3: [3, 2, 2, 3], 4: [3, 3, 2, 0]
+        let res = footrule_median(&example());
+        assert_eq!(res.as_ref().order(), [1, 0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn mc4_condorcet() {
+        // 1 beats every other item in a majority of the lists which compare
+        // them, so the chain ends up there.
+        let res = mc4(&example(), 0.05).unwrap();
+        assert_eq!(res.as_ref().order()[0], 1);
+        assert_eq!(res.len(), 5);
+        assert!(mc4(&example(), 2.0).is_err());
+    }
+}
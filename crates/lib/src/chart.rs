@@ -0,0 +1,132 @@
+//! Text rendering of results, for printing them in a terminal.
+//!
+//! ```
+//! use votery::chart::bar_chart;
+//!
+//! let chart = bar_chart(&["Alice", "Bob"], &[4.0, 1.5], 8);
+//! assert_eq!(chart, "Alice │████████ 4\nBob   │███      1.5\n");
+//! ```
+use std::fmt::Write;
+
+use crate::methods::{Irv, VotingMethod};
+
+// A block for every eighth of a character.
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A horizontal bar chart with one line per candidate, showing `values` with
+/// the labels `labels`. The largest value is drawn `width` characters wide and
+/// the others are scaled to match, so the values after the bars line up.
+/// Negative values and NaN are drawn as empty bars.
+///
+/// # Panics
+///
+/// Panics if `labels` and `values` have different lengths.
+pub fn bar_chart<L: AsRef<str>>(labels: &[L], values: &[f64], width: usize) -> String {
+    let notes = vec![String::new(); values.len()];
+    chart(labels, values, &notes, width)
+}
+
+/// A bar chart for every round of `irv`, with the first preferences of each
+/// remaining candidate. From the second round on, the votes transferred to a
+/// candidate are shown after its total, and every round ends with the
+/// candidates eliminated in it.
+///
+/// ```
+/// use votery::{chart::irv_rounds, formats::toi::TiedOrdersIncomplete, methods::Irv, prelude::*};
+///
+/// let mut votes = TiedOrdersIncomplete::new(3);
+/// votes.add_from_str_i("0,2,1", 4);
+/// votes.add_from_str_i("1,0,2", 3);
+/// votes.add_from_str_i("2,1,0", 2);
+/// let irv = Irv::count(&votes).unwrap();
+/// let expected = "\
+/// Round 1
+/// A │████ 4
+/// B │███  3
+/// C │██   2
+/// Eliminated: C
+/// Round 2
+/// A │███▎ 4
+/// B │████ 5 (+2)
+/// Eliminated: A
+/// Round 3
+/// B │████ 9 (+4)
+/// Eliminated: B
+/// ";
+/// assert_eq!(irv_rounds(&irv, &["A", "B", "C"], 4), expected);
+/// ```
+///
+/// # Panics
+///
+/// Panics if there isn't a label for every candidate.
+pub fn irv_rounds<L: AsRef<str>>(irv: &Irv, labels: &[L], width: usize) -> String {
+    // The round each candidate was eliminated in, starting from 1.
+    let eliminated = irv.get_score();
+    assert_eq!(labels.len(), eliminated.len(), "every candidate needs a label");
+    let mut res = String::new();
+    for (i, firsts) in irv.rounds().iter().enumerate() {
+        let round = i + 1;
+        let remaining: Vec<usize> = (0..labels.len()).filter(|&c| eliminated[c] >= round).collect();
+        let values: Vec<f64> = remaining.iter().map(|&c| firsts[c] as f64).collect();
+        let notes: Vec<String> = remaining
+            .iter()
+            .map(|&c| {
+                match i.checked_sub(1).map(|prev| firsts[c].saturating_sub(irv.rounds()[prev][c])) {
+                    Some(gained) if gained > 0 => format!(" (+{})", gained),
+                    _ => String::new(),
+                }
+            })
+            .collect();
+        let names: Vec<&str> = remaining.iter().map(|&c| labels[c].as_ref()).collect();
+        let out: Vec<&str> = remaining
+            .iter()
+            .filter(|&&c| eliminated[c] == round)
+            .map(|&c| labels[c].as_ref())
+            .collect();
+        writeln!(res, "Round {}", round).unwrap();
+        res.push_str(&chart(&names, &values, &notes, width));
+        writeln!(res, "Eliminated: {}", out.join(", ")).unwrap();
+    }
+    res
+}
+
+// A bar chart where `notes[i]` is written after the value on line `i`.
+fn chart<L: AsRef<str>>(labels: &[L], values: &[f64], notes: &[String], width: usize) -> String {
+    assert_eq!(labels.len(), values.len(), "every value needs a label");
+    let label_width = labels.iter().map(|l| l.as_ref().chars().count()).max().unwrap_or(0);
+    let max = values.iter().copied().filter(|v| *v > 0.0).fold(0.0, f64::max);
+    let mut res = String::new();
+    for ((label, &value), note) in labels.iter().zip(values).zip(notes) {
+        // The length of the bar, in eighths of a character.
+        let eighths =
+            if value > 0.0 { (value / max * (width * 8) as f64).round() as usize } else { 0 };
+        let mut bar = "█".repeat(eighths / 8);
+        if eighths % 8 != 0 {
+            bar.push(EIGHTHS[eighths % 8]);
+        }
+        let padding = width - bar.chars().count();
+        writeln!(
+            res,
+            "{:label_width$} │{}{} {}{}",
+            label.as_ref(),
+            bar,
+            " ".repeat(padding),
+            value,
+            note
+        )
+        .unwrap();
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_blocks() {
+        let chart = bar_chart(&["a", "bb", "c", "d"], &[8.0, 1.0, 0.0, -1.0], 2);
+        assert_eq!(chart, "a  │██ 8\nbb │▎  1\nc  │   0\nd  │   -1\n");
+        assert_eq!(bar_chart::<&str>(&[], &[], 10), "");
+    }
+}
@@ -0,0 +1,112 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{formats::toc::TiedOrdersComplete, methods::MethodConfig};
+
+/// A case where removing a candidate which didn't win changed the winners.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IiaViolation {
+    /// The removed candidate
+    pub removed: usize,
+    /// The winners before `removed` was removed
+    pub before: Vec<usize>,
+    /// The winners after `removed` was removed, using the original indices of
+    /// the candidates.
+    pub after: Vec<usize>,
+}
+
+/// Find every violation of independence of irrelevant alternatives (IIA) in
+/// `votes` under `method`, by removing every candidate which is not a winner,
+/// one at a time, and checking if the winners changed.
+///
+/// Every removal is counted with its own random number generator, seeded from
+/// `rng`, so the result does not depend on the order they are evaluated in.
+/// Note that methods which use randomness, e.g. to convert between formats,
+/// may report violations caused only by that randomness.
+pub fn iia_violations<R: Rng>(
+    votes: &TiedOrdersComplete,
+    method: &MethodConfig,
+    rng: &mut R,
+) -> Result<Vec<IiaViolation>, &'static str> {
+    Ok(iia_check(votes, method, rng)?.1)
+}
+
+// Like `iia_violations`, but also returns the winners of `votes`, sorted.
+pub(super) fn iia_check<R: Rng>(
+    votes: &TiedOrdersComplete,
+    method: &MethodConfig,
+    rng: &mut R,
+) -> Result<(Vec<usize>, Vec<IiaViolation>), &'static str> {
+    let seed: u64 = rng.gen();
+    let mut before =
+        method.rank(votes, &mut ChaCha8Rng::seed_from_u64(seed))?.as_ref().winners().to_vec();
+    before.sort();
+
+    let check = |removed: usize| -> Result<Option<IiaViolation>, &'static str> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(removed as u64 + 1);
+        let mut reduced = votes.clone();
+        reduced.remove_candidate(removed)?;
+        let ranking = method.rank(&reduced, &mut rng)?;
+        let mut after: Vec<usize> = ranking
+            .as_ref()
+            .winners()
+            .iter()
+            .map(|&c| if c >= removed { c + 1 } else { c })
+            .collect();
+        after.sort();
+        if after == before {
+            Ok(None)
+        } else {
+            Ok(Some(IiaViolation { removed, before: before.clone(), after }))
+        }
+    };
+
+    let losers: Vec<usize> =
+        (0..votes.candidates).filter(|c| before.binary_search(c).is_err()).collect();
+    #[cfg(feature = "rayon")]
+    let results: Vec<_> = losers.into_par_iter().map(check).collect();
+    #[cfg(not(feature = "rayon"))]
+    let results: Vec<_> = losers.into_iter().map(check).collect();
+
+    let mut violations = Vec::new();
+    for result in results {
+        if let Some(v) = result? {
+            violations.push(v);
+        }
+    }
+    Ok((before, violations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::TiePolicy;
+
+    #[test]
+    fn borda_spoiler() {
+        // Borda elects 1, but if 2 is removed then 0 wins.
+        let mut votes = TiedOrdersComplete::new(3);
+        for _ in 0..3 {
+            votes.add_from_str("0,1,2");
+        }
+        for _ in 0..2 {
+            votes.add_from_str("1,2,0");
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let violations = iia_violations(&votes, &MethodConfig::Borda, &mut rng).unwrap();
+        assert_eq!(violations, [IiaViolation { removed: 2, before: vec![1], after: vec![0] }]);
+    }
+
+    #[test]
+    fn unanimous() {
+        let mut votes = TiedOrdersComplete::new(4);
+        votes.add_from_str("2,0,3,1");
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for method in [MethodConfig::Borda, MethodConfig::Fptp(TiePolicy::Random)] {
+            assert!(iia_violations(&votes, &method, &mut rng).unwrap().is_empty());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    formats::{
+        orders::{TiedRank, TiedRankRef},
+        toi::TiedOrdersIncomplete,
+        VoteFormat,
+    },
+    methods::MethodConfig,
+};
+
+/// How adding a later preference affected the candidates already on a ballot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaterEffect {
+    /// A candidate on the ballot was a winner, but stopped winning. Violates
+    /// later-no-harm.
+    Harm,
+    /// A candidate on the ballot wasn't a winner, but started winning.
+    /// Violates later-no-help.
+    Help,
+}
+
+/// A truncated ballot where ranking one more candidate last changed the result
+/// for the candidates already ranked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LaterViolation {
+    pub effect: LaterEffect,
+    /// The index of the extended vote
+    pub voter: usize,
+    /// The vote after it was extended
+    pub extended: TiedRank,
+    /// The candidate which was added to the end of the vote
+    pub added: usize,
+    /// The candidates on the original vote which were harmed or helped
+    pub affected: Vec<usize>,
+    /// The winners before the vote was extended
+    pub before: Vec<usize>,
+    /// The winners after the vote was extended
+    pub after: Vec<usize>,
+}
+
+/// Search for violations of later-no-harm and later-no-help in `votes` under
+/// `method`.
+///
+/// Every vote which doesn't rank every candidate is extended with each of its
+/// unranked candidates, ranked below everyone else, one at a time. If a
+/// candidate which was already ranked stops winning it was harmed, and if it
+/// starts winning it was helped. Identical votes are only extended once, using
+/// the first of them.
+///
+/// Like [`iia_violations`](super::iia_violations), every count uses the same
+/// random numbers, seeded from `rng`.
+pub fn later_violations<R: Rng>(
+    votes: &TiedOrdersIncomplete,
+    method: &MethodConfig,
+    rng: &mut R,
+) -> Result<Vec<LaterViolation>, &'static str> {
+    let seed: u64 = rng.gen();
+    let winners = |v: &TiedOrdersIncomplete| -> Result<Vec<usize>, &'static str> {
+        let ranking = method.rank_incomplete(v, &mut ChaCha8Rng::seed_from_u64(seed))?;
+        let mut w = ranking.as_ref().winners().to_vec();
+        w.sort();
+        Ok(w)
+    };
+    let before = winners(votes)?;
+
+    // Every distinct truncated vote, with the index of its first voter
+    let mut truncated: Vec<(usize, TiedRankRef)> = Vec::new();
+    for (i, vote) in votes.into_iter().enumerate() {
+        if vote.len() < votes.candidates() && !truncated.iter().any(|(_, v)| *v == vote) {
+            truncated.push((i, vote));
+        }
+    }
+
+    let check = |(voter, vote): (usize, TiedRankRef)| -> Result<_, &'static str> {
+        let mut found = Vec::new();
+        for added in (0..votes.candidates()).filter(|c| !vote.order().contains(c)) {
+            let mut order = vote.order().to_vec();
+            let mut tied = vote.tied().to_vec();
+            order.push(added);
+            tied.push(false);
+            let extended = TiedRank::new(votes.candidates(), order, tied);
+            let after = winners(&replace_vote(votes, voter, extended.as_ref()))?;
+            for (effect, from, to) in
+                [(LaterEffect::Harm, &before, &after), (LaterEffect::Help, &after, &before)]
+            {
+                let affected: Vec<usize> = vote
+                    .order()
+                    .iter()
+                    .copied()
+                    .filter(|c| from.contains(c) && !to.contains(c))
+                    .collect();
+                if !affected.is_empty() {
+                    found.push(LaterViolation {
+                        effect,
+                        voter,
+                        extended: extended.clone(),
+                        added,
+                        affected,
+                        before: before.clone(),
+                        after: after.clone(),
+                    });
+                }
+            }
+        }
+        Ok(found)
+    };
+
+    #[cfg(feature = "rayon")]
+    let results: Vec<_> = truncated.into_par_iter().map(check).collect();
+    #[cfg(not(feature = "rayon"))]
+    let results: Vec<_> = truncated.into_iter().map(check).collect();
+
+    let mut violations = Vec::new();
+    for result in results {
+        violations.extend(result?);
+    }
+    Ok(violations)
+}
+
+// A copy of `votes` where vote `i` is replaced by `vote`.
+fn replace_vote(votes: &TiedOrdersIncomplete, i: usize, vote: TiedRankRef) -> TiedOrdersIncomplete {
+    let mut res = TiedOrdersIncomplete::new(votes.candidates());
+    for (j, v) in votes.into_iter().enumerate() {
+        res.add(if j == i { vote } else { v }).unwrap();
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enumerate::tied_orders_incomplete, formats::TiePolicy};
+
+    #[test]
+    fn borda_harm() {
+        // If the first voter also ranks 1, then 1 beats 0.
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("0");
+        votes.add_from_str("1,2,0");
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let violations = later_violations(&votes, &MethodConfig::Borda, &mut rng).unwrap();
+        assert_eq!(violations.len(), 1);
+        let v = &violations[0];
+        assert_eq!(v.effect, LaterEffect::Harm);
+        assert_eq!((v.voter, v.added), (0, 1));
+        assert_eq!(v.extended.as_ref().to_string(), "0,1");
+        assert_eq!((&v.affected[..], &v.before[..], &v.after[..]), (&[0][..], &[0][..], &[1][..]));
+    }
+
+    #[test]
+    fn irv_later_no_harm() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        for (s, n) in [("0", 4), ("1,2", 3), ("2,1,3", 2), ("3", 2)] {
+            votes.add_from_str_i(s, n);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let violations = later_violations(&votes, &MethodConfig::Irv, &mut rng).unwrap();
+        assert!(violations.is_empty(), "{:?}", violations);
+    }
+
+    #[test]
+    fn fptp_exhaustive() {
+        // Only the first preferences count, so every profile of two votes
+        // passes. Empty votes aren't allowed.
+        let orders: Vec<TiedRank> = tied_orders_incomplete(3).filter(|o| o.len() != 0).collect();
+        let fptp = MethodConfig::Fptp(TiePolicy::FirstListed);
+        for a in &orders {
+            for b in &orders {
+                let mut votes = TiedOrdersIncomplete::new(3);
+                votes.add(a.as_ref()).unwrap();
+                votes.add(b.as_ref()).unwrap();
+                let mut rng = ChaCha8Rng::seed_from_u64(0);
+                let violations = later_violations(&votes, &fptp, &mut rng).unwrap();
+                assert!(violations.is_empty(), "{:?}", violations);
+            }
+        }
+    }
+}
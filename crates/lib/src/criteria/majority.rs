@@ -0,0 +1,124 @@
+use rand::Rng;
+
+use crate::{formats::toc::TiedOrdersComplete, methods::MethodConfig};
+
+/// Every set of candidates which more than half of the voters rank above all
+/// other candidates, ordered from the smallest set to the largest.
+///
+/// Only sets which don't contain every candidate are returned. A voter who
+/// ties a candidate in the set with one outside of it doesn't count as ranking
+/// the set above the others, so unlike
+/// [`TiedOrdersIncomplete::majority`](crate::formats::toi::TiedOrdersIncomplete::majority)
+/// a single candidate only forms a set if a majority ranks it alone at the top.
+///
+/// Every such set is supported by more than half of the voters, so some voter
+/// supports any two of them. The sets are therefore nested: each one contains
+/// all the smaller ones.
+pub fn mutual_majority_sets(votes: &TiedOrdersComplete) -> Vec<Vec<usize>> {
+    // The sets a voter ranks above all others are the unions of their first
+    // groups, so we count how many voters have each such prefix.
+    let mut counts: Vec<(Vec<usize>, usize)> = Vec::new();
+    for vote in votes {
+        let mut prefix = Vec::new();
+        for group in vote.iter_groups() {
+            prefix.extend_from_slice(group);
+            if prefix.len() == votes.candidates {
+                break;
+            }
+            let mut set = prefix.clone();
+            set.sort_unstable();
+            match counts.iter_mut().find(|(s, _)| *s == set) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((set, 1)),
+            }
+        }
+    }
+    let mut sets: Vec<Vec<usize>> = counts
+        .into_iter()
+        .filter(|(_, count)| *count > votes.voters() / 2)
+        .map(|(set, _)| set)
+        .collect();
+    sets.sort_by_key(Vec::len);
+    sets
+}
+
+/// A profile where a method elected a candidate outside of a mutual majority
+/// set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutualMajorityViolation {
+    /// The smallest mutual majority set
+    pub set: Vec<usize>,
+    /// The winners, where at least one isn't in `set`
+    pub winners: Vec<usize>,
+}
+
+/// Check if `method` satisfies the mutual majority criterion for `votes`: if
+/// a majority ranks some set of candidates above all others, then every winner
+/// must be in that set. It's enough to check the smallest set from
+/// [`mutual_majority_sets`], as it's contained in every other set.
+pub fn mutual_majority_check<R: Rng>(
+    votes: &TiedOrdersComplete,
+    method: &MethodConfig,
+    rng: &mut R,
+) -> Result<Option<MutualMajorityViolation>, &'static str> {
+    let Some(set) = mutual_majority_sets(votes).into_iter().next() else {
+        return Ok(None);
+    };
+    let mut winners = method.rank(votes, rng)?.as_ref().winners().to_vec();
+    winners.sort();
+    if winners.iter().all(|w| set.binary_search(w).is_ok()) {
+        Ok(None)
+    } else {
+        Ok(Some(MutualMajorityViolation { set, winners }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::formats::TiePolicy;
+
+    fn split_majority() -> TiedOrdersComplete {
+        // A majority prefers 0 and 1 over 2, but splits its first preferences.
+        let mut votes = TiedOrdersComplete::new(3);
+        for (s, n) in [("0,1,2", 3), ("1,0,2", 2), ("2,0,1", 4)] {
+            for _ in 0..n {
+                votes.add_from_str(s);
+            }
+        }
+        votes
+    }
+
+    #[test]
+    fn sets() {
+        assert_eq!(mutual_majority_sets(&split_majority()), [vec![0, 1]]);
+
+        let mut votes = TiedOrdersComplete::new(4);
+        votes.add_from_str("2,1,0,3");
+        votes.add_from_str("2,{0,1},3");
+        votes.add_from_str("3,2,1,0");
+        assert_eq!(mutual_majority_sets(&votes), [vec![2], vec![0, 1, 2]]);
+
+        // Ties with a candidate outside the set don't count.
+        let mut votes = TiedOrdersComplete::new(2);
+        votes.add_from_str("{0,1}");
+        votes.add_from_str("0,1");
+        votes.add_from_str("1,0");
+        assert!(mutual_majority_sets(&votes).is_empty());
+    }
+
+    #[test]
+    fn per_method() {
+        let votes = split_majority();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let fptp = MethodConfig::Fptp(TiePolicy::Random);
+        let violation = mutual_majority_check(&votes, &fptp, &mut rng).unwrap();
+        assert_eq!(violation, Some(MutualMajorityViolation { set: vec![0, 1], winners: vec![2] }));
+        for method in [MethodConfig::Irv, MethodConfig::Borda] {
+            assert_eq!(mutual_majority_check(&votes, &method, &mut rng).unwrap(), None);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Tools to find profiles where voting methods fail different criteria.
+//!
+//! With the `rayon` feature, checks which count many profiles are done in
+//! parallel.
+
+mod iia;
+pub use iia::{iia_violations, IiaViolation};
+mod later;
+pub use later::{later_violations, LaterEffect, LaterViolation};
+mod majority;
+pub use majority::{mutual_majority_check, mutual_majority_sets, MutualMajorityViolation};
+mod monotonicity;
+pub use monotonicity::{monotonicity_violations, Direction, MonotonicityViolation};
+mod participation;
+pub use participation::{participation_check, NoShowViolation};
+mod recommend;
+pub use recommend::{method_registry, recommend_methods, CriteriaSet, MethodInfo};
+mod spoiler;
+pub use spoiler::{spoilers, Spoiler, SpoilerReport};
@@ -0,0 +1,173 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    election::Voter,
+    formats::{orders::TiedRank, toc::TiedOrdersComplete},
+    methods::MethodConfig,
+};
+
+/// The direction a candidate was moved in a [`MonotonicityViolation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// A winner was raised to the top of some votes, and then lost.
+    Upward,
+    /// A candidate which didn't win was lowered to the bottom of some votes,
+    /// and then won.
+    Downward,
+}
+
+/// A case where moving a candidate in some votes changed the result in the
+/// opposite direction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonotonicityViolation {
+    pub direction: Direction,
+    /// The candidate which was moved
+    pub candidate: usize,
+    /// The indices of the votes where `candidate` was moved
+    pub votes: Vec<usize>,
+    /// The winners after `candidate` was moved
+    pub after: Vec<usize>,
+}
+
+/// Search for violations of monotonicity in `votes` under `method`.
+///
+/// Every winner is raised to the top of every subset of at most `max_size`
+/// votes, to see if it stops being a winner. Every other candidate is lowered
+/// to the bottom of every such subset, to see if it becomes a winner. Smaller
+/// subsets are tried first, so every returned witness is minimal, and at most
+/// one witness is returned for every candidate.
+///
+/// The number of subsets grows quickly with `max_size`, so it should be kept
+/// small. This is mostly interesting for methods like [`Irv`] and [`Coombs`].
+///
+/// [`Irv`]: crate::methods::Irv
+/// [`Coombs`]: crate::methods::Coombs
+pub fn monotonicity_violations<R: Rng>(
+    votes: &TiedOrdersComplete,
+    method: &MethodConfig,
+    max_size: usize,
+    rng: &mut R,
+) -> Result<Vec<MonotonicityViolation>, &'static str> {
+    // Every count uses the same random numbers, so that randomness in the method
+    // doesn't look like a violation.
+    let seed: u64 = rng.gen();
+    let winners = |v: &TiedOrdersComplete| -> Result<Vec<usize>, &'static str> {
+        let ranking = method.rank(v, &mut ChaCha8Rng::seed_from_u64(seed))?;
+        let mut w = ranking.as_ref().winners().to_vec();
+        w.sort();
+        Ok(w)
+    };
+    let before = winners(votes)?;
+
+    let mut violations = Vec::new();
+    let mut rank = TiedRank::new_zero();
+    for candidate in 0..votes.candidates {
+        let direction = if before.binary_search(&candidate).is_ok() {
+            Direction::Upward
+        } else {
+            Direction::Downward
+        };
+        let top = direction == Direction::Upward;
+
+        // Only votes where `candidate` can actually be moved.
+        let movable: Vec<usize> = votes
+            .into_iter()
+            .enumerate()
+            .filter(|(_, v)| {
+                let groups = v.iter_groups().count();
+                let group = v.group_of(candidate).unwrap();
+                let alone = v.group(group).unwrap().len() == 1;
+                !alone || (top && group != 0) || (!top && group != groups - 1)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        'sizes: for size in 1..=max_size.min(movable.len()) {
+            let mut subset: Vec<usize> = (0..size).collect();
+            loop {
+                let mut moved = votes.clone();
+                for &i in &subset {
+                    rank.copy_from(votes.into_iter().nth(movable[i]).unwrap());
+                    rank.remove(candidate);
+                    if top {
+                        rank.order.insert(0, candidate);
+                        rank.tied.insert(0, false);
+                    } else {
+                        rank.order.push(candidate);
+                        rank.tied.push(false);
+                    }
+                    moved.set_vote(Voter(movable[i]), rank.as_ref());
+                }
+                let after = winners(&moved)?;
+                let won = after.binary_search(&candidate).is_ok();
+                if won != top {
+                    let votes = subset.iter().map(|&i| movable[i]).collect();
+                    violations.push(MonotonicityViolation { direction, candidate, votes, after });
+                    break 'sizes;
+                }
+                if !next_subset(&mut subset, movable.len()) {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(violations)
+}
+
+// Step `subset` to the next subset of `0..n` of the same size, in lexicographic
+// order. Returns false if there is no next subset.
+fn next_subset(subset: &mut [usize], n: usize) -> bool {
+    let k = subset.len();
+    for i in (0..k).rev() {
+        if subset[i] < n - k + i {
+            subset[i] += 1;
+            for j in (i + 1)..k {
+                subset[j] = subset[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_subset_count() {
+        let mut subset = vec![0, 1, 2];
+        let mut count = 1;
+        while next_subset(&mut subset, 6) {
+            count += 1;
+        }
+        assert_eq!(count, 20);
+    }
+
+    #[test]
+    fn irv_upward() {
+        let mut votes = TiedOrdersComplete::new(3);
+        for (s, n) in [("0,2,1", 8), ("1,0,2", 7), ("2,1,0", 6)] {
+            for _ in 0..n {
+                votes.add_from_str(s);
+            }
+        }
+        // 1 wins, but if it is raised to the top in three votes of "0,2,1", then
+        // 0 is eliminated instead of 2, and 2 wins.
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let violations = monotonicity_violations(&votes, &MethodConfig::Irv, 3, &mut rng).unwrap();
+        let upward = MonotonicityViolation {
+            direction: Direction::Upward,
+            candidate: 1,
+            votes: vec![0, 1, 2],
+            after: vec![2],
+        };
+        assert!(violations.contains(&upward));
+
+        // Borda is monotonic
+        let violations =
+            monotonicity_violations(&votes, &MethodConfig::Borda, 2, &mut rng).unwrap();
+        assert!(violations.is_empty());
+    }
+}
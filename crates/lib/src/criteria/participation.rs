@@ -0,0 +1,137 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    election::Voter,
+    formats::{
+        orders::{TiedRank, TiedRankRef},
+        toc::TiedOrdersComplete,
+    },
+    methods::MethodConfig,
+};
+
+/// A group of identical votes which would get a better result by not voting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoShowViolation {
+    /// The vote shared by every abstaining voter
+    pub vote: TiedRank,
+    /// The indices of the votes which were removed
+    pub voters: Vec<usize>,
+    /// The winners when everyone votes
+    pub before: Vec<usize>,
+    /// The winners after `voters` abstained
+    pub after: Vec<usize>,
+}
+
+/// Search for violations of the participation criterion (the no-show paradox)
+/// in `votes` under `method`.
+///
+/// For every distinct vote, this removes one to `max_size` voters with that
+/// vote, and checks if the remaining voters elect winners which the removed
+/// voters strictly prefer, i.e. every new winner is ranked above every
+/// previous winner. At most one witness is returned for every distinct vote,
+/// using the smallest number of abstaining voters.
+///
+/// Like [`iia_violations`](super::iia_violations), every count uses the same
+/// random numbers, seeded from `rng`.
+pub fn participation_check<R: Rng>(
+    votes: &TiedOrdersComplete,
+    method: &MethodConfig,
+    max_size: usize,
+    rng: &mut R,
+) -> Result<Vec<NoShowViolation>, &'static str> {
+    let seed: u64 = rng.gen();
+    let winners = |v: &TiedOrdersComplete| -> Result<Vec<usize>, &'static str> {
+        let ranking = method.rank(v, &mut ChaCha8Rng::seed_from_u64(seed))?;
+        let mut w = ranking.as_ref().winners().to_vec();
+        w.sort();
+        Ok(w)
+    };
+    let before = winners(votes)?;
+
+    // Group the voters by their vote
+    let mut groups: Vec<(TiedRankRef, Vec<usize>)> = Vec::new();
+    for (i, vote) in votes.into_iter().enumerate() {
+        match groups.iter_mut().find(|(v, _)| *v == vote) {
+            Some((_, voters)) => voters.push(i),
+            None => groups.push((vote, vec![i])),
+        }
+    }
+
+    let check = |(vote, voters): (TiedRankRef, Vec<usize>)| -> Result<_, &'static str> {
+        let mut reduced = votes.clone();
+        for size in 1..=max_size.min(voters.len()) {
+            // `voters` is increasing, so removing the latest voter first keeps
+            // the indices of the others.
+            reduced.remove_voter(Voter(voters[size - 1] + 1 - size));
+            if reduced.voters() == 0 {
+                break;
+            }
+            let after = winners(&reduced)?;
+            if prefers(vote, &after, &before) {
+                return Ok(Some(NoShowViolation {
+                    vote: vote.owned(),
+                    voters: voters[..size].to_vec(),
+                    before: before.clone(),
+                    after,
+                }));
+            }
+        }
+        Ok(None)
+    };
+
+    #[cfg(feature = "rayon")]
+    let results: Vec<_> = groups.into_par_iter().map(check).collect();
+    #[cfg(not(feature = "rayon"))]
+    let results: Vec<_> = groups.into_iter().map(check).collect();
+
+    let mut violations = Vec::new();
+    for result in results {
+        if let Some(v) = result? {
+            violations.push(v);
+        }
+    }
+    Ok(violations)
+}
+
+// Returns true if `vote` ranks every candidate in `a` above every candidate in
+// `b`.
+fn prefers(vote: TiedRankRef, a: &[usize], b: &[usize]) -> bool {
+    let worst_a = a.iter().map(|&c| vote.group_of(c).unwrap()).max();
+    let best_b = b.iter().map(|&c| vote.group_of(c).unwrap()).min();
+    match (worst_a, best_b) {
+        (Some(a), Some(b)) => a < b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn irv_no_show() {
+        let mut votes = TiedOrdersComplete::new(3);
+        for (s, n) in [("0,1,2", 7), ("1,2,0", 6), ("2,0,1", 5)] {
+            for _ in 0..n {
+                votes.add_from_str(s);
+            }
+        }
+        // 2 is eliminated first and 0 wins. If two voters of "1,2,0" abstain,
+        // then 1 is eliminated instead and 2 wins.
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let violations = participation_check(&votes, &MethodConfig::Irv, 3, &mut rng).unwrap();
+        assert_eq!(violations.len(), 1);
+        let v = &violations[0];
+        assert_eq!(v.vote.as_ref().to_string(), "1,2,0");
+        assert_eq!(v.voters, [7, 8]);
+        assert_eq!(v.before, [0]);
+        assert_eq!(v.after, [2]);
+
+        // Borda satisfies participation
+        let violations = participation_check(&votes, &MethodConfig::Borda, 3, &mut rng).unwrap();
+        assert!(violations.is_empty());
+    }
+}
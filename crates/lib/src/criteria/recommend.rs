@@ -0,0 +1,147 @@
+use crate::{
+    formats::TiePolicy,
+    methods::{BucklinConfig, CopelandConfig, MethodConfig, StarConfig},
+};
+
+/// A set of criteria a voting method can satisfy, where `true` means that the
+/// criterion is in the set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CriteriaSet {
+    /// The Condorcet winner always wins, if there is one.
+    pub condorcet: bool,
+    /// Raising a winner on some votes never makes it lose, see
+    /// [`monotonicity_violations`](super::monotonicity_violations).
+    pub monotonicity: bool,
+    /// Adding a candidate similar to an existing one never changes which of
+    /// the other candidates wins.
+    pub clone_independence: bool,
+    /// Ranking another candidate last never hurts the candidates already
+    /// ranked, see [`later_violations`](super::later_violations).
+    pub later_no_harm: bool,
+}
+
+impl CriteriaSet {
+    pub const NONE: CriteriaSet = CriteriaSet {
+        condorcet: false,
+        monotonicity: false,
+        clone_independence: false,
+        later_no_harm: false,
+    };
+
+    /// Returns true if every criterion in `other` is also in `self`.
+    pub fn contains(&self, other: &CriteriaSet) -> bool {
+        (self.condorcet || !other.condorcet)
+            && (self.monotonicity || !other.monotonicity)
+            && (self.clone_independence || !other.clone_independence)
+            && (self.later_no_harm || !other.later_no_harm)
+    }
+}
+
+/// A voting method, and the criteria it's proven to satisfy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodInfo {
+    pub method: MethodConfig,
+    /// A criterion which isn't in the set may still hold for some profiles,
+    /// but there are profiles where the method fails it.
+    pub criteria: CriteriaSet,
+}
+
+/// Every [`MethodConfig`] with its default configuration, and the criteria it
+/// satisfies when counting complete rankings. [`MethodConfig::Positional`] is
+/// left out, as its criteria depend on its weights.
+pub fn method_registry() -> Vec<MethodInfo> {
+    let info = |method, [condorcet, monotonicity, clone_independence, later_no_harm]: [bool; 4]| {
+        let criteria = CriteriaSet { condorcet, monotonicity, clone_independence, later_no_harm };
+        MethodInfo { method, criteria }
+    };
+    vec![
+        info(MethodConfig::Borda, [false, true, false, false]),
+        info(MethodConfig::Bucklin(BucklinConfig::default()), [false, true, false, false]),
+        info(MethodConfig::Condorcet, [true, true, false, false]),
+        info(MethodConfig::Coombs, [false, false, false, false]),
+        info(MethodConfig::Copeland(CopelandConfig::default()), [true, true, false, false]),
+        info(MethodConfig::Fptp(TiePolicy::default()), [false, true, false, true]),
+        info(MethodConfig::Irv, [false, false, true, true]),
+        info(MethodConfig::MajorityJudgment, [false, true, false, false]),
+        info(MethodConfig::Score, [false, true, false, false]),
+        info(MethodConfig::SmithIrv, [true, false, true, false]),
+        info(MethodConfig::SmithMinimax, [true, true, false, false]),
+        // The tiebreakers of the runoff can make raising the winner change
+        // which candidates reach it.
+        info(MethodConfig::Star(StarConfig::default()), [false, false, false, false]),
+    ]
+}
+
+/// Every method in the [`method_registry`] which satisfies every criterion in
+/// `requirements`.
+///
+/// ```
+/// use votery::{
+///     criteria::{recommend_methods, CriteriaSet},
+///     methods::MethodConfig,
+/// };
+///
+/// let requirements =
+///     CriteriaSet { condorcet: true, clone_independence: true, ..CriteriaSet::NONE };
+/// let methods: Vec<MethodConfig> =
+///     recommend_methods(requirements).into_iter().map(|info| info.method).collect();
+/// assert_eq!(methods, [MethodConfig::SmithIrv]);
+/// ```
+pub fn recommend_methods(requirements: CriteriaSet) -> Vec<MethodInfo> {
+    method_registry().into_iter().filter(|info| info.criteria.contains(&requirements)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{
+        criteria::{later_violations, monotonicity_violations, LaterEffect},
+        formats::{
+            soc::StrictOrdersComplete, toc::TiedOrdersComplete, toi::TiedOrdersIncomplete,
+            VoteFormat,
+        },
+    };
+
+    #[test]
+    fn every_method_without_requirements() {
+        assert_eq!(recommend_methods(CriteriaSet::NONE), method_registry());
+        let all = CriteriaSet {
+            condorcet: true,
+            monotonicity: true,
+            clone_independence: true,
+            later_no_harm: true,
+        };
+        assert!(recommend_methods(all).is_empty());
+    }
+
+    // Look for counterexamples to the claimed criteria in small random
+    // profiles.
+    #[quickcheck]
+    fn claims_hold(seed: u64) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut soc = StrictOrdersComplete::new(3 + (seed % 2) as usize);
+        soc.generate_uniform(&mut rng, 5 + (seed % 4) as usize);
+        let votes = TiedOrdersComplete::from(soc);
+        let condorcet_winner = votes.pairwise_matrix().unwrap().condorcet_winner();
+        // Only the two highest ranked candidates of every vote, so a later
+        // preference can be added.
+        let mut truncated = TiedOrdersIncomplete::new(votes.candidates);
+        for vote in &votes {
+            truncated.add(vote.top(2)).unwrap();
+        }
+        method_registry().iter().all(|MethodInfo { method, criteria }| {
+            let winners = method.rank(&votes, &mut rng).unwrap().as_ref().winners().to_vec();
+            (!criteria.condorcet || condorcet_winner.is_none_or(|w| winners == [w]))
+                && (!criteria.monotonicity
+                    || monotonicity_violations(&votes, method, 1, &mut rng).unwrap().is_empty())
+                && (!criteria.later_no_harm
+                    || later_violations(&truncated, method, &mut rng)
+                        .unwrap()
+                        .into_iter()
+                        .all(|v| v.effect != LaterEffect::Harm))
+        })
+    }
+}
@@ -0,0 +1,139 @@
+use rand::Rng;
+
+use super::iia::iia_check;
+use crate::{formats::toc::TiedOrdersComplete, methods::MethodConfig};
+
+/// A candidate which didn't win, but whose removal changes the winners.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spoiler {
+    pub candidate: usize,
+    /// The winners with `candidate`
+    pub before: Vec<usize>,
+    /// The winners without `candidate`, using the original indices of the
+    /// candidates.
+    pub after: Vec<usize>,
+    /// If there is a single winner both before and after, the number of
+    /// voters preferring the winner after to the winner before, minus the
+    /// number of voters with the opposite preference. A positive margin means
+    /// that `candidate` made a candidate preferred by more voters lose.
+    pub margin: Option<isize>,
+}
+
+/// Every spoiler of a profile, see [`spoilers`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpoilerReport {
+    pub candidates: usize,
+    /// The winners of the profile, sorted.
+    pub winners: Vec<usize>,
+    pub spoilers: Vec<Spoiler>,
+}
+
+impl SpoilerReport {
+    /// The spoiler index of the profile: the share of the candidates which
+    /// didn't win that are spoilers. `None` if every candidate won.
+    pub fn index(&self) -> Option<f64> {
+        let losers = self.candidates - self.winners.len();
+        (losers != 0).then(|| self.spoilers.len() as f64 / losers as f64)
+    }
+}
+
+/// Find every candidate which doesn't win `votes` under `method`, but whose
+/// removal changes the winners, i.e. every violation of independence of
+/// irrelevant alternatives found by
+/// [`iia_violations`](super::iia_violations).
+///
+/// ```
+/// use rand::SeedableRng;
+/// use rand_chacha::ChaCha8Rng;
+/// use votery::{
+///     criteria::spoilers,
+///     formats::{toc::TiedOrdersComplete, TiePolicy},
+///     methods::MethodConfig,
+/// };
+///
+/// // 1 and 2 split the votes of the majority preferring them to 0.
+/// let mut votes = TiedOrdersComplete::new(3);
+/// for (vote, n) in [("0,1,2", 4), ("1,2,0", 3), ("2,1,0", 2)] {
+///     for _ in 0..n {
+///         assert!(votes.add_from_str(vote));
+///     }
+/// }
+/// let mut rng = ChaCha8Rng::seed_from_u64(0);
+/// let fptp = MethodConfig::Fptp(TiePolicy::default());
+/// let report = spoilers(&votes, &fptp, &mut rng).unwrap();
+/// assert_eq!(report.winners, [0]);
+/// // Without 1 or 2 the other one wins, preferred by 5 voters to 4.
+/// assert_eq!(report.spoilers[0].after, [2]);
+/// assert_eq!(report.spoilers[1].after, [1]);
+/// assert!(report.spoilers.iter().all(|s| s.margin == Some(1)));
+/// assert_eq!(report.index(), Some(1.0));
+/// ```
+pub fn spoilers<R: Rng>(
+    votes: &TiedOrdersComplete,
+    method: &MethodConfig,
+    rng: &mut R,
+) -> Result<SpoilerReport, &'static str> {
+    let (winners, violations) = iia_check(votes, method, rng)?;
+    let matrix = votes.pairwise_matrix()?;
+    let spoilers = violations
+        .into_iter()
+        .map(|v| {
+            let margin = match (&v.before[..], &v.after[..]) {
+                ([before], [after]) => Some(matrix.margin(*after, *before)),
+                _ => None,
+            };
+            Spoiler { candidate: v.removed, before: v.before, after: v.after, margin }
+        })
+        .collect();
+    Ok(SpoilerReport { candidates: votes.candidates, winners, spoilers })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // 0 wins under FPTP, 1 under IRV and 1 is the Condorcet winner.
+    fn split() -> TiedOrdersComplete {
+        let mut votes = TiedOrdersComplete::new(4);
+        for (vote, n) in [("0,1,2,3", 4), ("1,2,0,3", 3), ("2,1,0,3", 2)] {
+            for _ in 0..n {
+                votes.add_from_str(vote);
+            }
+        }
+        votes
+    }
+
+    #[test]
+    fn fptp_irv_condorcet() {
+        let votes = split();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let fptp = spoilers(&votes, &MethodConfig::Fptp(Default::default()), &mut rng).unwrap();
+        assert_eq!(
+            fptp.spoilers,
+            [
+                Spoiler { candidate: 1, before: vec![0], after: vec![2], margin: Some(1) },
+                Spoiler { candidate: 2, before: vec![0], after: vec![1], margin: Some(1) },
+            ]
+        );
+        // 3 is last in every vote, and isn't a spoiler.
+        assert_eq!(fptp.index(), Some(2.0 / 3.0));
+        for method in [MethodConfig::Irv, MethodConfig::Condorcet] {
+            let report = spoilers(&votes, &method, &mut rng).unwrap();
+            assert_eq!(report.winners, [1]);
+            assert_eq!(report.index(), Some(0.0));
+        }
+    }
+
+    #[test]
+    fn everyone_wins() {
+        let mut votes = TiedOrdersComplete::new(2);
+        votes.add_from_str("0,1");
+        votes.add_from_str("1,0");
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let report = spoilers(&votes, &MethodConfig::Borda, &mut rng).unwrap();
+        assert_eq!(report.index(), None);
+    }
+}
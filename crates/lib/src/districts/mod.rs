@@ -0,0 +1,330 @@
+//! Elections split into districts, like an electoral college.
+//!
+//! Every district counts its own votes and awards its seats according to an
+//! [`Allocation`], and the seats are then added up over all districts. The
+//! [`DistrictResult`] also keeps the popular vote, so one can see when the
+//! seats don't match the votes, e.g. when a candidate wins the most seats
+//! without winning the popular vote.
+//!
+//! When the voters have positions, e.g. from a spatial model, [`redistrict`]
+//! draws many random district plans and reports the seats won under each.
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    formats::toc::TiedOrdersComplete,
+    methods::{pairwise::HeadToHead, MethodConfig},
+};
+
+mod redistricting;
+pub use redistricting::{partition, redistrict, Partition, Redistricting, RedistrictingConfig};
+
+/// How the seats of a district are awarded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Allocation {
+    /// Every seat goes to the winner of the district. If several candidates
+    /// win, one of them is picked at random.
+    #[default]
+    WinnerTakeAll,
+    /// The seats are split by first preferences using the largest remainder
+    /// method, ignoring the voting method. Votes with several candidates tied
+    /// first are split equally between them. Equal remainders go to the
+    /// candidate with the smallest index.
+    Proportional,
+}
+
+/// A profile split into districts, each with a number of seats.
+#[derive(Clone, Debug)]
+pub struct Districts {
+    districts: Vec<(TiedOrdersComplete, usize)>,
+    candidates: usize,
+}
+
+impl Districts {
+    /// Create districts from a profile for each of them, together with their
+    /// number of seats. The profiles can e.g. come from different generators.
+    pub fn new(districts: Vec<(TiedOrdersComplete, usize)>) -> Result<Self, &'static str> {
+        let candidates = districts.first().ok_or("No districts")?.0.candidates;
+        if districts.iter().any(|(v, _)| v.candidates != candidates) {
+            return Err("Districts have different numbers of candidates");
+        }
+        Ok(Districts { districts, candidates })
+    }
+
+    /// Split `votes` into districts, where `tags[i]` is the district of voter
+    /// `i`, and district `d` has `seats[d]` seats. Every district needs at
+    /// least one voter.
+    pub fn from_tags(
+        votes: &TiedOrdersComplete,
+        tags: &[usize],
+        seats: &[usize],
+    ) -> Result<Self, &'static str> {
+        if tags.len() != votes.voters() {
+            return Err("Every voter needs a district");
+        }
+        let mut districts: Vec<(TiedOrdersComplete, usize)> =
+            seats.iter().map(|&s| (TiedOrdersComplete::new(votes.candidates), s)).collect();
+        for (vote, &tag) in votes.into_iter().zip(tags) {
+            let (district, _) = districts.get_mut(tag).ok_or("Voter in non-existing district")?;
+            district.add(vote);
+        }
+        if districts.iter().any(|(v, _)| v.voters() == 0) {
+            return Err("District without voters");
+        }
+        Districts::new(districts)
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    pub fn len(&self) -> usize {
+        self.districts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.districts.is_empty()
+    }
+
+    /// The votes and seats of district `d`.
+    pub fn district(&self, d: usize) -> Option<(&TiedOrdersComplete, usize)> {
+        self.districts.get(d).map(|(v, s)| (v, *s))
+    }
+
+    /// How the voters of every district compare `a` and `b`, see
+    /// [`HeadToHead`]. Summing them gives the breakdown of the whole
+    /// electorate.
+    pub fn head_to_head(&self, a: usize, b: usize) -> Result<Vec<HeadToHead>, &'static str> {
+        self.districts.iter().map(|(votes, _)| votes.head_to_head(a, b)).collect()
+    }
+
+    /// Count every district with `method` and award its seats according to
+    /// `allocation`.
+    pub fn count<R: Rng>(
+        &self,
+        method: &MethodConfig,
+        allocation: Allocation,
+        rng: &mut R,
+    ) -> Result<DistrictResult, &'static str> {
+        let n = self.candidates;
+        let mut winners = Vec::with_capacity(self.districts.len());
+        let mut seats = vec![0; n];
+        let mut popular_vote = vec![0.0; n];
+        for (votes, district_seats) in &self.districts {
+            let firsts = first_preferences(votes);
+            for (p, f) in popular_vote.iter_mut().zip(&firsts) {
+                *p += f;
+            }
+            let mut w = method.rank(votes, rng)?.as_ref().winners().to_vec();
+            w.sort();
+            match allocation {
+                Allocation::WinnerTakeAll => {
+                    let &c = w.choose(rng).ok_or("District without a winner")?;
+                    seats[c] += district_seats;
+                }
+                Allocation::Proportional => {
+                    for (s, won) in
+                        seats.iter_mut().zip(largest_remainder(&firsts, *district_seats))
+                    {
+                        *s += won;
+                    }
+                }
+            }
+            winners.push(w);
+        }
+        Ok(DistrictResult { winners, seats, popular_vote })
+    }
+}
+
+/// The result of counting [`Districts`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistrictResult {
+    /// The sorted winners of every district.
+    pub winners: Vec<Vec<usize>>,
+    /// The number of seats every candidate won.
+    pub seats: Vec<usize>,
+    /// The number of first preferences of every candidate, over all
+    /// districts. Votes with several candidates tied first are split equally.
+    pub popular_vote: Vec<f64>,
+}
+
+impl DistrictResult {
+    /// The share of the seats every candidate won.
+    pub fn seat_shares(&self) -> Vec<f64> {
+        shares(&self.seats.iter().map(|&s| s as f64).collect::<Vec<_>>())
+    }
+
+    /// The share of the first preferences every candidate got.
+    pub fn vote_shares(&self) -> Vec<f64> {
+        shares(&self.popular_vote)
+    }
+
+    /// The seat share minus the vote share of every candidate. Positive values
+    /// mean that a candidate won more seats than its votes would give it.
+    pub fn discrepancies(&self) -> Vec<f64> {
+        self.seat_shares().iter().zip(self.vote_shares()).map(|(s, v)| s - v).collect()
+    }
+
+    /// The largest absolute discrepancy between seat share and vote share.
+    pub fn max_discrepancy(&self) -> f64 {
+        self.discrepancies().into_iter().map(f64::abs).fold(0.0, f64::max)
+    }
+
+    /// The Gallagher index, or least squares index, of disproportionality:
+    /// the square root of half the sum of the squared discrepancies. It is
+    /// between 0.0 and 1.0, so multiply by 100 to get the usual percentage
+    /// points.
+    pub fn gallagher(&self) -> f64 {
+        let squares: f64 = self.discrepancies().iter().map(|d| d * d).sum();
+        (squares / 2.0).sqrt()
+    }
+
+    /// The Loosemore–Hanby index of disproportionality: half the sum of the
+    /// absolute discrepancies, i.e. the share of the seats which went to
+    /// candidates beyond their vote share. It is between 0.0 and 1.0.
+    pub fn loosemore_hanby(&self) -> f64 {
+        self.discrepancies().iter().map(|d| d.abs()).sum::<f64>() / 2.0
+    }
+
+    /// Returns true if no candidate with the most seats also has the most
+    /// first preferences, i.e. the popular vote winner lost.
+    pub fn is_inversion(&self) -> bool {
+        let most_seats = self.seats.iter().max().copied().unwrap_or(0);
+        let most_votes = self.popular_vote.iter().copied().fold(0.0, f64::max);
+        !(0..self.seats.len())
+            .any(|c| self.seats[c] == most_seats && self.popular_vote[c] == most_votes)
+    }
+}
+
+// The number of first preferences of every candidate, where ties are split.
+fn first_preferences(votes: &TiedOrdersComplete) -> Vec<f64> {
+    let mut firsts = vec![0.0; votes.candidates];
+    for vote in votes {
+        let w = vote.winners();
+        for &c in w {
+            firsts[c] += 1.0 / w.len() as f64;
+        }
+    }
+    firsts
+}
+
+// Split `seats` proportionally to `votes` using the largest remainder method.
+fn largest_remainder(votes: &[f64], seats: usize) -> Vec<usize> {
+    let total: f64 = votes.iter().sum();
+    if total == 0.0 {
+        return vec![0; votes.len()];
+    }
+    let quotas: Vec<f64> = votes.iter().map(|v| v / total * seats as f64).collect();
+    let mut won: Vec<usize> = quotas.iter().map(|q| q.floor() as usize).collect();
+    let mut order: Vec<usize> = (0..votes.len()).collect();
+    // A stable sort, so equal remainders keep the smallest index first.
+    order.sort_by(|&a, &b| {
+        let remainder = |c: usize| quotas[c] - quotas[c].floor();
+        remainder(b).total_cmp(&remainder(a))
+    });
+    let left = seats.saturating_sub(won.iter().sum());
+    for &c in order.iter().take(left) {
+        won[c] += 1;
+    }
+    won
+}
+
+fn shares(values: &[f64]) -> Vec<f64> {
+    let total: f64 = values.iter().sum();
+    if total == 0.0 {
+        return vec![0.0; values.len()];
+    }
+    values.iter().map(|v| v / total).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // Candidate 0 narrowly wins two districts, while candidate 1 wins the
+    // third by a landslide.
+    fn example() -> Districts {
+        let mut votes = TiedOrdersComplete::new(2);
+        let mut tags = Vec::new();
+        for (d, s, n) in [(0, "0,1", 3), (0, "1,0", 2), (1, "0,1", 3), (1, "1,0", 2), (2, "1,0", 5)]
+        {
+            for _ in 0..n {
+                votes.add_from_str(s);
+                tags.push(d);
+            }
+        }
+        Districts::from_tags(&votes, &tags, &[1, 1, 1]).unwrap()
+    }
+
+    #[test]
+    fn head_to_head() {
+        let districts = example();
+        let h = districts.head_to_head(0, 1).unwrap();
+        assert_eq!(h.iter().map(HeadToHead::margin).collect::<Vec<_>>(), [1, 1, -5]);
+        let total: HeadToHead = h.into_iter().sum();
+        assert_eq!((total.a_over_b, total.b_over_a), (6, 9));
+        assert!(districts.head_to_head(0, 2).is_err());
+    }
+
+    #[test]
+    fn empty_district() {
+        let mut votes = TiedOrdersComplete::new(2);
+        votes.add_from_str("0,1");
+        votes.add_from_str("1,0");
+        assert!(Districts::from_tags(&votes, &[0, 2], &[1, 1, 1]).is_err());
+        assert!(Districts::from_tags(&votes, &[0, 1], &[1, 1]).is_ok());
+    }
+
+    #[test]
+    fn winner_take_all() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let method = MethodConfig::Irv;
+        let res = example().count(&method, Allocation::WinnerTakeAll, &mut rng).unwrap();
+        assert_eq!(res.winners, [vec![0], vec![0], vec![1]]);
+        assert_eq!(res.seats, [2, 1]);
+        assert_eq!(res.popular_vote, [6.0, 9.0]);
+        assert!(res.is_inversion());
+        assert!((res.max_discrepancy() - (2.0 / 3.0 - 0.4)).abs() < 1e-9);
+        // With two candidates both indices equal the discrepancy.
+        assert!((res.gallagher() - (2.0 / 3.0 - 0.4)).abs() < 1e-9);
+        assert!((res.loosemore_hanby() - (2.0 / 3.0 - 0.4)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn indices() {
+        let res = DistrictResult {
+            winners: Vec::new(),
+            seats: vec![5, 3, 2, 0],
+            popular_vote: vec![40.0, 30.0, 20.0, 10.0],
+        };
+        // The discrepancies are 0.1, 0.0, 0.0 and -0.1.
+        assert!((res.loosemore_hanby() - 0.1).abs() < 1e-9);
+        assert!((res.gallagher() - 0.1).abs() < 1e-9);
+        let exact = DistrictResult { seats: vec![4, 3, 2, 1], ..res };
+        assert!(exact.gallagher().abs() < 1e-9);
+        assert!(exact.loosemore_hanby().abs() < 1e-9);
+    }
+
+    #[test]
+    fn proportional() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut districts = example();
+        districts.districts[0].1 = 5;
+        let res =
+            districts.count(&MethodConfig::Borda, Allocation::Proportional, &mut rng).unwrap();
+        // The 5 seats are split 3 to 2, and the single seats go to the local
+        // leaders.
+        assert_eq!(res.seats, [4, 3]);
+        assert_eq!(res.winners, [vec![0], vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn remainders() {
+        assert_eq!(largest_remainder(&[1.0, 1.0, 1.0], 2), [1, 1, 0]);
+        assert_eq!(largest_remainder(&[5.0, 3.0, 2.0], 4), [2, 1, 1]);
+        assert_eq!(largest_remainder(&[0.0, 0.0], 3), [0, 0]);
+    }
+}
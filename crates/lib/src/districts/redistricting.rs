@@ -0,0 +1,328 @@
+use rand::{seq::index::sample, Rng};
+use rand_distr::StandardNormal;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::{Allocation, DistrictResult, Districts};
+use crate::{
+    formats::toc::TiedOrdersComplete,
+    methods::MethodConfig,
+    seed::{ElectionSeed, Stream},
+    spatial::Point,
+};
+
+/// How voters are split into districts by their positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Partition {
+    /// k-means clustering, starting from randomly picked voters and moving
+    /// every center to the mean of its voters at most `iterations` times.
+    /// A district left without voters takes the voter farthest from its
+    /// center. Districts can have very different numbers of voters.
+    KMeans { iterations: usize },
+    /// Recursively cut the voters in two with a hyperplane, so that each side
+    /// gets voters in proportion to its number of districts. Of `tries`
+    /// random directions, the cut which is shortest, i.e. where the voters
+    /// are spread the least along the hyperplane, is used.
+    SplitLine { tries: usize },
+}
+
+/// Assign every voter in `positions` to one of `districts` districts
+/// according to `partition`, returning the district of every voter.
+pub fn partition<const D: usize, R: Rng>(
+    positions: &[Point<D>],
+    districts: usize,
+    partition: Partition,
+    rng: &mut R,
+) -> Result<Vec<usize>, &'static str> {
+    if districts == 0 {
+        return Err("Need at least one district");
+    }
+    if districts > positions.len() {
+        return Err("More districts than voters");
+    }
+    let mut tags = vec![0; positions.len()];
+    match partition {
+        Partition::KMeans { iterations } => {
+            let mut centers: Vec<Point<D>> =
+                sample(rng, positions.len(), districts).iter().map(|i| positions[i]).collect();
+            for iteration in 0..=iterations {
+                let mut changed = false;
+                for (tag, p) in tags.iter_mut().zip(positions) {
+                    let closest = (0..districts)
+                        .min_by(|&a, &b| {
+                            p.distance(&centers[a]).total_cmp(&p.distance(&centers[b]))
+                        })
+                        .unwrap();
+                    changed |= *tag != closest;
+                    *tag = closest;
+                }
+                // Move the voter farthest from its center into every empty
+                // district, so that every district gets a voter.
+                let mut counts = vec![0; districts];
+                for &tag in &tags {
+                    counts[tag] += 1;
+                }
+                for d in 0..districts {
+                    if counts[d] != 0 {
+                        continue;
+                    }
+                    let distance = |i: usize| positions[i].distance(&centers[tags[i]]);
+                    let farthest = (0..positions.len())
+                        .filter(|&i| counts[tags[i]] > 1)
+                        .max_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+                        .unwrap();
+                    counts[tags[farthest]] -= 1;
+                    counts[d] = 1;
+                    tags[farthest] = d;
+                    centers[d] = positions[farthest];
+                    changed = true;
+                }
+                if iteration == iterations || (!changed && iteration != 0) {
+                    break;
+                }
+                let mut sums = vec![([0.0; D], 0); districts];
+                for (&tag, p) in tags.iter().zip(positions) {
+                    let (sum, count) = &mut sums[tag];
+                    for (s, x) in sum.iter_mut().zip(p.coords()) {
+                        *s += x;
+                    }
+                    *count += 1;
+                }
+                for (center, (sum, count)) in centers.iter_mut().zip(sums) {
+                    *center = Point::new(sum.map(|s| s / count as f64));
+                }
+            }
+        }
+        Partition::SplitLine { tries } => {
+            let mut indices: Vec<usize> = (0..positions.len()).collect();
+            split_line(positions, &mut indices, districts, 0, tries.max(1), &mut tags, rng);
+        }
+    }
+    Ok(tags)
+}
+
+// Split the voters `indices` into `districts` districts, numbered from
+// `first`.
+fn split_line<const D: usize, R: Rng>(
+    positions: &[Point<D>],
+    indices: &mut [usize],
+    districts: usize,
+    first: usize,
+    tries: usize,
+    tags: &mut [usize],
+    rng: &mut R,
+) {
+    if districts == 1 {
+        for &i in indices.iter() {
+            tags[i] = first;
+        }
+        return;
+    }
+    let left = districts / 2;
+    let mid = indices.len() * left / districts;
+    let mut best: Option<(f64, [f64; D])> = None;
+    for _ in 0..tries {
+        let mut dir: [f64; D] = std::array::from_fn(|_| rng.sample(StandardNormal));
+        let norm = dir.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        dir.iter_mut().for_each(|x| *x /= norm);
+        let length = cut_length(positions, indices, &dir);
+        if best.is_none_or(|(l, _)| length < l) {
+            best = Some((length, dir));
+        }
+    }
+    // Without a direction, e.g. with no dimensions, any split will do.
+    if let Some((_, dir)) = best {
+        let project =
+            |i: usize| -> f64 { positions[i].coords().iter().zip(&dir).map(|(x, d)| x * d).sum() };
+        indices.sort_by(|&a, &b| project(a).total_cmp(&project(b)));
+    }
+    let (l, r) = indices.split_at_mut(mid);
+    split_line(positions, l, left, first, tries, tags, rng);
+    split_line(positions, r, districts - left, first + left, tries, tags, rng);
+}
+
+// The diameter of the voters `indices` projected onto the hyperplane
+// orthogonal to `dir`, approximated by twice the largest distance from their
+// mean.
+fn cut_length<const D: usize>(positions: &[Point<D>], indices: &[usize], dir: &[f64; D]) -> f64 {
+    let residual = |i: usize| -> [f64; D] {
+        let p = positions[i].coords();
+        let dot: f64 = p.iter().zip(dir).map(|(x, d)| x * d).sum();
+        std::array::from_fn(|k| p[k] - dot * dir[k])
+    };
+    let mut mean = [0.0; D];
+    for &i in indices {
+        for (m, r) in mean.iter_mut().zip(residual(i)) {
+            *m += r / indices.len() as f64;
+        }
+    }
+    let mean = Point::new(mean);
+    let max = indices.iter().map(|&i| Point::new(residual(i)).distance(&mean)).fold(0.0, f64::max);
+    2.0 * max
+}
+
+/// How to redistrict an electorate in [`redistrict`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RedistrictingConfig {
+    /// The number of districts in every plan.
+    pub districts: usize,
+    /// The number of seats of every district.
+    pub seats: usize,
+    /// The number of redistricting plans to try.
+    pub plans: usize,
+    pub partition: Partition,
+    pub method: MethodConfig,
+    pub allocation: Allocation,
+}
+
+/// The results of every plan in [`redistrict`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redistricting {
+    pub plans: Vec<DistrictResult>,
+}
+
+impl Redistricting {
+    /// How many plans gave `candidate` each number of seats, where index `s`
+    /// is the number of plans where it won `s` seats.
+    pub fn seat_histogram(&self, candidate: usize) -> Vec<usize> {
+        let mut histogram = Vec::new();
+        for plan in &self.plans {
+            let s = plan.seats[candidate];
+            if histogram.len() <= s {
+                histogram.resize(s + 1, 0);
+            }
+            histogram[s] += 1;
+        }
+        histogram
+    }
+
+    /// The average number of seats `candidate` won over every plan.
+    pub fn mean_seats(&self, candidate: usize) -> f64 {
+        let total: usize = self.plans.iter().map(|p| p.seats[candidate]).sum();
+        total as f64 / self.plans.len() as f64
+    }
+
+    /// The share of the plans where the popular vote winner lost, see
+    /// [`DistrictResult::is_inversion`].
+    pub fn inversion_rate(&self) -> f64 {
+        let inversions = self.plans.iter().filter(|p| p.is_inversion()).count();
+        inversions as f64 / self.plans.len() as f64
+    }
+}
+
+/// Draw `config.plans` random redistricting plans of the voters in `votes`,
+/// where voter `i` lives at `positions[i]`, and count the election under
+/// each of them. The positions can be sampled together with the votes using
+/// [`Spatial::sample_with_positions`](crate::generators::spatial::Spatial::sample_with_positions).
+pub fn redistrict<const D: usize, R: Rng>(
+    votes: &TiedOrdersComplete,
+    positions: &[Point<D>],
+    config: &RedistrictingConfig,
+    rng: &mut R,
+) -> Result<Redistricting, &'static str> {
+    if positions.len() != votes.voters() {
+        return Err("Every voter needs a position");
+    }
+    if config.plans == 0 {
+        return Err("Need at least one plan");
+    }
+    let seats = vec![config.seats; config.districts];
+    let seed = ElectionSeed::random(rng);
+    let plan = |i: u64| -> Result<DistrictResult, &'static str> {
+        let seed = seed.derive(i);
+        let mut rng = seed.rng(Stream::Sampling);
+        let tags = partition(positions, config.districts, config.partition, &mut rng)?;
+        let districts = Districts::from_tags(votes, &tags, &seats)?;
+        districts.count(&config.method, config.allocation, &mut seed.rng(Stream::TieBreaking))
+    };
+    let plans = 0..config.plans as u64;
+
+    #[cfg(feature = "rayon")]
+    let plans = plans.into_par_iter().map(plan).collect::<Result<_, _>>()?;
+    #[cfg(not(feature = "rayon"))]
+    let plans = plans.map(plan).collect::<Result<_, _>>()?;
+    Ok(Redistricting { plans })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::generators::spatial::{FuzzyType, Spatial};
+
+    fn sizes(tags: &[usize], districts: usize) -> Vec<usize> {
+        let mut sizes = vec![0; districts];
+        for &t in tags {
+            sizes[t] += 1;
+        }
+        sizes
+    }
+
+    #[test]
+    fn split_line_sizes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let positions: Vec<Point<2>> =
+            (0..30).map(|i| Point::new([i as f64, (i * 7 % 5) as f64])).collect();
+        let tags = partition(&positions, 3, Partition::SplitLine { tries: 4 }, &mut rng).unwrap();
+        assert_eq!(sizes(&tags, 3), [10, 10, 10]);
+        assert!(partition(&positions, 31, Partition::SplitLine { tries: 4 }, &mut rng).is_err());
+    }
+
+    #[test]
+    fn k_means_clusters() {
+        // Two clear clusters are always found.
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let positions: Vec<Point<1>> =
+            [0.0, 0.1, 0.2, 10.0, 10.1, 10.2].into_iter().map(|x| Point::new([x])).collect();
+        for _ in 0..10 {
+            let tags =
+                partition(&positions, 2, Partition::KMeans { iterations: 10 }, &mut rng).unwrap();
+            assert!(tags[0] == tags[1] && tags[1] == tags[2] && tags[3] == tags[4]);
+            assert_ne!(tags[0], tags[3]);
+        }
+    }
+
+    #[test]
+    fn k_means_no_empty_districts() {
+        // Every voter is closest to the first center, so the other districts
+        // start out empty.
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let positions = vec![Point::new([0.5]); 5];
+        for iterations in [0, 3] {
+            let tags =
+                partition(&positions, 3, Partition::KMeans { iterations }, &mut rng).unwrap();
+            assert!(sizes(&tags, 3).iter().all(|&s| s > 0));
+        }
+    }
+
+    #[test]
+    fn seat_distribution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut g = Spatial::new(0.3, 200, FuzzyType::Equal);
+        g.add_candidate([0.4, 0.5]);
+        g.add_candidate([0.6, 0.5]);
+        let (votes, positions) = g.sample_with_positions(&mut rng, &Point::new([0.5, 0.5]));
+        let config = RedistrictingConfig {
+            districts: 5,
+            seats: 1,
+            plans: 20,
+            partition: Partition::SplitLine { tries: 3 },
+            method: MethodConfig::Borda,
+            allocation: Allocation::WinnerTakeAll,
+        };
+        let res = redistrict(&votes, &positions, &config, &mut rng).unwrap();
+        assert_eq!(res.plans.len(), 20);
+        assert_eq!(res.seat_histogram(0).iter().sum::<usize>(), 20);
+        assert!((res.mean_seats(0) + res.mean_seats(1) - 5.0).abs() < 1e-9);
+        assert!((0.0..=1.0).contains(&res.inversion_rate()));
+        let none = RedistrictingConfig { plans: 0, ..config };
+        assert!(redistrict(&votes, &positions, &none, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! The vocabulary of an election.
+//!
+//! Internally, votes are orders of *elements*, see
+//! [`orders`](crate::formats::orders), and both candidates and voters are
+//! plain indices. At the level of elections, [`Candidate`] and [`Voter`] say
+//! which kind of index is meant, so that one can't be passed as the other.
+//! They convert to and from `usize` when going between the two levels.
+//!
+//! The results of methods are described by a [`Rank`] or a [`Score`] of
+//! every candidate. Note that they are ordered in opposite directions: the
+//! best rank is the smallest, while the best score is the largest.
+//!
+//! An [`Election`] keeps the names of the candidates together with the votes,
+//! so results can be reported by name.
+
+use std::{cmp::Ordering, fmt};
+
+use crate::{formats::VoteFormat, methods::VotingMethod};
+
+/// A candidate of an election, with the index of its element in the votes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Candidate(pub usize);
+
+/// A voter of an election, with the index of its vote in the profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Voter(pub usize);
+
+/// The position of a candidate in the result of a method, where the winners
+/// have rank 0 and tied candidates have the same rank.
+///
+/// Lower ranks are better, so the best rank is the *smallest* one according
+/// to `Ord`. Use [`Rank::is_better_than`] to avoid comparing in the wrong
+/// direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Rank(pub usize);
+
+/// The score of a candidate in a method, e.g. the number of votes it got.
+///
+/// Higher scores are better, so the best score is the *largest* one according
+/// to `Ord`. Use [`Score::is_better_than`] to avoid comparing in the wrong
+/// direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Score(pub u64);
+
+/// A score which doesn't have to be a whole number, e.g. the half points
+/// of [`Copeland`](crate::methods::Copeland). Fractions are compared by
+/// their value, so `1/2` and `2/4` are equal.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fraction {
+    numer: u64,
+    denom: u64,
+}
+
+impl Rank {
+    /// The rank of the winners.
+    pub const FIRST: Rank = Rank(0);
+
+    /// Returns true if a candidate with this rank is ranked above a
+    /// candidate with rank `other`.
+    pub fn is_better_than(self, other: Rank) -> bool {
+        self.0 < other.0
+    }
+}
+
+impl Score {
+    /// Returns true if a candidate with this score is ranked above a
+    /// candidate with score `other`.
+    pub fn is_better_than(self, other: Score) -> bool {
+        self.0 > other.0
+    }
+}
+
+impl Fraction {
+    /// The fraction `numer / denom`. Panics if `denom` is 0.
+    pub fn new(numer: u64, denom: u64) -> Self {
+        assert!(denom != 0, "Denominator can't be 0");
+        Fraction { numer, denom }
+    }
+
+    pub fn numer(self) -> u64 {
+        self.numer
+    }
+
+    pub fn denom(self) -> u64 {
+        self.denom
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+}
+
+impl PartialEq for Fraction {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Fraction {}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let a = u128::from(self.numer) * u128::from(other.denom);
+        let b = u128::from(other.numer) * u128::from(self.denom);
+        a.cmp(&b)
+    }
+}
+
+impl Candidate {
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    /// Every candidate of an election with `candidates` candidates.
+    pub fn all(candidates: usize) -> impl Iterator<Item = Candidate> {
+        (0..candidates).map(Candidate)
+    }
+}
+
+impl Voter {
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    /// Every voter of an election with `voters` voters.
+    pub fn all(voters: usize) -> impl Iterator<Item = Voter> {
+        (0..voters).map(Voter)
+    }
+}
+
+impl From<usize> for Candidate {
+    fn from(i: usize) -> Self {
+        Candidate(i)
+    }
+}
+
+impl From<Candidate> for usize {
+    fn from(c: Candidate) -> Self {
+        c.0
+    }
+}
+
+impl From<usize> for Voter {
+    fn from(i: usize) -> Self {
+        Voter(i)
+    }
+}
+
+impl From<Voter> for usize {
+    fn from(v: Voter) -> Self {
+        v.0
+    }
+}
+
+impl From<usize> for Score {
+    fn from(s: usize) -> Self {
+        Score(s as u64)
+    }
+}
+
+impl From<u64> for Fraction {
+    fn from(n: u64) -> Self {
+        Fraction::new(n, 1)
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numer, self.denom)
+    }
+}
+
+// Written like in orders, e.g. `2,{0,1}`.
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Voter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Votes of any format together with the names of the candidates, where
+/// candidate `i` is called `names[i]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Election<F> {
+    votes: F,
+    names: Vec<String>,
+}
+
+impl<F> Election<F> {
+    /// Returns an error if there isn't exactly one name for every candidate
+    /// of `votes`, or if two candidates have the same name.
+    pub fn new<'a>(votes: F, names: Vec<String>) -> Result<Self, &'static str>
+    where
+        F: VoteFormat<'a>,
+    {
+        if names.len() != votes.candidates() {
+            return Err("Need one name for every candidate");
+        }
+        if names.iter().enumerate().any(|(i, a)| names[..i].contains(a)) {
+            return Err("Candidates must have different names");
+        }
+        Ok(Election { votes, names })
+    }
+
+    pub fn votes(&self) -> &F {
+        &self.votes
+    }
+
+    /// Returns the votes, and the names of the candidates.
+    pub fn into_parts(self) -> (F, Vec<String>) {
+        (self.votes, self.names)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn name(&self, candidate: Candidate) -> &str {
+        &self.names[candidate.index()]
+    }
+
+    /// The candidate called `name`.
+    pub fn candidate(&self, name: &str) -> Option<Candidate> {
+        self.names.iter().position(|n| n == name).map(Candidate)
+    }
+
+    pub fn add<'a>(&mut self, vote: F::Vote) -> Result<(), &'static str>
+    where
+        F: VoteFormat<'a>,
+    {
+        self.votes.add(vote)
+    }
+
+    /// Remove `candidate` from the votes and its name, so the candidates after
+    /// it keep their names.
+    pub fn remove_candidate<'a>(&mut self, candidate: Candidate) -> Result<(), &'static str>
+    where
+        F: VoteFormat<'a>,
+    {
+        self.votes.remove_candidate(candidate.index())?;
+        self.names.remove(candidate.index());
+        Ok(())
+    }
+
+    /// Count the votes with the method `M`.
+    pub fn count<'a, M>(&self) -> Result<M, &'static str>
+    where
+        M: VotingMethod<'a, Format = F>,
+        F: VoteFormat<'a> + Clone,
+    {
+        M::count(&self.votes)
+    }
+
+    /// The names of the winners of the method `M`, in the order of the
+    /// candidates.
+    pub fn winner_names<'a, M>(&self) -> Result<Vec<&str>, &'static str>
+    where
+        M: VotingMethod<'a, Format = F>,
+        F: VoteFormat<'a> + Clone,
+    {
+        Ok(self.ranked_names::<M>()?.into_iter().next().unwrap_or_default())
+    }
+
+    /// The names of the candidates grouped by their rank in the result of
+    /// the method `M`, starting with the winners.
+    pub fn ranked_names<'a, M>(&self) -> Result<Vec<Vec<&str>>, &'static str>
+    where
+        M: VotingMethod<'a, Format = F>,
+        F: VoteFormat<'a> + Clone,
+    {
+        let mut groups: Vec<Vec<&str>> = Vec::new();
+        for (c, rank) in self.count::<M>()?.ranks().into_iter().enumerate() {
+            if groups.len() <= rank.0 {
+                groups.resize(rank.0 + 1, Vec::new());
+            }
+            groups[rank.0].push(&self.names[c]);
+        }
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formats::{toi::TiedOrdersIncomplete, Cardinal},
+        methods::{Borda, Star},
+    };
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn names_follow_candidates() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("2,1,0", 3);
+        votes.add_from_str_i("1,2,0", 2);
+        assert!(Election::new(votes.clone(), names(&["a", "b"])).is_err());
+        assert!(Election::new(votes.clone(), names(&["a", "b", "a"])).is_err());
+        let mut election = Election::new(votes, names(&["a", "b", "c"])).unwrap();
+        assert_eq!(election.winner_names::<Borda>().unwrap(), ["c"]);
+        assert_eq!(election.ranked_names::<Borda>().unwrap(), [["c"], ["b"], ["a"]]);
+        election.remove_candidate(election.candidate("c").unwrap()).unwrap();
+        assert_eq!(election.names(), ["a", "b"]);
+        assert_eq!(election.winner_names::<Borda>().unwrap(), ["b"]);
+        assert_eq!(election.name(Candidate(1)), "b");
+        assert_eq!(election.candidate("c"), None);
+    }
+
+    #[test]
+    fn star_names() {
+        let mut votes = Cardinal::new(4, 0, 4);
+        for vote in [[1, 3, 2, 4], [3, 1, 1, 3], [0, 2, 1, 2], [2, 4, 2, 2]] {
+            votes.add(&vote).unwrap();
+        }
+        let election = Election::new(votes, names(&["a", "b", "c", "d"])).unwrap();
+        let winners = election.count::<Star>().unwrap().as_vote();
+        let winners: Vec<&str> =
+            winners.as_ref().winners().iter().map(|&c| election.name(Candidate(c))).collect();
+        assert_eq!(winners, ["d"]);
+        assert_eq!(election.winner_names::<Star>().unwrap(), winners);
+        assert_eq!(
+            election.ranked_names::<Star>().unwrap(),
+            [vec!["d"], vec!["b"], vec!["a", "c"]]
+        );
+    }
+}
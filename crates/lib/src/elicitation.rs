@@ -0,0 +1,357 @@
+//! Elicit preferences one pairwise question at a time.
+//!
+//! When only parts of the votes are known, each vote is a [`PartialOrder`] and
+//! the winner depends on how they are completed. A candidate who wins in some
+//! completion is a possible winner, and one who wins in every completion is a
+//! necessary winner. [`next_question`] suggests which voter to ask about which
+//! pair of candidates, to learn the most about the winner.
+//!
+//! Everything here enumerates every completion of the votes, so it is only
+//! useful for small elections.
+
+use std::fmt;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    formats::{
+        orders::{write_matrix, TiedRank},
+        toc::TiedOrdersComplete,
+    },
+    methods::MethodConfig,
+};
+
+/// A strict partial order of candidates, the known parts of a vote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialOrder {
+    candidates: usize,
+    // above[a * candidates + b] is true if `a` is known to be preferred to `b`.
+    // Always transitively closed.
+    above: Vec<bool>,
+}
+
+impl PartialOrder {
+    /// A partial order where nothing is known.
+    pub fn new(candidates: usize) -> Self {
+        PartialOrder { candidates, above: vec![false; candidates * candidates] }
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    /// Returns true if `a` is known to be preferred to `b`.
+    pub fn prefers(&self, a: usize, b: usize) -> bool {
+        self.above[a * self.candidates + b]
+    }
+
+    /// Returns true if the order between `a` and `b` is known.
+    pub fn is_known(&self, a: usize, b: usize) -> bool {
+        a == b || self.prefers(a, b) || self.prefers(b, a)
+    }
+
+    /// Add that `a` is preferred to `b`, and everything that follows from it.
+    pub fn add(&mut self, a: usize, b: usize) -> Result<(), &'static str> {
+        let n = self.candidates;
+        if a >= n || b >= n {
+            return Err("Candidate does not exist");
+        }
+        if a == b || self.prefers(b, a) {
+            return Err("Preference contradicts the order");
+        }
+        // Everything at or above `a` is now above everything at or below `b`.
+        let higher: Vec<usize> = (0..n).filter(|&c| c == a || self.prefers(c, a)).collect();
+        let lower: Vec<usize> = (0..n).filter(|&c| c == b || self.prefers(b, c)).collect();
+        for &h in &higher {
+            for &l in &lower {
+                self.above[h * n + l] = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Display the order as a matrix, where the element in row `a` and column
+    /// `b` is `>` if `a` is known to be preferred to `b`, `<` if `b` is known
+    /// to be preferred to `a` and `?` otherwise. The diagonal is `=`.
+    pub fn display_matrix(&self) -> DisplayPartialOrder<'_> {
+        DisplayPartialOrder { order: self }
+    }
+
+    /// Every strict order of the candidates which agrees with this order.
+    pub fn linear_extensions(&self) -> Vec<Vec<usize>> {
+        let mut res = Vec::new();
+        let mut order = Vec::with_capacity(self.candidates);
+        let mut placed = vec![false; self.candidates];
+        self.extend(&mut order, &mut placed, &mut res);
+        res
+    }
+
+    fn extend(&self, order: &mut Vec<usize>, placed: &mut [bool], res: &mut Vec<Vec<usize>>) {
+        if order.len() == self.candidates {
+            res.push(order.clone());
+            return;
+        }
+        for c in 0..self.candidates {
+            if !placed[c] && (0..self.candidates).all(|p| placed[p] || !self.prefers(p, c)) {
+                placed[c] = true;
+                order.push(c);
+                self.extend(order, placed, res);
+                order.pop();
+                placed[c] = false;
+            }
+        }
+    }
+}
+
+/// Displays a [`PartialOrder`] as a matrix, see
+/// [`PartialOrder::display_matrix`].
+pub struct DisplayPartialOrder<'a> {
+    order: &'a PartialOrder,
+}
+
+impl<'a> fmt::Display for DisplayPartialOrder<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let order = self.order;
+        write_matrix(f, order.candidates, |a, b| {
+            if a == b {
+                '='
+            } else if order.prefers(a, b) {
+                '>'
+            } else if order.prefers(b, a) {
+                '<'
+            } else {
+                '?'
+            }
+        })
+    }
+}
+
+/// A question to ask: does `voter` prefer `a` to `b`?
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Question {
+    pub voter: usize,
+    pub a: usize,
+    pub b: usize,
+}
+
+// Every completion of the votes, with the winners of each.
+struct Completions {
+    extensions: Vec<Vec<Vec<usize>>>,
+    // For every completion, the extension used for every voter
+    chosen: Vec<Vec<usize>>,
+    winners: Vec<Vec<usize>>,
+    candidates: usize,
+}
+
+impl Completions {
+    fn new<R: Rng>(
+        votes: &[PartialOrder],
+        method: &MethodConfig,
+        max_completions: usize,
+        rng: &mut R,
+    ) -> Result<Self, &'static str> {
+        let candidates = votes.first().ok_or("No votes")?.candidates;
+        if candidates == 0 {
+            return Err("No candidates");
+        }
+        if votes.iter().any(|v| v.candidates != candidates) {
+            return Err("Votes have different number of candidates");
+        }
+        let extensions: Vec<Vec<Vec<usize>>> =
+            votes.iter().map(PartialOrder::linear_extensions).collect();
+        let mut total: usize = 1;
+        for e in &extensions {
+            total = total
+                .checked_mul(e.len())
+                .filter(|&t| t <= max_completions)
+                .ok_or("Too many completions")?;
+        }
+
+        let seed: u64 = rng.gen();
+        let mut chosen = Vec::with_capacity(total);
+        let mut winners = Vec::with_capacity(total);
+        let mut current = vec![0; votes.len()];
+        let tied = vec![false; candidates - 1];
+        loop {
+            let mut profile = TiedOrdersComplete::new(candidates);
+            for (v, &i) in current.iter().enumerate() {
+                let vote = TiedRank::new(candidates, extensions[v][i].clone(), tied.clone());
+                profile.add(vote.as_ref());
+            }
+            let ranking = method.rank(&profile, &mut ChaCha8Rng::seed_from_u64(seed))?;
+            let mut w = ranking.as_ref().winners().to_vec();
+            w.sort();
+            winners.push(w);
+            chosen.push(current.clone());
+
+            // Step to the next completion, like counting with mixed radix.
+            let mut v = 0;
+            while v < current.len() {
+                current[v] += 1;
+                if current[v] < extensions[v].len() {
+                    break;
+                }
+                current[v] = 0;
+                v += 1;
+            }
+            if v == current.len() {
+                break;
+            }
+        }
+        Ok(Completions { extensions, chosen, winners, candidates })
+    }
+
+    // The probability of each candidate winning in a uniformly random
+    // completion which passes `filter`, with ties split equally. Also returns
+    // the number of such completions.
+    fn distribution<F: Fn(usize) -> bool>(&self, filter: F) -> (Vec<f64>, usize) {
+        let mut dist = vec![0.0; self.candidates];
+        let mut count = 0;
+        for (_, w) in self.winners.iter().enumerate().filter(|&(i, _)| filter(i)) {
+            count += 1;
+            for &c in w {
+                dist[c] += 1.0 / w.len() as f64;
+            }
+        }
+        if count != 0 {
+            dist.iter_mut().for_each(|p| *p /= count as f64);
+        }
+        (dist, count)
+    }
+}
+
+fn entropy(dist: &[f64]) -> f64 {
+    dist.iter().filter(|&&p| p > 0.0).map(|&p| -p * p.log2()).sum()
+}
+
+/// The candidates who win in at least one completion of `votes`.
+///
+/// Returns an error if there are more than `max_completions` completions.
+pub fn possible_winners<R: Rng>(
+    votes: &[PartialOrder],
+    method: &MethodConfig,
+    max_completions: usize,
+    rng: &mut R,
+) -> Result<Vec<usize>, &'static str> {
+    let completions = Completions::new(votes, method, max_completions, rng)?;
+    let mut res: Vec<usize> = completions.winners.concat();
+    res.sort();
+    res.dedup();
+    Ok(res)
+}
+
+/// The candidates who win in every completion of `votes`.
+///
+/// Returns an error if there are more than `max_completions` completions.
+pub fn necessary_winners<R: Rng>(
+    votes: &[PartialOrder],
+    method: &MethodConfig,
+    max_completions: usize,
+    rng: &mut R,
+) -> Result<Vec<usize>, &'static str> {
+    let completions = Completions::new(votes, method, max_completions, rng)?;
+    let res = (0..completions.candidates)
+        .filter(|c| completions.winners.iter().all(|w| w.binary_search(c).is_ok()))
+        .collect();
+    Ok(res)
+}
+
+/// Suggest the next question to ask, to learn the most about the winner.
+///
+/// Treating every completion of `votes` as equally likely, this picks the
+/// question which minimizes the expected entropy of the winner after it has
+/// been answered. Returns `None` if the winners are already known.
+///
+/// Returns an error if there are more than `max_completions` completions.
+pub fn next_question<R: Rng>(
+    votes: &[PartialOrder],
+    method: &MethodConfig,
+    max_completions: usize,
+    rng: &mut R,
+) -> Result<Option<Question>, &'static str> {
+    let completions = Completions::new(votes, method, max_completions, rng)?;
+    let first = &completions.winners[0];
+    if completions.winners.iter().all(|w| w == first) {
+        return Ok(None);
+    }
+
+    let total = completions.winners.len() as f64;
+    let mut best: Option<(f64, Question)> = None;
+    for (voter, vote) in votes.iter().enumerate() {
+        for a in 0..vote.candidates {
+            for b in (a + 1)..vote.candidates {
+                if vote.is_known(a, b) {
+                    continue;
+                }
+                let prefers_a = |i: usize| {
+                    let ext = &completions.extensions[voter][completions.chosen[i][voter]];
+                    ext.iter().position(|&c| c == a) < ext.iter().position(|&c| c == b)
+                };
+                let (yes, yes_count) = completions.distribution(prefers_a);
+                let (no, no_count) = completions.distribution(|i| !prefers_a(i));
+                let expected =
+                    (yes_count as f64 * entropy(&yes) + no_count as f64 * entropy(&no)) / total;
+                if !matches!(best, Some((e, _)) if e <= expected) {
+                    best = Some((expected, Question { voter, a, b }));
+                }
+            }
+        }
+    }
+    Ok(best.map(|(_, q)| q))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::TiePolicy;
+
+    #[test]
+    fn transitive() {
+        let mut order = PartialOrder::new(4);
+        order.add(0, 1).unwrap();
+        order.add(1, 2).unwrap();
+        assert!(order.prefers(0, 2));
+        assert!(order.add(2, 0).is_err());
+        assert_eq!(order.linear_extensions().len(), 4);
+        let matrix = "= > > ?\n< = > ?\n< < = ?\n? ? ? =";
+        assert_eq!(order.display_matrix().to_string(), matrix);
+    }
+
+    #[test]
+    fn winners() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut known = PartialOrder::new(3);
+        known.add(0, 1).unwrap();
+        known.add(1, 2).unwrap();
+        let mut votes = vec![known.clone(), known, PartialOrder::new(3)];
+        // 0 is first in two of three votes, so it always wins plurality.
+        let method = MethodConfig::Fptp(TiePolicy::FirstListed);
+        assert_eq!(necessary_winners(&votes, &method, 100, &mut rng).unwrap(), [0]);
+        assert_eq!(next_question(&votes, &method, 100, &mut rng).unwrap(), None);
+
+        votes[1] = PartialOrder::new(3);
+        assert_eq!(possible_winners(&votes, &method, 100, &mut rng).unwrap(), [0, 1, 2]);
+        assert!(necessary_winners(&votes, &method, 100, &mut rng).unwrap().is_empty());
+        assert!(possible_winners(&votes, &method, 10, &mut rng).is_err());
+    }
+
+    #[test]
+    fn question() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut first = PartialOrder::new(3);
+        first.add(0, 1).unwrap();
+        first.add(1, 2).unwrap();
+        let mut second = PartialOrder::new(3);
+        second.add(1, 0).unwrap();
+        second.add(2, 0).unwrap();
+        let mut third = PartialOrder::new(3);
+        third.add(0, 1).unwrap();
+        third.add(1, 2).unwrap();
+        // Only the order of 1 and 2 in the second vote is unknown, and it
+        // decides if Borda elects 0 or 1.
+        let votes = [first, second, third];
+        let q = next_question(&votes, &MethodConfig::Borda, 100, &mut rng).unwrap();
+        assert_eq!(q.map(|q| q.voter), Some(1));
+    }
+}
@@ -0,0 +1,458 @@
+//! Deterministic enumeration of every order of a few candidates.
+//!
+//! The number of orders grows very quickly: there are `m!` strict orders of
+//! `m` candidates and [`ordered_bell(m)`](ordered_bell) tied orders, e.g.
+//! 4683 for `m = 6`, so these are only useful for small `m`. Then every order,
+//! and every profile made from them, can be checked, which proves that a
+//! method satisfies a criterion for that number of candidates instead of only
+//! failing to find a counterexample.
+//!
+//! The orders are always enumerated in the same order, and the candidates in
+//! every tied group are sorted, like after [`TiedRank::normalize`].
+//!
+//! The position of an order in the enumeration is also its index, see
+//! [`strict_index`] and [`tied_index`]. A profile of complete orders can then
+//! be stored as one integer per vote, which is compact and quick to hash.
+
+use crate::formats::orders::{Rank, TiedRank, TiedRankRef};
+
+/// The most candidates an order can have and still be indexed by a `u64`.
+pub const MAX_INDEXED: usize = 18;
+
+/// The number of tied orders of `m` candidates, i.e. the `m`th ordered Bell
+/// number. It only fits in a `u64` for up to [`MAX_INDEXED`] candidates.
+///
+/// ```
+/// use votery::enumerate::ordered_bell;
+///
+/// let counts: Vec<u64> = (0..6).map(ordered_bell).collect();
+/// assert_eq!(counts, [1, 1, 3, 13, 75, 541]);
+/// ```
+pub fn ordered_bell(m: usize) -> u64 {
+    // a(n) = sum_{k=1}^{n} binomial(n, k) a(n - k)
+    let mut bell = vec![1u64; m + 1];
+    for n in 1..=m {
+        let mut binomial = 1u64;
+        let mut sum = 0;
+        for k in 1..=n {
+            binomial = binomial * (n - k + 1) as u64 / k as u64;
+            sum += binomial * bell[n - k];
+        }
+        bell[n] = sum;
+    }
+    bell[m]
+}
+
+/// Every strict order of `m` candidates, in lexicographic order.
+pub fn strict_orders(m: usize) -> StrictOrders {
+    StrictOrders { order: (0..m).collect(), done: false }
+}
+
+/// Every tied order ranking all of `m` candidates.
+pub fn tied_orders(m: usize) -> TiedOrders {
+    TiedOrders::of(m, (0..m).collect())
+}
+
+/// Every tied order ranking some of `m` candidates, including the empty one.
+/// The orders of fewer candidates come first.
+pub fn tied_orders_incomplete(m: usize) -> IncompleteTiedOrders {
+    IncompleteTiedOrders {
+        candidates: m,
+        ranked: 0,
+        subsets: Vec::new().into_iter(),
+        current: None,
+    }
+}
+
+/// See [`strict_orders`].
+pub struct StrictOrders {
+    order: Vec<usize>,
+    done: bool,
+}
+
+impl Iterator for StrictOrders {
+    type Item = Rank;
+
+    fn next(&mut self) -> Option<Rank> {
+        if self.done {
+            return None;
+        }
+        let rank = Rank::new(self.order.len(), self.order.clone());
+        self.done = !next_permutation(&mut self.order);
+        Some(rank)
+    }
+}
+
+/// See [`tied_orders`].
+pub struct TiedOrders {
+    candidates: usize,
+    // The candidates to rank, sorted.
+    elements: Vec<usize>,
+    // The tied groups form a set partition, where `blocks[i]` is the block of
+    // `elements[i]`, written as a restricted growth string: every block is
+    // at most one larger than the blocks before it.
+    blocks: Vec<usize>,
+    // The order of the blocks.
+    permutation: Vec<usize>,
+    done: bool,
+}
+
+impl TiedOrders {
+    fn of(candidates: usize, elements: Vec<usize>) -> Self {
+        let blocks = vec![0; elements.len()];
+        let permutation = if elements.is_empty() { Vec::new() } else { vec![0] };
+        TiedOrders { candidates, elements, blocks, permutation, done: false }
+    }
+
+    fn current(&self) -> TiedRank {
+        to_tied_rank(self.candidates, &self.elements, &self.blocks, &self.permutation)
+    }
+
+    // Move to the next set partition, returning false if this was the last.
+    fn next_partition(&mut self) -> bool {
+        for i in (1..self.blocks.len()).rev() {
+            let max = self.blocks[..i].iter().copied().max().unwrap();
+            if self.blocks[i] <= max {
+                self.blocks[i] += 1;
+                self.blocks[i + 1..].fill(0);
+                let count = self.blocks.iter().copied().max().unwrap() + 1;
+                self.permutation = (0..count).collect();
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for TiedOrders {
+    type Item = TiedRank;
+
+    fn next(&mut self) -> Option<TiedRank> {
+        if self.done {
+            return None;
+        }
+        let rank = self.current();
+        self.done = !next_permutation(&mut self.permutation) && !self.next_partition();
+        Some(rank)
+    }
+}
+
+/// See [`tied_orders_incomplete`].
+pub struct IncompleteTiedOrders {
+    candidates: usize,
+    // How many candidates are ranked by the orders of `subsets`.
+    ranked: usize,
+    subsets: std::vec::IntoIter<Vec<usize>>,
+    current: Option<TiedOrders>,
+}
+
+impl Iterator for IncompleteTiedOrders {
+    type Item = TiedRank;
+
+    fn next(&mut self) -> Option<TiedRank> {
+        loop {
+            if let Some(rank) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(rank);
+            }
+            match self.subsets.next() {
+                Some(subset) => self.current = Some(TiedOrders::of(self.candidates, subset)),
+                None if self.ranked <= self.candidates => {
+                    self.subsets = subsets(self.candidates, self.ranked).into_iter();
+                    self.ranked += 1;
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+/// The index of the strict order `order` of every candidate, i.e. its
+/// position in [`strict_orders`], computed from its Lehmer code.
+///
+/// Panics if there are more than [`MAX_INDEXED`] candidates.
+///
+/// ```
+/// use votery::enumerate::{strict_from_index, strict_index};
+///
+/// assert_eq!(strict_index(&[0, 1, 2]), 0);
+/// assert_eq!(strict_index(&[2, 1, 0]), 5);
+/// let order = strict_from_index(3, 3).unwrap();
+/// assert_eq!(strict_index(order.as_ref().order()), 3);
+/// ```
+pub fn strict_index(order: &[usize]) -> u64 {
+    let m = order.len();
+    assert!(m <= MAX_INDEXED, "Too many candidates to index");
+    let mut index = 0;
+    for (i, &c) in order.iter().enumerate() {
+        let smaller_after = order[i + 1..].iter().filter(|&&d| d < c).count() as u64;
+        index = index * (m - i) as u64 + smaller_after;
+    }
+    index
+}
+
+/// The strict order of `m` candidates with index `index`, see
+/// [`strict_index`].
+pub fn strict_from_index(m: usize, index: u64) -> Result<Rank, &'static str> {
+    if m > MAX_INDEXED {
+        return Err("Too many candidates to index");
+    }
+    let order = permutation_from_index(m, index).ok_or("Index of order is too large")?;
+    Ok(Rank::new(m, order))
+}
+
+/// The index of the tied order `order` of every candidate, i.e. its position
+/// in [`tied_orders`]. It's smaller than [`ordered_bell(m)`](ordered_bell)
+/// for `m` candidates.
+///
+/// Panics if `order` doesn't rank every candidate, or if there are more than
+/// [`MAX_INDEXED`] candidates.
+///
+/// ```
+/// use votery::{
+///     enumerate::{tied_from_index, tied_index},
+///     formats::orders::TiedRank,
+/// };
+///
+/// let order = TiedRank::parse_vote(3, "2,{0,1}").unwrap();
+/// let index = tied_index(order.as_ref());
+/// assert_eq!(tied_from_index(3, index).unwrap(), order);
+/// ```
+pub fn tied_index(order: TiedRankRef) -> u64 {
+    let m = order.candidates;
+    assert!(m <= MAX_INDEXED, "Too many candidates to index");
+    assert!(order.len() == m, "Only complete orders have an index");
+    if m == 0 {
+        return 0;
+    }
+    // The blocks are numbered by their smallest candidates, so
+    // `block_of_group` lists the blocks in the order they are ranked.
+    let groups: Vec<&[usize]> = order.iter_groups().collect();
+    let mut group_of = vec![0; m];
+    for (g, group) in groups.iter().enumerate() {
+        for &c in group.iter() {
+            group_of[c] = g;
+        }
+    }
+    let mut block_of_group = vec![usize::MAX; groups.len()];
+    let mut blocks = Vec::with_capacity(m);
+    let mut count = 0;
+    for &g in &group_of {
+        if block_of_group[g] == usize::MAX {
+            block_of_group[g] = count;
+            count += 1;
+        }
+        blocks.push(block_of_group[g]);
+    }
+
+    let weights = completions(m);
+    let mut index = 0;
+    let mut used = 1;
+    for (i, &b) in blocks.iter().enumerate().skip(1) {
+        index += b as u64 * weights[m - i - 1][used];
+        used = used.max(b + 1);
+    }
+    index + strict_index(&block_of_group)
+}
+
+/// The tied order of `m` candidates with index `index`, see [`tied_index`].
+pub fn tied_from_index(m: usize, mut index: u64) -> Result<TiedRank, &'static str> {
+    if m > MAX_INDEXED {
+        return Err("Too many candidates to index");
+    }
+    if index >= ordered_bell(m) {
+        return Err("Index of order is too large");
+    }
+    if m == 0 {
+        return Ok(TiedRank::new(0, Vec::new(), Vec::new()));
+    }
+    let weights = completions(m);
+    let mut blocks = vec![0; m];
+    let mut used = 1;
+    for i in 1..m {
+        // Every existing block leads to `weight` orders, and a new block to
+        // the rest.
+        let weight = weights[m - i - 1][used];
+        let existing = used as u64 * weight;
+        if index < existing {
+            blocks[i] = (index / weight) as usize;
+            index %= weight;
+        } else {
+            index -= existing;
+            blocks[i] = used;
+            used += 1;
+        }
+    }
+    let permutation = permutation_from_index(used, index).unwrap();
+    let elements: Vec<usize> = (0..m).collect();
+    Ok(to_tied_rank(m, &elements, &blocks, &permutation))
+}
+
+// `completions(m)[r][j]` is the number of tied orders with a set partition
+// starting with some `j` blocks and `r` more elements to place, i.e. the sum
+// of `k!` over every way to finish it with `k` blocks.
+fn completions(m: usize) -> Vec<Vec<u64>> {
+    let mut w = vec![vec![0u64; m + 2]; m];
+    let mut factorial = 1u64;
+    for (j, w) in w[0].iter_mut().enumerate().take(m + 1) {
+        if j > 0 {
+            factorial *= j as u64;
+        }
+        *w = factorial;
+    }
+    for r in 1..m {
+        for j in 1..=(m - r) {
+            w[r][j] = j as u64 * w[r - 1][j] + w[r - 1][j + 1];
+        }
+    }
+    w
+}
+
+// The tied order of `elements`, split into the blocks `blocks` and ranked in
+// the order `permutation`.
+fn to_tied_rank(
+    candidates: usize,
+    elements: &[usize],
+    blocks: &[usize],
+    permutation: &[usize],
+) -> TiedRank {
+    let mut order = Vec::with_capacity(elements.len());
+    let mut tied = Vec::with_capacity(elements.len().saturating_sub(1));
+    for &block in permutation {
+        let group = elements.iter().zip(blocks).filter(|(_, &b)| b == block);
+        for (i, (&e, _)) in group.enumerate() {
+            if !order.is_empty() {
+                tied.push(i != 0);
+            }
+            order.push(e);
+        }
+    }
+    TiedRank::new(candidates, order, tied)
+}
+
+// The permutation of `0..m` with the lexicographic index `index`, or `None`
+// if there are fewer than `index + 1` permutations.
+fn permutation_from_index(m: usize, mut index: u64) -> Option<Vec<usize>> {
+    let mut factorials = vec![1u64; m + 1];
+    for i in 1..=m {
+        factorials[i] = factorials[i - 1] * i as u64;
+    }
+    if index >= factorials[m] {
+        return None;
+    }
+    let mut left: Vec<usize> = (0..m).collect();
+    let mut order = Vec::with_capacity(m);
+    for i in (0..m).rev() {
+        let digit = (index / factorials[i]) as usize;
+        index %= factorials[i];
+        order.push(left.remove(digit));
+    }
+    Some(order)
+}
+
+// Every subset of `0..n` with `k` elements, in lexicographic order.
+fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut all = Vec::new();
+    let mut subset: Vec<usize> = (0..k).collect();
+    loop {
+        all.push(subset.clone());
+        // Find the last element which can be increased.
+        let Some(i) = (0..k).rev().find(|&i| subset[i] < n - k + i) else {
+            return all;
+        };
+        subset[i] += 1;
+        for j in i + 1..k {
+            subset[j] = subset[j - 1] + 1;
+        }
+    }
+}
+
+// Rearrange `v` into the next permutation in lexicographic order, returning
+// false if it was already the last one.
+fn next_permutation(v: &mut [usize]) -> bool {
+    let Some(i) = (1..v.len()).rev().find(|&i| v[i - 1] < v[i]) else {
+        return false;
+    };
+    let j = (i..v.len()).rev().find(|&j| v[i - 1] < v[j]).unwrap();
+    v.swap(i - 1, j);
+    v[i..].reverse();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn counts() {
+        for m in 0..6 {
+            let factorial: usize = (1..=m).product();
+            assert_eq!(strict_orders(m).count(), factorial);
+            assert_eq!(tied_orders(m).count() as u64, ordered_bell(m));
+        }
+        // The number of tied orders of every subset.
+        let incomplete: Vec<usize> = (0..5).map(|m| tied_orders_incomplete(m).count()).collect();
+        assert_eq!(incomplete, [1, 2, 6, 26, 150]);
+    }
+
+    #[test]
+    fn distinct_and_normalized() {
+        let orders: Vec<TiedRank> = tied_orders_incomplete(4).collect();
+        let unique: HashSet<String> = orders.iter().map(|o| o.as_ref().to_string()).collect();
+        assert_eq!(unique.len(), orders.len());
+        for order in orders {
+            let mut normalized = order.clone();
+            normalized.normalize();
+            assert_eq!(order, normalized);
+        }
+        let strict: Vec<String> = strict_orders(3).map(|r| format!("{:?}", r)).collect();
+        assert_eq!(strict.len(), strict.iter().collect::<HashSet<_>>().len());
+    }
+
+    #[test]
+    fn small_orders() {
+        let orders: Vec<String> = tied_orders(2).map(|o| o.as_ref().to_string()).collect();
+        assert_eq!(orders, ["{0,1}", "0,1", "1,0"]);
+        let incomplete: Vec<String> =
+            tied_orders_incomplete(2).map(|o| o.as_ref().to_string()).collect();
+        assert_eq!(incomplete, ["", "0", "1", "{0,1}", "0,1", "1,0"]);
+        let strict: Vec<Vec<usize>> =
+            strict_orders(3).map(|r| r.as_ref().order().to_vec()).collect();
+        assert_eq!(strict[..3], [vec![0, 1, 2], vec![0, 2, 1], vec![1, 0, 2]]);
+    }
+
+    #[test]
+    fn indices_follow_enumeration() {
+        for m in 0..6 {
+            for (i, order) in strict_orders(m).enumerate() {
+                assert_eq!(strict_index(order.as_ref().order()), i as u64);
+                assert_eq!(strict_from_index(m, i as u64).unwrap(), order);
+            }
+            for (i, order) in tied_orders(m).enumerate() {
+                assert_eq!(tied_index(order.as_ref()), i as u64);
+                assert_eq!(tied_from_index(m, i as u64).unwrap(), order);
+            }
+            assert!(tied_from_index(m, ordered_bell(m)).is_err());
+        }
+        assert!(strict_from_index(3, 6).is_err());
+        let m = MAX_INDEXED;
+        let last = tied_from_index(m, ordered_bell(m) - 1).unwrap();
+        assert_eq!(tied_index(last.as_ref()), ordered_bell(m) - 1);
+    }
+
+    #[quickcheck]
+    fn unnormalized_index(order: TiedRank) -> bool {
+        // The order inside of tied groups doesn't matter.
+        if order.candidates > MAX_INDEXED {
+            return true;
+        }
+        let mut complete = order.clone();
+        complete.make_complete(false);
+        let mut normalized = complete.clone();
+        normalized.normalize();
+        let index = tied_index(complete.as_ref());
+        index == tied_index(normalized.as_ref())
+            && tied_from_index(complete.candidates, index).unwrap() == normalized
+    }
+}
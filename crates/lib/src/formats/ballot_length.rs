@@ -0,0 +1,108 @@
+use rand::{
+    distributions::{Bernoulli, Uniform, WeightedIndex},
+    prelude::Distribution,
+    Rng,
+};
+
+/// How many candidates a generated incomplete vote ranks, used by
+/// [`StrictOrdersIncomplete::generate_with_length`](super::soi::StrictOrdersIncomplete::generate_with_length)
+/// and
+/// [`TiedOrdersIncomplete::generate_with_length`](super::toi::TiedOrdersIncomplete::generate_with_length).
+/// Every vote ranks at least one candidate.
+#[derive(Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BallotLength {
+    /// Every length between 1 and the number of candidates is equally likely.
+    #[default]
+    Uniform,
+    /// After ranking a candidate, a voter continues with another one with the
+    /// given probability, until every candidate is ranked. Models voters who
+    /// tire of ranking.
+    Geometric(f64),
+    /// `weights[i]` is the relative probability of ranking `i + 1` candidates,
+    /// e.g. the lengths observed in real elections. Lengths longer than the
+    /// number of candidates rank every candidate.
+    Empirical(Vec<f64>),
+}
+
+impl BallotLength {
+    pub(crate) fn sampler(&self, candidates: usize) -> Result<LengthSampler, &'static str> {
+        debug_assert!(candidates != 0);
+        Ok(match self {
+            BallotLength::Uniform => LengthSampler::Uniform(Uniform::from(0..candidates)),
+            BallotLength::Geometric(p) => LengthSampler::Geometric(
+                Bernoulli::new(*p).or(Err("Probability has to be between 0 and 1"))?,
+                candidates,
+            ),
+            BallotLength::Empirical(weights) => LengthSampler::Empirical(
+                WeightedIndex::new(weights).or(Err("Invalid ballot length weights"))?,
+                candidates,
+            ),
+        })
+    }
+}
+
+pub(crate) enum LengthSampler {
+    Uniform(Uniform<usize>),
+    Geometric(Bernoulli, usize),
+    Empirical(WeightedIndex<f64>, usize),
+}
+
+impl Distribution<usize> for LengthSampler {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match self {
+            LengthSampler::Uniform(range) => range.sample(rng) + 1,
+            LengthSampler::Geometric(next, candidates) => {
+                let mut len = 1;
+                while len < *candidates && next.sample(rng) {
+                    len += 1;
+                }
+                len
+            }
+            LengthSampler::Empirical(weights, candidates) => {
+                (weights.sample(rng) + 1).min(*candidates)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::formats::{soi::StrictOrdersIncomplete, toi::TiedOrdersIncomplete};
+
+    fn lengths(votes: &TiedOrdersIncomplete) -> Vec<usize> {
+        votes.into_iter().map(|v| v.len()).collect()
+    }
+
+    #[test]
+    fn geometric() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.generate_with_length(&mut rng, 5, &BallotLength::Geometric(0.0)).unwrap();
+        votes.generate_with_length(&mut rng, 5, &BallotLength::Geometric(1.0)).unwrap();
+        assert_eq!(lengths(&votes), [1, 1, 1, 1, 1, 4, 4, 4, 4, 4]);
+        assert!(votes.generate_with_length(&mut rng, 5, &BallotLength::Geometric(1.5)).is_err());
+
+        // Most voters stop early.
+        let mut votes = TiedOrdersIncomplete::new(10);
+        votes.generate_with_length(&mut rng, 1000, &BallotLength::Geometric(0.5)).unwrap();
+        let short = lengths(&votes).iter().filter(|&&l| l <= 2).count();
+        assert!((700..800).contains(&short));
+    }
+
+    #[test]
+    fn empirical() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut votes = StrictOrdersIncomplete::new(3);
+        let length = BallotLength::Empirical(vec![0.0, 1.0, 0.0, 0.0, 2.0]);
+        votes.generate_with_length(&mut rng, 20, &length).unwrap();
+        assert!(votes.into_iter().all(|v| v.len() == 2 || v.len() == 3));
+        let invalid = BallotLength::Empirical(vec![0.0, 0.0]);
+        assert!(votes.generate_with_length(&mut rng, 5, &invalid).is_err());
+        assert!(votes.generate_with_length(&mut rng, 5, &BallotLength::Empirical(vec![])).is_err());
+    }
+}
@@ -2,16 +2,20 @@ use std::{
     cmp::Ordering,
     fmt::{self, Display},
     io::BufRead,
-    slice::{Windows, Chunks},
+    slice::{Chunks, Windows},
 };
 
-use rand::distributions::{Distribution, Uniform};
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng,
+};
 
 use super::{
     orders::{TiedRank, TiedRankRef},
     remove_newline,
     toc::TiedOrdersComplete,
     toi::TiedOrdersIncomplete,
+    view::new_indices,
     Binary, VoteFormat,
 };
 use crate::pairwise_lt;
@@ -25,15 +29,44 @@ pub struct Cardinal {
     pub max: usize,
 }
 
+serde_checked!(Cardinal {
+    votes: Vec<usize>,
+    candidates: usize,
+    voters: usize,
+    min: usize,
+    max: usize,
+});
+
 impl Cardinal {
     pub fn new(candidates: usize, min: usize, max: usize) -> Cardinal {
         debug_assert!(min <= max);
         Cardinal { votes: Vec::new(), candidates, voters: 0, min, max }
     }
 
+    /// The total score of every candidate. Returns an error if the sum could
+    /// overflow.
+    ///
+    /// Whole votes are added at a time, so the compiler can vectorize the
+    /// inner loop.
+    pub fn column_sums(&self) -> Result<Vec<usize>, &'static str> {
+        let mut sums = vec![0; self.candidates];
+        if self.candidates == 0 {
+            return Ok(sums);
+        }
+        // If this doesn't overflow, then no sum can overflow.
+        self.voters.checked_mul(self.max).ok_or("Integer overflow: Too high scores")?;
+        for vote in self.votes.chunks_exact(self.candidates) {
+            for (s, &v) in sums.iter_mut().zip(vote) {
+                *s += v;
+            }
+        }
+        Ok(sums)
+    }
+
     pub(crate) fn valid(&self) -> bool {
         if self.candidates == 0 && (self.voters != 0 || !self.votes.is_empty())
-            || self.votes.len() != self.voters * self.candidates
+            || self.voters.checked_mul(self.candidates) != Some(self.votes.len())
+            || self.min > self.max
         {
             return false;
         }
@@ -192,6 +225,24 @@ impl Cardinal {
         self.votes.chunks(self.candidates)
     }
 
+    /// A copy of the votes with only the candidates in `keep`, indexed like
+    /// in [`ProfileView`](super::view::ProfileView). Takes time linear in the
+    /// size of the votes, unlike removing every other candidate one at a time.
+    pub fn restrict(&self, keep: &[usize]) -> Result<Self, &'static str> {
+        if keep.is_empty() {
+            return Err("Can not remove the last candidate");
+        }
+        let new_index = new_indices(self.candidates, keep)?;
+        let kept: Vec<usize> = (0..self.candidates).filter(|&c| new_index[c].is_some()).collect();
+        let mut votes = Vec::with_capacity(self.voters * kept.len());
+        for vote in self.votes.chunks_exact(self.candidates) {
+            votes.extend(kept.iter().map(|&c| vote[c]));
+        }
+        let restricted = Cardinal { votes, candidates: kept.len(), ..*self };
+        debug_assert!(restricted.valid());
+        Ok(restricted)
+    }
+
     /// Fill the given preference matrix for the candidates listed in `keep`.
     ///
     /// The middle row in the matrix will always be zero
@@ -247,6 +298,61 @@ impl Cardinal {
     }
 }
 
+/// How far apart the utilities of consecutive positions in a ranking are
+/// assumed to be, when converting rankings to cardinal votes with
+/// [`TiedOrdersIncomplete::to_cardinal_spaced`].
+///
+/// The first position always has utility 1.0 and the last 0.0.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spacing {
+    /// Every position is equally far from the next one.
+    #[default]
+    Equal,
+    /// The gap after every position is the given ratio times the gap before
+    /// it. With a ratio below 1.0 the voter cares most about the top of the
+    /// ranking, and with 1.0 it's the same as `Equal`.
+    Geometric(f64),
+    /// Random utilities for every vote, drawn uniformly and then sorted so
+    /// they agree with the ranking.
+    Random,
+}
+
+impl Spacing {
+    /// The utility of every position in a ranking of `candidates`.
+    pub(crate) fn utilities<R: Rng>(
+        self,
+        candidates: usize,
+        rng: &mut R,
+    ) -> Result<Vec<f64>, &'static str> {
+        if candidates <= 1 {
+            return Ok(vec![1.0; candidates]);
+        }
+        let last = (candidates - 1) as f64;
+        Ok(match self {
+            Spacing::Equal => (0..candidates).map(|p| 1.0 - p as f64 / last).collect(),
+            Spacing::Geometric(ratio) => {
+                if !(ratio.is_finite() && ratio > 0.0) {
+                    return Err("Spacing ratio has to be positive");
+                }
+                let mut gaps = 0.0;
+                let mut distance = vec![0.0];
+                for p in 0..(candidates - 1) {
+                    gaps += ratio.powi(p as i32);
+                    distance.push(gaps);
+                }
+                distance.iter().map(|d| 1.0 - d / gaps).collect()
+            }
+            Spacing::Random => {
+                let mut u: Vec<f64> = vec![1.0, 0.0];
+                u.extend((0..(candidates - 2)).map(|_| rng.gen::<f64>()));
+                u.sort_by(|a, b| b.total_cmp(a));
+                u
+            }
+        })
+    }
+}
+
 impl Display for Cardinal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for i in 0..self.voters {
@@ -366,4 +472,43 @@ mod tests {
             Err(_) => true,
         }
     }
+
+    #[quickcheck]
+    fn column_sums_naive(votes: Cardinal) -> bool {
+        let c = votes.candidates;
+        let sums: Vec<usize> =
+            (0..c).map(|j| (0..votes.voters).map(|i| votes.votes[i * c + j]).sum()).collect();
+        votes.column_sums() == Ok(sums)
+    }
+
+    #[test]
+    fn restrict() {
+        let mut votes = Cardinal::new(3, 0, 5);
+        votes.add(&[1, 5, 2]).unwrap();
+        votes.add(&[0, 3, 4]).unwrap();
+        let restricted = votes.restrict(&[2, 0]).unwrap();
+        assert_eq!(restricted.votes, [1, 2, 0, 4]);
+        assert_eq!((restricted.min, restricted.max), (0, 5));
+        assert!(votes.restrict(&[]).is_err());
+        assert!(votes.restrict(&[0, 0]).is_err());
+        assert!(votes.restrict(&[3]).is_err());
+    }
+
+    #[quickcheck]
+    fn restrict_naive(votes: Cardinal, keep: Vec<bool>) -> bool {
+        let c = votes.candidates;
+        let keep: Vec<usize> = (0..c).filter(|&j| keep.get(j).copied().unwrap_or(true)).collect();
+        let (keep, original) = (&keep, &votes.votes);
+        match votes.restrict(keep) {
+            Ok(restricted) => {
+                let naive: Vec<usize> = (0..votes.voters)
+                    .flat_map(|i| keep.iter().map(move |&j| original[i * c + j]))
+                    .collect();
+                restricted.candidates == keep.len()
+                    && restricted.voters == votes.voters
+                    && restricted.votes == naive
+            }
+            Err(_) => keep.is_empty(),
+        }
+    }
 }
@@ -0,0 +1,140 @@
+use super::orders::TiedRankRef;
+
+/// How the votes ranked two candidates, `a` and `b`, relative to each other,
+/// see [`CoRanking`]. Every vote is counted in exactly one field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoRank {
+    /// Both are ranked and `a` is ranked above `b`.
+    pub above: usize,
+    /// Both are ranked and `b` is ranked above `a`.
+    pub below: usize,
+    /// Both are ranked and tied.
+    pub tied: usize,
+    /// Only `a` is ranked.
+    pub only_a: usize,
+    /// Only `b` is ranked.
+    pub only_b: usize,
+    /// Neither is ranked.
+    pub neither: usize,
+}
+
+impl CoRank {
+    /// The number of votes which rank both candidates.
+    pub fn both_ranked(&self) -> usize {
+        self.above + self.below + self.tied
+    }
+
+    /// The same counts, but from the view of `b`.
+    pub fn reversed(&self) -> CoRank {
+        CoRank {
+            above: self.below,
+            below: self.above,
+            tied: self.tied,
+            only_a: self.only_b,
+            only_b: self.only_a,
+            neither: self.neither,
+        }
+    }
+}
+
+/// How every pair of candidates was ranked relative to each other, counted
+/// from a list of ranked votes.
+///
+/// Unlike a pairwise matrix, it keeps apart candidates which are ranked below
+/// each other and candidates which a vote doesn't rank at all, so it shows how
+/// incomplete the votes are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoRanking {
+    // Has length candidates * candidates, where element `a * candidates + b`
+    // counts the pair `(a, b)`.
+    pairs: Vec<CoRank>,
+    candidates: usize,
+    voters: usize,
+}
+
+impl CoRanking {
+    pub(crate) fn count<'a, I>(candidates: usize, votes: I) -> Self
+    where
+        I: IntoIterator<Item = TiedRankRef<'a>>,
+    {
+        let mut pairs = vec![CoRank::default(); candidates * candidates];
+        let mut voters = 0;
+        // The group of every candidate in the current vote
+        let mut groups: Vec<Option<usize>> = vec![None; candidates];
+        for vote in votes {
+            groups.iter_mut().for_each(|g| *g = None);
+            for (i, group) in vote.iter_groups().enumerate() {
+                for &c in group {
+                    groups[c] = Some(i);
+                }
+            }
+            for (a, &ga) in groups.iter().enumerate() {
+                for (b, &gb) in groups.iter().enumerate() {
+                    let pair = &mut pairs[a * candidates + b];
+                    match (ga, gb) {
+                        (Some(x), Some(y)) if x < y => pair.above += 1,
+                        (Some(x), Some(y)) if x > y => pair.below += 1,
+                        (Some(_), Some(_)) => pair.tied += 1,
+                        (Some(_), None) => pair.only_a += 1,
+                        (None, Some(_)) => pair.only_b += 1,
+                        (None, None) => pair.neither += 1,
+                    }
+                }
+            }
+            voters += 1;
+        }
+        CoRanking { pairs, candidates, voters }
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    /// The number of counted votes.
+    pub fn voters(&self) -> usize {
+        self.voters
+    }
+
+    /// How the votes ranked `a` relative to `b`.
+    pub fn get(&self, a: usize, b: usize) -> CoRank {
+        assert!(a < self.candidates && b < self.candidates);
+        self.pairs[a * self.candidates + b]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use crate::formats::{toi::TiedOrdersIncomplete, VoteFormat};
+
+    #[test]
+    fn incomplete() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str("0,{1,2}");
+        votes.add_from_str("1");
+        votes.add_from_str("2,0,1,3");
+        let co = votes.co_ranking_matrix();
+        assert_eq!(co.voters(), 3);
+        let pair = co.get(0, 1);
+        assert_eq!((pair.above, pair.below, pair.only_b), (2, 0, 1));
+        assert_eq!(co.get(1, 2).tied, 1);
+        assert_eq!(co.get(3, 0).only_b, 1);
+        assert_eq!(co.get(3, 3).neither, 2);
+    }
+
+    #[quickcheck]
+    fn consistent(votes: TiedOrdersIncomplete) -> bool {
+        let co = votes.co_ranking_matrix();
+        let n = votes.candidates();
+        (0..n).all(|a| {
+            (0..n).all(|b| {
+                let pair = co.get(a, b);
+                let total = pair.both_ranked() + pair.only_a + pair.only_b + pair.neither;
+                total == votes.voters() && co.get(b, a) == pair.reversed()
+            })
+        })
+    }
+}
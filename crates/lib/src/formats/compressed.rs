@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+
+use rand::{distributions::Uniform, prelude::Distribution, Rng};
+
+use super::{
+    orders::{TiedRank, TiedRankRef},
+    toc::TiedOrdersComplete,
+    toi::TiedOrdersIncomplete,
+    VoteFormat,
+};
+use crate::{
+    enumerate::{ordered_bell, tied_from_index, tied_index, MAX_INDEXED},
+    methods::pairwise::PairwiseMatrix,
+};
+
+// With at most this many candidates, every index fits in a `u32`.
+const MAX_SMALL: usize = 11;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Indices {
+    Small(Vec<u32>),
+    Large(Vec<u64>),
+}
+
+/// Complete orders with ties, like [`TiedOrdersComplete`], where every vote
+/// is stored as its index, see [`tied_index`].
+///
+/// Every vote takes 4 bytes with up to 11 candidates and 8 bytes with up to
+/// [`MAX_INDEXED`], instead of about 9 bytes per candidate. The votes are
+/// decoded when they are counted, and identical votes are only decoded once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedDense {
+    indices: Indices,
+    candidates: usize,
+}
+
+serde_checked!(CompressedDense { indices: Indices, candidates: usize });
+
+impl CompressedDense {
+    /// Returns an error if there are more than [`MAX_INDEXED`] candidates.
+    pub fn new(candidates: usize) -> Result<Self, &'static str> {
+        if candidates == 0 {
+            return Err("Need at least one candidate");
+        }
+        if candidates > MAX_INDEXED {
+            return Err("Too many candidates to index");
+        }
+        let indices = if candidates <= MAX_SMALL {
+            Indices::Small(Vec::new())
+        } else {
+            Indices::Large(Vec::new())
+        };
+        Ok(CompressedDense { indices, candidates })
+    }
+
+    // Returns true if every index is a vote, stored in the right size.
+    pub(crate) fn valid(&self) -> bool {
+        let small = matches!(self.indices, Indices::Small(_));
+        (1..=MAX_INDEXED).contains(&self.candidates)
+            && small == (self.candidates <= MAX_SMALL)
+            && self.indices().all(|i| i < ordered_bell(self.candidates))
+    }
+
+    pub fn voters(&self) -> usize {
+        match &self.indices {
+            Indices::Small(v) => v.len(),
+            Indices::Large(v) => v.len(),
+        }
+    }
+
+    /// The index of every vote.
+    pub fn indices(&self) -> impl Iterator<Item = u64> + '_ {
+        let (small, large) = match &self.indices {
+            Indices::Small(v) => (&v[..], &[][..]),
+            Indices::Large(v) => (&[][..], &v[..]),
+        };
+        small.iter().map(|&i| i as u64).chain(large.iter().copied())
+    }
+
+    fn push(&mut self, index: u64) {
+        debug_assert!(index < ordered_bell(self.candidates));
+        match &mut self.indices {
+            Indices::Small(v) => v.push(index as u32),
+            Indices::Large(v) => v.push(index),
+        }
+    }
+
+    /// Every vote, decoded one at a time.
+    pub fn iter(&self) -> impl Iterator<Item = TiedRank> + '_ {
+        self.indices().map(|i| tied_from_index(self.candidates, i).unwrap())
+    }
+
+    /// Every distinct vote, decoded once, with how many voters cast it. The
+    /// votes are ordered by their indices.
+    pub fn tally(&self) -> Vec<(TiedRank, usize)> {
+        let mut counts = BTreeMap::new();
+        for i in self.indices() {
+            *counts.entry(i).or_insert(0) += 1;
+        }
+        counts.into_iter().map(|(i, n)| (tied_from_index(self.candidates, i).unwrap(), n)).collect()
+    }
+
+    /// How many voters prefer every candidate over every other candidate.
+    pub fn pairwise_matrix(&self) -> Result<PairwiseMatrix, &'static str> {
+        let tally = self.tally();
+        let votes = tally.iter().flat_map(|(v, n)| std::iter::repeat_n(v.as_ref(), *n));
+        PairwiseMatrix::from_votes(self.candidates, votes)
+    }
+
+    /// Decode every vote, e.g. to count it with a method which needs a
+    /// [`TiedOrdersComplete`].
+    pub fn to_toc(&self) -> TiedOrdersComplete {
+        let mut toc = TiedOrdersComplete::new(self.candidates);
+        for vote in self.iter() {
+            toc.add(vote.as_ref());
+        }
+        toc
+    }
+
+    /// Encode every vote of `votes`.
+    pub fn from_toc(votes: &TiedOrdersComplete) -> Result<Self, &'static str> {
+        let mut compressed = CompressedDense::new(votes.candidates)?;
+        for vote in votes {
+            compressed.push(tied_index(vote));
+        }
+        Ok(compressed)
+    }
+}
+
+impl<'a> VoteFormat<'a> for CompressedDense {
+    type Vote = TiedRankRef<'a>;
+    fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn add(&mut self, v: Self::Vote) -> Result<(), &'static str> {
+        if v.candidates != self.candidates || v.len() != self.candidates {
+            return Err("Vote must rank every candidate");
+        }
+        self.push(tied_index(v));
+        Ok(())
+    }
+
+    fn remove_candidate(&mut self, target: usize) -> Result<(), &'static str> {
+        let mut toc = self.to_toc();
+        toc.remove_candidate(target)?;
+        *self = CompressedDense::from_toc(&toc)?;
+        Ok(())
+    }
+
+    /// Add `new_voters` votes, each drawn uniformly from every complete order
+    /// with ties.
+    fn generate_uniform<R: Rng>(&mut self, rng: &mut R, new_voters: usize) {
+        let dist = Uniform::new(0, ordered_bell(self.candidates));
+        for _ in 0..new_voters {
+            self.push(dist.sample(rng));
+        }
+    }
+
+    fn to_partial_ranking(self) -> TiedOrdersIncomplete {
+        self.to_toc().to_toi().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::{Arbitrary, Gen};
+
+    use super::*;
+    use crate::formats::tests::std_rng;
+
+    impl Arbitrary for CompressedDense {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let (voters, candidates): (usize, usize) = Arbitrary::arbitrary(g);
+            let voters = voters % g.size();
+            let candidates = candidates % MAX_INDEXED + 1;
+            let mut votes = CompressedDense::new(candidates).unwrap();
+            votes.generate_uniform(&mut std_rng(g), voters);
+            votes
+        }
+    }
+
+    #[test]
+    fn tally() {
+        let mut toc = TiedOrdersComplete::new(3);
+        for s in ["2,{0,1}", "0,1,2", "2,{1,0}", "0,1,2", "0,1,2"] {
+            assert!(toc.add_from_str(s));
+        }
+        let votes = CompressedDense::from_toc(&toc).unwrap();
+        assert_eq!(votes.voters(), 5);
+        let tally: Vec<(String, usize)> =
+            votes.tally().into_iter().map(|(v, n)| (v.as_ref().to_string(), n)).collect();
+        assert_eq!(tally, [("2,{0,1}".to_string(), 2), ("0,1,2".to_string(), 3)]);
+        assert!(CompressedDense::new(MAX_INDEXED + 1).is_err());
+        let large = CompressedDense::new(MAX_SMALL + 1).unwrap();
+        assert!(matches!(large.indices, Indices::Large(_)));
+    }
+
+    #[quickcheck]
+    fn same_as_toc(votes: CompressedDense) -> bool {
+        let toc = votes.to_toc();
+        CompressedDense::from_toc(&toc).unwrap() == votes
+            && votes.pairwise_matrix().unwrap() == toc.pairwise_matrix().unwrap()
+    }
+}
@@ -0,0 +1,572 @@
+//! Reading and writing ballots as CSV, e.g. exported from a spreadsheet or an
+//! online form, with one ballot on each row.
+//!
+//! The first row is a header, and the cells of the ballots are laid out in
+//! one of two ways, chosen with [`CsvConfig::cells`]:
+//!
+//! ```text
+//! Cells::Ranks               Cells::Names
+//! alice,bob,carol            1,2,3
+//! 2,1,                       bob,alice,
+//! 1,1,2                      alice=bob,carol,
+//! ```
+//!
+//! Both describe the same two ballots. With [`Cells::Ranks`] every column is
+//! a candidate and the cells are ranks, where 1 is the best, or scores. With
+//! [`Cells::Names`] every column is a position and the cells are the names of
+//! the candidates at that position, with tied candidates separated by `=`.
+//!
+//! Cells can be quoted like `"a,b"`, but not span multiple lines.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    mem,
+};
+
+use super::{
+    cardinal::Cardinal, orders::TiedRankRef, parse::CandidateRegistry, toi::TiedOrdersIncomplete,
+    VoteFormat,
+};
+
+/// What the cells of a ballot contain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Cells {
+    /// Every column is a candidate, and cells are its rank or score.
+    #[default]
+    Ranks,
+    /// Every column is a position, and cells are names of candidates.
+    Names,
+}
+
+/// How to treat candidates a ballot doesn't rank, i.e. blank cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Blanks {
+    /// The candidates are not ranked, and get the lowest score. Ballots
+    /// without any ranked candidate are skipped.
+    #[default]
+    Unranked,
+    /// The candidates are tied below every ranked candidate, and get the
+    /// lowest score.
+    Last,
+    /// Blank cells are an error.
+    Error,
+}
+
+/// Configuration of reading and writing CSV files.
+///
+/// ```
+/// use votery::formats::csv::{Blanks, Cells, CsvConfig};
+///
+/// let config = CsvConfig::new()
+///     .delimiter(';')
+///     .cells(Cells::Ranks)
+///     .blanks(Blanks::Last)
+///     .ignore_column("Timestamp")
+///     .map_column("Rank of Alice", "alice");
+/// let file = "Timestamp;Rank of Alice;bob;carol\n12:00;2;1;\n";
+/// let (votes, registry) = config.read_ranked(file.as_bytes()).unwrap();
+/// assert_eq!(registry.name(0.into()), Some("alice"));
+/// assert_eq!(votes.vote(0.into()).to_string(), "1,0,2");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvConfig {
+    delimiter: char,
+    cells: Cells,
+    blanks: Blanks,
+    registry: Option<CandidateRegistry>,
+    mapped: HashMap<String, String>,
+    ignored: Vec<String>,
+    score_range: Option<(usize, usize)>,
+}
+
+impl Default for CsvConfig {
+    fn default() -> Self {
+        CsvConfig {
+            delimiter: ',',
+            cells: Cells::Ranks,
+            blanks: Blanks::Unranked,
+            registry: None,
+            mapped: HashMap::new(),
+            ignored: Vec::new(),
+            score_range: None,
+        }
+    }
+}
+
+// One column of a file.
+#[derive(Clone, Copy)]
+enum Column {
+    Ignored,
+    // A candidate or a position, depending on the cells.
+    Used(usize),
+}
+
+fn invalid(line: usize, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+impl CsvConfig {
+    /// Comma separated cells with ranks, where blank cells are unranked.
+    pub fn new() -> Self {
+        CsvConfig::default()
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn cells(mut self, cells: Cells) -> Self {
+        self.cells = cells;
+        self
+    }
+
+    pub fn blanks(mut self, blanks: Blanks) -> Self {
+        self.blanks = blanks;
+        self
+    }
+
+    /// The candidates of the election, which the columns and names are
+    /// matched against. Without it, the candidates are the columns of the
+    /// header, which only works with [`Cells::Ranks`].
+    pub fn candidates(mut self, registry: CandidateRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// The column called `header` is for the candidate called `name`.
+    pub fn map_column(mut self, header: &str, name: &str) -> Self {
+        self.mapped.insert(header.to_string(), name.to_string());
+        self
+    }
+
+    /// Skip the column called `header`, e.g. a timestamp or an email address.
+    pub fn ignore_column(mut self, header: &str) -> Self {
+        self.ignored.push(header.to_string());
+        self
+    }
+
+    /// The lowest and highest score of [`read_scores`](Self::read_scores).
+    /// Without it, the scores go from 0 to the highest score in the file.
+    /// Returns an error if `min` is larger than `max`.
+    pub fn score_range(mut self, min: usize, max: usize) -> Result<Self, &'static str> {
+        if min > max {
+            return Err("Minimum score is larger than maximum score");
+        }
+        self.score_range = Some((min, max));
+        Ok(self)
+    }
+
+    /// Read ranked ballots, returning them and the candidates.
+    pub fn read_ranked<R: BufRead>(
+        &self,
+        r: R,
+    ) -> io::Result<(TiedOrdersIncomplete, CandidateRegistry)> {
+        let mut lines = records(r, self.delimiter);
+        let (columns, registry) = self.header(lines.next())?;
+        let candidates = registry.len();
+        let mut votes = TiedOrdersIncomplete::new(candidates);
+        // The rank of every candidate, or the candidates of every position.
+        let mut ranks: Vec<(usize, usize)> = Vec::with_capacity(candidates);
+        let (mut order, mut tied) = (Vec::new(), Vec::new());
+        let mut seen = vec![false; candidates];
+        for (line, record) in lines {
+            let record = record?;
+            if record.len() > columns.len() {
+                return Err(invalid(line, "more cells than columns"));
+            }
+            ranks.clear();
+            for (&column, cell) in columns.iter().zip(record.iter().map(|c| c.trim())) {
+                let Column::Used(i) = column else { continue };
+                if cell.is_empty() {
+                    if self.blanks == Blanks::Error {
+                        return Err(invalid(line, "blank cell"));
+                    }
+                    continue;
+                }
+                match self.cells {
+                    Cells::Ranks => {
+                        let rank = cell.parse().map_err(|_| invalid(line, "expected a rank"))?;
+                        ranks.push((rank, i));
+                    }
+                    Cells::Names => {
+                        for name in cell.split('=') {
+                            let c = registry
+                                .candidate(name.trim())
+                                .ok_or_else(|| invalid(line, "unknown candidate"))?;
+                            ranks.push((i, c.index()));
+                        }
+                    }
+                }
+            }
+            if self.blanks == Blanks::Error && record.len() < columns.len() {
+                return Err(invalid(line, "blank cell"));
+            }
+            ranks.sort_unstable();
+
+            order.clear();
+            tied.clear();
+            seen.iter_mut().for_each(|s| *s = false);
+            for (j, &(rank, c)) in ranks.iter().enumerate() {
+                if mem::replace(&mut seen[c], true) {
+                    return Err(invalid(line, "candidate ranked twice"));
+                }
+                order.push(c);
+                if j != 0 {
+                    tied.push(ranks[j - 1].0 == rank);
+                }
+            }
+            if self.blanks == Blanks::Last && order.len() < candidates {
+                if !order.is_empty() {
+                    tied.push(false);
+                }
+                for c in (0..candidates).filter(|&c| !seen[c]) {
+                    order.push(c);
+                    tied.push(true);
+                }
+                tied.pop();
+            }
+            if !order.is_empty() {
+                votes.add(TiedRankRef::new(candidates, &order, &tied)).unwrap();
+            }
+        }
+        Ok((votes, registry))
+    }
+
+    /// Read ballots with a score of every candidate, returning them and the
+    /// candidates. The cells must be [`Cells::Ranks`], holding the scores.
+    pub fn read_scores<R: BufRead>(&self, r: R) -> io::Result<(Cardinal, CandidateRegistry)> {
+        if self.cells != Cells::Ranks {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Scores need a column for every candidate",
+            ));
+        }
+        let mut lines = records(r, self.delimiter);
+        let (columns, registry) = self.header(lines.next())?;
+        let candidates = registry.len();
+        let (min, max) = self.score_range.unwrap_or((0, 0));
+        let mut scores: Vec<Vec<Option<usize>>> = Vec::new();
+        let mut highest = max;
+        for (line, record) in lines {
+            let record = record?;
+            if record.len() > columns.len() {
+                return Err(invalid(line, "more cells than columns"));
+            }
+            // A `Cardinal` can't have voters without candidates.
+            if candidates == 0 {
+                return Err(invalid(line, "ballot without any candidates"));
+            }
+            let mut vote = vec![None; candidates];
+            for (&column, cell) in columns.iter().zip(record.iter().map(|c| c.trim())) {
+                let Column::Used(c) = column else { continue };
+                if cell.is_empty() {
+                    continue;
+                }
+                let score = cell.parse().map_err(|_| invalid(line, "expected a score"))?;
+                if self.score_range.is_some() && !(min..=max).contains(&score) {
+                    return Err(invalid(line, "score out of range"));
+                }
+                highest = highest.max(score);
+                vote[c] = Some(score);
+            }
+            if self.blanks == Blanks::Error && vote.contains(&None) {
+                return Err(invalid(line, "blank cell"));
+            }
+            if self.blanks == Blanks::Unranked && vote.iter().all(Option::is_none) {
+                continue;
+            }
+            scores.push(vote);
+        }
+        let mut votes = Cardinal::new(candidates, min, highest);
+        let mut vote = Vec::with_capacity(candidates);
+        for scores in scores {
+            vote.clear();
+            vote.extend(scores.into_iter().map(|s| s.unwrap_or(min)));
+            votes.add(&vote).unwrap();
+        }
+        Ok((votes, registry))
+    }
+
+    /// Write `votes` so that [`read_ranked`](Self::read_ranked) with the same
+    /// configuration reads them back. The names of the candidates are taken
+    /// from `registry`, and otherwise they are called by their number.
+    pub fn write_ranked<W: Write>(
+        &self,
+        w: &mut W,
+        votes: &TiedOrdersIncomplete,
+        registry: Option<&CandidateRegistry>,
+    ) -> io::Result<()> {
+        let names = self.names(votes.candidates(), registry)?;
+        let columns = match self.cells {
+            Cells::Ranks => votes.candidates(),
+            Cells::Names => votes.into_iter().map(|v| v.iter_groups().count()).max().unwrap_or(0),
+        };
+        let mut row = vec![String::new(); columns];
+        match self.cells {
+            Cells::Ranks => row.clone_from(&names),
+            Cells::Names => row.iter_mut().enumerate().for_each(|(i, r)| *r = (i + 1).to_string()),
+        }
+        self.write_record(w, &row)?;
+        for vote in votes {
+            row.iter_mut().for_each(String::clear);
+            for (i, group) in vote.iter_groups().enumerate() {
+                for &c in group {
+                    match self.cells {
+                        Cells::Ranks => row[c] = (i + 1).to_string(),
+                        Cells::Names => {
+                            if !row[i].is_empty() {
+                                row[i].push('=');
+                            }
+                            row[i].push_str(&names[c]);
+                        }
+                    }
+                }
+            }
+            self.write_record(w, &row)?;
+        }
+        Ok(())
+    }
+
+    /// Write `votes` so that [`read_scores`](Self::read_scores) reads them
+    /// back. The names of the candidates are taken from `registry`, and
+    /// otherwise they are called by their number.
+    pub fn write_scores<W: Write>(
+        &self,
+        w: &mut W,
+        votes: &Cardinal,
+        registry: Option<&CandidateRegistry>,
+    ) -> io::Result<()> {
+        let names = self.names(votes.candidates(), registry)?;
+        self.write_record(w, &names)?;
+        let mut row = Vec::with_capacity(names.len());
+        for vote in votes.iter() {
+            row.clear();
+            row.extend(vote.iter().map(|s| s.to_string()));
+            self.write_record(w, &row)?;
+        }
+        Ok(())
+    }
+
+    fn names(
+        &self,
+        candidates: usize,
+        registry: Option<&CandidateRegistry>,
+    ) -> io::Result<Vec<String>> {
+        match registry.or(self.registry.as_ref()) {
+            Some(r) if r.len() != candidates => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Registry must have every candidate",
+            )),
+            Some(r) => Ok(r.candidates().map(|c| r.name(c).unwrap().to_string()).collect()),
+            None => Ok((0..candidates).map(|c| c.to_string()).collect()),
+        }
+    }
+
+    // Find what every column of the header is, and the candidates.
+    fn header(
+        &self,
+        header: Option<(usize, io::Result<Vec<String>>)>,
+    ) -> io::Result<(Vec<Column>, CandidateRegistry)> {
+        // An empty file has no candidates.
+        let (line, header) = header.unwrap_or((1, Ok(Vec::new())));
+        let header = header?;
+        let mut columns = Vec::with_capacity(header.len());
+        let mut names = Vec::new();
+        for cell in &header {
+            let cell = cell.trim();
+            if self.ignored.iter().any(|i| i == cell) {
+                columns.push(Column::Ignored);
+                continue;
+            }
+            let i = match (self.cells, &self.registry) {
+                (Cells::Names, _) => columns.len(),
+                (Cells::Ranks, Some(registry)) => {
+                    let name = self.mapped.get(cell).map_or(cell, |n| n.as_str());
+                    let c =
+                        registry.candidate(name).ok_or_else(|| invalid(line, "unknown column"))?;
+                    if columns.iter().any(|&col| matches!(col, Column::Used(i) if i == c.index())) {
+                        return Err(invalid(line, "two columns for one candidate"));
+                    }
+                    c.index()
+                }
+                (Cells::Ranks, None) => {
+                    let name = self.mapped.get(cell).map_or(cell, |n| n.as_str());
+                    names.push(name.to_string());
+                    names.len() - 1
+                }
+            };
+            columns.push(Column::Used(i));
+        }
+        let registry = match (&self.registry, self.cells) {
+            (Some(registry), _) => registry.clone(),
+            (None, Cells::Ranks) => CandidateRegistry::new(names).map_err(|e| invalid(line, e))?,
+            (None, Cells::Names) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Names in cells need the candidates",
+                ))
+            }
+        };
+        Ok((columns, registry))
+    }
+
+    fn write_record<W: Write>(&self, w: &mut W, record: &[String]) -> io::Result<()> {
+        for (i, cell) in record.iter().enumerate() {
+            if i != 0 {
+                write!(w, "{}", self.delimiter)?;
+            }
+            let quote = cell.contains([self.delimiter, '"', '\n', '\r']) || cell.trim() != cell;
+            if quote {
+                write!(w, "\"{}\"", cell.replace('"', "\"\""))?;
+            } else {
+                write!(w, "{}", cell)?;
+            }
+        }
+        writeln!(w)
+    }
+}
+
+// Every non-empty record, with its line number.
+fn records<R: BufRead>(
+    r: R,
+    delimiter: char,
+) -> impl Iterator<Item = (usize, io::Result<Vec<String>>)> {
+    r.lines().enumerate().filter_map(move |(i, line)| {
+        let record = match line {
+            Ok(line) if line.trim().is_empty() => return None,
+            Ok(line) => split_record(&line, delimiter).map_err(|e| invalid(i + 1, e)),
+            Err(e) => Err(e),
+        };
+        Some((i + 1, record))
+    })
+}
+
+// Split `line` into its cells, where a cell starting with `"` continues until
+// the next `"` which isn't written as `""`.
+fn split_record(line: &str, delimiter: char) -> Result<Vec<String>, &'static str> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c != '"' {
+                cell.push(c);
+            } else if chars.next_if_eq(&'"').is_some() {
+                cell.push('"');
+            } else {
+                quoted = false;
+            }
+        } else if c == '"' && cell.trim().is_empty() {
+            cell.clear();
+            quoted = true;
+        } else if c == delimiter {
+            cells.push(mem::take(&mut cell));
+        } else {
+            cell.push(c);
+        }
+    }
+    if quoted {
+        return Err("unclosed quote");
+    }
+    cells.push(cell);
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(names: &[&str]) -> CandidateRegistry {
+        CandidateRegistry::new(names.iter().map(|n| n.to_string()).collect()).unwrap()
+    }
+
+    #[test]
+    fn layouts_agree() {
+        let ranks = "alice,bob,carol\n2,1,\n1,1,2\n";
+        let names = "1,2,3\nbob,alice,\nalice = bob,carol,\n";
+        let (by_ranks, registry) = CsvConfig::new().read_ranked(ranks.as_bytes()).unwrap();
+        let config = CsvConfig::new().cells(Cells::Names).candidates(registry);
+        let (by_names, _) = config.read_ranked(names.as_bytes()).unwrap();
+        assert_eq!(by_ranks, by_names);
+        assert_eq!(by_ranks.vote(0.into()).to_string(), "1,0");
+        assert_eq!(by_ranks.vote(1.into()).to_string(), "{0,1},2");
+
+        let (last, _) =
+            CsvConfig::new().blanks(Blanks::Last).read_ranked(ranks.as_bytes()).unwrap();
+        assert_eq!(last.vote(0.into()).to_string(), "1,0,2");
+        assert!(CsvConfig::new().blanks(Blanks::Error).read_ranked(ranks.as_bytes()).is_err());
+        assert!(CsvConfig::new().cells(Cells::Names).read_ranked(names.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn columns() {
+        let file = "id;\"Alice; PhD\";b\n7;1;\"2\"\n";
+        let config = CsvConfig::new()
+            .delimiter(';')
+            .candidates(registry(&["b", "alice"]))
+            .ignore_column("id")
+            .map_column("Alice; PhD", "alice");
+        let (votes, _) = config.read_ranked(file.as_bytes()).unwrap();
+        assert_eq!(votes.vote(0.into()).to_string(), "1,0");
+        assert!(CsvConfig::new()
+            .candidates(registry(&["b", "alice"]))
+            .read_ranked(file.as_bytes())
+            .is_err());
+        assert_eq!(
+            split_record("a,\"b,\"\"c\"\"\",", ','),
+            Ok(vec!["a".into(), "b,\"c\"".into(), "".into()])
+        );
+        assert!(split_record("a,\"b", ',').is_err());
+    }
+
+    #[test]
+    fn scores() {
+        let file = "a,b,c\n5,,2\n,,\n";
+        let (votes, _) = CsvConfig::new().read_scores(file.as_bytes()).unwrap();
+        assert_eq!((votes.voters, votes.min, votes.max), (1, 0, 5));
+        let (votes, _) =
+            CsvConfig::new().blanks(Blanks::Last).read_scores(file.as_bytes()).unwrap();
+        assert_eq!(votes.votes, [5, 0, 2, 0, 0, 0]);
+        let config = CsvConfig::new().score_range(1, 4).unwrap();
+        assert!(config.read_scores(file.as_bytes()).is_err());
+        assert!(CsvConfig::new().score_range(4, 1).is_err());
+        let config = CsvConfig::new().ignore_column("id");
+        let (votes, _) = config.read_scores("id\n".as_bytes()).unwrap();
+        assert_eq!((votes.candidates(), votes.voters), (0, 0));
+        let err = config.read_scores("id\n1\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("ballot without any candidates"));
+    }
+
+    #[quickcheck]
+    fn ranked_roundtrip(votes: TiedOrdersIncomplete) -> bool {
+        let names: Vec<String> = (0..votes.candidates()).map(|c| format!("c \"{}\"", c)).collect();
+        let registry = CandidateRegistry::new(names).unwrap();
+        [Cells::Ranks, Cells::Names].into_iter().all(|cells| {
+            let config = CsvConfig::new().cells(cells).candidates(registry.clone());
+            let mut buf = Vec::new();
+            config.write_ranked(&mut buf, &votes, None).unwrap();
+            let read = config.read_ranked(&buf[..]).unwrap().0;
+            // Tied candidates are read in the order of their number.
+            read.voters() == votes.voters()
+                && read.into_iter().zip(&votes).all(|(a, b)| {
+                    let mut b = b.owned();
+                    b.normalize();
+                    a == b.as_ref()
+                })
+        })
+    }
+
+    #[quickcheck]
+    fn scores_roundtrip(votes: Cardinal) -> bool {
+        if votes.candidates() == 0 {
+            return true;
+        }
+        let config = CsvConfig::new().delimiter('\t').score_range(votes.min, votes.max).unwrap();
+        let mut buf = Vec::new();
+        config.write_scores(&mut buf, &votes, None).unwrap();
+        config.read_scores(&buf[..]).unwrap().0 == votes
+    }
+}
@@ -0,0 +1,209 @@
+use rand::{seq::index, Rng};
+
+use super::{orders::TiedRank, toi::TiedOrdersIncomplete, Cardinal, VoteFormat};
+
+/// Cumulative votes, where every voter distributes exactly `budget` points
+/// among the candidates, possibly giving several points to the same
+/// candidate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cumulative {
+    // Has length voters * candidates
+    pub(crate) votes: Vec<usize>,
+    pub(crate) candidates: usize,
+    pub(crate) voters: usize,
+    pub budget: usize,
+}
+
+serde_checked!(Cumulative { votes: Vec<usize>, candidates: usize, voters: usize, budget: usize });
+
+impl Cumulative {
+    pub fn new(candidates: usize, budget: usize) -> Cumulative {
+        Cumulative { votes: Vec::new(), candidates, voters: 0, budget }
+    }
+
+    pub fn voters(&self) -> usize {
+        self.voters
+    }
+
+    /// The points every voter gave to every candidate, one vote at a time.
+    pub fn iter(&self) -> impl Iterator<Item = &[usize]> {
+        self.votes.chunks_exact(self.candidates.max(1)).take(self.voters)
+    }
+
+    /// The total number of points of every candidate.
+    pub fn column_sums(&self) -> Vec<usize> {
+        let mut sums = vec![0; self.candidates];
+        for vote in self.iter() {
+            for (s, &v) in sums.iter_mut().zip(vote) {
+                *s += v;
+            }
+        }
+        sums
+    }
+
+    pub(crate) fn valid(&self) -> bool {
+        !(self.candidates == 0 && (self.voters != 0 || !self.votes.is_empty())
+            || self.voters.checked_mul(self.candidates) != Some(self.votes.len())
+            || self.iter().any(|v| {
+                v.iter().try_fold(0usize, |sum, &p| sum.checked_add(p)) != Some(self.budget)
+            }))
+    }
+
+    /// Convert each vote to a cardinal vote, rating every candidate with its
+    /// points, from 0 to `budget`.
+    ///
+    /// Returns `Err` if it failed to allocate
+    pub fn to_cardinal(&self) -> Result<Cardinal, &'static str> {
+        let mut votes: Vec<usize> = Vec::new();
+        votes.try_reserve_exact(self.votes.len()).or(Err("Could not allocate"))?;
+        votes.extend_from_slice(&self.votes);
+        let v = Cardinal {
+            votes,
+            candidates: self.candidates,
+            voters: self.voters,
+            min: 0,
+            max: self.budget,
+        };
+        debug_assert!(v.valid());
+        Ok(v)
+    }
+}
+
+impl<'a> VoteFormat<'a> for Cumulative {
+    type Vote = &'a [usize];
+    fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    /// Add a vote, returning an error if it doesn't list the points of every
+    /// candidate or doesn't spend exactly the budget.
+    fn add(&mut self, v: Self::Vote) -> Result<(), &'static str> {
+        if v.len() != self.candidates {
+            return Err("Vote must contains all candidates");
+        }
+        let spent = v.iter().try_fold(0usize, |sum, &p| sum.checked_add(p));
+        if spent != Some(self.budget) {
+            return Err("Vote must spend exactly the budget");
+        }
+        self.votes.try_reserve(self.candidates).or(Err("Could not add vote"))?;
+        self.votes.extend_from_slice(v);
+        self.voters += 1;
+        Ok(())
+    }
+
+    /// Remove candidate `target`. Returns an error if any voter gave it
+    /// points, as they would then spend less than the budget.
+    fn remove_candidate(&mut self, target: usize) -> Result<(), &'static str> {
+        if target >= self.candidates {
+            return Err("Candidate doesn't exist");
+        }
+        if self.iter().any(|v| v[target] != 0) {
+            return Err("Can't remove a candidate with points");
+        }
+        let candidates = self.candidates;
+        let mut i = 0;
+        self.votes.retain(|_| {
+            i += 1;
+            (i - 1) % candidates != target
+        });
+        self.candidates -= 1;
+        if self.candidates == 0 {
+            self.voters = 0;
+        }
+        debug_assert!(self.valid());
+        Ok(())
+    }
+
+    /// Add `new_voters` votes, each drawn uniformly from every way to
+    /// distribute the budget.
+    fn generate_uniform<R: Rng>(&mut self, rng: &mut R, new_voters: usize) {
+        if self.candidates == 0 || new_voters == 0 {
+            return;
+        }
+        // Stars and bars: the positions of `candidates - 1` bars among
+        // `budget` points split the points between the candidates.
+        let slots = self.budget + self.candidates - 1;
+        self.votes.reserve(new_voters * self.candidates);
+        for _ in 0..new_voters {
+            let mut bars = index::sample(rng, slots, self.candidates - 1).into_vec();
+            bars.sort_unstable();
+            let mut last = 0;
+            for &bar in &bars {
+                self.votes.push(bar - last);
+                last = bar + 1;
+            }
+            self.votes.push(slots - last);
+        }
+        self.voters += new_voters;
+        debug_assert!(self.valid());
+    }
+
+    /// Rank the candidates by their points. Candidates without any points
+    /// aren't ranked, and votes without any points are left out.
+    fn to_partial_ranking(self) -> TiedOrdersIncomplete {
+        let mut toi = TiedOrdersIncomplete::new(self.candidates);
+        for vote in self.iter() {
+            let mut ranking = TiedRank::from_scores(self.candidates, vote);
+            let ranked = vote.iter().filter(|&&p| p != 0).count();
+            if ranked == 0 {
+                continue;
+            }
+            ranking.keep_top(ranked);
+            toi.add(ranking.as_ref()).unwrap();
+        }
+        toi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::{Arbitrary, Gen};
+
+    use super::*;
+    use crate::formats::tests::std_rng;
+
+    impl Arbitrary for Cumulative {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let (voters, candidates, budget): (usize, usize, usize) = Arbitrary::arbitrary(g);
+
+            // `Arbitrary` for numbers will generate "problematic" examples such as
+            // `usize::max_value()` and `usize::min_value()` but we'll use them to
+            // allocate vectors so we'll limit them.
+            let voters = voters % g.size();
+            let candidates = candidates % g.size();
+            let budget = budget % g.size();
+
+            let mut votes = Cumulative::new(candidates, budget);
+            votes.generate_uniform(&mut std_rng(g), voters);
+            debug_assert!(votes.valid());
+            votes
+        }
+    }
+
+    #[test]
+    fn budget() {
+        let mut votes = Cumulative::new(3, 5);
+        assert!(votes.add(&[2, 0, 3]).is_ok());
+        assert!(votes.add(&[5, 0, 0]).is_ok());
+        assert!(votes.add(&[2, 2, 2]).is_err());
+        assert!(votes.add(&[1, 1]).is_err());
+        assert!(votes.add(&[usize::MAX, 6, 0]).is_err());
+        assert_eq!(votes.voters(), 2);
+        assert!(votes.clone().remove_candidate(0).is_err());
+        votes.remove_candidate(1).unwrap();
+        assert_eq!(votes.iter().collect::<Vec<_>>(), [[2, 3], [5, 0]]);
+        let toi = votes.to_partial_ranking();
+        let res: Vec<String> = toi.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(res, ["1,0", "0"]);
+    }
+
+    #[quickcheck]
+    fn uniform_is_valid(votes: Cumulative) -> bool {
+        votes.valid() && votes.to_cardinal().is_ok()
+    }
+
+    #[quickcheck]
+    fn partial_ranking_is_valid(votes: Cumulative) -> bool {
+        votes.to_partial_ranking().valid()
+    }
+}
@@ -23,6 +23,14 @@
 //!       represent ties, but it can be done by having auxiliary flags
 //!       specifying which ranks contain multiple candidates.
 //!
+//! # Threads
+//! Every format, and every reference to a vote such as
+//! [`TiedRankRef`](orders::TiedRankRef), only owns plain vectors, so they are
+//! all `Send + Sync` and can be shared between threads without copying the
+//! votes. With the `rayon` feature, the ranked formats also have a `par_iter`
+//! method to go through their votes in parallel, e.g.
+//! [`TiedOrdersIncomplete::par_iter`].
+//!
 //! # Conversions
 
 use rand::Rng;
@@ -50,20 +58,76 @@ pub trait VoteFormat<'a> {
     fn to_partial_ranking(self) -> TiedOrdersIncomplete;
 }
 
+// Implement `Serialize` and `Deserialize` for a format using its `fields`,
+// where deserializing checks that the format is `valid()`, so a damaged
+// snapshot is an error instead of a panic when the votes are used. The
+// remaining fields are set to `value`.
+#[cfg(feature = "serde")]
+macro_rules! serde_checked {
+    ($format:ident { $($field:ident: $ty:ty),* $(,)? } $(, $rest:ident: $value:expr)*) => {
+        impl serde::Serialize for $format {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                #[derive(serde::Serialize)]
+                struct Fields<'a> {
+                    $($field: &'a $ty),*
+                }
+                serde::Serialize::serialize(&Fields { $($field: &self.$field),* }, s)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $format {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                #[derive(serde::Deserialize)]
+                struct Fields {
+                    $($field: $ty),*
+                }
+                let Fields { $($field),* } = serde::Deserialize::deserialize(d)?;
+                let format = $format { $($field,)* $($rest: $value),* };
+                if !format.valid() {
+                    return Err(serde::de::Error::custom(concat!("invalid ", stringify!($format))));
+                }
+                Ok(format)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+macro_rules! serde_checked {
+    ($($t:tt)*) => {};
+}
+
+pub mod csv;
 pub mod orders;
+pub mod parse;
+pub mod preflib;
 pub mod soc;
 pub mod soi;
 pub mod toc;
 pub mod toi;
+pub mod view;
+pub mod vot;
 
+mod ballot_length;
+pub use ballot_length::BallotLength;
 mod binary;
 pub use binary::Binary;
 mod cardinal;
-pub use cardinal::Cardinal;
+pub use cardinal::{Cardinal, Spacing};
+mod compressed;
+pub use compressed::CompressedDense;
+mod cumulative;
+pub use cumulative::Cumulative;
 mod specific;
-pub use specific::Specific;
+pub use specific::{Specific, TiePolicy};
+mod co_ranking;
+pub use co_ranking::{CoRank, CoRanking};
+mod rank_distribution;
+pub use rank_distribution::RankDistribution;
 mod total_ranking;
 pub use total_ranking::TotalRanking;
+mod validated;
+pub use validated::{Validate, Validated};
 
 use self::toi::TiedOrdersIncomplete;
 
@@ -82,6 +146,14 @@ mod tests {
     use quickcheck::{Arbitrary, Gen};
     use rand::{rngs::StdRng, SeedableRng};
 
+    use super::{
+        orders::{RankRef, TiedRank, TiedRankRef},
+        soc::{ColumnMajor, StrictOrdersComplete},
+        soi::StrictOrdersIncomplete,
+        toc::TiedOrdersComplete,
+        *,
+    };
+
     // `Gen` contains a rng, but it's a private member so this method is used to get
     // a standard rng generated from `Gen`
     pub fn std_rng(g: &mut Gen) -> StdRng {
@@ -91,4 +163,86 @@ mod tests {
         }
         StdRng::from_seed(seed)
     }
+
+    #[test]
+    fn send_sync() {
+        fn check<T: Send + Sync>() {}
+        check::<Binary>();
+        check::<Cardinal>();
+        check::<CompressedDense>();
+        check::<Cumulative>();
+        check::<Specific>();
+        check::<StrictOrdersComplete>();
+        check::<StrictOrdersComplete<ColumnMajor>>();
+        check::<StrictOrdersIncomplete>();
+        check::<TiedOrdersComplete>();
+        check::<TiedOrdersIncomplete>();
+        check::<TiedRank>();
+        check::<TiedRankRef>();
+        check::<RankRef>();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[quickcheck]
+    fn par_iter_same_as_iter(toi: TiedOrdersIncomplete, seed: u64) -> bool {
+        use rand_chacha::ChaCha8Rng;
+        use rayon::prelude::*;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let (c, n) = (toi.candidates().max(1), toi.voters());
+        let mut toc = TiedOrdersComplete::new(c);
+        toc.generate_uniform(&mut rng, n);
+        let mut soc = StrictOrdersComplete::new(c);
+        soc.generate_uniform(&mut rng, n);
+        let mut soi = StrictOrdersIncomplete::new(c);
+        soi.generate_uniform(&mut rng, n);
+        toi.par_iter().collect::<Vec<_>>() == toi.into_iter().collect::<Vec<_>>()
+            && toc.par_iter().collect::<Vec<_>>() == toc.into_iter().collect::<Vec<_>>()
+            && soc.par_iter().collect::<Vec<_>>() == soc.into_iter().collect::<Vec<_>>()
+            && soi.par_iter().collect::<Vec<_>>() == soi.into_iter().collect::<Vec<_>>()
+    }
+
+    #[cfg(feature = "serde")]
+    #[quickcheck]
+    fn serde_roundtrip(toi: TiedOrdersIncomplete, binary: Binary, cardinal: Cardinal) -> bool {
+        use rand_chacha::ChaCha8Rng;
+        use serde::{de::DeserializeOwned, Serialize};
+
+        // Not every format implements `PartialEq`, so we compare the JSON.
+        fn same<T: Serialize + DeserializeOwned>(votes: &T) -> bool {
+            let json = serde_json::to_string(votes).unwrap();
+            let read: T = serde_json::from_str(&json).unwrap();
+            serde_json::to_string(&read).unwrap() == json
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(toi.voters() as u64);
+        let (c, n) = (toi.candidates().max(1), toi.voters());
+        let mut toc = TiedOrdersComplete::new(c);
+        toc.generate_uniform(&mut rng, n);
+        let mut soc = StrictOrdersComplete::new(c);
+        soc.generate_uniform(&mut rng, n);
+        let mut soi = StrictOrdersIncomplete::new(c);
+        soi.generate_uniform(&mut rng, n);
+        same(&toi) && same(&toc) && same(&soc) && same(&soi) && same(&binary) && same(&cardinal)
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_invalid() {
+        let invalid = [
+            r#"{"votes":[0,0],"ties":[false],"vote_len":[2],"candidates":2}"#,
+            r#"{"votes":[0,3],"ties":[false],"vote_len":[2],"candidates":2}"#,
+            r#"{"votes":[0,1],"ties":[false],"vote_len":[3],"candidates":2}"#,
+            r#"{"votes":[0,1],"ties":[],"vote_len":[2],"candidates":2}"#,
+        ];
+        for json in invalid {
+            assert!(serde_json::from_str::<TiedOrdersIncomplete>(json).is_err(), "{}", json);
+        }
+        let valid = r#"{"votes":[1,0],"ties":[true],"vote_len":[2],"candidates":2}"#;
+        let toi: TiedOrdersIncomplete = serde_json::from_str(valid).unwrap();
+        assert_eq!(toi.vote(0.into()).to_string(), "{1,0}");
+        let toc = r#"{"votes":[1,0,1],"ties":[false],"candidates":2}"#;
+        assert!(serde_json::from_str::<TiedOrdersComplete>(toc).is_err());
+        let cardinal = r#"{"votes":[7],"candidates":1,"voters":1,"min":0,"max":5}"#;
+        assert!(serde_json::from_str::<Cardinal>(cardinal).is_err());
+    }
 }
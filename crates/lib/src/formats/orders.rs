@@ -20,8 +20,13 @@ use rand::{
 };
 use rand_distr::{Bernoulli, Uniform};
 
+use super::parse::{
+    parse_order, parse_order_spans, CandidateRegistry, ParseOrderError, ParseOrderErrorKind,
+};
+
 // A vote without any ties
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rank {
     candidates: usize,
     order: Vec<usize>,
@@ -44,20 +49,36 @@ impl Rank {
         self.order.len()
     }
 
-    pub fn parse_vote(candidates: usize, s: &str) -> Option<Self> {
-        let mut order: Vec<usize> = Vec::with_capacity(candidates);
-        for number in s.split(',') {
-            let n: usize = match number.parse() {
-                Ok(n) => n,
-                Err(_) => return None,
-            };
-            if !(n < candidates) {
-                return None;
-            }
-            order.push(n);
+    /// Try to parse a ranking of `candidates` from `s`, like `2,0,1`. It has
+    /// to rank at least one candidate, and no candidates can be tied.
+    pub fn parse_vote(candidates: usize, s: &str) -> Result<Self, ParseOrderError> {
+        Rank::parse_vote_with(candidates, s, None)
+    }
+
+    /// Like [`Rank::parse_vote`], but candidates can also be written using
+    /// their names in `registry`.
+    pub fn parse_vote_with(
+        candidates: usize,
+        s: &str,
+        registry: Option<&CandidateRegistry>,
+    ) -> Result<Self, ParseOrderError> {
+        let (order, tied, spans) = parse_order_spans(candidates, s, registry)?;
+        if order.is_empty() {
+            let offset = s.len() - s.trim_start().len();
+            let kind = ParseOrderErrorKind::Empty;
+            return Err(ParseOrderError { kind, offset, token: String::new() });
         }
-
-        Some(Rank::new(candidates, order))
+        // The candidate after the first tie is the one which shouldn't be
+        // there.
+        if let Some(i) = tied.iter().position(|&t| t) {
+            let span = spans[i + 1].clone();
+            return Err(ParseOrderError {
+                kind: ParseOrderErrorKind::UnexpectedTie,
+                offset: span.start,
+                token: s[span].to_string(),
+            });
+        }
+        Ok(Rank::new(candidates, order))
     }
 
     pub fn as_ref(&self) -> RankRef {
@@ -71,6 +92,10 @@ impl<'a> RankRef<'a> {
         RankRef { candidates, order }
     }
 
+    pub fn order(&self) -> &[usize] {
+        self.order
+    }
+
     pub fn top(&self, n: usize) -> Self {
         RankRef::new(self.candidates, &self.order[0..n])
     }
@@ -91,6 +116,7 @@ impl<'a> RankRef<'a> {
 
 /// A vote with possible ties.
 #[derive(Clone, Debug, PartialEq, Eq, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TiedRank {
     pub order: Vec<usize>,
     pub tied: Vec<bool>,
@@ -165,8 +191,9 @@ impl<'a> TiedRank {
         self.candidates = candidates;
     }
 
-    /// Try to parse a ranking of `candidates` from `s`. Returns None if `s` is
-    /// not a valid ranking.
+    /// Try to parse a ranking of `candidates` from `s`. Returns an error
+    /// describing where `s` is invalid if it's not a valid ranking. See
+    /// [`parse`](super::parse) for the accepted syntax.
     ///
     /// ```
     /// use votery::formats::orders::TiedRank;
@@ -185,51 +212,28 @@ impl<'a> TiedRank {
     /// let rank = TiedRank::parse_vote(5, "0,{1}").unwrap();
     /// assert!(rank.as_ref().to_string() == "0,1");
     /// ```
-    pub fn parse_vote(candidates: usize, s: &str) -> Option<Self> {
-        if s == "" {
-            let mut rank = TiedRank::new_zero();
-            rank.increase_candidates(candidates);
-            return Some(rank);
-        }
-        let l = (s.len() / 2).min(candidates);
-        let mut order: Vec<usize> = Vec::with_capacity(l);
-        let mut tied: Vec<bool> = Vec::with_capacity(l);
-        let mut grouped = false;
-        for mut part in s.split(',') {
-            // Are we starting a group?
-            if !grouped {
-                part = part.strip_prefix('{').map_or(part, |s| {
-                    grouped = true;
-                    s
-                });
-            }
-
-            // Are we ending a group? We check both cases as this part may be a group with
-            // only one element.
-            if grouped {
-                part = part.strip_suffix('}').map_or(part, |s| {
-                    grouped = !grouped;
-                    s
-                })
-            }
-            let n: usize = match part.parse() {
-                Ok(n) => n,
-                Err(_) => return None,
-            };
-            if !(n < candidates) {
-                return None;
-            }
-            order.push(n);
-            tied.push(grouped);
-        }
-        // The last one will never be tied, so we'll ignore it.
-        tied.pop();
+    pub fn parse_vote(candidates: usize, s: &str) -> Result<Self, ParseOrderError> {
+        TiedRank::parse_vote_with(candidates, s, None)
+    }
 
-        // We didn't end our group
-        if grouped {
-            return None;
-        }
-        Some(TiedRank::new(candidates, order, tied))
+    /// Like [`TiedRank::parse_vote`], but candidates can also be written using
+    /// their names in `registry`.
+    ///
+    /// ```
+    /// use votery::formats::{orders::TiedRank, parse::CandidateRegistry};
+    ///
+    /// let names = vec!["alice".to_string(), "bob".to_string(), "carol".to_string()];
+    /// let registry = CandidateRegistry::new(names).unwrap();
+    /// let vote = TiedRank::parse_vote_with(3, "{carol,alice},bob", Some(&registry)).unwrap();
+    /// assert_eq!(vote.as_ref().to_string(), "{2,0},1");
+    /// ```
+    pub fn parse_vote_with(
+        candidates: usize,
+        s: &str,
+        registry: Option<&CandidateRegistry>,
+    ) -> Result<Self, ParseOrderError> {
+        let (order, tied) = parse_order(candidates, s, registry)?;
+        Ok(TiedRank::new(candidates, order, tied))
     }
 
     pub fn single(candidates: usize, n: usize) -> TiedRank {
@@ -293,18 +297,33 @@ impl<'a> TiedRank {
         self.tied.reverse();
     }
 
+    /// Remove candidate `c` from the ranking. Candidates which were tied with
+    /// `c` remain tied with each other. Returns false if `c` was not ranked.
+    pub fn remove(&mut self, c: usize) -> bool {
+        let p = match self.order.iter().position(|&x| x == c) {
+            Some(p) => p,
+            None => return false,
+        };
+        self.order.remove(p);
+        if p == self.tied.len() {
+            self.tied.pop();
+        } else {
+            if p != 0 {
+                self.tied[p - 1] = self.tied[p - 1] && self.tied[p];
+            }
+            self.tied.remove(p);
+        }
+        true
+    }
+
     /// Remove every candidate from the ranking which had the highest ranking
     pub fn remove_winners(&mut self) {
         let l = self.order.len();
         if l == 0 {
             return;
         }
-        let mut winners = 1;
-        for &b in &self.tied {
-            if b {
-                winners += 1;
-            }
-        }
+        // Only the ties at the start are between winners.
+        let winners = 1 + self.tied.iter().take_while(|&&b| b).count();
         self.order.copy_within(winners..l, 0);
         self.order.truncate(l - winners);
         if l == winners {
@@ -474,6 +493,70 @@ impl<'a> fmt::Display for TiedRankRef<'a> {
     }
 }
 
+/// Displays a [`TiedRankRef`] as the rank of every candidate, see
+/// [`TiedRankRef::display_ranks`].
+pub struct DisplayRanks<'a> {
+    rank: TiedRankRef<'a>,
+}
+
+impl<'a> fmt::Display for DisplayRanks<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (c, group) in self.rank.groups_of_candidates().into_iter().enumerate() {
+            if c != 0 {
+                f.write_char(',')?;
+            }
+            match group {
+                Some(g) => write!(f, "{}", g + 1)?,
+                None => f.write_char('-')?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays a [`TiedRankRef`] as a matrix, see
+/// [`TiedRankRef::display_matrix`].
+pub struct DisplayMatrix<'a> {
+    rank: TiedRankRef<'a>,
+}
+
+impl<'a> fmt::Display for DisplayMatrix<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups = self.rank.groups_of_candidates();
+        write_matrix(f, groups.len(), |a, b| match (groups[a], groups[b]) {
+            _ if a == b => '=',
+            (None, None) => '?',
+            // Ranked candidates are above unranked ones
+            (Some(_), None) => '>',
+            (None, Some(_)) => '<',
+            (Some(x), Some(y)) if x < y => '>',
+            (Some(x), Some(y)) if x > y => '<',
+            _ => '=',
+        })
+    }
+}
+
+/// Write a matrix of relations between `n` candidates, with the character
+/// `relation(a, b)` in row `a` and column `b`.
+pub(crate) fn write_matrix<F: Fn(usize, usize) -> char>(
+    f: &mut fmt::Formatter<'_>,
+    n: usize,
+    relation: F,
+) -> fmt::Result {
+    for a in 0..n {
+        if a != 0 {
+            f.write_char('\n')?;
+        }
+        for b in 0..n {
+            if b != 0 {
+                f.write_char(' ')?;
+            }
+            f.write_char(relation(a, b))?;
+        }
+    }
+    Ok(())
+}
+
 impl<'a> TiedRankRef<'a> {
     pub fn new(candidates: usize, order: &'a [usize], tied: &'a [bool]) -> Self {
         debug_assert!(tied.len() + 1 == order.len() || order.len() == 0 && tied.len() == 0);
@@ -589,16 +672,55 @@ impl<'a> TiedRankRef<'a> {
             if self.order()[i] == c {
                 return Some(group);
             }
-            if i != self.len() && !self.tied()[i] {
+            if i + 1 != self.len() && !self.tied()[i] {
                 group += 1;
             }
         }
         None
     }
 
+    /// Display the rank of every candidate, in order of candidates, where 1 is
+    /// the highest rank. Tied candidates have the same rank, and unranked
+    /// candidates are shown as `-`.
+    ///
+    /// ```
+    /// use votery::formats::orders::TiedRank;
+    ///
+    /// let rank = TiedRank::parse_vote(5, "2,{0,3},1").unwrap();
+    /// assert_eq!(rank.as_ref().display_ranks().to_string(), "2,3,1,2,-");
+    /// ```
+    pub fn display_ranks(self) -> DisplayRanks<'a> {
+        DisplayRanks { rank: self }
+    }
+
+    /// Display the order as a matrix, where the element in row `a` and column
+    /// `b` is `>` if `a` is ranked above `b`, `<` if `a` is ranked below `b`,
+    /// `=` if they are tied and `?` if neither is ranked. The diagonal is `=`.
+    ///
+    /// ```
+    /// use votery::formats::orders::TiedRank;
+    ///
+    /// let rank = TiedRank::parse_vote(3, "{0,2}").unwrap();
+    /// assert_eq!(rank.as_ref().display_matrix().to_string(), "= > =\n< = <\n= > =");
+    /// ```
+    pub fn display_matrix(self) -> DisplayMatrix<'a> {
+        DisplayMatrix { rank: self }
+    }
+
+    // The group of every candidate, or `None` if it's unranked.
+    fn groups_of_candidates(&self) -> Vec<Option<usize>> {
+        let mut res = vec![None; self.candidates];
+        for (i, group) in self.iter_groups().enumerate() {
+            for &c in group {
+                res[c] = Some(i);
+            }
+        }
+        res
+    }
+
+    /// The candidates with the highest rank. Empty if no candidate is ranked.
     pub fn winners(self: &TiedRankRef<'a>) -> &'a [usize] {
-        let i = self.tied().iter().take_while(|x| **x).count();
-        &self.order()[0..=i]
+        self.split_winner_group().0
     }
 
     pub fn empty(&self) -> bool {
@@ -736,6 +858,43 @@ mod tests {
         rank == rank.as_ref().owned()
     }
 
+    #[test]
+    fn empty_order() {
+        let rank = TiedRankRef::new_zero_c(3);
+        assert!(rank.winners().is_empty());
+        let (winners, rest) = rank.split_winner_group();
+        assert!(winners.is_empty() && rest.empty());
+        assert_eq!(rank.group_of(0), None);
+        assert_eq!(rank.group(0), None);
+        assert!(TiedRankRef::new_zero().winners().is_empty());
+    }
+
+    #[test]
+    fn remove_winners_later_ties() {
+        let mut rank = TiedRank::parse_vote(4, "0,{1,2},3").unwrap();
+        rank.remove_winners();
+        assert_eq!(rank.as_ref().to_string(), "{1,2},3");
+        rank.remove_winners();
+        rank.remove_winners();
+        assert!(rank.as_ref().empty());
+        rank.remove_winners();
+        assert!(rank.tied.is_empty());
+    }
+
+    #[quickcheck]
+    fn winners_first_group(rank: TiedRank) -> bool {
+        let rank = rank.as_ref();
+        let first = rank.iter_groups().next().unwrap_or(&[]);
+        rank.winners() == first && rank.split_winner_group().0 == first
+    }
+
+    #[quickcheck]
+    fn remove_winners_rest(mut rank: TiedRank) -> bool {
+        let rest = rank.as_ref().split_winner_group().1.owned();
+        rank.remove_winners();
+        rank == rest
+    }
+
     #[test]
     fn iter_groups_zero() {
         let rank = TiedRank::new_zero();
@@ -767,8 +926,8 @@ mod tests {
     fn parse_random(rank: TiedRank) -> bool {
         let new_rank_o = TiedRank::parse_vote(rank.candidates, &format!("{}", rank.as_ref()));
         match new_rank_o {
-            Some(new_rank) => rank == new_rank,
-            None => false,
+            Ok(new_rank) => rank == new_rank,
+            Err(_) => false,
         }
     }
 
@@ -791,6 +950,35 @@ mod tests {
         }
     }
 
+    #[quickcheck]
+    fn remove_len(mut rank: TiedRank, c: usize) -> bool {
+        if rank.len() == 0 {
+            return !rank.remove(c);
+        }
+        let l = rank.len();
+        let c = rank.order[c % l];
+        rank.remove(c) && rank.len() == l - 1 && rank.tied.len() == (l - 1).saturating_sub(1)
+    }
+
+    #[test]
+    fn display_matrix_unranked() {
+        let rank = TiedRank::parse_vote(3, "1").unwrap();
+        let matrix = rank.as_ref().display_matrix().to_string();
+        assert_eq!(matrix, "= < ?\n> = >\n? < =");
+        assert_eq!(rank.as_ref().group_of(0), None);
+    }
+
+    #[test]
+    fn remove_tied() {
+        let mut rank = TiedRank::parse_vote(5, "0,{1,2,3},4").unwrap();
+        rank.remove(2);
+        assert_eq!(rank.as_ref().to_string(), "0,{1,3},4");
+        rank.remove(0);
+        assert_eq!(rank.as_ref().to_string(), "{1,3},4");
+        rank.remove(4);
+        assert_eq!(rank.as_ref().to_string(), "{1,3}");
+    }
+
     #[test]
     fn tied_remove_last() {
         let mut r = TiedRank::new_tied(20);
@@ -863,15 +1051,37 @@ mod tests {
             ("{0}}", false),
             ("{0},}", false),
             ("{,{0},}", false),
-            (" 1", false),
+            (" 1", true),
+            ("1 2", false),
         ];
         for (s, some) in examples {
             let vote_o = TiedRank::parse_vote(candidates, s);
             match (vote_o, some) {
-                (Some(_), true) | (None, false) => {}
-                (None, true) => panic!("`{}` could not be parsed", s),
-                (Some(vote), false) => panic!("`{}` was parsed to `{}`", s, vote.as_ref()),
+                (Ok(_), true) | (Err(_), false) => {}
+                (Err(e), true) => panic!("`{}` could not be parsed: {}", s, e),
+                (Ok(vote), false) => panic!("`{}` was parsed to `{}`", s, vote.as_ref()),
             }
         }
     }
+
+    #[test]
+    fn parse_rank_errors() {
+        let err = |s: &str, registry: Option<&CandidateRegistry>| {
+            let e = Rank::parse_vote_with(4, s, registry).unwrap_err();
+            (e.kind, e.offset, e.token)
+        };
+        let tie = ParseOrderErrorKind::UnexpectedTie;
+        assert_eq!(err("0,{1,2}", None), (tie, 5, "2".to_string()));
+        assert_eq!(err("0 > 1 = 2", None), (tie, 8, "2".to_string()));
+        // The token is the name which was written.
+        let names = ["a", "b", "c", "d"].map(String::from).to_vec();
+        let registry = CandidateRegistry::new(names).unwrap();
+        assert_eq!(err("c > b = a", Some(&registry)), (tie, 8, "a".to_string()));
+        // Unlike a `TiedRank`, a `Rank` must rank someone.
+        let empty = ParseOrderErrorKind::Empty;
+        assert_eq!(err("", None), (empty, 0, "".to_string()));
+        assert_eq!(err("  ", None), (empty, 2, "".to_string()));
+        assert!(Rank::parse_vote(4, "c").is_err());
+        assert_eq!(Rank::parse_vote_with(4, "c,a", Some(&registry)).unwrap().order, [2, 0]);
+    }
 }
@@ -0,0 +1,411 @@
+//! Parsing orders of candidates from strings, with errors pointing to where
+//! the string went wrong.
+//!
+//! Two dialects are supported, see [`Dialect`]:
+//! - A list of candidates separated by `,`, where candidates inside `{...}` are
+//!   tied, e.g. `2,{0,1},4`.
+//! - Candidates separated by `>` and `=`, e.g. `2 > 0 = 1 > 4`.
+//!
+//! Candidates are written as their index, or as their name if a
+//! [`CandidateRegistry`] is used. Whitespace around candidates and separators
+//! is ignored.
+
+use std::{
+    error::Error,
+    fmt::{self, Write},
+    ops::Range,
+};
+
+use super::orders::TiedRankRef;
+use crate::election::Candidate;
+
+/// The syntax used to write an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    /// `2,{0,1},4`
+    Braces,
+    /// `2 > 0 = 1 > 4`
+    Relations,
+}
+
+/// Names of candidates, used to parse orders like `alice,{bob,carol}`. Each
+/// candidate can also have a color, for programs which draw them.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct CandidateRegistry {
+    names: Vec<String>,
+    colors: Vec<Option<[u8; 3]>>,
+}
+
+serde_checked!(CandidateRegistry { names: Vec<String>, colors: Vec<Option<[u8; 3]>> });
+
+impl CandidateRegistry {
+    /// Create a registry where candidate `i` is called `names[i]`.
+    pub fn new(names: Vec<String>) -> Result<Self, &'static str> {
+        for (i, name) in names.iter().enumerate() {
+            if name.is_empty() || name.contains([',', '{', '}', '>', '=']) || name.trim() != name {
+                return Err("Invalid candidate name");
+            }
+            if names[..i].contains(name) {
+                return Err("Duplicate candidate name");
+            }
+        }
+        let colors = vec![None; names.len()];
+        Ok(CandidateRegistry { names, colors })
+    }
+
+    // Returns true if the names could have been given to `new`.
+    fn valid(&self) -> bool {
+        self.colors.len() == self.names.len() && Self::new(self.names.clone()).is_ok()
+    }
+
+    /// Number of candidates
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every candidate of the registry.
+    pub fn candidates(&self) -> impl Iterator<Item = Candidate> {
+        Candidate::all(self.len())
+    }
+
+    /// The name of `candidate`
+    pub fn name(&self, candidate: Candidate) -> Option<&str> {
+        self.names.get(candidate.index()).map(String::as_str)
+    }
+
+    /// The candidate called `name`
+    pub fn candidate(&self, name: &str) -> Option<Candidate> {
+        self.names.iter().position(|n| n == name).map(Candidate)
+    }
+
+    /// The RGB color of `candidate`, if it has been set.
+    pub fn color(&self, candidate: Candidate) -> Option<[u8; 3]> {
+        self.colors.get(candidate.index()).copied().flatten()
+    }
+
+    /// Set the RGB color of `candidate`.
+    pub fn set_color(&mut self, candidate: Candidate, color: [u8; 3]) -> Result<(), &'static str> {
+        let c = self.colors.get_mut(candidate.index()).ok_or("Candidate does not exist")?;
+        *c = Some(color);
+        Ok(())
+    }
+}
+
+/// What went wrong when parsing an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseOrderErrorKind {
+    /// A candidate was missing, e.g. between two commas
+    Empty,
+    /// The token is not a candidate
+    UnknownCandidate,
+    /// The candidate was already ranked
+    Duplicate,
+    /// A `{` was never closed
+    UnclosedBrace,
+    /// A brace in a place where it's not allowed, e.g. a nested group
+    UnexpectedBrace,
+    /// A tie in an order which can't have ties
+    UnexpectedTie,
+}
+
+/// Error returned when parsing an order, with the byte offset in the string
+/// where the error was found and the offending token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOrderError {
+    pub kind: ParseOrderErrorKind,
+    pub offset: usize,
+    pub token: String,
+}
+
+impl fmt::Display for ParseOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self.kind {
+            ParseOrderErrorKind::Empty => "missing candidate",
+            ParseOrderErrorKind::UnknownCandidate => "unknown candidate",
+            ParseOrderErrorKind::Duplicate => "candidate ranked twice",
+            ParseOrderErrorKind::UnclosedBrace => "unclosed brace",
+            ParseOrderErrorKind::UnexpectedBrace => "unexpected brace",
+            ParseOrderErrorKind::UnexpectedTie => "ties are not allowed",
+        };
+        write!(f, "{} `{}` at byte {}", description, self.token, self.offset)
+    }
+}
+
+impl Error for ParseOrderError {}
+
+fn error(kind: ParseOrderErrorKind, offset: usize, token: &str) -> ParseOrderError {
+    ParseOrderError { kind, offset, token: token.to_string() }
+}
+
+/// Parse an order of `candidates`, returning the order and if each candidate
+/// is tied with the next one. Names are resolved using `registry`, and
+/// otherwise candidates are parsed as numbers. The dialect is chosen based on
+/// if `s` contains `>` or `=`.
+pub(crate) fn parse_order(
+    candidates: usize,
+    s: &str,
+    registry: Option<&CandidateRegistry>,
+) -> Result<(Vec<usize>, Vec<bool>), ParseOrderError> {
+    let (order, tied, _) = parse_order_spans(candidates, s, registry)?;
+    Ok((order, tied))
+}
+
+// An order, if each candidate is tied with the next one, and the bytes where
+// each candidate was written.
+type Spans = (Vec<usize>, Vec<bool>, Vec<Range<usize>>);
+
+/// Like [`parse_order`], but also returns the bytes of `s` where every
+/// candidate of the order was written.
+pub(crate) fn parse_order_spans(
+    candidates: usize,
+    s: &str,
+    registry: Option<&CandidateRegistry>,
+) -> Result<Spans, ParseOrderError> {
+    let capacity = (s.len() / 2).min(candidates);
+    let mut parser = Parser {
+        candidates,
+        registry,
+        seen: vec![false; candidates],
+        order: Vec::with_capacity(capacity),
+        tied: Vec::with_capacity(capacity),
+        spans: Vec::with_capacity(capacity),
+    };
+    if !s.trim().is_empty() {
+        if s.contains(['>', '=']) {
+            parser.relations(s)?;
+        } else {
+            parser.braces(s)?;
+        }
+        // The last one will never be tied, so we'll ignore it.
+        parser.tied.pop();
+    }
+    Ok((parser.order, parser.tied, parser.spans))
+}
+
+struct Parser<'a> {
+    candidates: usize,
+    registry: Option<&'a CandidateRegistry>,
+    seen: Vec<bool>,
+    order: Vec<usize>,
+    tied: Vec<bool>,
+    spans: Vec<Range<usize>>,
+}
+
+impl<'a> Parser<'a> {
+    fn braces(&mut self, s: &str) -> Result<(), ParseOrderError> {
+        // Offset of the `{` of the current group, if we're in one.
+        let mut group: Option<usize> = None;
+        let mut offset = 0;
+        for part in s.split(',') {
+            let (mut token, mut start) = trim(part, offset);
+            offset += part.len() + 1;
+
+            if let Some(t) = token.strip_prefix('{') {
+                if group.is_some() {
+                    return Err(error(ParseOrderErrorKind::UnexpectedBrace, start, "{"));
+                }
+                group = Some(start);
+                (token, start) = trim(t, start + 1);
+            }
+            // Are we ending a group? We check this after the start as this part
+            // may be a group with only one element.
+            let mut closed = false;
+            if group.is_some() {
+                if let Some(t) = token.strip_suffix('}') {
+                    closed = true;
+                    token = t.trim_end();
+                }
+            }
+            self.push(token, start, group.is_some() && !closed)?;
+            if closed {
+                group = None;
+            }
+        }
+        if let Some(start) = group {
+            return Err(error(ParseOrderErrorKind::UnclosedBrace, start, "{"));
+        }
+        Ok(())
+    }
+
+    fn relations(&mut self, s: &str) -> Result<(), ParseOrderError> {
+        let mut offset = 0;
+        for group in s.split('>') {
+            let mut inner = offset;
+            for part in group.split('=') {
+                let (token, start) = trim(part, inner);
+                inner += part.len() + 1;
+                self.push(token, start, true)?;
+            }
+            // The group has at least one element, as `split` never is empty.
+            *self.tied.last_mut().unwrap() = false;
+            offset += group.len() + 1;
+        }
+        Ok(())
+    }
+
+    // Add the candidate `token`, found at byte `start`.
+    fn push(&mut self, token: &str, start: usize, tied: bool) -> Result<(), ParseOrderError> {
+        if let Some(i) = token.find(['{', '}']) {
+            return Err(error(ParseOrderErrorKind::UnexpectedBrace, start + i, &token[i..=i]));
+        }
+        let n = resolve(self.candidates, token, self.registry)
+            .map_err(|kind| error(kind, start, token))?;
+        if self.seen[n] {
+            return Err(error(ParseOrderErrorKind::Duplicate, start, token));
+        }
+        self.seen[n] = true;
+        self.order.push(n);
+        self.tied.push(tied);
+        self.spans.push(start..(start + token.len()));
+        Ok(())
+    }
+}
+
+// Remove whitespace around `s`, which starts at byte `offset`. Returns the new
+// string and where it starts.
+fn trim(s: &str, offset: usize) -> (&str, usize) {
+    let t = s.trim_start();
+    (t.trim_end(), offset + s.len() - t.len())
+}
+
+/// Write `rank` using `dialect`, with names from `registry` if it's given.
+/// [`parse_order`] can read the result.
+///
+/// ```
+/// use votery::formats::{
+///     orders::TiedRank,
+///     parse::{format_order, Dialect},
+/// };
+///
+/// let rank = TiedRank::parse_vote(4, "2 > 0 = 1 > 3").unwrap();
+/// assert_eq!(format_order(rank.as_ref(), None, Dialect::Braces), "2,{0,1},3");
+/// assert_eq!(format_order(rank.as_ref(), None, Dialect::Relations), "2 > 0 = 1 > 3");
+/// ```
+pub fn format_order(
+    rank: TiedRankRef,
+    registry: Option<&CandidateRegistry>,
+    dialect: Dialect,
+) -> String {
+    let mut s = String::new();
+    for (i, group) in rank.iter_groups().enumerate() {
+        let grouped = dialect == Dialect::Braces && group.len() > 1;
+        if i != 0 {
+            s.push_str(if dialect == Dialect::Braces { "," } else { " > " });
+        }
+        if grouped {
+            s.push('{');
+        }
+        for (j, &c) in group.iter().enumerate() {
+            if j != 0 {
+                s.push_str(if dialect == Dialect::Braces { "," } else { " = " });
+            }
+            match registry.and_then(|r| r.name(Candidate(c))) {
+                Some(name) => s.push_str(name),
+                None => write!(s, "{}", c).unwrap(),
+            }
+        }
+        if grouped {
+            s.push('}');
+        }
+    }
+    s
+}
+
+fn resolve(
+    candidates: usize,
+    token: &str,
+    registry: Option<&CandidateRegistry>,
+) -> Result<usize, ParseOrderErrorKind> {
+    if token.is_empty() {
+        return Err(ParseOrderErrorKind::Empty);
+    }
+    registry
+        .and_then(|r| r.candidate(token))
+        .map(usize::from)
+        .or_else(|| token.parse().ok())
+        .filter(|&n| n < candidates)
+        .ok_or(ParseOrderErrorKind::UnknownCandidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::orders::TiedRank;
+
+    #[test]
+    fn error_offsets() {
+        let examples = [
+            ("0,,1", ParseOrderErrorKind::Empty, 2, ""),
+            ("0,{1,7}", ParseOrderErrorKind::UnknownCandidate, 5, "7"),
+            ("0,x", ParseOrderErrorKind::UnknownCandidate, 2, "x"),
+            ("0,{1,0}", ParseOrderErrorKind::Duplicate, 5, "0"),
+            ("0,{1,2", ParseOrderErrorKind::UnclosedBrace, 2, "{"),
+            ("{0,{1}}", ParseOrderErrorKind::UnexpectedBrace, 3, "{"),
+            ("0,1}", ParseOrderErrorKind::UnexpectedBrace, 3, "}"),
+            ("0, { 1 ,2 ", ParseOrderErrorKind::UnclosedBrace, 3, "{"),
+            ("0 > 1 = 0", ParseOrderErrorKind::Duplicate, 8, "0"),
+            ("0 >> 1", ParseOrderErrorKind::Empty, 3, ""),
+            ("0 > {1}", ParseOrderErrorKind::UnexpectedBrace, 4, "{"),
+            ("0 > 1,2", ParseOrderErrorKind::UnknownCandidate, 4, "1,2"),
+        ];
+        for (s, kind, offset, token) in examples {
+            let err = parse_order(5, s, None).unwrap_err();
+            assert_eq!(err, error(kind, offset, token), "{}", s);
+        }
+    }
+
+    #[test]
+    fn names() {
+        let names = ["alice", "bob", "carol"].map(String::from).to_vec();
+        let registry = CandidateRegistry::new(names).unwrap();
+        let (order, tied) = parse_order(3, "carol,{alice,1}", Some(&registry)).unwrap();
+        assert_eq!(order, [2, 0, 1]);
+        assert_eq!(tied, [false, true]);
+        let err = parse_order(3, "carol,dave", Some(&registry)).unwrap_err();
+        assert_eq!(err.to_string(), "unknown candidate `dave` at byte 6");
+        assert!(CandidateRegistry::new(vec!["a,b".to_string()]).is_err());
+        assert!(CandidateRegistry::new(vec![" a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn colors() {
+        let mut registry = CandidateRegistry::new(vec!["a".to_string(), "b".to_string()]).unwrap();
+        let [a, b, c] = [0, 1, 2].map(Candidate);
+        assert_eq!(registry.color(b), None);
+        registry.set_color(b, [255, 0, 16]).unwrap();
+        assert_eq!(registry.color(b), Some([255, 0, 16]));
+        assert_eq!(registry.color(a), None);
+        assert!(registry.set_color(c, [0, 0, 0]).is_err());
+        assert_eq!(registry.candidate("b"), Some(b));
+        assert_eq!(registry.candidates().collect::<Vec<_>>(), [a, b]);
+    }
+
+    #[test]
+    fn whitespace() {
+        let expected = (vec![2, 0, 1, 3], vec![false, true, false]);
+        for s in ["2,{0,1},3", " 2 , { 0 , 1 } , 3 ", "2>0=1>3", "\t2 > 0 =1>  3"] {
+            assert_eq!(parse_order(4, s, None).unwrap(), expected, "{:?}", s);
+        }
+        assert_eq!(parse_order(4, "  ", None).unwrap(), (vec![], vec![]));
+    }
+
+    fn registry(candidates: usize) -> CandidateRegistry {
+        CandidateRegistry::new((0..candidates).map(|i| format!("c{}", i)).collect()).unwrap()
+    }
+
+    #[quickcheck]
+    fn round_trip(rank: TiedRank, names: bool, relations: bool) -> bool {
+        let registry = registry(rank.candidates);
+        let registry = if names { Some(&registry) } else { None };
+        let dialect = if relations { Dialect::Relations } else { Dialect::Braces };
+        let s = format_order(rank.as_ref(), registry, dialect);
+        match parse_order(rank.candidates, &s, registry) {
+            Ok((order, tied)) => TiedRank::new(rank.candidates, order, tied) == rank,
+            Err(_) => false,
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Reading and writing files in the formats of [PrefLib](https://www.preflib.org),
+//! the largest collection of real elections.
+//!
+//! A file starts with metadata on lines beginning with `#`, followed by every
+//! distinct order and how many voters cast it:
+//!
+//! ```text
+//! # DATA TYPE: toi
+//! # NUMBER ALTERNATIVES: 3
+//! # NUMBER VOTERS: 5
+//! # NUMBER UNIQUE ORDERS: 2
+//! # ALTERNATIVE NAME 1: alice
+//! # ALTERNATIVE NAME 2: bob
+//! # ALTERNATIVE NAME 3: carol
+//! 3: 2,{1,3}
+//! 2: 3
+//! ```
+//!
+//! PrefLib numbers the candidates from 1, so candidate `1` in a file is
+//! candidate `0` of the profile. Each data type is read into the format with
+//! the same restrictions:
+//!
+//! | Data type | Format                    |
+//! |-----------|---------------------------|
+//! | `soc`     | [`StrictOrdersComplete`]  |
+//! | `soi`     | [`StrictOrdersIncomplete`]|
+//! | `toc`     | [`TiedOrdersComplete`]    |
+//! | `toi`     | [`TiedOrdersIncomplete`]  |
+//!
+//! A file can also be read into a format with fewer restrictions, e.g. a `soc`
+//! file as a [`TiedOrdersIncomplete`], as long as every vote fits.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use super::{
+    orders::TiedRankRef,
+    parse::{parse_order, CandidateRegistry},
+    soc::StrictOrdersComplete,
+    soi::StrictOrdersIncomplete,
+    toc::TiedOrdersComplete,
+    toi::TiedOrdersIncomplete,
+    VoteFormat,
+};
+
+// Files are untrusted, so larger numbers of candidates are rejected instead of
+// making us allocate memory for them.
+const MAX_CANDIDATES: usize = 1 << 20;
+
+/// The most candidates the votes of a file read by [`read_preflib`] can rank
+/// in total, counting every voter of an order, e.g. 2^24 voters ranking one
+/// candidate each.
+pub const DEFAULT_MAX_RANKED: usize = 1 << 24;
+
+/// The metadata of a PrefLib file.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PrefLibHeader {
+    pub title: Option<String>,
+    /// The data type given by the file, e.g. `soc`.
+    pub data_type: Option<String>,
+    /// The name of every candidate. Candidates without a name in the file are
+    /// called by their number in the file, i.e. `"1"` for the first one.
+    pub names: Vec<String>,
+    pub voters: usize,
+    pub unique_orders: usize,
+    /// Every other line of metadata, e.g. `("FILE NAME",
+    /// "00004-00000001.soc")`.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl PrefLibHeader {
+    /// The names of the candidates, which have to be different.
+    pub fn registry(&self) -> Result<CandidateRegistry, &'static str> {
+        CandidateRegistry::new(self.names.clone())
+    }
+}
+
+/// A format which can be read from and written to a PrefLib file.
+pub trait PrefLib: Sized {
+    /// The data type written to the file.
+    const DATA_TYPE: &'static str;
+    /// If votes can have ties.
+    const TIES: bool;
+    /// If votes must rank every candidate.
+    const COMPLETE: bool;
+
+    fn preflib_new(candidates: usize) -> Self;
+
+    fn preflib_candidates(&self) -> usize;
+
+    /// Add a vote, which has already been checked to fit the format.
+    fn preflib_add(&mut self, order: &[usize], tied: &[bool]);
+
+    /// Call `f` with the order and ties of every vote.
+    fn preflib_for_each(&self, f: &mut dyn FnMut(&[usize], &[bool]));
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Read a PrefLib file into the format `P`.
+///
+/// Returns an error if the file is malformed, if the number of voters doesn't
+/// match the metadata, or if a vote doesn't fit `P`. Files with more than
+/// 2^20 candidates, or where the votes rank more than [`DEFAULT_MAX_RANKED`]
+/// candidates in total, are also rejected.
+pub fn read_preflib<R: BufRead, P: PrefLib>(r: R) -> io::Result<(P, PrefLibHeader)> {
+    read_preflib_limited(r, DEFAULT_MAX_RANKED)
+}
+
+/// Like [`read_preflib`], but rejects files where the votes rank more than
+/// `max_ranked` candidates in total. Every voter is stored as a separate
+/// vote, so this limits the memory used by the votes.
+pub fn read_preflib_limited<R: BufRead, P: PrefLib>(
+    r: R,
+    max_ranked: usize,
+) -> io::Result<(P, PrefLibHeader)> {
+    let mut header = PrefLibHeader::default();
+    let mut names: Vec<(usize, String)> = Vec::new();
+    let mut candidates = None;
+    let mut expected_voters = None;
+    let mut votes: Option<P> = None;
+    let mut ranked = 0;
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let error = |msg: &str| invalid(format!("line {}: {}", i + 1, msg));
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(meta) = line.strip_prefix('#') {
+            if votes.is_some() {
+                return Err(error("metadata after the votes"));
+            }
+            let (key, value) = meta.split_once(':').ok_or_else(|| error("missing `:`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let number = || value.parse::<usize>().map_err(|_| error("expected a number"));
+            if let Some(n) = key.strip_prefix("ALTERNATIVE NAME ") {
+                let n = n.parse().map_err(|_| error("expected a number"))?;
+                names.push((n, value.to_string()));
+                continue;
+            }
+            match key {
+                "TITLE" => header.title = Some(value.to_string()),
+                "DATA TYPE" => header.data_type = Some(value.to_string()),
+                "NUMBER ALTERNATIVES" => {
+                    let n = number()?;
+                    if n > MAX_CANDIDATES {
+                        return Err(error("too many candidates"));
+                    }
+                    candidates = Some(n);
+                }
+                "NUMBER VOTERS" => expected_voters = Some(number()?),
+                "NUMBER UNIQUE ORDERS" => {}
+                _ => header.metadata.push((key.to_string(), value.to_string())),
+            }
+            continue;
+        }
+
+        let candidates = candidates.ok_or_else(|| error("missing NUMBER ALTERNATIVES"))?;
+        let votes = votes.get_or_insert_with(|| P::preflib_new(candidates));
+        let (count, order) = line.split_once(':').ok_or_else(|| error("missing `:`"))?;
+        let count: usize = count.trim().parse().map_err(|_| error("expected a number"))?;
+        let voters = header
+            .voters
+            .checked_add(count)
+            .filter(|&v| expected_voters.is_none_or(|e| v <= e))
+            .ok_or_else(|| error("too many voters"))?;
+        // Parse as if there was a candidate 0, so the ones in the file are
+        // valid and then shift them.
+        let (mut order, tied) =
+            parse_order(candidates + 1, order, None).map_err(|e| error(&e.to_string()))?;
+        if order.is_empty() || order.contains(&0) {
+            return Err(error("invalid vote"));
+        }
+        if !P::TIES && tied.contains(&true) {
+            return Err(error("ties are not allowed"));
+        }
+        if P::COMPLETE && order.len() != candidates {
+            return Err(error("every candidate must be ranked"));
+        }
+        ranked = count
+            .checked_mul(order.len())
+            .and_then(|n| n.checked_add(ranked))
+            .filter(|&n| n <= max_ranked)
+            .ok_or_else(|| error("too many votes"))?;
+        order.iter_mut().for_each(|c| *c -= 1);
+        for _ in 0..count {
+            votes.preflib_add(&order, &tied);
+        }
+        header.voters = voters;
+        header.unique_orders += 1;
+    }
+
+    let candidates = candidates.ok_or_else(|| invalid("missing NUMBER ALTERNATIVES".into()))?;
+    if expected_voters.is_some_and(|v| v != header.voters) {
+        return Err(invalid("NUMBER VOTERS doesn't match the votes".into()));
+    }
+    header.names = (1..=candidates).map(|n| n.to_string()).collect();
+    for (n, name) in names {
+        if n == 0 || n > candidates {
+            return Err(invalid(format!("name of unknown candidate {}", n)));
+        }
+        header.names[n - 1] = name;
+    }
+    Ok((votes.unwrap_or_else(|| P::preflib_new(candidates)), header))
+}
+
+/// Write `votes` as a PrefLib file, with the names of the candidates in
+/// `registry` if it's given. Identical votes are written once with their
+/// count, the most common first.
+pub fn write_preflib<W: Write, P: PrefLib>(
+    w: &mut W,
+    votes: &P,
+    registry: Option<&CandidateRegistry>,
+) -> io::Result<()> {
+    let candidates = votes.preflib_candidates();
+    if registry.is_some_and(|r| r.len() != candidates) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Registry must have every candidate",
+        ));
+    }
+    let mut unique: Vec<(Vec<usize>, Vec<bool>, usize)> = Vec::new();
+    let mut index: HashMap<(Vec<usize>, Vec<bool>), usize> = HashMap::new();
+    votes.preflib_for_each(&mut |order, tied| {
+        let key = (order.to_vec(), tied.to_vec());
+        let i = *index.entry(key).or_insert_with(|| {
+            unique.push((order.to_vec(), tied.to_vec(), 0));
+            unique.len() - 1
+        });
+        unique[i].2 += 1;
+    });
+    unique.sort_by_key(|u| std::cmp::Reverse(u.2));
+    let voters: usize = unique.iter().map(|u| u.2).sum();
+
+    writeln!(w, "# DATA TYPE: {}", P::DATA_TYPE)?;
+    writeln!(w, "# NUMBER ALTERNATIVES: {}", candidates)?;
+    writeln!(w, "# NUMBER VOTERS: {}", voters)?;
+    writeln!(w, "# NUMBER UNIQUE ORDERS: {}", unique.len())?;
+    if let Some(registry) = registry {
+        for c in registry.candidates() {
+            writeln!(w, "# ALTERNATIVE NAME {}: {}", c.0 + 1, registry.name(c).unwrap())?;
+        }
+    }
+    for (order, tied, count) in unique {
+        write!(w, "{}: ", count)?;
+        for (i, group) in TiedRankRef::new(candidates, &order, &tied).iter_groups().enumerate() {
+            if i != 0 {
+                write!(w, ",")?;
+            }
+            if group.len() > 1 {
+                write!(w, "{{")?;
+            }
+            for (j, c) in group.iter().enumerate() {
+                if j != 0 {
+                    write!(w, ",")?;
+                }
+                write!(w, "{}", c + 1)?;
+            }
+            if group.len() > 1 {
+                write!(w, "}}")?;
+            }
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+impl PrefLib for StrictOrdersComplete {
+    const DATA_TYPE: &'static str = "soc";
+    const TIES: bool = false;
+    const COMPLETE: bool = true;
+
+    fn preflib_new(candidates: usize) -> Self {
+        StrictOrdersComplete::new(candidates)
+    }
+
+    fn preflib_candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn preflib_add(&mut self, order: &[usize], _: &[bool]) {
+        self.add(order);
+    }
+
+    fn preflib_for_each(&self, f: &mut dyn FnMut(&[usize], &[bool])) {
+        let tied = vec![false; self.candidates.saturating_sub(1)];
+        for vote in self {
+            f(vote, &tied);
+        }
+    }
+}
+
+impl PrefLib for StrictOrdersIncomplete {
+    const DATA_TYPE: &'static str = "soi";
+    const TIES: bool = false;
+    const COMPLETE: bool = false;
+
+    fn preflib_new(candidates: usize) -> Self {
+        StrictOrdersIncomplete::new(candidates)
+    }
+
+    fn preflib_candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn preflib_add(&mut self, order: &[usize], _: &[bool]) {
+        self.add(order).unwrap();
+    }
+
+    fn preflib_for_each(&self, f: &mut dyn FnMut(&[usize], &[bool])) {
+        let tied = vec![false; self.candidates.saturating_sub(1)];
+        for vote in self {
+            f(vote, &tied[..(vote.len() - 1)]);
+        }
+    }
+}
+
+impl PrefLib for TiedOrdersComplete {
+    const DATA_TYPE: &'static str = "toc";
+    const TIES: bool = true;
+    const COMPLETE: bool = true;
+
+    fn preflib_new(candidates: usize) -> Self {
+        TiedOrdersComplete::new(candidates)
+    }
+
+    fn preflib_candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn preflib_add(&mut self, order: &[usize], tied: &[bool]) {
+        self.add(TiedRankRef::new(self.candidates, order, tied));
+    }
+
+    fn preflib_for_each(&self, f: &mut dyn FnMut(&[usize], &[bool])) {
+        for vote in self {
+            f(vote.order(), vote.tied());
+        }
+    }
+}
+
+impl PrefLib for TiedOrdersIncomplete {
+    const DATA_TYPE: &'static str = "toi";
+    const TIES: bool = true;
+    const COMPLETE: bool = false;
+
+    fn preflib_new(candidates: usize) -> Self {
+        TiedOrdersIncomplete::new(candidates)
+    }
+
+    fn preflib_candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn preflib_add(&mut self, order: &[usize], tied: &[bool]) {
+        self.add(TiedRankRef::new(self.candidates, order, tied)).unwrap();
+    }
+
+    fn preflib_for_each(&self, f: &mut dyn FnMut(&[usize], &[bool])) {
+        for vote in self {
+            f(vote.order(), vote.tied());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "# FILE NAME: 00000-00000001.toi
+# TITLE: Example
+# DATA TYPE: toi
+# NUMBER ALTERNATIVES: 3
+# NUMBER VOTERS: 5
+# NUMBER UNIQUE ORDERS: 2
+# ALTERNATIVE NAME 1: alice
+# ALTERNATIVE NAME 3: carol
+3: 2,{1,3}
+2: 3
+";
+
+    #[test]
+    fn read_example() {
+        let (votes, header) = read_preflib::<_, TiedOrdersIncomplete>(FILE.as_bytes()).unwrap();
+        assert_eq!(header.title.as_deref(), Some("Example"));
+        assert_eq!(header.names, ["alice", "2", "carol"]);
+        assert_eq!((header.voters, header.unique_orders), (5, 2));
+        assert_eq!(header.metadata, [("FILE NAME".to_string(), "00000-00000001.toi".to_string())]);
+        let mut expected = TiedOrdersIncomplete::new(3);
+        expected.add_from_str_i("1,{0,2}", 3);
+        expected.add_from_str_i("2", 2);
+        assert_eq!(votes, expected);
+
+        // The votes don't fit these formats.
+        assert!(read_preflib::<_, TiedOrdersComplete>(FILE.as_bytes()).is_err());
+        assert!(read_preflib::<_, StrictOrdersIncomplete>(FILE.as_bytes()).is_err());
+        let wrong_count = FILE.replace("VOTERS: 5", "VOTERS: 6");
+        assert!(read_preflib::<_, TiedOrdersIncomplete>(wrong_count.as_bytes()).is_err());
+        let unknown = FILE.replace("2: 3", "2: 4");
+        assert!(read_preflib::<_, TiedOrdersIncomplete>(unknown.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn untrusted_counts() {
+        let huge = |from: &str, to: String| {
+            let file = FILE.replace(from, &to);
+            read_preflib::<_, TiedOrdersIncomplete>(file.as_bytes()).unwrap_err().to_string()
+        };
+        let max = usize::MAX.to_string();
+        assert_eq!(
+            huge("ALTERNATIVES: 3", format!("ALTERNATIVES: {}", max)),
+            "line 4: too many candidates"
+        );
+        // More voters than in the metadata are rejected before they're added.
+        assert_eq!(huge("2: 3", format!("{}: 3", max)), "line 10: too many voters");
+        // Without the metadata, the votes can't rank too many candidates.
+        let without_count = FILE.replace("# NUMBER VOTERS: 5\n", "");
+        for count in [u32::MAX, DEFAULT_MAX_RANKED as u32 + 1] {
+            let file = without_count.replace("2: 3", &format!("{}: 3", count));
+            let error = read_preflib::<_, TiedOrdersIncomplete>(file.as_bytes()).unwrap_err();
+            assert_eq!(error.to_string(), "line 9: too many votes");
+        }
+        // 3 voters rank 3 candidates and 2 voters rank 1.
+        let read = |max_ranked| {
+            read_preflib_limited::<_, TiedOrdersIncomplete>(FILE.as_bytes(), max_ranked)
+        };
+        assert!(read(10).is_err());
+        assert_eq!(read(11).unwrap().1.voters, 5);
+    }
+
+    #[test]
+    fn write_example() {
+        let (votes, header) = read_preflib::<_, TiedOrdersIncomplete>(FILE.as_bytes()).unwrap();
+        let mut buf = Vec::new();
+        write_preflib(&mut buf, &votes, Some(&header.registry().unwrap())).unwrap();
+        let written = String::from_utf8(buf).unwrap();
+        assert!(written.ends_with("# ALTERNATIVE NAME 3: carol\n3: 2,{1,3}\n2: 3\n"));
+        let (read, _) = read_preflib::<_, TiedOrdersIncomplete>(written.as_bytes()).unwrap();
+        assert_eq!(read, votes);
+    }
+
+    #[quickcheck]
+    fn roundtrip(votes: TiedOrdersIncomplete) -> bool {
+        let mut buf = Vec::new();
+        write_preflib(&mut buf, &votes, None).unwrap();
+        let (read, header) = read_preflib::<_, TiedOrdersIncomplete>(&buf[..]).unwrap();
+        // Identical votes are grouped, so only compare the sorted votes.
+        let sorted = |v: &TiedOrdersIncomplete| {
+            let mut all: Vec<_> =
+                v.into_iter().map(|v| (v.order().to_vec(), v.tied().to_vec())).collect();
+            all.sort();
+            all
+        };
+        header.voters == votes.voters() && sorted(&read) == sorted(&votes)
+    }
+}
@@ -0,0 +1,184 @@
+use super::orders::TiedRankRef;
+use crate::methods::TieScoring;
+
+/// How often every candidate was ranked at every position, counted from a
+/// list of ranked votes.
+///
+/// A group of `t` tied candidates spans `t` positions, and how it is counted
+/// depends on the [`TieScoring`]:
+/// - `Average`: every candidate is counted as `1 / t` at each spanned position.
+/// - `Max`: every candidate is counted at the first spanned position.
+/// - `Min`: every candidate is counted at the last spanned position.
+/// - `Split`: every candidate is counted as `1 / t` at the first spanned
+///   position.
+///
+/// To stay integers, every count is multiplied by [`scale`](Self::scale).
+/// Candidates which a vote doesn't rank aren't counted for that vote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankDistribution {
+    // Has length candidates * candidates, where row `c` holds the counts of
+    // candidate `c`.
+    counts: Vec<usize>,
+    candidates: usize,
+    scale: usize,
+}
+
+impl RankDistribution {
+    pub(crate) fn count<'a, I>(
+        candidates: usize,
+        votes: I,
+        tie_scoring: TieScoring,
+    ) -> Result<Self, &'static str>
+    where
+        I: IntoIterator<Item = TiedRankRef<'a>> + Clone,
+    {
+        let scale = tie_scoring.scale(votes.clone())?;
+        let mut counts: Vec<usize> = vec![0; candidates * candidates];
+        for vote in votes {
+            let mut seen = 0;
+            for group in vote.iter_groups() {
+                let len = group.len();
+                let (positions, amount) = match tie_scoring {
+                    TieScoring::Average => (seen..(seen + len), scale / len),
+                    TieScoring::Max => (seen..(seen + 1), scale),
+                    TieScoring::Min => ((seen + len - 1)..(seen + len), scale),
+                    TieScoring::Split => (seen..(seen + 1), scale / len),
+                };
+                for &c in group {
+                    for p in positions.clone() {
+                        let count = &mut counts[c * candidates + p];
+                        *count =
+                            count.checked_add(amount).ok_or("Integer overflow: Too many votes")?;
+                    }
+                }
+                seen += len;
+            }
+        }
+        Ok(RankDistribution { counts, candidates, scale })
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    /// The factor every count was multiplied with.
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    /// How often `candidate` was ranked at `position`, multiplied by
+    /// [`scale`](Self::scale).
+    pub fn get(&self, candidate: usize, position: usize) -> usize {
+        assert!(position < self.candidates);
+        self.counts[candidate * self.candidates + position]
+    }
+
+    /// The counts of `candidate` at every position, multiplied by
+    /// [`scale`](Self::scale).
+    pub fn row(&self, candidate: usize) -> &[usize] {
+        &self.counts[candidate * self.candidates..(candidate + 1) * self.candidates]
+    }
+
+    /// How often `candidate` was ranked at `position`.
+    pub fn frequency(&self, candidate: usize, position: usize) -> f64 {
+        self.get(candidate, position) as f64 / self.scale as f64
+    }
+
+    /// The average position of `candidate`, where the highest position is 0,
+    /// over the votes which rank it. Returns `None` if no vote ranks it.
+    pub fn average_rank(&self, candidate: usize) -> Option<f64> {
+        let row = self.row(candidate);
+        let total: usize = row.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let sum: f64 = row.iter().enumerate().map(|(p, &n)| p as f64 * n as f64).sum();
+        Some(sum / total as f64)
+    }
+
+    /// The positional score of every candidate, where `weights[i]` is the
+    /// number of points for position `i`, multiplied by
+    /// [`scale`](Self::scale). Positions after `weights` give no points.
+    ///
+    /// This matches [`Positional`](crate::methods::Positional) with the same
+    /// `TieScoring`, except that `Max` and `Min` only match when `weights` is
+    /// non-increasing.
+    pub fn positional_score(&self, weights: &[usize]) -> Result<Vec<usize>, &'static str> {
+        let mut score: Vec<usize> = vec![0; self.candidates];
+        for (c, s) in score.iter_mut().enumerate() {
+            for (&n, &w) in self.row(c).iter().zip(weights) {
+                *s = n
+                    .checked_mul(w)
+                    .and_then(|points| s.checked_add(points))
+                    .ok_or("Integer overflow: Too many points")?;
+            }
+        }
+        Ok(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formats::{toi::TiedOrdersIncomplete, VoteFormat},
+        methods::Positional,
+    };
+
+    const TIE_SCORINGS: [TieScoring; 4] =
+        [TieScoring::Average, TieScoring::Max, TieScoring::Min, TieScoring::Split];
+
+    #[test]
+    fn tie_scoring() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("0,{1,2}");
+        votes.add_from_str("1");
+        let dist = |t| votes.rank_distribution(t).unwrap();
+
+        let average = dist(TieScoring::Average);
+        assert_eq!(average.scale(), 2);
+        assert_eq!(average.row(0), [2, 0, 0]);
+        assert_eq!(average.row(1), [2, 1, 1]);
+        assert_eq!(average.row(2), [0, 1, 1]);
+        assert_eq!(average.frequency(1, 0), 1.0);
+        assert_eq!(average.average_rank(1), Some(0.75));
+        assert_eq!(average.average_rank(2), Some(1.5));
+
+        let max = dist(TieScoring::Max);
+        assert_eq!((max.row(1), max.row(2)), (&[1, 1, 0][..], &[0, 1, 0][..]));
+        let min = dist(TieScoring::Min);
+        assert_eq!((min.row(1), min.row(2)), (&[1, 0, 1][..], &[0, 0, 1][..]));
+        let split = dist(TieScoring::Split);
+        assert_eq!((split.row(1), split.row(2)), (&[2, 1, 0][..], &[0, 1, 0][..]));
+
+        let mut unranked = TiedOrdersIncomplete::new(2);
+        unranked.add_from_str("0");
+        let dist = unranked.rank_distribution(TieScoring::Average).unwrap();
+        assert_eq!(dist.average_rank(1), None);
+    }
+
+    #[quickcheck]
+    fn rows_count_ranking_voters(votes: TiedOrdersIncomplete) -> bool {
+        // `Split` only counts a share of each tied candidate.
+        [TieScoring::Average, TieScoring::Max, TieScoring::Min].into_iter().all(|t| {
+            let Ok(dist) = votes.rank_distribution(t) else {
+                return true;
+            };
+            (0..votes.candidates()).all(|c| {
+                let ranked = votes.into_iter().filter(|v| v.order().contains(&c)).count();
+                dist.row(c).iter().sum::<usize>() == ranked * dist.scale()
+            })
+        })
+    }
+
+    #[quickcheck]
+    fn matches_positional(votes: TiedOrdersIncomplete, weights: Vec<u8>) -> bool {
+        let mut weights: Vec<usize> = weights.into_iter().map(usize::from).collect();
+        weights.sort_unstable_by(|a, b| b.cmp(a));
+        TIE_SCORINGS.into_iter().all(|t| {
+            let score = votes.rank_distribution(t).and_then(|d| d.positional_score(&weights));
+            let expected = Positional::count(&votes, &weights, t).map(|p| p.get_score().to_vec());
+            score == expected
+        })
+    }
+}
@@ -1,18 +1,162 @@
+use std::{fmt::Debug, marker::PhantomData};
+
 use rand::seq::SliceRandom;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::view::new_indices;
+use crate::enumerate::{strict_from_index, strict_index, MAX_INDEXED};
+
+/// How the votes of a [`StrictOrdersComplete`] are laid out in memory.
+pub trait Layout: Clone + Debug {
+    /// The candidates ranked at `position` by every voter, given the packed
+    /// `votes` of `candidates` candidates.
+    fn position(
+        votes: &[usize],
+        candidates: usize,
+        position: usize,
+    ) -> impl Iterator<Item = usize> + '_;
+}
+
+/// Every vote is contiguous, which is the default and the only layout votes
+/// can be added to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowMajor;
+
+/// Every position is contiguous, i.e. the first preference of every voter
+/// comes first, then the second preference of every voter and so on. This
+/// makes positional methods and first preference tallies faster.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnMajor;
+
+impl Layout for RowMajor {
+    fn position(
+        votes: &[usize],
+        candidates: usize,
+        position: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        votes.chunks_exact(candidates).map(move |v| v[position])
+    }
+}
+
+impl Layout for ColumnMajor {
+    fn position(
+        votes: &[usize],
+        candidates: usize,
+        position: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let voters = votes.len() / candidates;
+        votes[(position * voters)..((position + 1) * voters)].iter().copied()
+    }
+}
 
 /// SOC - Strict Orders - Complete List
 ///
 /// A packed list of complete strict orders, with related methods. Each vote is
-/// a permutation of the candidates
+/// a permutation of the candidates. The votes are stored according to `L`,
+/// see [`RowMajor`] and [`ColumnMajor`].
 #[derive(Clone, Debug)]
-pub struct StrictOrdersComplete {
+pub struct StrictOrdersComplete<L: Layout = RowMajor> {
     pub(crate) votes: Vec<usize>,
     pub candidates: usize,
+    layout: PhantomData<L>,
+}
+
+serde_checked!(StrictOrdersComplete { votes: Vec<usize>, candidates: usize }, layout: PhantomData);
+
+impl<L: Layout> StrictOrdersComplete<L> {
+    pub fn voters(&self) -> usize {
+        debug_assert!(self.votes.len() % self.candidates == 0);
+        self.votes.len() / self.candidates
+    }
+
+    /// The candidate every voter ranked at `position`.
+    pub fn position(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        assert!(position < self.candidates);
+        L::position(&self.votes, self.candidates, position)
+    }
+
+    /// The number of voters who ranked every candidate first.
+    pub fn first_preferences(&self) -> Vec<usize> {
+        let mut score = vec![0; self.candidates];
+        if self.candidates != 0 {
+            for c in self.position(0) {
+                score[c] += 1;
+            }
+        }
+        score
+    }
+
+    /// The score of every candidate when position `i` gives `weights[i]`
+    /// points. Positions after `weights` give no points.
+    pub fn positional_score(&self, weights: &[usize]) -> Result<Vec<usize>, &'static str> {
+        let mut score: Vec<usize> = vec![0; self.candidates];
+        for (position, &w) in weights.iter().enumerate().take(self.candidates) {
+            for c in self.position(position) {
+                score[c] = score[c].checked_add(w).ok_or("Integer overflow: Too many points")?;
+            }
+        }
+        Ok(score)
+    }
+}
+
+impl StrictOrdersComplete<ColumnMajor> {
+    /// The candidate every voter ranked at `position`, see
+    /// [`position`](StrictOrdersComplete::position).
+    pub fn column(&self, position: usize) -> &[usize] {
+        let voters = self.voters();
+        &self.votes[(position * voters)..((position + 1) * voters)]
+    }
+
+    pub fn to_row_major(&self) -> StrictOrdersComplete {
+        let mut votes = vec![0; self.votes.len()];
+        let voters = self.voters();
+        for (position, column) in self.votes.chunks_exact(voters.max(1)).enumerate() {
+            for (i, &c) in column.iter().enumerate() {
+                votes[i * self.candidates + position] = c;
+            }
+        }
+        StrictOrdersComplete { votes, candidates: self.candidates, layout: PhantomData }
+    }
 }
 
 impl StrictOrdersComplete {
     pub fn new(candidates: usize) -> Self {
-        StrictOrdersComplete { votes: Vec::new(), candidates }
+        StrictOrdersComplete { votes: Vec::new(), candidates, layout: PhantomData }
+    }
+
+    /// Every vote, like iterating over `&self`, but in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = &[usize]> {
+        self.votes.par_chunks_exact(self.candidates)
+    }
+
+    /// Lay out the votes by position instead, see [`ColumnMajor`]. Votes can
+    /// not be added afterwards, so this is best done once every vote is
+    /// added.
+    pub fn to_column_major(&self) -> StrictOrdersComplete<ColumnMajor> {
+        let voters = self.voters();
+        let mut votes = vec![0; self.votes.len()];
+        for (i, vote) in self.into_iter().enumerate() {
+            for (position, &c) in vote.iter().enumerate() {
+                votes[position * voters + i] = c;
+            }
+        }
+        StrictOrdersComplete { votes, candidates: self.candidates, layout: PhantomData }
+    }
+
+    /// A copy of the votes with only the candidates in `keep`, which are given
+    /// new indices in the same order as their original indices.
+    pub fn restrict(&self, keep: &[usize]) -> Result<Self, &'static str> {
+        if keep.is_empty() {
+            return Err("Can not remove the last candidate");
+        }
+        let new_index = new_indices(self.candidates, keep)?;
+        let votes = self.votes.iter().filter_map(|&c| new_index[c]).collect();
+        let restricted =
+            StrictOrdersComplete { votes, candidates: keep.len(), layout: PhantomData };
+        debug_assert!(restricted.valid());
+        Ok(restricted)
     }
 
     pub fn add(&mut self, vote: &[usize]) {
@@ -27,11 +171,6 @@ impl StrictOrdersComplete {
         debug_assert!(self.valid());
     }
 
-    pub fn voters(&self) -> usize {
-        debug_assert!(self.votes.len() % self.candidates == 0);
-        self.votes.len() / self.candidates
-    }
-
     /// Return true if it was a valid vote.
     pub fn add_from_str(&mut self, s: &str) -> bool {
         let mut vote = Vec::with_capacity(self.candidates);
@@ -56,7 +195,13 @@ impl StrictOrdersComplete {
     }
 
     /// Returns true if this struct is in a valid state, used for debugging.
-    fn valid(&self) -> bool {
+    pub(crate) fn valid(&self) -> bool {
+        if self.candidates == 0 {
+            return self.votes.is_empty();
+        }
+        if !self.votes.len().is_multiple_of(self.candidates) {
+            return false;
+        }
         for vote in self {
             let mut seen = vec![false; self.candidates];
             for &i in vote {
@@ -83,6 +228,29 @@ impl StrictOrdersComplete {
         }
         debug_assert!(self.valid());
     }
+
+    /// The index of every vote, see [`strict_index`]. Returns an error if
+    /// there are too many candidates for the indices to fit in a `u64`.
+    pub fn to_indices(&self) -> Result<Vec<u64>, &'static str> {
+        if self.candidates > MAX_INDEXED {
+            return Err("Too many candidates to index");
+        }
+        Ok(self.into_iter().map(strict_index).collect())
+    }
+
+    /// The profile of `candidates` candidates with a vote for every index in
+    /// `indices`, the inverse of
+    /// [`to_indices`](StrictOrdersComplete::to_indices).
+    pub fn from_indices(candidates: usize, indices: &[u64]) -> Result<Self, &'static str> {
+        if candidates == 0 {
+            return Err("Need at least one candidate");
+        }
+        let mut votes = StrictOrdersComplete::new(candidates);
+        for &index in indices {
+            votes.add(strict_from_index(candidates, index)?.as_ref().order());
+        }
+        Ok(votes)
+    }
 }
 
 impl<'a> IntoIterator for &'a StrictOrdersComplete {
@@ -102,6 +270,9 @@ pub struct StrictOrdersCompleteIterator<'a> {
 impl<'a> Iterator for StrictOrdersCompleteIterator<'a> {
     type Item = &'a [usize];
     fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.orig.voters() {
+            return None;
+        }
         let len = self.orig.candidates;
         let start = self.i * self.orig.candidates;
         let vote = &self.orig.votes[start..(start + len)];
@@ -116,3 +287,25 @@ impl<'a> Iterator for StrictOrdersCompleteIterator<'a> {
 }
 
 impl<'a> ExactSizeIterator for StrictOrdersCompleteIterator<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[quickcheck]
+    fn layouts_agree(seed: u64, voters: u8, candidates: u8) -> bool {
+        let candidates = usize::from(candidates % 8) + 1;
+        let mut votes = StrictOrdersComplete::new(candidates);
+        votes.generate_uniform(&mut ChaCha8Rng::seed_from_u64(seed), usize::from(voters));
+        let columns = votes.to_column_major();
+        let borda: Vec<usize> = (0..candidates).rev().collect();
+        columns.voters() == votes.voters()
+            && columns.first_preferences() == votes.first_preferences()
+            && columns.positional_score(&borda) == votes.positional_score(&borda)
+            && (0..candidates).all(|p| columns.column(p).iter().copied().eq(votes.position(p)))
+            && columns.to_row_major().votes == votes.votes
+    }
+}
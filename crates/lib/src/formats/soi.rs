@@ -1,6 +1,9 @@
-use rand::{distributions::Uniform, prelude::Distribution, seq::SliceRandom};
+use rand::{prelude::Distribution, seq::SliceRandom};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-use super::{soc::StrictOrdersComplete, VoteFormat};
+use super::{soc::StrictOrdersComplete, view::new_indices, BallotLength, VoteFormat};
+use crate::election::Voter;
 
 /// SOI - Strict Orders - Incomplete List
 ///
@@ -14,6 +17,12 @@ pub struct StrictOrdersIncomplete {
     pub candidates: usize,
 }
 
+serde_checked!(StrictOrdersIncomplete {
+    votes: Vec<usize>,
+    vote_len: Vec<usize>,
+    candidates: usize,
+});
+
 impl StrictOrdersIncomplete {
     pub fn new(candidates: usize) -> Self {
         StrictOrdersIncomplete { votes: Vec::new(), vote_len: Vec::new(), candidates }
@@ -23,6 +32,49 @@ impl StrictOrdersIncomplete {
         self.vote_len.len()
     }
 
+    /// Every vote, like iterating over `&self`, but in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = &[usize]> {
+        // Where every vote starts, as the votes have different lengths.
+        let starts: Vec<usize> = self
+            .vote_len
+            .iter()
+            .scan(0, |start, &len| {
+                *start += len;
+                Some(*start - len)
+            })
+            .collect();
+        starts
+            .into_par_iter()
+            .zip(&self.vote_len)
+            .map(|(start, &len)| &self.votes[start..(start + len)])
+    }
+
+    /// Add `new_voters` random votes, where the number of ranked candidates
+    /// of every vote is sampled from `length`, and the ranked candidates are
+    /// uniformly random.
+    pub fn generate_with_length<R: rand::Rng>(
+        &mut self,
+        rng: &mut R,
+        new_voters: usize,
+        length: &BallotLength,
+    ) -> Result<(), &'static str> {
+        if self.candidates == 0 {
+            return Ok(());
+        }
+        let mut v: Vec<usize> = (0..self.candidates).collect();
+        self.votes.reserve(self.candidates * new_voters);
+        let lengths = length.sampler(self.candidates)?;
+        for _ in 0..new_voters {
+            let candidates = lengths.sample(rng);
+            v.shuffle(rng);
+            self.votes.extend_from_slice(&v[..candidates]);
+            self.vote_len.push(candidates);
+        }
+        debug_assert!(self.valid());
+        Ok(())
+    }
+
     /// Return true if it was a valid vote.
     pub fn add_from_str(&mut self, s: &str) -> bool {
         let mut vote = Vec::with_capacity(self.candidates);
@@ -44,7 +96,11 @@ impl StrictOrdersIncomplete {
     }
 
     /// Returns true if this struct is in a valid state, used for debugging.
-    fn valid(&self) -> bool {
+    pub(crate) fn valid(&self) -> bool {
+        let total = self.vote_len.iter().try_fold(0usize, |sum, &len| sum.checked_add(len));
+        if self.vote_len.contains(&0) || total != Some(self.votes.len()) {
+            return false;
+        }
         let mut seen = vec![false; self.candidates];
         for vote in self {
             seen.fill(false);
@@ -58,8 +114,32 @@ impl StrictOrdersIncomplete {
         true
     }
 
-    pub fn vote_i(&self, i: usize) -> &[usize] {
-        todo!();
+    /// The vote of `voter`. This sums the lengths of the earlier votes, so
+    /// iterate over the votes to look up every vote.
+    pub fn vote(&self, voter: Voter) -> &[usize] {
+        let i = voter.index();
+        let start: usize = self.vote_len[..i].iter().sum();
+        &self.votes[start..(start + self.vote_len[i])]
+    }
+
+    /// A copy of the votes with only the candidates in `keep`, which are given
+    /// new indices in the same order as their original indices. Votes which
+    /// only ranked removed candidates are removed.
+    pub fn restrict(&self, keep: &[usize]) -> Result<Self, &'static str> {
+        if keep.is_empty() {
+            return Err("Can not remove the last candidate");
+        }
+        let new_index = new_indices(self.candidates, keep)?;
+        let mut restricted = StrictOrdersIncomplete::new(keep.len());
+        for vote in self {
+            let start = restricted.votes.len();
+            restricted.votes.extend(vote.iter().filter_map(|&c| new_index[c]));
+            if restricted.votes.len() > start {
+                restricted.vote_len.push(restricted.votes.len() - start);
+            }
+        }
+        debug_assert!(restricted.valid());
+        Ok(restricted)
     }
 }
 
@@ -71,12 +151,12 @@ impl<'a> VoteFormat<'a> for StrictOrdersIncomplete {
     }
 
     fn add(&mut self, v: Self::Vote) -> Result<(), &'static str> {
-        debug_assert!(v.len() < self.candidates);
+        debug_assert!(v.len() <= self.candidates);
         debug_assert!(0 < v.len());
         self.votes.reserve(v.len());
         let mut seen = vec![false; self.candidates];
         for &i in v {
-            debug_assert!(i < self.candidates || !seen[i]);
+            debug_assert!(i < self.candidates && !seen[i]);
             seen[i] = true;
             self.votes.push(i);
         }
@@ -90,21 +170,7 @@ impl<'a> VoteFormat<'a> for StrictOrdersIncomplete {
     }
 
     fn generate_uniform<R: rand::Rng>(&mut self, rng: &mut R, new_voters: usize) {
-        if self.candidates == 0 {
-            return;
-        }
-        let mut v: Vec<usize> = (0..self.candidates).collect();
-        self.votes.reserve(self.candidates * new_voters);
-        let range = Uniform::from(0..self.candidates);
-        for _ in 0..new_voters {
-            let candidates = range.sample(rng) + 1;
-            v.shuffle(rng);
-            for i in 0..candidates {
-                self.votes.push(v[i]);
-            }
-            self.vote_len.push(candidates);
-        }
-        debug_assert!(self.valid());
+        self.generate_with_length(rng, new_voters, &BallotLength::Uniform).unwrap();
     }
 
     fn to_partial_ranking(self) -> super::toi::TiedOrdersIncomplete {
@@ -130,7 +196,7 @@ pub struct StrictOrdersIncompleteIterator<'a> {
 impl<'a> Iterator for StrictOrdersIncompleteIterator<'a> {
     type Item = &'a [usize];
     fn next(&mut self) -> Option<Self::Item> {
-        let len = self.orig.vote_len[self.i];
+        let len = *self.orig.vote_len.get(self.i)?;
         let vote = &self.orig.votes[self.start..(self.start + len)];
         self.i += 1;
         self.start += len;
@@ -2,12 +2,44 @@ use std::{fmt, fmt::Display, io::BufRead};
 
 use rand::{
     distributions::{Distribution, Uniform},
+    seq::SliceRandom,
     Rng,
 };
 
 use super::{remove_newline, toi::TiedOrdersIncomplete, VoteFormat};
 use crate::pairwise_lt;
 
+/// How to convert a vote with multiple candidates tied first into a vote for a
+/// single candidate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TiePolicy {
+    /// Pick the tied candidate which is listed first in the vote.
+    FirstListed,
+    /// Pick one of the tied candidates uniformly at random.
+    #[default]
+    Random,
+    /// Fail the conversion.
+    Error,
+}
+
+impl TiePolicy {
+    /// Pick a single candidate from `winners` according to the policy.
+    pub(crate) fn pick<R: Rng>(
+        self,
+        winners: &[usize],
+        rng: &mut R,
+    ) -> Result<usize, &'static str> {
+        match self {
+            _ if winners.is_empty() => Err("Vote did not rank any candidate"),
+            TiePolicy::Random => Ok(*winners.choose(rng).unwrap()),
+            TiePolicy::FirstListed => Ok(winners[0]),
+            TiePolicy::Error if winners.len() > 1 => Err("Vote had multiple candidates tied first"),
+            TiePolicy::Error => Ok(winners[0]),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Specific {
     // number of voters = votes.len()
@@ -15,6 +47,8 @@ pub struct Specific {
     pub(crate) candidates: usize,
 }
 
+serde_checked!(Specific { votes: Vec<usize>, candidates: usize });
+
 impl Specific {
     pub fn new(candidates: usize) -> Self {
         Specific { votes: Vec::new(), candidates }
@@ -33,7 +67,7 @@ impl Specific {
 
     // Checks if all invariants of the format are valid, used in debug_asserts and
     // tests
-    fn valid(&self) -> bool {
+    pub(crate) fn valid(&self) -> bool {
         if self.candidates == 0 && !self.votes.is_empty() {
             return false;
         }
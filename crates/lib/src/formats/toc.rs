@@ -1,7 +1,22 @@
 use rand::{distributions::Bernoulli, prelude::Distribution, seq::SliceRandom};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use super::{
-    orders::TiedRankRef, soc::StrictOrdersComplete, toi::TiedOrdersIncomplete, Cardinal, Specific,
+    orders::{TiedRank, TiedRankRef},
+    soc::StrictOrdersComplete,
+    toi::TiedOrdersIncomplete,
+    view::ProfileView,
+    Cardinal, CoRanking, RankDistribution, Specific, TiePolicy,
+};
+use crate::{
+    election::Voter,
+    enumerate::{tied_from_index, tied_index, MAX_INDEXED},
+    methods::{
+        pairwise::{HeadToHead, PairwiseMatrix},
+        TieScoring,
+    },
+    profile,
 };
 
 /// TOC - Orders with Ties - Complete List
@@ -18,6 +33,8 @@ pub struct TiedOrdersComplete {
     pub candidates: usize,
 }
 
+serde_checked!(TiedOrdersComplete { votes: Vec<usize>, ties: Vec<bool>, candidates: usize });
+
 impl TiedOrdersComplete {
     pub fn new(candidates: usize) -> Self {
         TiedOrdersComplete { votes: Vec::new(), ties: Vec::new(), candidates }
@@ -46,59 +63,44 @@ impl TiedOrdersComplete {
         self.votes.len() / self.candidates
     }
 
+    /// Every vote, like iterating over `&self`, but in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = TiedRankRef<'_>> {
+        let c = self.candidates;
+        (0..self.voters()).into_par_iter().map(move |i| {
+            let order = &self.votes[(i * c)..((i + 1) * c)];
+            let tied = &self.ties[(i * (c - 1))..((i + 1) * (c - 1))];
+            TiedRankRef::new(c, order, tied)
+        })
+    }
+
     /// Add a single vote from a string. Return true if it was a valid vote.
     pub fn add_from_str(&mut self, s: &str) -> bool {
-        let mut vote: Vec<usize> = Vec::with_capacity(self.candidates);
-        let mut tie: Vec<bool> = Vec::with_capacity(self.candidates);
-        let mut grouped = false;
-        for part in s.split(',') {
-            let number: &str = if grouped {
-                part.strip_suffix('}').map_or(part, |s| {
-                    grouped = !grouped;
-                    s
-                })
-            } else {
-                part.strip_prefix('{').map_or(part, |s| {
-                    grouped = !grouped;
-                    s
-                })
-            };
-            let n: usize = match number.parse() {
-                Ok(n) => n,
-                Err(_) => return false,
-            };
-            if !(n < self.candidates) {
-                return false;
+        match TiedRank::parse_vote(self.candidates, s) {
+            // We didn't list all candidates
+            Ok(vote) if vote.len() == self.candidates => {
+                self.add(vote.as_ref());
+                true
             }
-            vote.push(n);
-            tie.push(grouped);
-        }
-        // The last one will never be tied, so we'll ignore it.
-        tie.pop();
-
-        // We didn't end our group or we didn't list all candidates
-        if grouped || vote.len() != self.candidates {
-            return false;
+            _ => false,
         }
-        self.add(TiedRankRef::new(self.candidates, &vote, &tie));
-        debug_assert!(self.valid());
-        true
     }
 
     /// Returns true if this struct is in a valid state, used for debugging.
-    fn valid(&self) -> bool {
-        if self.votes.len() != self.voters() * self.candidates
+    pub(crate) fn valid(&self) -> bool {
+        if self.candidates == 0 {
+            return self.votes.is_empty() && self.ties.is_empty();
+        }
+        if !self.votes.len().is_multiple_of(self.candidates)
             || self.ties.len() != self.voters() * (self.candidates - 1)
         {
             return false;
         }
         let mut seen = vec![false; self.candidates];
-        for vote in self {
+        // Not using `TiedRankRef`, as it assumes the vote is valid.
+        for vote in self.votes.chunks_exact(self.candidates) {
             seen.fill(false);
-            if vote.order().len() != self.candidates || vote.tied().len() != self.candidates - 1 {
-                return false;
-            }
-            for &i in vote.order() {
+            for &i in vote {
                 if i >= self.candidates || seen[i] {
                     return false;
                 }
@@ -130,13 +132,29 @@ impl TiedOrdersComplete {
         debug_assert!(self.valid());
     }
 
+    /// Convert every vote to a vote for one of its highest ranked candidates,
+    /// picked uniformly at random.
     pub fn to_specific_using<R: rand::Rng>(self, rng: &mut R) -> Specific {
-        let candidates = self.candidates;
-        let mut votes: Specific =
-            self.into_iter().map(|v| *v.winners().choose(rng).unwrap()).collect();
+        self.to_specific_with(TiePolicy::Random, rng).unwrap()
+    }
 
-        votes.set_candidates(candidates);
-        votes
+    /// Convert every vote to a vote for one of its highest ranked candidates,
+    /// picked according to `policy`. `rng` is only used by
+    /// [`TiePolicy::Random`].
+    pub fn to_specific_with<R: rand::Rng>(
+        &self,
+        policy: TiePolicy,
+        rng: &mut R,
+    ) -> Result<Specific, &'static str> {
+        profile::phase("convert", || {
+            let mut votes = Vec::with_capacity(self.voters());
+            for v in self {
+                votes.push(policy.pick(v.winners(), rng)?);
+            }
+            let mut votes: Specific = votes.into_iter().collect();
+            votes.set_candidates(self.candidates);
+            Ok(votes)
+        })
     }
 
     /// Convert each vote to a cardinal vote, with the highest rank candidates
@@ -144,38 +162,168 @@ impl TiedOrdersComplete {
     ///
     /// Returns `Err` if it failed to allocate
     pub fn to_cardinal(&self) -> Result<Cardinal, &'static str> {
-        let mut votes: Vec<usize> = Vec::new();
-        votes.try_reserve_exact(self.candidates * self.voters()).or(Err("Could not allocate"))?;
-        let max = self.candidates - 1;
-        let mut new_vote = vec![0; self.candidates];
-        for vote in self {
-            for (i, group) in vote.iter_groups().enumerate() {
-                for &c in group {
-                    debug_assert!(max >= i);
-                    new_vote[c] = max - i;
+        profile::phase("convert", || {
+            let mut votes: Vec<usize> = Vec::new();
+            votes
+                .try_reserve_exact(self.candidates * self.voters())
+                .or(Err("Could not allocate"))?;
+            let max = self.candidates - 1;
+            let mut new_vote = vec![0; self.candidates];
+            for vote in self {
+                for (i, group) in vote.iter_groups().enumerate() {
+                    for &c in group {
+                        debug_assert!(max >= i);
+                        new_vote[c] = max - i;
+                    }
                 }
+                // `vote` is a ranking of all candidates, so `new_vote` will be different
+                // between iterations.
+                votes.extend(&new_vote);
             }
-            // `vote` is a ranking of all candidates, so `new_vote` will be different
-            // between iterations.
-            votes.extend(&new_vote);
+            let v =
+                Cardinal { votes, candidates: self.candidates, voters: self.voters(), min: 0, max };
+            debug_assert!(v.valid());
+            Ok(v)
+        })
+    }
+
+    /// Replace the vote of `voter` with `v`.
+    pub fn set_vote(&mut self, voter: Voter, v: TiedRankRef) {
+        let i = voter.index();
+        debug_assert!(i < self.voters());
+        debug_assert!(v.len() == self.candidates);
+        let c = self.candidates;
+        self.votes[(i * c)..((i + 1) * c)].copy_from_slice(v.order());
+        self.ties[(i * (c - 1))..((i + 1) * (c - 1))].copy_from_slice(v.tied());
+        debug_assert!(self.valid());
+    }
+
+    /// A view of the votes with only the candidates in `keep`, see
+    /// [`ProfileView`].
+    pub fn view(&self, keep: &[usize]) -> Result<ProfileView<'_, Self>, &'static str> {
+        ProfileView::new(self, self.candidates, keep)
+    }
+
+    /// A copy of the votes with only the candidates in `keep`, indexed like
+    /// in [`ProfileView`]. Takes time linear in the size of the votes, unlike
+    /// removing every other candidate one at a time.
+    pub fn restrict(&self, keep: &[usize]) -> Result<Self, &'static str> {
+        if keep.is_empty() {
+            return Err("Can not remove the last candidate");
+        }
+        let view = self.view(keep)?;
+        let mut restricted = TiedOrdersComplete::new(view.candidates());
+        restricted.votes.reserve(self.voters() * keep.len());
+        restricted.ties.reserve(self.voters() * (keep.len() - 1));
+        view.for_each_vote(|vote| {
+            restricted.votes.extend_from_slice(vote.order());
+            restricted.ties.extend_from_slice(vote.tied());
+        });
+        debug_assert!(restricted.valid());
+        Ok(restricted)
+    }
+
+    /// Remove the vote of `voter`. Later voters are shifted down one index.
+    pub fn remove_voter(&mut self, voter: Voter) {
+        let i = voter.index();
+        assert!(i < self.voters());
+        let c = self.candidates;
+        self.votes.drain((i * c)..((i + 1) * c));
+        self.ties.drain((i * (c - 1))..((i + 1) * (c - 1)));
+        debug_assert!(self.valid());
+    }
+
+    /// Remove the candidate with index `n`, and shift indices of candidates
+    /// with higher index. Candidates which were tied through `n` remain tied.
+    pub fn remove_candidate(&mut self, n: usize) -> Result<(), &'static str> {
+        if n >= self.candidates {
+            return Err("Tried to remove non-existing candidate");
+        }
+        if self.candidates == 1 {
+            return Err("Can not remove the last candidate");
         }
-        let v = Cardinal { votes, candidates: self.candidates, voters: self.voters(), min: 0, max };
-        debug_assert!(v.valid());
-        Ok(v)
+        let voters = self.voters();
+        let mut votes = Vec::with_capacity(voters * (self.candidates - 1));
+        let mut ties = Vec::with_capacity(voters * (self.candidates - 2));
+        let mut rank = TiedRank::new_zero();
+        for vote in &*self {
+            rank.copy_from(vote);
+            rank.remove(n);
+            votes.extend(rank.order.iter().map(|&x| if x > n { x - 1 } else { x }));
+            ties.extend_from_slice(&rank.tied);
+        }
+        self.votes = votes;
+        self.ties = ties;
+        self.candidates -= 1;
+        debug_assert!(self.valid());
+        Ok(())
+    }
+
+    /// How often every candidate is ranked at every position, where ties are
+    /// counted according to `tie_scoring`.
+    pub fn rank_distribution(
+        &self,
+        tie_scoring: TieScoring,
+    ) -> Result<RankDistribution, &'static str> {
+        RankDistribution::count(self.candidates, self, tie_scoring)
+    }
+
+    /// How every pair of candidates is ranked relative to each other,
+    /// including how often they aren't ranked.
+    pub fn co_ranking_matrix(&self) -> CoRanking {
+        CoRanking::count(self.candidates, self)
+    }
+
+    /// How many voters prefer every candidate over every other candidate.
+    pub fn pairwise_matrix(&self) -> Result<PairwiseMatrix, &'static str> {
+        PairwiseMatrix::from_votes(self.candidates, self)
+    }
+
+    /// How the voters compare `a` and `b`, see [`HeadToHead`].
+    pub fn head_to_head(&self, a: usize, b: usize) -> Result<HeadToHead, &'static str> {
+        if a >= self.candidates || b >= self.candidates {
+            return Err("Candidate out of bounds");
+        }
+        Ok(HeadToHead::from_votes(self, a, b))
     }
 
     pub fn to_toi(self) -> Result<TiedOrdersIncomplete, &'static str> {
-        let mut vote_len = Vec::new();
-        vote_len.try_reserve_exact(self.voters()).or(Err("Could not allocate"))?;
-        vote_len.resize(self.voters(), self.candidates);
-        let v = TiedOrdersIncomplete {
-            votes: self.votes,
-            ties: self.ties,
-            vote_len,
-            candidates: self.candidates,
-        };
-        debug_assert!(v.valid());
-        Ok(v)
+        profile::phase("convert", || {
+            let mut vote_len = Vec::new();
+            vote_len.try_reserve_exact(self.voters()).or(Err("Could not allocate"))?;
+            vote_len.resize(self.voters(), self.candidates);
+            let v = TiedOrdersIncomplete {
+                votes: self.votes,
+                ties: self.ties,
+                vote_len,
+                candidates: self.candidates,
+            };
+            debug_assert!(v.valid());
+            Ok(v)
+        })
+    }
+
+    /// The index of every vote, see [`tied_index`]. Returns an error if there
+    /// are too many candidates for the indices to fit in a `u64`.
+    pub fn to_indices(&self) -> Result<Vec<u64>, &'static str> {
+        if self.candidates > MAX_INDEXED {
+            return Err("Too many candidates to index");
+        }
+        Ok(self.into_iter().map(tied_index).collect())
+    }
+
+    /// The profile of `candidates` candidates with a vote for every index in
+    /// `indices`, the inverse of
+    /// [`to_indices`](TiedOrdersComplete::to_indices).
+    pub fn from_indices(candidates: usize, indices: &[u64]) -> Result<Self, &'static str> {
+        if candidates == 0 {
+            return Err("Need at least one candidate");
+        }
+        let mut votes = TiedOrdersComplete::new(candidates);
+        for &index in indices {
+            votes.add(tied_from_index(candidates, index)?.as_ref());
+        }
+        Ok(votes)
     }
 }
 
@@ -231,3 +379,57 @@ impl From<StrictOrdersComplete> for TiedOrdersComplete {
         s
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[test]
+    fn remove_candidate() {
+        let mut votes = TiedOrdersComplete::new(4);
+        for s in ["{0,2},1,3", "0,{1,2,3}", "{1,0},3,2", "3,2,1,0"] {
+            assert!(votes.add_from_str(s));
+        }
+        votes.remove_candidate(2).unwrap();
+        let res: Vec<String> = votes.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(res, ["0,1,2", "0,{1,2}", "{1,0},2", "2,1,0"]);
+    }
+
+    #[test]
+    fn indices() {
+        let mut votes = TiedOrdersComplete::new(3);
+        for s in ["{0,1,2}", "2,{1,0}", "0,1,2", "2,1,0"] {
+            assert!(votes.add_from_str(s));
+        }
+        let indices = votes.to_indices().unwrap();
+        assert_eq!(indices[0], 0);
+        assert_eq!(indices[3], 12);
+        let decoded = TiedOrdersComplete::from_indices(3, &indices).unwrap();
+        let res: Vec<String> = decoded.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(res, ["{0,1,2}", "2,{0,1}", "0,1,2", "2,1,0"]);
+        assert!(TiedOrdersComplete::from_indices(3, &[13]).is_err());
+    }
+
+    #[test]
+    fn tie_policies() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut votes = TiedOrdersComplete::new(3);
+        for s in ["{2,0},1", "1,{0,2}"] {
+            assert!(votes.add_from_str(s));
+        }
+        let first = votes.to_specific_with(TiePolicy::FirstListed, &mut rng).unwrap();
+        assert_eq!(first.to_string(), "2\n1\n");
+        assert_eq!(first.candidates(), 3);
+        assert!(votes.to_specific_with(TiePolicy::Error, &mut rng).is_err());
+        let random = votes.to_specific_with(TiePolicy::Random, &mut rng).unwrap();
+        assert!(["0\n1\n", "2\n1\n"].contains(&random.to_string().as_str()));
+
+        votes.remove_voter(Voter(0));
+        let strict = votes.to_specific_with(TiePolicy::Error, &mut rng).unwrap();
+        assert_eq!(strict.to_string(), "1\n");
+    }
+}
@@ -1,14 +1,21 @@
-use rand::{
-    distributions::{Bernoulli, Uniform},
-    prelude::Distribution,
-    seq::SliceRandom,
-};
+use rand::{distributions::Bernoulli, prelude::Distribution, seq::SliceRandom};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use super::{
     orders::{TiedRank, TiedRankRef},
     soi::StrictOrdersIncomplete,
     toc::TiedOrdersComplete,
-    Cardinal, VoteFormat,
+    view::ProfileView,
+    BallotLength, Cardinal, CoRanking, RankDistribution, Spacing, Specific, TiePolicy, VoteFormat,
+};
+use crate::{
+    election::Voter,
+    methods::{
+        pairwise::{HeadToHead, PairwiseMatrix},
+        TieScoring,
+    },
+    profile,
 };
 
 /// TOI - Orders with Ties - Incomplete List
@@ -29,6 +36,13 @@ pub struct TiedOrdersIncomplete {
     pub(crate) candidates: usize,
 }
 
+serde_checked!(TiedOrdersIncomplete {
+    votes: Vec<usize>,
+    ties: Vec<bool>,
+    vote_len: Vec<usize>,
+    candidates: usize,
+});
+
 impl TiedOrdersIncomplete {
     pub fn new(candidates: usize) -> Self {
         TiedOrdersIncomplete {
@@ -39,15 +53,68 @@ impl TiedOrdersIncomplete {
         }
     }
 
-    pub fn vote_i(&self, i: usize) -> TiedRankRef {
+    /// The vote of `voter`.
+    pub fn vote(&self, voter: Voter) -> TiedRankRef {
         // TODO: Make more efficient
-        self.into_iter().nth(i).unwrap()
+        self.into_iter().nth(voter.index()).unwrap()
     }
 
     pub fn voters(&self) -> usize {
         self.vote_len.len()
     }
 
+    /// Every vote, like iterating over `&self`, but in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = TiedRankRef<'_>> {
+        // Where every vote starts, as the votes have different lengths.
+        let starts: Vec<usize> = self
+            .vote_len
+            .iter()
+            .scan(0, |start, &len| {
+                *start += len;
+                Some(*start - len)
+            })
+            .collect();
+        starts.into_par_iter().zip(&self.vote_len).enumerate().map(move |(i, (start, &len))| {
+            // Every vote has one less tie than it has candidates.
+            let order = &self.votes[start..(start + len)];
+            let tied = &self.ties[(start - i)..(start - i + len - 1)];
+            TiedRankRef::new(self.candidates, order, tied)
+        })
+    }
+
+    /// Add `new_voters` random votes, where the number of ranked candidates
+    /// of every vote is sampled from `length`, and the ranked candidates and
+    /// their ties are uniformly random.
+    pub fn generate_with_length<R: rand::Rng>(
+        &mut self,
+        rng: &mut R,
+        new_voters: usize,
+        length: &BallotLength,
+    ) -> Result<(), &'static str> {
+        if self.candidates == 0 {
+            return Ok(());
+        }
+        let mut v: Vec<usize> = (0..self.candidates).collect();
+        self.votes.reserve(new_voters * self.candidates);
+        self.ties.reserve(new_voters * (self.candidates - 1));
+        let dist = Bernoulli::new(0.5).unwrap();
+        let lengths = length.sampler(self.candidates)?;
+        for _ in 0..new_voters {
+            let candidates = lengths.sample(rng);
+            v.shuffle(rng);
+            self.votes.extend_from_slice(&v[..candidates]);
+
+            for _ in 0..(candidates - 1) {
+                let b = dist.sample(rng);
+                self.ties.push(b);
+            }
+            self.vote_len.push(candidates);
+        }
+        debug_assert!(self.valid());
+        Ok(())
+    }
+
     /// Add a single vote from a string. Return true if it was a valid vote.
     pub fn add_from_str(&mut self, s: &str) -> bool {
         self.add_from_str_i(s, 1)
@@ -57,14 +124,15 @@ impl TiedOrdersIncomplete {
     pub fn add_from_str_i(&mut self, s: &str, i: usize) -> bool {
         debug_assert!(i != 0);
         match TiedRank::parse_vote(self.candidates, s) {
-            Some(vote) => {
+            // Every vote has to rank some candidate
+            Ok(vote) if vote.len() != 0 => {
                 for _ in 0..i {
                     self.add(vote.as_ref()).unwrap();
                     debug_assert!(self.valid());
                 }
                 true
             }
-            None => false,
+            _ => false,
         }
     }
 
@@ -73,19 +141,26 @@ impl TiedOrdersIncomplete {
         let mut votes_len = 0;
         let mut ties_len = 0;
         for &i in &self.vote_len {
-            if i == 0 {
+            if i == 0 || i > self.votes.len() {
                 return false;
             }
             votes_len += i;
             ties_len += i - 1;
+            if votes_len > self.votes.len() {
+                return false;
+            }
         }
         if votes_len != self.votes.len() || ties_len != self.ties.len() {
             return false;
         }
         let mut seen = vec![false; self.candidates];
-        for vote in self {
+        let mut start = 0;
+        // Not using `TiedRankRef`, as it assumes the vote is valid.
+        for &len in &self.vote_len {
+            let vote = &self.votes[start..(start + len)];
+            start += len;
             seen.fill(false);
-            for &i in vote.order() {
+            for &i in vote {
                 if i >= self.candidates || seen[i] {
                     return false;
                 }
@@ -95,6 +170,30 @@ impl TiedOrdersIncomplete {
         true
     }
 
+    /// A view of the votes with only the candidates in `keep`, see
+    /// [`ProfileView`].
+    pub fn view(&self, keep: &[usize]) -> Result<ProfileView<'_, Self>, &'static str> {
+        ProfileView::new(self, self.candidates, keep)
+    }
+
+    /// A copy of the votes with only the candidates in `keep`, indexed like
+    /// in [`ProfileView`]. Takes time linear in the size of the votes, unlike
+    /// removing every other candidate one at a time. Votes which only ranked
+    /// removed candidates are removed.
+    pub fn restrict(&self, keep: &[usize]) -> Result<Self, &'static str> {
+        let view = self.view(keep)?;
+        let mut restricted = TiedOrdersIncomplete::new(view.candidates());
+        view.for_each_vote(|vote| {
+            if !vote.empty() {
+                restricted.votes.extend_from_slice(vote.order());
+                restricted.ties.extend_from_slice(vote.tied());
+                restricted.vote_len.push(vote.len());
+            }
+        });
+        debug_assert!(restricted.valid());
+        Ok(restricted)
+    }
+
     // Increase the number of candidates to `n`. Panics if `n < self.candidates`
     pub fn set_candidates(&mut self, n: usize) {
         debug_assert!(n >= self.candidates);
@@ -216,17 +315,115 @@ impl TiedOrdersIncomplete {
         true
     }
 
+    /// Convert every vote to a vote for one of its highest ranked candidates,
+    /// picked according to `policy`. `rng` is only used by
+    /// [`TiePolicy::Random`].
+    pub fn to_specific_with<R: rand::Rng>(
+        &self,
+        policy: TiePolicy,
+        rng: &mut R,
+    ) -> Result<Specific, &'static str> {
+        profile::phase("convert", || {
+            let mut votes = Vec::with_capacity(self.voters());
+            for v in self {
+                votes.push(policy.pick(v.winners(), rng)?);
+            }
+            let mut votes: Specific = votes.into_iter().collect();
+            votes.set_candidates(self.candidates);
+            Ok(votes)
+        })
+    }
+
+    /// How often every candidate is ranked at every position, where ties are
+    /// counted according to `tie_scoring`.
+    pub fn rank_distribution(
+        &self,
+        tie_scoring: TieScoring,
+    ) -> Result<RankDistribution, &'static str> {
+        RankDistribution::count(self.candidates, self, tie_scoring)
+    }
+
+    /// How every pair of candidates is ranked relative to each other,
+    /// including how often they aren't ranked.
+    pub fn co_ranking_matrix(&self) -> CoRanking {
+        CoRanking::count(self.candidates, self)
+    }
+
+    /// How many voters prefer every candidate over every other candidate.
+    pub fn pairwise_matrix(&self) -> Result<PairwiseMatrix, &'static str> {
+        PairwiseMatrix::from_votes(self.candidates, self)
+    }
+
+    /// How the voters compare `a` and `b`, see [`HeadToHead`].
+    ///
+    /// ```
+    /// use votery::formats::{toi::TiedOrdersIncomplete, VoteFormat};
+    ///
+    /// let mut votes = TiedOrdersIncomplete::new(3);
+    /// for vote in ["0,1,2", "1", "2,{0,1}", "2"] {
+    ///     assert!(votes.add_from_str(vote));
+    /// }
+    /// let h = votes.head_to_head(0, 1).unwrap();
+    /// assert_eq!((h.a_over_b, h.b_over_a, h.tied, h.unranked), (1, 1, 1, 1));
+    /// ```
+    pub fn head_to_head(&self, a: usize, b: usize) -> Result<HeadToHead, &'static str> {
+        if a >= self.candidates || b >= self.candidates {
+            return Err("Candidate out of bounds");
+        }
+        Ok(HeadToHead::from_votes(self, a, b))
+    }
+
     pub fn to_cardinal(self) -> Result<Cardinal, &'static str> {
+        profile::phase("convert", || {
+            if self.candidates == 0 {
+                return Err("No candidates");
+            }
+            let mut v = TiedRank::new_tied(self.candidates);
+            let mut cardinal_rank = vec![0; self.candidates];
+            let max = self.candidates - 1;
+            let mut cardinal_votes = Cardinal::new(self.candidates, 0, max);
+            for vote in &self {
+                v.copy_from(vote);
+                v.make_complete(false);
+                v.as_ref().cardinal_high(&mut cardinal_rank, 0, max);
+                cardinal_votes.add(&cardinal_rank)?;
+                cardinal_rank.fill(0);
+            }
+            Ok(cardinal_votes)
+        })
+    }
+
+    /// Convert every vote to a cardinal vote from 0 to `max`, assuming the
+    /// utilities of the positions are spaced according to `spacing`. Tied
+    /// candidates get the average utility of the positions they span, and
+    /// unranked candidates are tied last. `rng` is only used by
+    /// [`Spacing::Random`], which draws new utilities for every vote.
+    pub fn to_cardinal_spaced<R: rand::Rng>(
+        &self,
+        spacing: Spacing,
+        max: usize,
+        rng: &mut R,
+    ) -> Result<Cardinal, &'static str> {
         let mut v = TiedRank::new_tied(self.candidates);
-        let mut cardinal_rank = vec![0; self.candidates];
-        let max = self.candidates - 1;
+        let mut values = vec![0; self.candidates];
         let mut cardinal_votes = Cardinal::new(self.candidates, 0, max);
-        for vote in &self {
+        let mut utilities = spacing.utilities(self.candidates, rng)?;
+        for vote in self {
+            if spacing == Spacing::Random {
+                utilities = spacing.utilities(self.candidates, rng)?;
+            }
             v.copy_from(vote);
             v.make_complete(false);
-            v.as_ref().cardinal_high(&mut cardinal_rank, 0, max);
-            cardinal_votes.add(&cardinal_rank)?;
-            cardinal_rank.fill(0);
+            let mut seen = 0;
+            for group in v.as_ref().iter_groups() {
+                let span = &utilities[seen..(seen + group.len())];
+                let average = span.iter().sum::<f64>() / group.len() as f64;
+                for &c in group {
+                    values[c] = (average * max as f64).round() as usize;
+                }
+                seen += group.len();
+            }
+            cardinal_votes.add(&values)?;
         }
         Ok(cardinal_votes)
     }
@@ -240,17 +437,18 @@ impl<'a> VoteFormat<'a> for TiedOrdersIncomplete {
     }
 
     fn add(&mut self, vote: TiedRankRef) -> Result<(), &'static str> {
-        debug_assert!(vote.len() < self.candidates);
+        debug_assert!(vote.len() <= self.candidates);
         debug_assert!(0 < vote.len());
         self.votes.reserve(vote.len());
         self.ties.reserve(vote.len() - 1);
         let mut seen = vec![false; self.candidates];
         for &i in vote.order() {
-            debug_assert!(i < self.candidates || !seen[i]);
+            debug_assert!(i < self.candidates && !seen[i]);
             seen[i] = true;
             self.votes.push(i);
         }
         self.ties.extend(vote.tied());
+        self.vote_len.push(vote.len());
         debug_assert!(self.valid());
         Ok(())
     }
@@ -258,60 +456,33 @@ impl<'a> VoteFormat<'a> for TiedOrdersIncomplete {
     /// Remove the candidate with index `n`, and shift indices of candidates
     /// with higher index. May remove votes if they only voted for `n`.
     fn remove_candidate(&mut self, n: usize) -> Result<(), &'static str> {
-        let new_candidates = self.candidates - 1;
-        let mut res: TiedOrdersIncomplete = self
-            .into_iter()
-            .filter_map(|vote| {
-                let mut order: Vec<usize> = Vec::with_capacity(vote.order().len() - 1);
-                let mut tied: Vec<bool> = Vec::with_capacity(vote.tied().len().saturating_sub(1));
-                for i in 0..order.len() {
-                    let mut v = order[i];
-                    if v == n {
-                        continue;
-                    }
-                    if v > n {
-                        v -= 1;
-                    }
-                    order.push(v);
-                    if i != tied.len() {
-                        tied.push(tied[i]);
-                    }
-                }
-                if order.is_empty() {
-                    None
-                } else {
-                    Some(TiedRank::new(new_candidates, order, tied))
-                }
-            })
-            .collect();
+        if n >= self.candidates {
+            return Err("Tried to remove non-existing candidate");
+        }
+        let mut votes = Vec::with_capacity(self.votes.len());
+        let mut ties = Vec::with_capacity(self.ties.len());
+        let mut vote_len = Vec::with_capacity(self.voters());
+        let mut rank = TiedRank::new_zero();
+        for vote in &*self {
+            rank.copy_from(vote);
+            rank.remove(n);
+            if rank.order.is_empty() {
+                continue;
+            }
+            votes.extend(rank.order.iter().map(|&x| if x > n { x - 1 } else { x }));
+            ties.extend_from_slice(&rank.tied);
+            vote_len.push(rank.order.len());
+        }
+        self.votes = votes;
+        self.ties = ties;
+        self.vote_len = vote_len;
+        self.candidates -= 1;
         debug_assert!(self.valid());
-        *self = res;
         Ok(())
     }
 
     fn generate_uniform<R: rand::Rng>(&mut self, rng: &mut R, new_voters: usize) {
-        if self.candidates == 0 {
-            return;
-        }
-        let mut v: Vec<usize> = (0..self.candidates).collect();
-        self.votes.reserve(new_voters * self.candidates);
-        self.ties.reserve(new_voters * (self.candidates - 1));
-        let dist = Bernoulli::new(0.5).unwrap();
-        let range = Uniform::from(0..self.candidates);
-        for _ in 0..new_voters {
-            let candidates = range.sample(rng) + 1;
-            v.shuffle(rng);
-            for i in 0..candidates {
-                self.votes.push(v[i]);
-            }
-
-            for _ in 0..(candidates - 1) {
-                let b = dist.sample(rng);
-                self.ties.push(b);
-            }
-            self.vote_len.push(candidates);
-        }
-        debug_assert!(self.valid());
+        self.generate_with_length(rng, new_voters, &BallotLength::Uniform).unwrap();
     }
 
     fn to_partial_ranking(self) -> TiedOrdersIncomplete {
@@ -414,6 +585,7 @@ impl From<TiedOrdersComplete> for TiedOrdersIncomplete {
 #[cfg(test)]
 mod tests {
     use quickcheck::{Arbitrary, Gen};
+    use rand::{rngs::StdRng, SeedableRng};
 
     use super::*;
     use crate::formats::tests::std_rng;
@@ -434,6 +606,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cardinal_spacing() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("0,1,2");
+        votes.add_from_str("{0,1},2");
+        votes.add_from_str("2");
+        let mut rng = StdRng::seed_from_u64(0);
+        let spaced = |s, rng: &mut StdRng| votes.to_cardinal_spaced(s, 10, rng).unwrap().votes;
+        assert_eq!(spaced(Spacing::Equal, &mut rng), [10, 5, 0, 8, 8, 0, 3, 3, 10]);
+        assert_eq!(spaced(Spacing::Geometric(0.5), &mut rng), [10, 3, 0, 7, 7, 0, 2, 2, 10]);
+        assert_eq!(spaced(Spacing::Geometric(1.0), &mut rng), spaced(Spacing::Equal, &mut rng));
+        assert!(votes.to_cardinal_spaced(Spacing::Geometric(0.0), 10, &mut rng).is_err());
+    }
+
+    #[quickcheck]
+    fn random_spacing_keeps_order(votes: TiedOrdersIncomplete, seed: u64) -> bool {
+        if votes.candidates == 0 {
+            return true;
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let cardinal = votes.to_cardinal_spaced(Spacing::Random, 100, &mut rng).unwrap();
+        votes.into_iter().zip(cardinal.iter()).all(|(vote, values)| {
+            let groups: Vec<&[usize]> = vote.iter_groups().collect();
+            groups
+                .windows(2)
+                .all(|w| w[0].iter().all(|&a| w[1].iter().all(|&b| values[a] >= values[b])))
+        })
+    }
+
+    #[test]
+    fn remove_candidate() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str("0,{1,2},3");
+        votes.add_from_str("{1,3}");
+        votes.add_from_str("1");
+        votes.remove_candidate(1).unwrap();
+        let s: Vec<String> = votes.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(s, ["0,1,2", "2"]);
+        assert_eq!(votes.candidates(), 3);
+        assert!(votes.remove_candidate(3).is_err());
+    }
+
+    #[test]
+    fn empty() {
+        let mut votes = TiedOrdersIncomplete::new(2);
+        assert!(!votes.add_from_str(""));
+        assert_eq!(votes.voters(), 0);
+    }
+
+    // Found by the `toi_operations` fuzz target: converting without any
+    // candidates used to underflow.
+    #[test]
+    fn cardinal_without_candidates() {
+        let mut votes = TiedOrdersIncomplete::new(0);
+        assert!(!votes.add_from_str("0"));
+        assert!(votes.clone().to_cardinal().is_err());
+        assert!(votes.pairwise_matrix().is_ok());
+    }
+
     #[quickcheck]
     fn clone_remove(votes: TiedOrdersIncomplete, i: usize) -> bool {
         let mut votes = votes.clone();
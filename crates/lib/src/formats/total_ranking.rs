@@ -18,6 +18,8 @@ pub struct TotalRanking {
     pub voters: usize,
 }
 
+serde_checked!(TotalRanking { votes: Vec<usize>, candidates: usize, voters: usize });
+
 impl TotalRanking {
     pub fn new(candidates: usize) -> Self {
         TotalRanking { votes: Vec::new(), candidates, voters: 0 }
@@ -26,9 +28,9 @@ impl TotalRanking {
     // Check if a given total ranking is valid, i.e.
     // 1. len(votes) = candidates * voters
     // 2. Every ranking is total
-    fn valid(&self) -> bool {
+    pub(crate) fn valid(&self) -> bool {
         if self.candidates == 0 && (self.voters != 0 || !self.votes.is_empty())
-            || self.votes.len() != self.voters * self.candidates
+            || self.voters.checked_mul(self.candidates) != Some(self.votes.len())
         {
             return false;
         }
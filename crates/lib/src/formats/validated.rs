@@ -0,0 +1,133 @@
+//! Profiles which are known to be valid.
+//!
+//! Some fields of the formats are public, like
+//! [`TiedOrdersComplete::candidates`], so a profile can be changed into a state
+//! where the votes no longer match it. [`Validated`] wraps a profile which was
+//! checked once, and only gives shared access to it, so code taking a
+//! `Validated` profile can rely on it without checking it again.
+
+use std::ops::Deref;
+
+use super::{
+    soc::StrictOrdersComplete, soi::StrictOrdersIncomplete, toc::TiedOrdersComplete,
+    toi::TiedOrdersIncomplete, Binary, Cardinal, CompressedDense, Cumulative, Specific,
+    TotalRanking,
+};
+
+/// A format whose invariants can be checked, see [`Validated`].
+pub trait Validate: Sized {
+    /// Returns true if every invariant of the format holds.
+    fn is_valid(&self) -> bool;
+
+    /// Check the profile once, so it can be given where a [`Validated`]
+    /// profile is required.
+    ///
+    /// ```
+    /// use votery::formats::{toc::TiedOrdersComplete, Validate, Validated};
+    ///
+    /// // Only takes profiles which are known to be valid.
+    /// fn voters(votes: &Validated<TiedOrdersComplete>) -> usize {
+    ///     votes.voters()
+    /// }
+    ///
+    /// let mut votes = TiedOrdersComplete::new(3);
+    /// assert!(votes.add_from_str("0,{1,2}"));
+    /// let votes = votes.validate().unwrap();
+    /// assert_eq!(voters(&votes), 1);
+    ///
+    /// let mut votes = votes.into_inner();
+    /// votes.candidates = 2;
+    /// assert!(votes.validate().is_err());
+    /// ```
+    fn validate(self) -> Result<Validated<Self>, &'static str> {
+        if self.is_valid() {
+            Ok(Validated(self))
+        } else {
+            Err("Invalid profile")
+        }
+    }
+}
+
+/// A profile which was valid when it was created by [`Validate::validate`].
+/// It can't be changed, so it stays valid, and it has the same layout as `P`.
+#[derive(Clone, Debug)]
+#[repr(transparent)]
+pub struct Validated<P>(P);
+
+impl<P: Validate> Validated<P> {
+    // Wrap `profile`, which is valid because of how it was made, e.g. by
+    // converting a `Validated` profile. Only checked in debug builds.
+    pub(crate) fn assume_valid(profile: P) -> Self {
+        debug_assert!(profile.is_valid());
+        Validated(profile)
+    }
+}
+
+impl<P> Validated<P> {
+    /// The profile, which can be changed again.
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+}
+
+impl<P> Deref for Validated<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P> AsRef<P> for Validated<P> {
+    fn as_ref(&self) -> &P {
+        &self.0
+    }
+}
+
+macro_rules! impl_validate {
+    ($($format:ty),*) => {
+        $(impl Validate for $format {
+            fn is_valid(&self) -> bool {
+                self.valid()
+            }
+        })*
+    };
+}
+
+impl_validate!(
+    Binary,
+    Cardinal,
+    CompressedDense,
+    Cumulative,
+    Specific,
+    StrictOrdersComplete,
+    StrictOrdersIncomplete,
+    TiedOrdersComplete,
+    TiedOrdersIncomplete,
+    TotalRanking
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[quickcheck]
+    fn arbitrary_valid(toi: TiedOrdersIncomplete, cardinal: Cardinal, binary: Binary) -> bool {
+        toi.validate().is_ok() && cardinal.validate().is_ok() && binary.validate().is_ok()
+    }
+
+    #[test]
+    fn invalid() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        assert!(votes.add_from_str("2,{0,1}"));
+        let validated = votes.clone().validate().unwrap();
+        assert_eq!(validated.candidates(), 3);
+        votes.votes[0] = 3;
+        assert!(votes.validate().is_err());
+        let mut soi = StrictOrdersIncomplete::new(2);
+        assert!(soi.add_from_str("1,0"));
+        soi.candidates = 1;
+        assert!(soi.validate().is_err());
+    }
+}
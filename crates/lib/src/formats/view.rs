@@ -0,0 +1,187 @@
+//! Views of a profile restricted to a subset of the candidates.
+//!
+//! Methods with multiple rounds, like runoffs, often count the same votes
+//! again with fewer candidates. A [`ProfileView`] borrows the votes and
+//! removes the other candidates from each vote as it's read, so no reduced
+//! copy of the votes has to be made.
+
+use super::orders::{TiedRank, TiedRankRef};
+
+// The new index of each of `candidates` when only the candidates in `keep` are
+// kept, in the same order as their original indices.
+pub(crate) fn new_indices(
+    candidates: usize,
+    keep: &[usize],
+) -> Result<Vec<Option<usize>>, &'static str> {
+    let mut kept = vec![false; candidates];
+    for &c in keep {
+        if c >= candidates {
+            return Err("Tried to keep non-existing candidate");
+        }
+        if kept[c] {
+            return Err("Tried to keep the same candidate twice");
+        }
+        kept[c] = true;
+    }
+    let mut next = 0..;
+    Ok(kept.into_iter().map(|k| if k { next.next() } else { None }).collect())
+}
+
+/// A profile of votes restricted to some of its candidates.
+///
+/// The kept candidates are given new indices `0..candidates()`, in the same
+/// order as their original indices. Candidates which were tied through a
+/// removed candidate remain tied.
+pub struct ProfileView<'a, P> {
+    profile: &'a P,
+    // The original index of each kept candidate
+    keep: Vec<usize>,
+    // The new index of each original candidate, if it's kept
+    new_index: Vec<Option<usize>>,
+}
+
+impl<'a, P> ProfileView<'a, P>
+where
+    &'a P: IntoIterator<Item = TiedRankRef<'a>>,
+{
+    /// Create a view of `profile`, which has `candidates` candidates, with only
+    /// the candidates in `keep`.
+    pub(crate) fn new(
+        profile: &'a P,
+        candidates: usize,
+        keep: &[usize],
+    ) -> Result<Self, &'static str> {
+        let new_index = new_indices(candidates, keep)?;
+        let mut sorted = keep.to_vec();
+        sorted.sort_unstable();
+        Ok(ProfileView { profile, keep: sorted, new_index })
+    }
+
+    /// The number of kept candidates.
+    pub fn candidates(&self) -> usize {
+        self.keep.len()
+    }
+
+    /// The original index of candidate `c` in the view.
+    pub fn original(&self, c: usize) -> usize {
+        self.keep[c]
+    }
+
+    /// Call `f` with every vote, restricted to the kept candidates. A vote
+    /// which only ranked removed candidates becomes empty.
+    pub fn for_each_vote<F: FnMut(TiedRankRef)>(&self, mut f: F) {
+        let mut rank = TiedRank::new_zero();
+        rank.increase_candidates(self.candidates());
+        for vote in self.profile {
+            rank.order.clear();
+            rank.tied.clear();
+            let tied = vote.tied();
+            // If every candidate since the last kept one was tied with the next
+            let mut between = true;
+            for (i, &c) in vote.order().iter().enumerate() {
+                if let Some(n) = self.new_index[c] {
+                    if !rank.order.is_empty() {
+                        rank.tied.push(between);
+                    }
+                    rank.order.push(n);
+                    between = true;
+                }
+                if let Some(&t) = tied.get(i) {
+                    between &= t;
+                }
+            }
+            f(rank.as_ref());
+        }
+    }
+
+    /// The number of votes ranking each kept candidate first. A vote with
+    /// multiple candidates tied first counts for all of them.
+    pub fn first_preferences(&self) -> Vec<usize> {
+        let mut firsts = vec![0; self.candidates()];
+        self.for_each_vote(|vote| {
+            if let Some(group) = vote.iter_groups().next() {
+                for &c in group {
+                    firsts[c] += 1;
+                }
+            }
+        });
+        firsts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use crate::formats::{
+        soc::StrictOrdersComplete, soi::StrictOrdersIncomplete, toc::TiedOrdersComplete,
+        toi::TiedOrdersIncomplete, VoteFormat,
+    };
+
+    #[test]
+    fn same_as_removal() {
+        let mut votes = TiedOrdersComplete::new(5);
+        for s in ["0,{1,2,3},4", "{4,2},1,0,3", "3,2,1,{0,4}", "{0,1,2,3,4}"] {
+            assert!(votes.add_from_str(s));
+        }
+        let view = votes.view(&[4, 0, 1, 3]).unwrap();
+        let mut viewed = Vec::new();
+        view.for_each_vote(|v| viewed.push(v.to_string()));
+
+        let mut removed = votes.clone();
+        removed.remove_candidate(2).unwrap();
+        let expected: Vec<String> = removed.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(viewed, expected);
+        assert_eq!(view.original(3), 4);
+    }
+
+    #[test]
+    fn incomplete() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str("2");
+        votes.add_from_str("{2,3},0");
+        votes.add_from_str("1,3");
+        let view = votes.view(&[0, 3]).unwrap();
+        assert_eq!(view.first_preferences(), [0, 2]);
+        assert!(votes.view(&[0, 0]).is_err());
+        assert!(votes.view(&[4]).is_err());
+    }
+
+    #[quickcheck]
+    fn restrict_same_as_removal(votes: TiedOrdersIncomplete, keep: Vec<bool>) -> bool {
+        let n = votes.candidates();
+        let keep: Vec<usize> = (0..n).filter(|&c| keep.get(c).copied().unwrap_or(true)).collect();
+        let mut removed = votes.clone();
+        for c in (0..n).rev().filter(|c| !keep.contains(c)) {
+            removed.remove_candidate(c).unwrap();
+        }
+        let restricted = votes.restrict(&keep).unwrap();
+        let strings = |v: &TiedOrdersIncomplete| -> Vec<String> {
+            v.into_iter().map(|v| v.to_string()).collect()
+        };
+        restricted.candidates() == keep.len() && strings(&restricted) == strings(&removed)
+    }
+
+    #[test]
+    fn restrict() {
+        let mut toc = TiedOrdersComplete::new(4);
+        assert!(toc.add_from_str("0,{1,2},3") && toc.add_from_str("{3,2},1,0"));
+        let strings: Vec<String> =
+            toc.restrict(&[3, 1]).unwrap().into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(strings, ["0,1", "1,0"]);
+        assert!(toc.restrict(&[]).is_err());
+        assert!(toc.restrict(&[1, 1]).is_err());
+
+        let mut soc = StrictOrdersComplete::new(3);
+        assert!(soc.add_from_str("2,0,1"));
+        let restricted = soc.restrict(&[1, 2]).unwrap();
+        assert_eq!(restricted.into_iter().collect::<Vec<_>>(), [[1, 0]]);
+
+        let mut soi = StrictOrdersIncomplete::new(3);
+        assert!(soi.add_from_str("2,0") && soi.add_from_str("1"));
+        let restricted = soi.restrict(&[0, 2]).unwrap();
+        assert_eq!(restricted.into_iter().collect::<Vec<_>>(), [[1, 0]]);
+        assert!(soi.restrict(&[3]).is_err());
+        assert!(soi.restrict(&[]).is_err());
+    }
+}
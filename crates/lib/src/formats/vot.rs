@@ -0,0 +1,449 @@
+//! A compact binary format for profiles, stored in `.vot` files, which is much
+//! faster to save and load than parsing orders from text.
+//!
+//! A file starts with a header, followed by the names of the candidates if it
+//! has a [`CandidateRegistry`], and then the votes. Every part ends with a
+//! CRC-32 checksum of itself, so damaged files are detected. All numbers are
+//! little-endian.
+//!
+//! | Part     | Contents                                                     |
+//! |----------|--------------------------------------------------------------|
+//! | Header   | `\x89VOT`, version (u16), kind (u8), flags (u8), candidates (u32), voters (u64) |
+//! | Registry | For every candidate: length of name (u16), name (UTF-8), has color (u8), RGB (3 × u8) |
+//! | Votes    | For every vote: length (incomplete only), candidates, ties packed as bits |
+//!
+//! Candidates and lengths take 1 byte with fewer than 256 candidates, 2 bytes
+//! with fewer than 65536 and otherwise 4 bytes. Bit `i % 8` of byte `i / 8` of
+//! the ties of a vote says if its `i`th candidate is tied with the next one.
+//!
+//! The version is increased whenever the format changes, and
+//! [`read_vot`] keeps reading every earlier version.
+
+use std::io::{self, Read, Write};
+
+use super::{parse::CandidateRegistry, toc::TiedOrdersComplete, toi::TiedOrdersIncomplete};
+use crate::election::Candidate;
+
+const MAGIC: [u8; 4] = *b"\x89VOT";
+
+/// The version written by [`write_vot`].
+pub const VERSION: u16 = 1;
+
+// The flag set if the file has a registry.
+const HAS_REGISTRY: u8 = 1;
+
+// Don't trust the number of voters in the header with more memory than this
+// before the votes are actually read.
+const MAX_RESERVE: usize = 1 << 20;
+
+// Files with more candidates are rejected, as every vote needs memory for each
+// candidate before it's read.
+const MAX_CANDIDATES: usize = 1 << 24;
+
+/// A profile which can be stored in a `.vot` file.
+pub trait VotProfile: Sized {
+    /// Stored in the header, so a file is read as the same kind of profile.
+    const KIND: u8;
+
+    fn vot_candidates(&self) -> usize;
+
+    fn vot_voters(&self) -> usize;
+
+    #[doc(hidden)]
+    fn write_votes<W: Write>(&self, w: &mut Encoder<W>) -> io::Result<()>;
+
+    #[doc(hidden)]
+    fn read_votes<R: Read>(r: &mut Decoder<R>, voters: u64) -> io::Result<Self>;
+}
+
+/// Write `votes` in the `.vot` format, with the names and colors of the
+/// candidates in `registry` if it's given.
+pub fn write_vot<W: Write, P: VotProfile>(
+    w: &mut W,
+    votes: &P,
+    registry: Option<&CandidateRegistry>,
+) -> io::Result<()> {
+    let candidates = votes.vot_candidates();
+    if candidates > MAX_CANDIDATES {
+        return Err(invalid("Too many candidates"));
+    }
+    if registry.is_some_and(|r| r.len() != candidates) {
+        return Err(invalid("Registry must have every candidate"));
+    }
+    let mut e = Encoder::new(w, candidates);
+    e.bytes(&MAGIC)?;
+    e.bytes(&VERSION.to_le_bytes())?;
+    e.bytes(&[P::KIND, if registry.is_some() { HAS_REGISTRY } else { 0 }])?;
+    e.bytes(&(candidates as u32).to_le_bytes())?;
+    e.bytes(&(votes.vot_voters() as u64).to_le_bytes())?;
+    e.checksum()?;
+    if let Some(registry) = registry {
+        for c in registry.candidates() {
+            let name = registry.name(c).unwrap().as_bytes();
+            e.bytes(
+                &u16::try_from(name.len()).map_err(|_| invalid("Too long name"))?.to_le_bytes(),
+            )?;
+            e.bytes(name)?;
+            match registry.color(c) {
+                Some(rgb) => e.bytes(&[1, rgb[0], rgb[1], rgb[2]])?,
+                None => e.bytes(&[0; 4])?,
+            }
+        }
+        e.checksum()?;
+    }
+    votes.write_votes(&mut e)?;
+    e.checksum()
+}
+
+/// Read a profile written by [`write_vot`], and its registry if it has one.
+///
+/// Returns an error if the file is damaged, was written by a newer version,
+/// or stores another kind of profile than `P`.
+pub fn read_vot<R: Read, P: VotProfile>(r: &mut R) -> io::Result<(P, Option<CandidateRegistry>)> {
+    let mut d = Decoder::new(r);
+    if d.array::<4>()? != MAGIC {
+        return Err(invalid("Not a .vot file"));
+    }
+    let version = u16::from_le_bytes(d.array()?);
+    let [kind, flags] = d.array()?;
+    let candidates = u32::from_le_bytes(d.array()?) as usize;
+    let voters = u64::from_le_bytes(d.array()?);
+    d.checksum()?;
+    if version == 0 || version > VERSION {
+        return Err(invalid("Unsupported .vot version"));
+    }
+    if kind != P::KIND {
+        return Err(invalid("The file stores another kind of profile"));
+    }
+    if candidates > MAX_CANDIDATES {
+        return Err(invalid("Too many candidates"));
+    }
+    d.candidates = candidates;
+    let registry = if flags & HAS_REGISTRY != 0 {
+        let mut names = Vec::with_capacity(candidates.min(MAX_RESERVE));
+        let mut colors = Vec::with_capacity(candidates.min(MAX_RESERVE));
+        for _ in 0..candidates {
+            let len = u16::from_le_bytes(d.array()?) as usize;
+            let mut name = vec![0; len];
+            d.read_exact(&mut name)?;
+            names.push(String::from_utf8(name).map_err(|_| invalid("Name is not UTF-8"))?);
+            let [has_color, r, g, b] = d.array()?;
+            colors.push((has_color != 0).then_some([r, g, b]));
+        }
+        d.checksum()?;
+        let mut registry = CandidateRegistry::new(names).map_err(invalid)?;
+        for (i, color) in colors.into_iter().enumerate() {
+            if let Some(color) = color {
+                registry.set_color(Candidate(i), color).unwrap();
+            }
+        }
+        Some(registry)
+    } else {
+        None
+    };
+    let votes = P::read_votes(&mut d, voters)?;
+    d.checksum()?;
+    Ok((votes, registry))
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// The number of bytes of a candidate or a length of a vote.
+fn width(candidates: usize) -> usize {
+    match candidates {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        _ => 4,
+    }
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc_update(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, &b| CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+/// Writes the parts of a `.vot` file, keeping track of their checksum.
+pub struct Encoder<W> {
+    inner: W,
+    crc: u32,
+    width: usize,
+}
+
+impl<W: Write> Encoder<W> {
+    fn new(inner: W, candidates: usize) -> Self {
+        Encoder { inner, crc: !0, width: width(candidates) }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.crc = crc_update(self.crc, bytes);
+        self.inner.write_all(bytes)
+    }
+
+    // End the current part with its checksum.
+    fn checksum(&mut self) -> io::Result<()> {
+        let crc = !self.crc;
+        self.inner.write_all(&crc.to_le_bytes())?;
+        self.crc = !0;
+        Ok(())
+    }
+
+    fn number(&mut self, n: usize) -> io::Result<()> {
+        let bytes = (n as u32).to_le_bytes();
+        self.bytes(&bytes[..self.width])
+    }
+
+    fn order(&mut self, order: &[usize], tied: &[bool]) -> io::Result<()> {
+        for &c in order {
+            self.number(c)?;
+        }
+        let mut packed = vec![0; tied.len().div_ceil(8)];
+        for (i, _) in tied.iter().enumerate().filter(|(_, &t)| t) {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+        self.bytes(&packed)
+    }
+}
+
+/// Reads the parts of a `.vot` file, keeping track of their checksum.
+pub struct Decoder<R> {
+    inner: R,
+    crc: u32,
+    candidates: usize,
+    // Reused for every vote.
+    seen: Vec<bool>,
+}
+
+impl<R: Read> Decoder<R> {
+    fn new(inner: R) -> Self {
+        Decoder { inner, crc: !0, candidates: 0, seen: Vec::new() }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.crc = crc_update(self.crc, buf);
+        Ok(())
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    // Check the checksum of the current part.
+    fn checksum(&mut self) -> io::Result<()> {
+        let expected = !self.crc;
+        let mut buf = [0; 4];
+        self.inner.read_exact(&mut buf)?;
+        self.crc = !0;
+        if u32::from_le_bytes(buf) != expected {
+            return Err(invalid("Checksum mismatch, the file is damaged"));
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> io::Result<usize> {
+        let mut bytes = [0; 4];
+        let width = width(self.candidates);
+        self.read_exact(&mut bytes[..width])?;
+        Ok(u32::from_le_bytes(bytes) as usize)
+    }
+
+    // Read a vote of `len` candidates into the end of `order` and `tied`.
+    fn order(
+        &mut self,
+        len: usize,
+        order: &mut Vec<usize>,
+        tied: &mut Vec<bool>,
+    ) -> io::Result<()> {
+        self.seen.clear();
+        self.seen.resize(self.candidates, false);
+        for _ in 0..len {
+            let c = self.number()?;
+            if c >= self.candidates || self.seen[c] {
+                return Err(invalid("Invalid vote"));
+            }
+            self.seen[c] = true;
+            order.push(c);
+        }
+        let ties = len.saturating_sub(1);
+        let mut packed = vec![0; ties.div_ceil(8)];
+        self.read_exact(&mut packed)?;
+        tied.extend((0..ties).map(|i| packed[i / 8] & (1 << (i % 8)) != 0));
+        Ok(())
+    }
+}
+
+impl VotProfile for TiedOrdersComplete {
+    const KIND: u8 = 1;
+
+    fn vot_candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn vot_voters(&self) -> usize {
+        self.voters()
+    }
+
+    fn write_votes<W: Write>(&self, w: &mut Encoder<W>) -> io::Result<()> {
+        for vote in self {
+            w.order(vote.order(), vote.tied())?;
+        }
+        Ok(())
+    }
+
+    fn read_votes<R: Read>(r: &mut Decoder<R>, voters: u64) -> io::Result<Self> {
+        let c = r.candidates;
+        if c == 0 {
+            return Err(invalid("Need at least one candidate"));
+        }
+        let mut votes = TiedOrdersComplete::new(c);
+        let voters = voters as usize;
+        votes.votes.reserve(voters.saturating_mul(c).min(MAX_RESERVE));
+        votes.ties.reserve(voters.saturating_mul(c - 1).min(MAX_RESERVE));
+        for _ in 0..voters {
+            r.order(c, &mut votes.votes, &mut votes.ties)?;
+        }
+        debug_assert!(votes.valid());
+        Ok(votes)
+    }
+}
+
+impl VotProfile for TiedOrdersIncomplete {
+    const KIND: u8 = 2;
+
+    fn vot_candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn vot_voters(&self) -> usize {
+        self.voters()
+    }
+
+    fn write_votes<W: Write>(&self, w: &mut Encoder<W>) -> io::Result<()> {
+        for vote in self {
+            w.number(vote.len())?;
+            w.order(vote.order(), vote.tied())?;
+        }
+        Ok(())
+    }
+
+    fn read_votes<R: Read>(r: &mut Decoder<R>, voters: u64) -> io::Result<Self> {
+        let mut votes = TiedOrdersIncomplete::new(r.candidates);
+        let voters = voters as usize;
+        votes.vote_len.reserve(voters.min(MAX_RESERVE));
+        for _ in 0..voters {
+            let len = r.number()?;
+            if len == 0 || len > r.candidates {
+                return Err(invalid("Invalid vote"));
+            }
+            r.order(len, &mut votes.votes, &mut votes.ties)?;
+            votes.vote_len.push(len);
+        }
+        debug_assert!(votes.valid());
+        Ok(votes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn roundtrip<P: VotProfile>(votes: &P, registry: Option<&CandidateRegistry>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_vot(&mut buf, votes, registry).unwrap();
+        buf
+    }
+
+    #[test]
+    fn registry() {
+        let mut votes = TiedOrdersComplete::new(3);
+        votes.add_from_str("2,{0,1}");
+        votes.add_from_str("{0,1,2}");
+        let names = ["alice", "bob", "carol"].map(String::from).to_vec();
+        let mut registry = CandidateRegistry::new(names).unwrap();
+        registry.set_color(Candidate(1), [10, 20, 30]).unwrap();
+        let buf = roundtrip(&votes, Some(&registry));
+        let (read, read_registry) = read_vot::<_, TiedOrdersComplete>(&mut &buf[..]).unwrap();
+        assert_eq!(read.votes, votes.votes);
+        assert_eq!(read.ties, votes.ties);
+        assert_eq!(read_registry, Some(registry));
+
+        // Every damaged byte is detected.
+        for i in 0..buf.len() {
+            let mut damaged = buf.clone();
+            damaged[i] ^= 0x10;
+            assert!(read_vot::<_, TiedOrdersComplete>(&mut &damaged[..]).is_err());
+        }
+        assert!(read_vot::<_, TiedOrdersComplete>(&mut &buf[..(buf.len() - 1)]).is_err());
+        assert!(read_vot::<_, TiedOrdersIncomplete>(&mut &buf[..]).is_err());
+    }
+
+    #[test]
+    fn newer_version() {
+        let mut votes = TiedOrdersIncomplete::new(2);
+        votes.add_from_str("1");
+        let mut buf = roundtrip(&votes, None);
+        buf[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let crc = !crc_update(!0, &buf[..20]);
+        buf[20..24].copy_from_slice(&crc.to_le_bytes());
+        let err = read_vot::<_, TiedOrdersIncomplete>(&mut &buf[..]).unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported .vot version");
+    }
+
+    // A header claiming `candidates` candidates and as many voters as possible.
+    fn huge_header<P: VotProfile>(votes: &P, candidates: u32) -> io::Result<P> {
+        let mut buf = roundtrip(votes, None);
+        buf[8..12].copy_from_slice(&candidates.to_le_bytes());
+        buf[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+        let crc = !crc_update(!0, &buf[..20]);
+        buf[20..24].copy_from_slice(&crc.to_le_bytes());
+        read_vot::<_, P>(&mut &buf[..]).map(|(votes, _)| votes)
+    }
+
+    #[test]
+    fn untrusted_header() {
+        for candidates in [u32::MAX, MAX_CANDIDATES as u32 + 1, MAX_CANDIDATES as u32] {
+            assert!(huge_header(&TiedOrdersComplete::new(1), candidates).is_err());
+            assert!(huge_header(&TiedOrdersIncomplete::new(1), candidates).is_err());
+        }
+    }
+
+    #[quickcheck]
+    fn incomplete_roundtrip(votes: TiedOrdersIncomplete) -> bool {
+        let buf = roundtrip(&votes, None);
+        let (read, registry) = read_vot::<_, TiedOrdersIncomplete>(&mut &buf[..]).unwrap();
+        read == votes && registry.is_none()
+    }
+
+    #[quickcheck]
+    fn complete_roundtrip(seed: u64, voters: u8, candidates: u16) -> bool {
+        // Also cover candidates written with 2 bytes.
+        let candidates = usize::from(candidates % 300) + 1;
+        let mut votes = TiedOrdersComplete::new(candidates);
+        votes.generate_uniform(&mut ChaCha8Rng::seed_from_u64(seed), usize::from(voters % 8));
+        let buf = roundtrip(&votes, None);
+        let (read, _) = read_vot::<_, TiedOrdersComplete>(&mut &buf[..]).unwrap();
+        read.votes == votes.votes && read.ties == votes.ties
+    }
+}
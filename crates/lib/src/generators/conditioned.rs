@@ -0,0 +1,165 @@
+//! Generate profiles with specific properties, e.g. where a candidate wins
+//! under one method but loses under another, which is useful when constructing
+//! examples.
+//!
+//! Profiles are found using a perturbation search: starting from some profile,
+//! e.g. one sampled from another generator, we randomly change single votes and
+//! keep the changes which don't move us further away from the [`Target`].
+
+use rand::{distributions::Uniform, Rng};
+
+use crate::{formats::toc::TiedOrdersComplete, methods::MethodConfig};
+
+/// A property we want a profile to have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// `candidate` is the single winner under `wins` but not a winner under
+    /// `loses`.
+    WinsLoses { candidate: usize, wins: MethodConfig, loses: MethodConfig },
+    /// The number of voters ranking `a` above `b`, minus the number of voters
+    /// ranking `b` above `a`, is equal to `margin`.
+    Margin { a: usize, b: usize, margin: isize },
+}
+
+impl Target {
+    /// How far `votes` is from satisfying the target, 0 if it is satisfied.
+    pub fn cost<R: Rng>(
+        &self,
+        votes: &TiedOrdersComplete,
+        rng: &mut R,
+    ) -> Result<usize, &'static str> {
+        match self {
+            Target::WinsLoses { candidate, wins, loses } => {
+                let c = *candidate;
+                debug_assert!(c < votes.candidates);
+                let win_rank = wins.rank(votes, rng)?;
+                let lose_rank = loses.rank(votes, rng)?;
+
+                // We want `c` to be alone in the first group of `win_rank`...
+                let mut cost = 0;
+                for group in win_rank.as_ref().iter_groups() {
+                    if group.contains(&c) {
+                        cost += group.len() - 1;
+                        break;
+                    }
+                    cost += group.len();
+                }
+                // ...and not in the first group of `lose_rank`.
+                if lose_rank.as_ref().winners().contains(&c) {
+                    cost += 1;
+                }
+                Ok(cost)
+            }
+            Target::Margin { a, b, margin } => Ok(pairwise_margin(votes, *a, *b).abs_diff(*margin)),
+        }
+    }
+
+    /// Search for a profile satisfying the target, starting from `votes` and
+    /// trying at most `max_steps` perturbations. Returns `None` if no profile
+    /// was found.
+    pub fn search<R: Rng>(
+        &self,
+        rng: &mut R,
+        mut votes: TiedOrdersComplete,
+        max_steps: usize,
+    ) -> Result<Option<TiedOrdersComplete>, &'static str> {
+        let mut cost = self.cost(&votes, rng)?;
+        for _ in 0..max_steps {
+            if cost == 0 {
+                return Ok(Some(votes));
+            }
+            let mut new_votes = votes.clone();
+            perturb(rng, &mut new_votes);
+            let new_cost = self.cost(&new_votes, rng)?;
+            // We also accept changes which keep the same cost, so we can move
+            // around on plateaus.
+            if new_cost <= cost {
+                votes = new_votes;
+                cost = new_cost;
+            }
+        }
+        if cost == 0 {
+            Ok(Some(votes))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Make a small random change to a single vote in `votes`: either two adjacent
+/// candidates swap places, or the tie between them is toggled.
+pub fn perturb<R: Rng>(rng: &mut R, votes: &mut TiedOrdersComplete) {
+    let c = votes.candidates;
+    if c < 2 || votes.voters() == 0 {
+        return;
+    }
+    let voter = rng.sample(Uniform::new(0, votes.voters()));
+    let i = rng.sample(Uniform::new(0, c - 1));
+    if rng.gen() {
+        votes.votes.swap(voter * c + i, voter * c + i + 1);
+    } else {
+        let tie = &mut votes.ties[voter * (c - 1) + i];
+        *tie = !*tie;
+    }
+}
+
+// The number of votes ranking `a` above `b`, minus the number of votes ranking
+// `b` above `a`.
+fn pairwise_margin(votes: &TiedOrdersComplete, a: usize, b: usize) -> isize {
+    let mut margin = 0;
+    for vote in votes {
+        let ga = vote.group_of(a);
+        let gb = vote.group_of(b);
+        if ga < gb {
+            margin += 1;
+        } else if gb < ga {
+            margin -= 1;
+        }
+    }
+    margin
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::formats::TiePolicy;
+
+    #[test]
+    fn search_margin() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut votes = TiedOrdersComplete::new(4);
+        votes.generate_uniform(&mut rng, 30);
+        let target = Target::Margin { a: 0, b: 1, margin: 10 };
+        let found = target.search(&mut rng, votes, 10000).unwrap().unwrap();
+        assert_eq!(pairwise_margin(&found, 0, 1), 10);
+    }
+
+    #[test]
+    fn search_wins_loses() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut votes = TiedOrdersComplete::new(3);
+        votes.generate_uniform(&mut rng, 10);
+        let target = Target::WinsLoses {
+            candidate: 2,
+            wins: MethodConfig::Borda,
+            loses: MethodConfig::Fptp(TiePolicy::Random),
+        };
+        let found = target.search(&mut rng, votes, 10000).unwrap().unwrap();
+        let borda = MethodConfig::Borda.rank(&found, &mut rng).unwrap();
+        assert_eq!(borda.as_ref().winners(), &[2]);
+    }
+
+    #[quickcheck]
+    fn perturb_valid(voters: u8, candidates: u8, seed: u64) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut profile = TiedOrdersComplete::new(candidates as usize % 6 + 1);
+        profile.generate_uniform(&mut rng, voters as usize);
+        for _ in 0..voters {
+            perturb(&mut rng, &mut profile);
+        }
+        profile.valid()
+    }
+}
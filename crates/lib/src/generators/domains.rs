@@ -0,0 +1,297 @@
+//! Single-peaked and single-crossing profiles, two restricted domains where
+//! many results which fail in general do hold, e.g. the majority relation of a
+//! single-peaked profile with an odd number of voters has no cycles.
+//!
+//! A vote is single-peaked on an axis, an order of the candidates from left
+//! to right, if it prefers candidates closer to its favorite on either side of
+//! it. A profile is single-crossing if the votes can be ordered so that, for
+//! every pair of candidates, the votes preferring one of them come first.
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::is_permutation;
+use crate::formats::soc::StrictOrdersComplete;
+
+/// Sample `voters` votes which are single-peaked on `axis`, uniformly among
+/// the `2^(candidates - 1)` such votes. Returns an error if the axis is
+/// empty.
+///
+/// Every vote is built from the bottom: the least preferred remaining
+/// candidate is always at the left or the right end of the remaining axis,
+/// chosen with a fair coin.
+pub fn single_peaked<R: Rng>(
+    rng: &mut R,
+    axis: &[usize],
+    voters: usize,
+) -> Result<StrictOrdersComplete, &'static str> {
+    if !is_permutation(axis) {
+        return Err("Axis is not a permutation");
+    }
+    if axis.is_empty() {
+        return Err("No candidates");
+    }
+    let candidates = axis.len();
+    let mut votes = StrictOrdersComplete::new(candidates);
+    let mut vote = vec![0; candidates];
+    for _ in 0..voters {
+        let (mut left, mut right) = (0, candidates);
+        for position in (0..candidates).rev() {
+            if right - left == 1 || rng.gen() {
+                left += 1;
+                vote[position] = axis[left - 1];
+            } else {
+                right -= 1;
+                vote[position] = axis[right];
+            }
+        }
+        votes.add(&vote);
+    }
+    Ok(votes)
+}
+
+/// Sample `voters` votes which are single-crossing when taken in the order
+/// they are added. Returns an error if `first` is empty.
+///
+/// We take a uniformly random path from `first` to its reverse, where every
+/// step swaps two adjacent candidates which are still in the same order as in
+/// `first`, so every pair is swapped exactly once. The votes are uniformly
+/// random points on that path.
+pub fn single_crossing<R: Rng>(
+    rng: &mut R,
+    first: &[usize],
+    voters: usize,
+) -> Result<StrictOrdersComplete, &'static str> {
+    if !is_permutation(first) {
+        return Err("First vote is not a permutation");
+    }
+    if first.is_empty() {
+        return Err("No candidates");
+    }
+    let candidates = first.len();
+    let steps = candidates * candidates.saturating_sub(1) / 2;
+    let mut points: Vec<usize> = (0..voters).map(|_| rng.gen_range(0..=steps)).collect();
+    points.sort_unstable();
+
+    let mut position_in_first = vec![0; candidates];
+    for (i, &c) in first.iter().enumerate() {
+        position_in_first[c] = i;
+    }
+    let mut votes = StrictOrdersComplete::new(candidates);
+    let mut vote = first.to_vec();
+    let mut swappable = Vec::with_capacity(candidates);
+    let mut step = 0;
+    for point in points {
+        while step < point {
+            swappable.clear();
+            swappable.extend(
+                (0..candidates - 1)
+                    .filter(|&i| position_in_first[vote[i]] < position_in_first[vote[i + 1]]),
+            );
+            let &i = swappable.choose(rng).unwrap();
+            vote.swap(i, i + 1);
+            step += 1;
+        }
+        votes.add(&vote);
+    }
+    Ok(votes)
+}
+
+/// Returns true if every vote of `votes` is single-peaked on `axis`, i.e. the
+/// candidates a vote ranks highest always form an interval of the axis.
+/// Without candidates there is no axis, so this returns false.
+pub fn is_single_peaked_on(votes: &StrictOrdersComplete, axis: &[usize]) -> bool {
+    if axis.is_empty() || axis.len() != votes.candidates || !is_permutation(axis) {
+        return false;
+    }
+    let mut position = vec![0; axis.len()];
+    for (i, &c) in axis.iter().enumerate() {
+        position[c] = i;
+    }
+    votes.into_iter().all(|vote| {
+        let Some((&top, rest)) = vote.split_first() else {
+            return true;
+        };
+        let (mut left, mut right) = (position[top], position[top]);
+        rest.iter().all(|&c| {
+            let p = position[c];
+            if p + 1 == left {
+                left = p;
+                true
+            } else if p == right + 1 {
+                right = p;
+                true
+            } else {
+                false
+            }
+        })
+    })
+}
+
+/// Find an axis where every vote of `votes` is single-peaked, or `None` if
+/// there is no such axis or no candidates.
+///
+/// The axis is built from both ends, as the least preferred remaining
+/// candidate of every vote has to be at one of the ends of the remaining axis.
+/// A candidate only goes next to the part of the axis already placed on one
+/// side if no vote ranks that part above it, unless it's the favorite of the
+/// vote among the remaining candidates, and otherwise to the other side.
+/// This is the algorithm by Escoffier, Lang and Öztürk, and takes
+/// `O(voters * candidates^2)` time.
+///
+/// ```
+/// use votery::{
+///     formats::soc::StrictOrdersComplete,
+///     generators::domains::{is_single_peaked_on, single_peaked_axis},
+/// };
+///
+/// let mut votes = StrictOrdersComplete::new(4);
+/// assert!(votes.add_from_str("2,1,3,0"));
+/// assert!(votes.add_from_str("3,2,1,0"));
+/// let axis = single_peaked_axis(&votes).unwrap();
+/// assert!(is_single_peaked_on(&votes, &axis));
+///
+/// assert!(votes.add_from_str("0,3,1,2"));
+/// assert_eq!(single_peaked_axis(&votes), None);
+/// ```
+pub fn single_peaked_axis(votes: &StrictOrdersComplete) -> Option<Vec<usize>> {
+    let candidates = votes.candidates;
+    if candidates == 0 {
+        return None;
+    }
+    // `rank[v * candidates + c]` is the position of `c` in vote `v`.
+    let mut rank = vec![0; votes.votes.len()];
+    for (v, vote) in votes.into_iter().enumerate() {
+        for (i, &c) in vote.iter().enumerate() {
+            rank[v * candidates + c] = i;
+        }
+    }
+    let ranks = || rank.chunks_exact(candidates);
+
+    let mut remaining = vec![true; candidates];
+    let mut left: Vec<usize> = Vec::with_capacity(candidates);
+    let mut right: Vec<usize> = Vec::with_capacity(candidates);
+    while left.len() + right.len() + 1 < candidates {
+        // The least preferred remaining candidates, of which there can be at
+        // most two, and the most preferred one of every vote.
+        let mut last: Vec<usize> = Vec::with_capacity(2);
+        let mut best = Vec::with_capacity(votes.voters());
+        for r in ranks() {
+            let left_out = || (0..candidates).filter(|&c| remaining[c]);
+            let worst = left_out().max_by_key(|&c| r[c]).unwrap();
+            if !last.contains(&worst) {
+                last.push(worst);
+            }
+            best.push(left_out().min_by_key(|&c| r[c]).unwrap());
+        }
+        // Every vote which prefers another remaining candidate to `c` ranks
+        // `c` above `next`, the innermost candidate on that side, so `c` can
+        // be placed next to it.
+        let fits = |c: usize, side: &[usize]| {
+            side.last()
+                .is_none_or(|&next| ranks().zip(&best).all(|(r, &b)| b == c || r[c] < r[next]))
+        };
+        match last[..] {
+            [c] => {
+                if fits(c, &left) {
+                    left.push(c);
+                } else {
+                    right.push(c);
+                }
+                remaining[c] = false;
+            }
+            [a, b] => {
+                let (a, b) = if fits(a, &left) && fits(b, &right) { (a, b) } else { (b, a) };
+                left.push(a);
+                right.push(b);
+                remaining[a] = false;
+                remaining[b] = false;
+            }
+            [] => break,
+            _ => return None,
+        }
+    }
+    left.extend((0..candidates).filter(|&c| remaining[c]));
+    left.extend(right.iter().rev());
+    is_single_peaked_on(votes, &left).then_some(left)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::enumerate::strict_orders;
+
+    // Every vote of `votes`, in the order they were added, prefers `a` to `b`
+    // until some vote and never after it, or the other way around.
+    fn single_crossing_in_order(votes: &StrictOrdersComplete) -> bool {
+        let m = votes.candidates;
+        (0..m).all(|a| {
+            (0..m).all(|b| {
+                let prefers: Vec<bool> = votes
+                    .into_iter()
+                    .map(|v| v.iter().position(|&c| c == a) < v.iter().position(|&c| c == b))
+                    .collect();
+                prefers.windows(2).filter(|w| w[0] != w[1]).count() <= 1
+            })
+        })
+    }
+
+    #[test]
+    fn not_single_peaked() {
+        let mut votes = StrictOrdersComplete::new(3);
+        assert!(votes.add_from_str("0,1,2"));
+        assert!(votes.add_from_str("1,2,0"));
+        assert!(single_peaked_axis(&votes).is_some());
+        // Every candidate is last in some vote.
+        assert!(votes.add_from_str("2,0,1"));
+        assert_eq!(single_peaked_axis(&votes), None);
+        assert!(!is_single_peaked_on(&votes, &[0, 1, 2]));
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(single_peaked(&mut rng, &[0, 2], 1).is_err());
+        assert!(single_crossing(&mut rng, &[1, 1], 1).is_err());
+    }
+
+    #[test]
+    fn no_candidates() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(single_peaked(&mut rng, &[], 3).is_err());
+        assert!(single_crossing(&mut rng, &[], 3).is_err());
+        let votes = StrictOrdersComplete::new(0);
+        assert_eq!(single_peaked_axis(&votes), None);
+        assert!(!is_single_peaked_on(&votes, &[]));
+    }
+
+    #[quickcheck]
+    fn generated_single_peaked(candidates: u8, seed: u64) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut axis: Vec<usize> = (0..candidates as usize % 10 + 1).collect();
+        axis.shuffle(&mut rng);
+        let votes = single_peaked(&mut rng, &axis, 8).unwrap();
+        is_single_peaked_on(&votes, &axis)
+            && single_peaked_axis(&votes).is_some_and(|a| is_single_peaked_on(&votes, &a))
+    }
+
+    #[quickcheck]
+    fn generated_single_crossing(candidates: u8, seed: u64) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let first: Vec<usize> = (0..candidates as usize % 10 + 1).rev().collect();
+        let votes = single_crossing(&mut rng, &first, 8).unwrap();
+        votes.voters() == 8 && single_crossing_in_order(&votes)
+    }
+
+    // Compare with trying every axis, on profiles which are often
+    // single-peaked.
+    #[quickcheck]
+    fn axis_exists(candidates: u8, seed: u64) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let m = candidates as usize % 6 + 1;
+        let axis: Vec<usize> = (0..m).collect();
+        let mut votes = single_peaked(&mut rng, &axis, 3).unwrap();
+        votes.generate_uniform(&mut rng, 1);
+        let exists = strict_orders(m).any(|a| is_single_peaked_on(&votes, a.as_ref().order()));
+        single_peaked_axis(&votes).is_some() == exists
+    }
+}
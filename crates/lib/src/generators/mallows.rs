@@ -0,0 +1,77 @@
+//! The Mallows model, where every vote is a noisy copy of a reference
+//! ranking. It's the standard model of voters trying to find a correct
+//! answer, like a jury.
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+use super::is_permutation;
+use crate::formats::soc::StrictOrdersComplete;
+
+/// Sample `voters` votes from the Mallows model around `reference`, a
+/// permutation of the candidates, with dispersion `phi`.
+///
+/// The probability of a vote is proportional to `phi^d`, where `d` is its
+/// Kendall tau distance to `reference`, i.e. the number of pairs of
+/// candidates it orders differently. With `phi = 0.0` every vote equals
+/// `reference`, and with `phi = 1.0` the votes are uniformly random. The votes
+/// are sampled with the repeated insertion model.
+pub fn mallows<R: Rng>(
+    rng: &mut R,
+    reference: &[usize],
+    phi: f64,
+    voters: usize,
+) -> Result<StrictOrdersComplete, &'static str> {
+    if !(0.0..=1.0).contains(&phi) {
+        return Err("Dispersion has to be between 0 and 1");
+    }
+    if !is_permutation(reference) {
+        return Err("Reference is not a permutation");
+    }
+    let candidates = reference.len();
+    // Inserting the `i`th candidate at position `j` moves it above `i - j`
+    // candidates which should be above it.
+    let insertions: Vec<WeightedIndex<f64>> = (0..candidates)
+        .map(|i| WeightedIndex::new((0..=i).map(|j| phi.powi((i - j) as i32))).unwrap())
+        .collect();
+    let mut votes = StrictOrdersComplete::new(candidates);
+    let mut vote = Vec::with_capacity(candidates);
+    for _ in 0..voters {
+        vote.clear();
+        for (&c, insertion) in reference.iter().zip(&insertions) {
+            vote.insert(insertion.sample(rng), c);
+        }
+        votes.add(&vote);
+    }
+    Ok(votes)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn extremes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let votes = mallows(&mut rng, &[2, 0, 1], 0.0, 10).unwrap();
+        assert_eq!(votes.voters(), 10);
+        assert!(votes.votes.chunks(3).all(|v| v == [2, 0, 1]));
+
+        // Every one of the 6 orders is about equally likely.
+        let votes = mallows(&mut rng, &[0, 1, 2], 1.0, 6000).unwrap();
+        let mut counts = std::collections::HashMap::new();
+        for v in votes.votes.chunks(3) {
+            *counts.entry(v.to_vec()).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(counts.values().all(|&n| (800..1200).contains(&n)));
+
+        assert!(mallows(&mut rng, &[0, 0], 0.5, 1).is_err());
+        assert!(mallows(&mut rng, &[0, 1], 1.5, 1).is_err());
+    }
+}
@@ -1,3 +1,13 @@
 //! Generators which can be used to simulate a group of voters.
 
-pub mod gaussian;
+pub mod conditioned;
+pub mod domains;
+pub mod mallows;
+pub mod spatial;
+pub mod tournament;
+
+// Returns true if `order` contains every number below its length once.
+fn is_permutation(order: &[usize]) -> bool {
+    let mut seen = vec![false; order.len()];
+    order.iter().all(|&c| c < order.len() && !std::mem::replace(&mut seen[c], true))
+}
@@ -0,0 +1,843 @@
+//! A spatial model of voting behaviour, where every candidate is a point in
+//! some space, and voters vote for nearby candidates.
+use std::{
+    mem,
+    slice::{ChunksExact, Iter, IterMut},
+};
+
+use rand::distributions::WeightedIndex;
+use rand_distr::{num_traits::Pow, Distribution, Normal};
+
+use crate::{
+    formats::{orders::TiedRank, toc::TiedOrdersComplete, Cardinal, VoteFormat},
+    spatial::{DistanceMetric, Point},
+};
+
+/// Voters distributed around a mean in `D` dimensional space, normally
+/// distributed unless another [`VoterDistribution`] is set.
+pub struct Spatial<const D: usize> {
+    candidates: Vec<Point<D>>,
+    // The salience weights of every candidate, one per dimension
+    salience: Vec<[f64; D]>,
+    metric: DistanceMetric,
+    model: UtilityModel,
+    // The neutral point of `UtilityModel::Directional`
+    neutral: Point<D>,
+    variance: f64,
+    points: usize,
+    fuzzy: FuzzyType,
+    turnout: Turnout,
+    distribution: VoterDistribution<D>,
+    // Voters relative to the mean, used for every sample instead of drawing
+    // new ones.
+    electorate: Option<Vec<Point<D>>>,
+}
+
+/// Decides when two candidates should be tied
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FuzzyType {
+    /// There are ties if the distance to two candidates are less than `fuzzy`
+    Absolute(f64),
+    /// Candidates further away are harder to differentiate, so larger distances
+    /// are treated as tied
+    Scaling(f64),
+    /// There are only ties if two candidates are exactly the same distance away
+    Equal,
+}
+
+/// How voters decide which candidates they like
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UtilityModel {
+    /// Voters prefer the candidates closest to them, measured by the
+    /// [`DistanceMetric`] of the generator.
+    #[default]
+    Proximity,
+    /// The directional model of Rabinowitz and Macdonald. Voters prefer
+    /// candidates on the same side of the neutral point as themselves, and
+    /// among those the ones taking the most intense positions. The utility is
+    /// the salience-weighted dot product `(v - n) · (c - n)` of the voter `v`
+    /// and candidate `c`, relative to the neutral point `n`.
+    Directional,
+}
+
+/// Decides which of the sampled voters actually vote. With
+/// [`UtilityModel::Directional`] the negated utilities are used in place of
+/// distances.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Turnout {
+    /// Every voter votes
+    #[default]
+    Full,
+    /// Voters far away from every candidate are less likely to vote. A voter
+    /// at distance `d` from the nearest candidate votes with probability
+    /// `exp(-d / scale)`.
+    Alienation(f64),
+    /// Voters who see little difference between their two favorite candidates
+    /// are less likely to vote. A voter whose distances to them differ by `g`
+    /// votes with probability `1 - exp(-g / scale)`.
+    Indifference(f64),
+}
+
+impl Turnout {
+    /// Check that the scale is finite and positive.
+    pub fn check(&self) -> Result<(), &'static str> {
+        match *self {
+            Turnout::Full => Ok(()),
+            Turnout::Alienation(scale) | Turnout::Indifference(scale) => {
+                if scale.is_finite() && scale > 0.0 {
+                    Ok(())
+                } else {
+                    Err("The scale of the turnout must be finite and positive")
+                }
+            }
+        }
+    }
+
+    // The probability that a voter with `distances` to the candidates votes.
+    fn probability(self, distances: &[f64]) -> f64 {
+        let mut nearest = f64::INFINITY;
+        let mut second = f64::INFINITY;
+        for &d in distances {
+            if d < nearest {
+                second = nearest;
+                nearest = d;
+            } else if d < second {
+                second = d;
+            }
+        }
+        match self {
+            Turnout::Full => 1.0,
+            Turnout::Alienation(scale) => (-nearest / scale).exp(),
+            Turnout::Indifference(_) if !second.is_finite() => 1.0,
+            Turnout::Indifference(scale) => 1.0 - (-(second - nearest) / scale).exp(),
+        }
+    }
+}
+
+/// Where the voters of a sample are placed around its mean.
+#[derive(Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VoterDistribution<const D: usize> {
+    /// Normally distributed around the mean, with the variance of the
+    /// generator in every dimension.
+    #[default]
+    Gaussian,
+    /// Uniformly distributed in a box centered on the mean, which is
+    /// `2 * half_size[i]` wide in dimension `i`.
+    UniformBox { half_size: Point<D> },
+    /// A mixture of normal distributions, where every voter belongs to one of
+    /// the components, chosen with probability proportional to its weight.
+    Mixture(Vec<MixtureComponent<D>>),
+    /// Every sample has `clusters` centers, normally distributed around the
+    /// mean with the variance of the generator, and every voter is normally
+    /// distributed around a uniformly random center with `variance`.
+    Clustered { clusters: usize, variance: f64 },
+}
+
+/// A component of [`VoterDistribution::Mixture`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MixtureComponent<const D: usize> {
+    pub weight: f64,
+    /// The center of the component, relative to the mean.
+    pub offset: Point<D>,
+    pub variance: f64,
+}
+
+impl<const D: usize> VoterDistribution<D> {
+    /// Check that every parameter is finite and that there is some voter to
+    /// draw, e.g. that not every weight of a mixture is zero.
+    pub fn check(&self) -> Result<(), &'static str> {
+        let variance = |v: f64| v.is_finite() && v >= 0.0;
+        match self {
+            VoterDistribution::Gaussian => Ok(()),
+            VoterDistribution::UniformBox { half_size } => {
+                if half_size.coords().iter().all(|&h| variance(h)) {
+                    Ok(())
+                } else {
+                    Err("The box must have a finite, non-negative size")
+                }
+            }
+            VoterDistribution::Mixture(components) => {
+                if !components.iter().all(|c| {
+                    c.offset.coords().iter().all(|o| o.is_finite()) && variance(c.variance)
+                }) {
+                    return Err("Every component must have a finite offset and variance");
+                }
+                match WeightedIndex::new(components.iter().map(|c| c.weight)) {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err("The weights of the mixture must be non-negative and not all 0"),
+                }
+            }
+            VoterDistribution::Clustered { clusters, variance: v } => {
+                if *clusters == 0 {
+                    Err("There must be at least one cluster")
+                } else if !variance(*v) {
+                    Err("The variance of the clusters must be finite and non-negative")
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    // The normal distributions to draw voters around `mean` from, with their
+    // weights, where `variance` is the variance of the generator. Not used by
+    // `UniformBox`.
+    fn components<R: rand::Rng>(
+        &self,
+        mean: &Point<D>,
+        variance: f64,
+        rng: &mut R,
+    ) -> (Vec<(Point<D>, f64)>, Option<WeightedIndex<f64>>) {
+        match self {
+            VoterDistribution::Gaussian | VoterDistribution::UniformBox { .. } => {
+                (vec![(*mean, variance)], None)
+            }
+            VoterDistribution::Mixture(components) => {
+                let centers = components
+                    .iter()
+                    .map(|c| {
+                        (Point::new(std::array::from_fn(|d| mean[d] + c.offset[d])), c.variance)
+                    })
+                    .collect();
+                (centers, WeightedIndex::new(components.iter().map(|c| c.weight)).ok())
+            }
+            VoterDistribution::Clustered { clusters, variance: v } => {
+                let centers =
+                    (0..*clusters).map(|_| (generate_point(mean, variance, rng), *v)).collect();
+                (centers, WeightedIndex::new(vec![1.0; *clusters]).ok())
+            }
+        }
+    }
+
+    // Draw `voters` voters around `mean` and call `f` with each of them.
+    fn sample<R: rand::Rng, F: FnMut(&mut R, Point<D>)>(
+        &self,
+        rng: &mut R,
+        mean: &Point<D>,
+        variance: f64,
+        voters: usize,
+        mut f: F,
+    ) {
+        if let VoterDistribution::UniformBox { half_size } = self {
+            for _ in 0..voters {
+                let point = Point::new(std::array::from_fn(|d| {
+                    mean[d] + half_size[d] * rng.gen_range(-1.0..=1.0)
+                }));
+                f(rng, point);
+            }
+            return;
+        }
+        let (components, weights) = self.components(mean, variance, rng);
+        for _ in 0..voters {
+            let (center, variance) = match &weights {
+                Some(weights) => components[weights.sample(rng)],
+                None => components[0],
+            };
+            let point = generate_point(&center, variance, rng);
+            f(rng, point);
+        }
+    }
+}
+
+impl<const D: usize> Spatial<D> {
+    pub fn new(variance: f64, points: usize, fuzzy: FuzzyType) -> Self {
+        Spatial {
+            candidates: Vec::new(),
+            salience: Vec::new(),
+            metric: DistanceMetric::Euclidean,
+            model: UtilityModel::Proximity,
+            neutral: Point::default(),
+            variance: variance,
+            points,
+            fuzzy,
+            turnout: Turnout::Full,
+            distribution: VoterDistribution::Gaussian,
+            electorate: None,
+        }
+    }
+
+    /// Use the same voters for every sample, placed at `electorate` relative
+    /// to the mean, instead of drawing new voters every time. The samples
+    /// then all describe one concrete electorate, moved to different means,
+    /// and have one vote per voter instead of [`Spatial::points`] votes.
+    /// With `None` new voters are drawn again.
+    pub fn set_electorate(&mut self, electorate: Option<Vec<Point<D>>>) {
+        self.electorate = electorate;
+    }
+
+    pub fn electorate(&self) -> Option<&[Point<D>]> {
+        self.electorate.as_deref()
+    }
+
+    /// Draw [`Spatial::points`] voters around the origin, e.g. to use as a
+    /// fixed electorate with [`Spatial::set_electorate`].
+    pub fn sample_electorate<R: rand::Rng>(&self, rng: &mut R) -> Vec<Point<D>> {
+        let mut electorate = Vec::with_capacity(self.points);
+        self.distribution
+            .sample(rng, &Point::default(), self.variance, self.points, |_, p| electorate.push(p));
+        electorate
+    }
+
+    /// Set where the voters of a sample are placed around its mean. The
+    /// default is [`VoterDistribution::Gaussian`].
+    pub fn set_distribution(
+        &mut self,
+        distribution: VoterDistribution<D>,
+    ) -> Result<(), &'static str> {
+        distribution.check()?;
+        self.distribution = distribution;
+        Ok(())
+    }
+
+    pub fn distribution(&self) -> &VoterDistribution<D> {
+        &self.distribution
+    }
+
+    /// Set which voters vote. By default everyone votes, otherwise a sample
+    /// may contain fewer than [`Spatial::points`] votes.
+    pub fn set_turnout(&mut self, turnout: Turnout) -> Result<(), &'static str> {
+        turnout.check()?;
+        self.turnout = turnout;
+        Ok(())
+    }
+
+    pub fn turnout(&self) -> Turnout {
+        self.turnout
+    }
+
+    /// Set how the distance between voters and candidates is measured. The
+    /// default is [`DistanceMetric::Euclidean`].
+    pub fn set_metric(&mut self, metric: DistanceMetric) {
+        self.metric = metric;
+    }
+
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    /// Set how voters decide which candidates they like. The default is
+    /// [`UtilityModel::Proximity`].
+    pub fn set_model(&mut self, model: UtilityModel) {
+        self.model = model;
+    }
+
+    pub fn model(&self) -> UtilityModel {
+        self.model
+    }
+
+    /// Set the neutral point of [`UtilityModel::Directional`], which is the
+    /// origin by default.
+    pub fn set_neutral<P: Into<Point<D>>>(&mut self, neutral: P) {
+        self.neutral = neutral.into();
+    }
+
+    pub fn neutral(&self) -> &Point<D> {
+        &self.neutral
+    }
+
+    /// Set how much voters care about each dimension when comparing
+    /// themselves to candidate `i`. New candidates have every weight set to
+    /// 1.0.
+    pub fn set_salience(&mut self, i: usize, weights: [f64; D]) -> Result<(), &'static str> {
+        if weights.iter().any(|w| !(w.is_finite() && *w >= 0.0)) {
+            return Err("Salience weights must be finite and non-negative");
+        }
+        match self.salience.get_mut(i) {
+            Some(s) => {
+                *s = weights;
+                Ok(())
+            }
+            None => Err("Tried to set salience of non-existing candidate"),
+        }
+    }
+
+    /// The salience weights of candidate `i`.
+    pub fn salience(&self, i: usize) -> Option<&[f64; D]> {
+        self.salience.get(i)
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn add_candidate<P: Into<Point<D>>>(&mut self, candidate: P) {
+        self.candidates.push(candidate.into());
+        self.salience.push([1.0; D]);
+    }
+
+    /// Remove candidate `i`. The candidates after it are moved down one index.
+    pub fn remove_candidate(&mut self, i: usize) -> Result<(), &'static str> {
+        if i >= self.candidates() {
+            return Err("Tried to remove non-existing candidate");
+        }
+        self.candidates.remove(i);
+        self.salience.remove(i);
+        Ok(())
+    }
+
+    /// Move candidate `i` to `position`.
+    pub fn move_candidate<P: Into<Point<D>>>(
+        &mut self,
+        i: usize,
+        position: P,
+    ) -> Result<(), &'static str> {
+        match self.candidates.get_mut(i) {
+            Some(c) => {
+                *c = position.into();
+                Ok(())
+            }
+            None => Err("Tried to move non-existing candidate"),
+        }
+    }
+
+    /// Remove every candidate.
+    pub fn clear(&mut self) {
+        self.candidates.clear();
+        self.salience.clear();
+    }
+
+    /// The position of candidate `i`.
+    pub fn candidate(&self, i: usize) -> Option<&Point<D>> {
+        self.candidates.get(i)
+    }
+
+    pub fn dimensions(&self) -> usize {
+        D
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.variance
+    }
+
+    /// The number of votes in every sample.
+    pub fn points(&self) -> usize {
+        self.points
+    }
+
+    pub fn fuzzy(&self) -> FuzzyType {
+        self.fuzzy
+    }
+
+    pub fn iter_candidates(&self) -> Iter<Point<D>> {
+        self.candidates.iter()
+    }
+
+    pub fn iter_candidates_mut(&mut self) -> IterMut<Point<D>> {
+        self.candidates.iter_mut()
+    }
+
+    pub fn sample<R: rand::Rng>(&self, rng: &mut R, mean: &Point<D>) -> TiedOrdersComplete {
+        let mut votes = TiedOrdersComplete::new(self.candidates());
+        self.sample_distances(rng, mean, |_, candidate_score| {
+            let vote = score_to_vote(candidate_score, self.fuzzy);
+            votes.add(vote.as_ref());
+        });
+        votes
+    }
+
+    /// Like [`Spatial::sample`], but also returns the position of every
+    /// voter, in the same order as the votes.
+    pub fn sample_with_positions<R: rand::Rng>(
+        &self,
+        rng: &mut R,
+        mean: &Point<D>,
+    ) -> (TiedOrdersComplete, Vec<Point<D>>) {
+        let mut votes = TiedOrdersComplete::new(self.candidates());
+        let mut positions = Vec::with_capacity(self.points);
+        self.sample_distances(rng, mean, |point, candidate_score| {
+            let vote = score_to_vote(candidate_score, self.fuzzy);
+            votes.add(vote.as_ref());
+            positions.push(*point);
+        });
+        (votes, positions)
+    }
+
+    /// Like [`Spatial::sample`], but also returns the utility every voter has
+    /// for every candidate. With [`UtilityModel::Proximity`] the utility is the
+    /// negated distance between them, so closer candidates have higher
+    /// utilities.
+    pub fn sample_with_utilities<R: rand::Rng>(
+        &self,
+        rng: &mut R,
+        mean: &Point<D>,
+    ) -> (TiedOrdersComplete, Utilities) {
+        let mut votes = TiedOrdersComplete::new(self.candidates());
+        let mut values = Vec::with_capacity(self.points * self.candidates());
+        self.sample_distances(rng, mean, |_, candidate_score| {
+            let vote = score_to_vote(candidate_score, self.fuzzy);
+            votes.add(vote.as_ref());
+            values.extend(candidate_score.iter().map(|d| -d));
+        });
+        (votes, Utilities { candidates: self.candidates(), values })
+    }
+
+    /// Like [`Spatial::sample`], but every voter gives the candidates scores
+    /// from 0 to `max` based on its utilities, see [`Utilities::to_cardinal`].
+    pub fn sample_cardinal<R: rand::Rng>(
+        &self,
+        rng: &mut R,
+        mean: &Point<D>,
+        max: usize,
+    ) -> Cardinal {
+        let mut votes = Cardinal::new(self.candidates(), 0, max);
+        let mut utilities = Vec::with_capacity(self.candidates());
+        let mut vote = vec![0; self.candidates()];
+        self.sample_distances(rng, mean, |_, candidate_score| {
+            utilities.clear();
+            utilities.extend(candidate_score.iter().map(|d| -d));
+            utilities_to_scores(&utilities, max, &mut vote);
+            votes.add(&vote).unwrap();
+        });
+        votes
+    }
+
+    // Generate voters around `mean` and call `f` with the position of each
+    // voter who votes and the disutility it has for each candidate. For
+    // `UtilityModel::Proximity` that's the distance between them.
+    fn sample_distances<R: rand::Rng, F: FnMut(&Point<D>, &[f64])>(
+        &self,
+        rng: &mut R,
+        mean: &Point<D>,
+        mut f: F,
+    ) {
+        let mut candidate_score = Vec::with_capacity(self.candidates());
+        let mut vote = |rng: &mut R, point: Point<D>| {
+            candidate_score.clear();
+            candidate_score.extend(
+                self.candidates
+                    .iter()
+                    .zip(&self.salience)
+                    .map(|(c, w)| self.disutility(&point, c, w)),
+            );
+            if self.turnout != Turnout::Full
+                && !rng.gen_bool(self.turnout.probability(&candidate_score).clamp(0.0, 1.0))
+            {
+                return;
+            }
+            f(&point, &candidate_score);
+        };
+        match &self.electorate {
+            Some(electorate) => {
+                for voter in electorate {
+                    vote(rng, Point::new(std::array::from_fn(|d| mean[d] + voter[d])));
+                }
+            }
+            None => self.distribution.sample(rng, mean, self.variance, self.points, vote),
+        }
+    }
+
+    fn disutility(&self, voter: &Point<D>, candidate: &Point<D>, weights: &[f64; D]) -> f64 {
+        match self.model {
+            UtilityModel::Proximity => self.metric.distance(voter, candidate, weights),
+            UtilityModel::Directional => {
+                let n = &self.neutral;
+                let mut dot = 0.0;
+                for i in 0..D {
+                    dot += weights[i] * (voter[i] - n[i]) * (candidate[i] - n[i]);
+                }
+                -dot
+            }
+        }
+    }
+}
+
+/// The utility every voter has for every candidate, from
+/// [`Spatial::sample_with_utilities`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Utilities {
+    candidates: usize,
+    // The utilities of voter `i` are at `values[i * candidates..(i + 1) * candidates]`
+    values: Vec<f64>,
+}
+
+impl Utilities {
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    pub fn voters(&self) -> usize {
+        self.values.len().checked_div(self.candidates).unwrap_or(0)
+    }
+
+    /// The utilities of voter `i`, one per candidate.
+    pub fn voter(&self, i: usize) -> &[f64] {
+        &self.values[i * self.candidates..(i + 1) * self.candidates]
+    }
+
+    /// Iterate over the utilities of every voter.
+    pub fn iter(&self) -> ChunksExact<'_, f64> {
+        self.values.chunks_exact(self.candidates.max(1))
+    }
+
+    /// Convert the utilities to scores from 0 to `max`, for methods like
+    /// [`ScoreVoting`](crate::methods::ScoreVoting). The utilities of every
+    /// voter are scaled linearly, so its favorite candidate gets `max` and its
+    /// least favorite gets 0, and then rounded. Unlike converting a ranking,
+    /// how much a voter prefers one candidate to another is kept. A voter
+    /// with the same utility for every candidate gives all of them `max`.
+    pub fn to_cardinal(&self, max: usize) -> Cardinal {
+        let mut votes = Cardinal::new(self.candidates, 0, max);
+        let mut vote = vec![0; self.candidates];
+        for utilities in self.iter() {
+            utilities_to_scores(utilities, max, &mut vote);
+            votes.add(&vote).unwrap();
+        }
+        votes
+    }
+}
+
+// Scale `utilities` linearly to `scores` from 0 to `max`, see
+// `Utilities::to_cardinal`.
+fn utilities_to_scores(utilities: &[f64], max: usize, scores: &mut [usize]) {
+    let best = utilities.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let worst = utilities.iter().copied().fold(f64::INFINITY, f64::min);
+    for (score, &u) in scores.iter_mut().zip(utilities) {
+        *score = if best > worst {
+            ((u - worst) / (best - worst) * max as f64).round() as usize
+        } else {
+            max
+        };
+    }
+}
+
+fn are_fuzzy(w0: f64, w1: f64, fuzzy: FuzzyType) -> bool {
+    match fuzzy {
+        FuzzyType::Absolute(f) => (w0 - w1).abs() <= f,
+        FuzzyType::Equal => w0 == w1,
+        FuzzyType::Scaling(f) => {
+            let (x, y) = if w0 < w1 { (w1, w0) } else { (w0, w1) };
+            y >= x - (x / ((1.0 - f.powf(0.1)) * 10.0)).powi(2)
+        }
+    }
+}
+
+fn score_to_vote(scores: &[f64], fuzzy: FuzzyType) -> TiedRank {
+    let mut list: Vec<(usize, f64)> = scores.iter().cloned().enumerate().collect();
+    list.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    // TODO: We assume self.dimension = 2 here
+    let tied: Vec<bool> = list.windows(2).map(|w| are_fuzzy(w[0].1, w[1].1, fuzzy)).collect();
+    let order: Vec<usize> = list.into_iter().map(|(i, _)| i).collect();
+    TiedRank::new(scores.len(), order, tied)
+}
+
+fn generate_point<const D: usize, R: rand::Rng>(
+    mean: &Point<D>,
+    variance: f64,
+    rng: &mut R,
+) -> Point<D> {
+    Point::new(std::array::from_fn(|i| {
+        let normal = Normal::new(mean[i], variance).unwrap();
+        normal.sample(rng)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manage_candidates() {
+        let mut g = Spatial::new(0.1, 10, FuzzyType::Equal);
+        g.add_candidate([0.0, 0.1]);
+        g.add_candidate([0.2, 0.3]);
+        g.add_candidate([0.4, 0.5]);
+        g.remove_candidate(1).unwrap();
+        assert_eq!(g.candidates(), 2);
+        assert_eq!(g.candidate(1), Some(&Point::new([0.4, 0.5])));
+        g.move_candidate(0, [0.9, 0.8]).unwrap();
+        assert_eq!(g.candidate(0), Some(&Point::new([0.9, 0.8])));
+        assert_eq!(g.candidate(2), None);
+        assert!(g.move_candidate(2, [0.0, 0.0]).is_err());
+        assert!(g.remove_candidate(2).is_err());
+        g.clear();
+        assert_eq!(g.candidates(), 0);
+    }
+
+    #[test]
+    fn fixed_electorate() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.3, 20, FuzzyType::Equal);
+        g.add_candidate([0.0, 0.1]);
+        g.add_candidate([0.7, 0.3]);
+        let electorate = g.sample_electorate(&mut rng);
+        g.set_electorate(Some(electorate.clone()));
+        let mean = Point::new([0.5, 0.25]);
+        let (votes, positions) = g.sample_with_positions(&mut rng, &mean);
+        let (again, _) = g.sample_with_positions(&mut rng, &mean);
+        assert_eq!(votes.votes, again.votes);
+        for (p, e) in positions.iter().zip(&electorate) {
+            assert_eq!(p.coords(), &[0.5 + e[0], 0.25 + e[1]]);
+        }
+        g.set_electorate(Some(vec![Point::new([0.1, 0.0])]));
+        assert_eq!(g.sample(&mut rng, &Point::default()).voters(), 1);
+    }
+
+    #[test]
+    fn utilities_match_votes() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.3, 50, FuzzyType::Equal);
+        g.add_candidate([0.0, 0.1]);
+        g.add_candidate([0.7, 0.3]);
+        g.add_candidate([0.4, 0.9]);
+        let (votes, utilities) = g.sample_with_utilities(&mut rng, &Point::new([0.5, 0.5]));
+        assert_eq!(utilities.voters(), 50);
+        assert_eq!(utilities.candidates(), 3);
+        for (vote, u) in votes.into_iter().zip(utilities.iter()) {
+            let order = vote.order();
+            assert!(order.windows(2).all(|w| u[w[0]] >= u[w[1]]));
+        }
+    }
+
+    #[test]
+    fn cardinal_utilities() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.0, 3, FuzzyType::Equal);
+        g.add_candidate([0.0, 0.0]);
+        g.add_candidate([1.0, 0.0]);
+        g.add_candidate([4.0, 0.0]);
+        // Every voter is at 0.0, so candidate 1 is closer to the favorite
+        // than to the least favorite.
+        let votes = g.sample_cardinal(&mut rng, &Point::default(), 8);
+        assert_eq!(votes.to_string(), "8,6,0\n8,6,0\n8,6,0\n");
+        let (ranked, utilities) = g.sample_with_utilities(&mut rng, &Point::default());
+        assert_eq!(utilities.to_cardinal(8), votes);
+        // Converting the ranking loses the intensity.
+        assert_eq!(ranked.to_cardinal().unwrap().to_string(), "2,1,0\n2,1,0\n2,1,0\n");
+
+        g.clear();
+        g.add_candidate([1.0, 0.0]);
+        g.add_candidate([0.0, 1.0]);
+        assert_eq!(
+            g.sample_cardinal(&mut rng, &Point::default(), 5).to_string(),
+            "5,5\n5,5\n5,5\n"
+        );
+    }
+
+    #[test]
+    fn turnout() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.01, 100, FuzzyType::Equal);
+        g.add_candidate([0.0, 0.0]);
+        g.add_candidate([0.0, 1.0]);
+        g.set_turnout(Turnout::Alienation(10.0)).unwrap();
+        assert!(g.sample(&mut rng, &Point::new([0.0, 0.0])).voters() > 90);
+        g.set_turnout(Turnout::Alienation(0.01)).unwrap();
+        assert_eq!(g.sample(&mut rng, &Point::new([5.0, 5.0])).voters(), 0);
+
+        // Voters are equally far away from both candidates.
+        g.set_turnout(Turnout::Indifference(0.01)).unwrap();
+        assert!(g.sample(&mut rng, &Point::new([10.0, 0.5])).voters() < 100);
+        assert!(g.sample(&mut rng, &Point::new([0.0, -1.0])).voters() > 0);
+
+        for scale in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(g.set_turnout(Turnout::Alienation(scale)).is_err());
+            assert!(g.set_turnout(Turnout::Indifference(scale)).is_err());
+        }
+        assert_eq!(g.turnout(), Turnout::Indifference(0.01));
+    }
+
+    #[test]
+    fn distributions() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.3, 100, FuzzyType::Equal);
+        g.add_candidate([0.0, 0.0]);
+        let mean = Point::new([1.0, 2.0]);
+        let half_size = Point::new([0.1, 0.0]);
+        g.set_distribution(VoterDistribution::UniformBox { half_size }).unwrap();
+        let (_, positions) = g.sample_with_positions(&mut rng, &mean);
+        assert_eq!(positions.len(), 100);
+        assert!(positions.iter().all(|p| (p[0] - 1.0).abs() <= 0.1 && p[1] == 2.0));
+
+        // Every voter is in the second component.
+        let component =
+            |weight, offset| MixtureComponent { weight, offset: Point::new(offset), variance: 0.0 };
+        let mixture = vec![component(0.0, [5.0, 5.0]), component(2.0, [-1.0, 0.0])];
+        g.set_distribution(VoterDistribution::Mixture(mixture)).unwrap();
+        let (_, positions) = g.sample_with_positions(&mut rng, &mean);
+        assert!(positions.iter().all(|p| p.coords() == &[0.0, 2.0]));
+
+        g.set_distribution(VoterDistribution::Clustered { clusters: 1, variance: 0.0 }).unwrap();
+        let electorate = g.sample_electorate(&mut rng);
+        assert!(electorate.iter().all(|p| p == &electorate[0]));
+
+        let invalid = [
+            VoterDistribution::Mixture(vec![component(0.0, [0.0, 0.0])]),
+            VoterDistribution::Mixture(Vec::new()),
+            VoterDistribution::Clustered { clusters: 0, variance: 0.1 },
+            VoterDistribution::UniformBox { half_size: Point::new([f64::NAN, 0.0]) },
+        ];
+        for distribution in invalid {
+            assert!(g.set_distribution(distribution).is_err());
+        }
+        assert!(matches!(g.distribution(), VoterDistribution::Clustered { .. }));
+    }
+
+    #[test]
+    fn many_dimensions() {
+        let mut rng = rand::thread_rng();
+        let mut g: Spatial<10> = Spatial::new(0.1, 20, FuzzyType::Equal);
+        assert_eq!(g.dimensions(), 10);
+        let mut far = [0.0; 10];
+        far[9] = 5.0;
+        g.add_candidate([0.0; 10]);
+        g.add_candidate(far);
+        let votes = g.sample(&mut rng, &Point::default());
+        assert_eq!(votes.voters(), 20);
+        assert!(votes.into_iter().all(|v| v.winners() == [0]));
+    }
+
+    #[test]
+    fn salience() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.01, 50, FuzzyType::Equal);
+        g.add_candidate([1.0, 0.0]);
+        g.add_candidate([0.0, 2.0]);
+        assert_eq!(g.salience(1), Some(&[1.0, 1.0]));
+        let center = Point::default();
+        assert!(g.sample(&mut rng, &center).into_iter().all(|v| v.winners() == [0]));
+        // Voters barely care about the second dimension for candidate 1.
+        g.set_salience(1, [1.0, 0.01]).unwrap();
+        assert!(g.sample(&mut rng, &center).into_iter().all(|v| v.winners() == [1]));
+        g.set_metric(DistanceMetric::Chebyshev);
+        assert!(g.sample(&mut rng, &center).into_iter().all(|v| v.winners() == [1]));
+        assert!(g.set_salience(2, [1.0, 1.0]).is_err());
+        assert!(g.set_salience(0, [-1.0, 1.0]).is_err());
+        g.remove_candidate(0).unwrap();
+        assert_eq!(g.salience(0), Some(&[1.0, 0.01]));
+    }
+
+    #[test]
+    fn directional() {
+        let mut rng = rand::thread_rng();
+        let mut g = Spatial::new(0.01, 50, FuzzyType::Equal);
+        g.add_candidate([0.6, 0.5]);
+        g.add_candidate([1.0, 0.5]);
+        g.add_candidate([0.0, 0.5]);
+        g.set_neutral([0.5, 0.5]);
+        let voter = Point::new([0.6, 0.5]);
+        assert!(g.sample(&mut rng, &voter).into_iter().all(|v| v.winners() == [0]));
+        // The more extreme candidate on the same side wins instead.
+        g.set_model(UtilityModel::Directional);
+        for vote in &g.sample(&mut rng, &voter) {
+            assert_eq!(vote.order(), &[1, 0, 2]);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_distribution() {
+        let distribution = VoterDistribution::Mixture(vec![MixtureComponent {
+            weight: 2.0,
+            offset: Point::new([0.5, -0.5]),
+            variance: 0.1,
+        }]);
+        let json = serde_json::to_string(&distribution).unwrap();
+        assert_eq!(serde_json::from_str::<VoterDistribution<2>>(&json).unwrap(), distribution);
+        assert!(serde_json::from_str::<VoterDistribution<3>>(&json).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+//! Random [`Tournament`]s, which can be used to test code working on pairwise
+//! comparisons without generating any votes.
+
+use rand::Rng;
+
+use crate::tournament::Tournament;
+
+/// Every pair of candidates is connected by a single edge with weight 1, with
+/// a uniformly random direction.
+pub fn uniform<R: Rng>(rng: &mut R, candidates: usize) -> Tournament {
+    let mut t = Tournament::new(candidates);
+    for a in 0..candidates {
+        for b in (a + 1)..candidates {
+            let (from, to) = if rng.gen() { (a, b) } else { (b, a) };
+            t.add_edge(from, to, 1).unwrap();
+        }
+    }
+    t
+}
+
+/// A tournament with many Condorcet cycles. We start with a regular
+/// tournament, where every candidate `a` beats the `(candidates - 1) / 2`
+/// candidates after it (modulo `candidates`), and then reverse every edge with
+/// probability `p`. Every edge has weight 1.
+///
+/// If `p` is 0.0 and the number of candidates is odd, then every candidate
+/// beats exactly half of the other candidates, so there is no Condorcet winner.
+pub fn cyclic<R: Rng>(rng: &mut R, candidates: usize, p: f64) -> Tournament {
+    debug_assert!((0.0..=1.0).contains(&p));
+    let mut t = Tournament::new(candidates);
+    for a in 0..candidates {
+        for b in (a + 1)..candidates {
+            // `a` beats `b` if `b` is among the candidates after `a`.
+            let a_beats_b = b - a <= (candidates - 1) / 2;
+            let reverse = rng.gen_bool(p);
+            let (from, to) = if a_beats_b != reverse { (a, b) } else { (b, a) };
+            t.add_edge(from, to, 1).unwrap();
+        }
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[quickcheck]
+    fn uniform_complete(candidates: u8, seed: u64) -> bool {
+        let c = candidates as usize % 20;
+        let t = uniform(&mut ChaCha8Rng::seed_from_u64(seed), c);
+        (0..c).all(|a| (0..c).all(|b| a == b || t.beats(a, b) != t.beats(b, a)))
+    }
+
+    #[quickcheck]
+    fn cyclic_no_winner(candidates: u8, seed: u64) -> bool {
+        let c = (candidates as usize % 10) * 2 + 3;
+        let t = cyclic(&mut ChaCha8Rng::seed_from_u64(seed), c, 0.0);
+        t.condorcet_winner().is_none()
+    }
+}
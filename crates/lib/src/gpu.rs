@@ -0,0 +1,333 @@
+//! Experimental tallying of approval and cardinal profiles on the GPU, behind
+//! the `gpu` feature.
+//!
+//! Counting [`Approval`](crate::methods::Approval) and
+//! [`ScoreVoting`](crate::methods::ScoreVoting) is a sum over every vote, so
+//! with very large synthetic electorates most of the time of a simulation is
+//! spent there. [`GpuTally`] computes the same sums as
+//! [`Binary::column_sums`] and [`Cardinal::column_sums`] with a compute shader.
+//! Approval votes are packed as bits before they're uploaded, so they take 32
+//! times less memory than on the CPU.
+//!
+//! Moving the votes to the GPU takes time, so this is only faster for profiles
+//! of millions of voters, and only when they were generated anyway. An
+//! [`Experiment`](crate::simulation::Experiment) counts score
+//! voting with [`GpuTally`] when there is a GPU, and on the CPU otherwise.
+
+use std::sync::mpsc;
+
+use wgpu::util::DeviceExt;
+
+use crate::formats::{Binary, Cardinal};
+
+// The sums of a workgroup are kept in an array of this size, so profiles with
+// more candidates are rejected.
+const MAX_CANDIDATES: usize = 256;
+const WORKGROUP_SIZE: usize = 256;
+// The number of elements every invocation adds, roughly.
+const PER_INVOCATION: usize = 16;
+const MAX_WORKGROUPS: usize = 65535;
+
+const SHADER: &str = r#"
+struct Params {
+    // The number of elements of this chunk.
+    len: u32,
+    candidates: u32,
+    // The candidate of the first element of the chunk.
+    first: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> data: array<u32>;
+@group(0) @binding(2) var<storage, read_write> sums: array<atomic<u32>>;
+
+var<workgroup> local: array<atomic<u32>, 256>;
+
+// Add the sums of the workgroup to the sums of the chunk.
+fn flush(id: u32) {
+    workgroupBarrier();
+    for (var c = id; c < params.candidates; c += 256u) {
+        let s = atomicLoad(&local[c]);
+        if s != 0u {
+            atomicAdd(&sums[c], s);
+        }
+    }
+}
+
+// Every element is the score of a candidate.
+@compute @workgroup_size(256)
+fn cardinal(
+    @builtin(local_invocation_index) id: u32,
+    @builtin(global_invocation_id) global: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let stride = groups.x * 256u;
+    for (var i = global.x; i < params.len; i += stride) {
+        atomicAdd(&local[(params.first + i % params.candidates) % params.candidates], data[i]);
+    }
+    flush(id);
+}
+
+// Every element is 32 approvals, the lowest bit first.
+@compute @workgroup_size(256)
+fn approval(
+    @builtin(local_invocation_index) id: u32,
+    @builtin(global_invocation_id) global: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let stride = groups.x * 256u;
+    for (var i = global.x; i < params.len; i += stride) {
+        let base = (params.first + (i % params.candidates) * 32u) % params.candidates;
+        var word = data[i];
+        while word != 0u {
+            let bit = firstTrailingBit(word);
+            atomicAdd(&local[(base + bit) % params.candidates], 1u);
+            word &= word - 1u;
+        }
+    }
+    flush(id);
+}
+"#;
+
+/// A connection to a GPU, which can tally many profiles.
+pub struct GpuTally {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    cardinal: wgpu::ComputePipeline,
+    approval: wgpu::ComputePipeline,
+    // The most elements uploaded at a time.
+    max_chunk: usize,
+}
+
+impl GpuTally {
+    /// Connect to the default GPU. Returns an error if there is none.
+    pub fn new() -> Result<Self, &'static str> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or("No GPU found")?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .or(Err("Could not connect to the GPU"))?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("tally"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer { ty, has_dynamic_offset: false, min_binding_size: None },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tally"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("tally"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let (cardinal, approval) = (pipeline("cardinal"), pipeline("approval"));
+        let limits = device.limits();
+        let max_bytes =
+            u64::from(limits.max_storage_buffer_binding_size).min(limits.max_buffer_size);
+        // Every approval of a chunk is counted in a `u32`.
+        let max_chunk = (max_bytes as usize / 4).min(u32::MAX as usize / 32);
+        Ok(GpuTally { device, queue, layout, cardinal, approval, max_chunk })
+    }
+
+    /// The number of approvals of every candidate, like
+    /// [`Binary::column_sums`]. Returns an error if there are more than 256
+    /// candidates.
+    pub fn approval_sums(&self, votes: &Binary) -> Result<Vec<usize>, &'static str> {
+        let c = votes.candidates;
+        if c == 0 || votes.voters == 0 {
+            return Ok(vec![0; c]);
+        }
+        if c > MAX_CANDIDATES {
+            return Err("Too many candidates for the GPU");
+        }
+        let words = votes.votes.len().div_ceil(32);
+        let chunks = (0..words).step_by(self.max_chunk).map(|start| {
+            let bits =
+                &votes.votes[(start * 32)..((start + self.max_chunk) * 32).min(votes.votes.len())];
+            let packed = bits
+                .chunks(32)
+                .map(|w| w.iter().rev().fold(0, |word, &b| (word << 1) | b as u32))
+                .collect();
+            ((start * 32) % c, packed)
+        });
+        self.tally(&self.approval, c, chunks)
+    }
+
+    /// The total score of every candidate, like [`Cardinal::column_sums`].
+    /// Returns an error if the sum could overflow, if a score doesn't fit in
+    /// a `u32`, or if there are more than 256 candidates.
+    pub fn cardinal_sums(&self, votes: &Cardinal) -> Result<Vec<usize>, &'static str> {
+        let c = votes.candidates;
+        if c == 0 || votes.voters == 0 {
+            return Ok(vec![0; c]);
+        }
+        if c > MAX_CANDIDATES {
+            return Err("Too many candidates for the GPU");
+        }
+        votes.voters.checked_mul(votes.max).ok_or("Integer overflow: Too high scores")?;
+        let max = u32::try_from(votes.max).or(Err("Too high scores for the GPU"))?;
+        // Every sum of a chunk has to fit in a `u32`.
+        let voters = (u32::MAX / max.max(1)) as usize;
+        let chunk = voters.saturating_mul(c).min(self.max_chunk / c * c).max(c);
+        let chunks =
+            votes.votes.chunks(chunk).map(|values| (0, values.iter().map(|&v| v as u32).collect()));
+        self.tally(&self.cardinal, c, chunks)
+    }
+
+    // Upload every chunk, given with the candidate of its first element, and
+    // add up the sums computed by `pipeline`.
+    fn tally<I>(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        candidates: usize,
+        chunks: I,
+    ) -> Result<Vec<usize>, &'static str>
+    where
+        I: Iterator<Item = (usize, Vec<u32>)>,
+    {
+        let sums_size = (candidates * 4) as wgpu::BufferAddress;
+        let sums_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sums"),
+            size: sums_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size: sums_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bytes =
+            |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+        let mut sums = vec![0usize; candidates];
+        for (first, data) in chunks {
+            let params = [data.len() as u32, candidates as u32, first as u32, 0];
+            let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: &bytes(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let data_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("votes"),
+                contents: &bytes(&data),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("tally"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: data_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 2, resource: sums_buffer.as_entire_binding() },
+                ],
+            });
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            encoder.clear_buffer(&sums_buffer, 0, None);
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                let groups = data.len().div_ceil(WORKGROUP_SIZE * PER_INVOCATION);
+                pass.dispatch_workgroups(groups.clamp(1, MAX_WORKGROUPS) as u32, 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&sums_buffer, 0, &staging, 0, sums_size);
+            self.queue.submit([encoder.finish()]);
+
+            let slice = staging.slice(..);
+            let (sender, receiver) = mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |res| sender.send(res).unwrap());
+            self.device.poll(wgpu::Maintain::Wait);
+            receiver.recv().unwrap().or(Err("Could not read the sums from the GPU"))?;
+            for (s, b) in sums.iter_mut().zip(slice.get_mapped_range().chunks_exact(4)) {
+                *s += u32::from_le_bytes(b.try_into().unwrap()) as usize;
+            }
+            staging.unmap();
+        }
+        Ok(sums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    // Connecting is slow, so every test shares one connection. Without a GPU
+    // there is nothing to test.
+    fn gpu() -> Option<&'static GpuTally> {
+        static GPU: OnceLock<Option<GpuTally>> = OnceLock::new();
+        GPU.get_or_init(|| GpuTally::new().ok()).as_ref()
+    }
+
+    #[quickcheck]
+    fn same_as_cpu(binary: Binary, cardinal: Cardinal) -> bool {
+        let Some(gpu) = gpu() else {
+            return true;
+        };
+        let approval = match gpu.approval_sums(&binary) {
+            Ok(sums) => sums == binary.column_sums(),
+            Err(_) => binary.candidates > MAX_CANDIDATES,
+        };
+        let score = match gpu.cardinal_sums(&cardinal) {
+            Ok(sums) => Ok(sums) == cardinal.column_sums(),
+            Err(_) => cardinal.candidates > MAX_CANDIDATES || cardinal.column_sums().is_err(),
+        };
+        approval && score
+    }
+
+    #[test]
+    fn chunks() {
+        let Ok(mut gpu) = GpuTally::new() else {
+            return;
+        };
+        gpu.max_chunk = 5;
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut binary = Binary::new(7);
+        binary.generate_uniform(&mut rng, 100);
+        assert_eq!(gpu.approval_sums(&binary), Ok(binary.column_sums()));
+        let mut cardinal = Cardinal::new(7, 0, 5);
+        cardinal.generate_uniform(&mut rng, 100);
+        assert_eq!(gpu.cardinal_sums(&cardinal), cardinal.column_sums());
+
+        // The sums don't fit in a `u32`, so every vote is its own chunk.
+        let max = u32::MAX as usize;
+        let mut high = Cardinal::new(2, 0, max);
+        for _ in 0..3 {
+            high.add(&[max, 1]).unwrap();
+        }
+        assert_eq!(gpu.cardinal_sums(&high), Ok(vec![3 * max, 3]));
+        high.max += 1;
+        assert!(gpu.cardinal_sums(&high).is_err());
+    }
+}
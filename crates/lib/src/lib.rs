@@ -17,16 +17,36 @@
 //! let count = Approval::count(&votes).unwrap().get_order();
 //! assert_eq!(count, &[0, 0, 1]);
 //! ```
+//!
+//! The ranks are smaller for better candidates, see [`election::Rank`].
 #![feature(is_sorted)]
 #![feature(option_zip)]
+#![forbid(unsafe_code)]
 #[cfg(test)]
 extern crate quickcheck;
 #[cfg(test)]
 #[macro_use(quickcheck)]
 extern crate quickcheck_macros;
 
+pub mod aggregation;
+pub mod chart;
+pub mod criteria;
+pub mod districts;
+pub mod election;
+pub mod elicitation;
+pub mod enumerate;
 pub mod generators;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod matching;
 pub mod methods;
+pub mod profile;
+pub mod seed;
+pub mod simulation;
+pub mod spatial;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod util;
 
 pub mod formats;
 
@@ -40,10 +60,10 @@ pub mod prelude {
     pub use super::{formats::VoteFormat, methods::VotingMethod};
 }
 
-pub fn single_winner(ranking: &Vec<usize>) -> Winner {
+pub fn single_winner(ranking: &[election::Rank]) -> Winner {
     let mut winners = Vec::with_capacity(1);
     for i in 0..ranking.len() {
-        if ranking[i] == 0 {
+        if ranking[i] == election::Rank::FIRST {
             winners.push(i);
         }
     }
@@ -79,3 +99,4 @@ fn pairwise_lt(v: &[usize]) -> bool {
 // }
 
 pub mod tarjan;
+pub mod tournament;
@@ -0,0 +1,165 @@
+//! Matching agents with objects, or with other agents, using their ranked
+//! preferences.
+//!
+//! Preferences are given as vote formats, where every voter is an agent and
+//! every candidate is something the agent can be matched with.
+
+use crate::formats::{soi::StrictOrdersIncomplete, toi::TiedOrdersIncomplete, VoteFormat};
+
+/// Find a stable matching using the Gale–Shapley algorithm.
+///
+/// `proposers` has one vote for every proposing agent, ranking the receiving
+/// agents, and `receivers` has one vote for every receiving agent, ranking the
+/// proposing agents. Agents which are not ranked are not acceptable partners.
+///
+/// Returns the receiver matched with every proposer, if any. The matching is
+/// the best stable matching for every proposer.
+///
+/// Every receiver's ranking of the proposers is first put in a lookup table,
+/// so every proposal is handled in constant time and the whole matching takes
+/// `O(proposers * receivers)` time.
+pub fn gale_shapley(
+    proposers: &StrictOrdersIncomplete,
+    receivers: &StrictOrdersIncomplete,
+) -> Result<Vec<Option<usize>>, &'static str> {
+    let p = proposers.voters();
+    let r = receivers.voters();
+    if proposers.candidates != r || receivers.candidates != p {
+        return Err("Proposers and receivers don't rank each other");
+    }
+
+    // rank[i * p + j] is the position of proposer `j` in the vote of receiver
+    // `i`, or `usize::MAX` if it is unranked.
+    let mut rank = vec![usize::MAX; r * p];
+    for (i, vote) in receivers.into_iter().enumerate() {
+        for (pos, &j) in vote.iter().enumerate() {
+            rank[i * p + j] = pos;
+        }
+    }
+
+    let prefs: Vec<&[usize]> = proposers.into_iter().collect();
+    let mut next = vec![0; p];
+    let mut partner_of_receiver: Vec<Option<usize>> = vec![None; r];
+    let mut free: Vec<usize> = (0..p).rev().collect();
+    while let Some(j) = free.pop() {
+        let Some(&i) = prefs[j].get(next[j]) else {
+            // `j` has proposed to everyone it accepts
+            continue;
+        };
+        next[j] += 1;
+        if rank[i * p + j] == usize::MAX {
+            free.push(j);
+            continue;
+        }
+        match partner_of_receiver[i] {
+            None => partner_of_receiver[i] = Some(j),
+            Some(k) if rank[i * p + j] < rank[i * p + k] => {
+                partner_of_receiver[i] = Some(j);
+                free.push(k);
+            }
+            Some(_) => free.push(j),
+        }
+    }
+
+    let mut partner = vec![None; p];
+    for (i, j) in partner_of_receiver.into_iter().enumerate() {
+        if let Some(j) = j {
+            partner[j] = Some(i);
+        }
+    }
+    Ok(partner)
+}
+
+/// Assign objects to agents using serial dictatorship.
+///
+/// `agents` has one vote for every agent, ranking the objects. In the order
+/// given by `order`, every agent picks its most preferred object which is still
+/// available. If multiple available objects are tied, the first one listed in
+/// the vote is picked. Agents only pick objects they have ranked.
+///
+/// Returns the object assigned to every agent, if any.
+pub fn serial_dictatorship(
+    agents: &TiedOrdersIncomplete,
+    order: &[usize],
+) -> Result<Vec<Option<usize>>, &'static str> {
+    let n = agents.voters();
+    let mut seen = vec![false; n];
+    for &a in order {
+        if a >= n || seen[a] {
+            return Err("Order is not a permutation of the agents");
+        }
+        seen[a] = true;
+    }
+    if order.len() != n {
+        return Err("Order is not a permutation of the agents");
+    }
+
+    let votes: Vec<_> = agents.into_iter().collect();
+    let mut taken = vec![false; agents.candidates()];
+    let mut assigned = vec![None; n];
+    for &a in order {
+        if let Some(&o) = votes[a].order().iter().find(|&&o| !taken[o]) {
+            taken[o] = true;
+            assigned[a] = Some(o);
+        }
+    }
+    Ok(assigned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_marriage() {
+        let mut men = StrictOrdersIncomplete::new(3);
+        for s in ["0,1,2", "0,2,1", "1,0,2"] {
+            assert!(men.add_from_str(s));
+        }
+        let mut women = StrictOrdersIncomplete::new(3);
+        for s in ["1,0,2", "0,1,2", "0,1,2"] {
+            assert!(women.add_from_str(s));
+        }
+        // 0 and 1 both propose to 0, who prefers 1. Then 0 proposes to 1, who
+        // prefers 0 to 2.
+        let res = gale_shapley(&men, &women).unwrap();
+        assert_eq!(res, [Some(1), Some(0), Some(2)]);
+    }
+
+    #[test]
+    fn unacceptable() {
+        let mut proposers = StrictOrdersIncomplete::new(2);
+        for s in ["0", "0,1"] {
+            assert!(proposers.add_from_str(s));
+        }
+        let mut receivers = StrictOrdersIncomplete::new(2);
+        for s in ["1", "0"] {
+            assert!(receivers.add_from_str(s));
+        }
+        // Receiver 0 doesn't accept proposer 0, who doesn't accept anyone else
+        let res = gale_shapley(&proposers, &receivers).unwrap();
+        assert_eq!(res, [None, Some(0)]);
+        assert_eq!(proposers.vote(1.into()), [0, 1]);
+        assert_eq!(receivers.vote(0.into()), [1]);
+    }
+
+    #[test]
+    fn mismatched() {
+        let proposers = StrictOrdersIncomplete::new(2);
+        let receivers = StrictOrdersIncomplete::new(2);
+        assert!(gale_shapley(&proposers, &receivers).is_err());
+    }
+
+    #[test]
+    fn dictatorship() {
+        let mut agents = TiedOrdersIncomplete::new(3);
+        agents.add_from_str("0,1,2");
+        agents.add_from_str("{0,2},1");
+        agents.add_from_str("0");
+        let res = serial_dictatorship(&agents, &[2, 1, 0]).unwrap();
+        assert_eq!(res, [Some(1), Some(2), Some(0)]);
+        let res = serial_dictatorship(&agents, &[0, 1, 2]).unwrap();
+        assert_eq!(res, [Some(0), Some(2), None]);
+        assert!(serial_dictatorship(&agents, &[0, 0, 2]).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use super::{
+    fptp::order_to_vote,
+    positional::{positional_score, TieScoring},
+};
+use crate::{
+    formats::{orders::TiedRank, toi::TiedOrdersIncomplete, VoteFormat},
+    methods::VotingMethod,
+};
+
+/// Configuration of [`Bucklin`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BucklinConfig {
+    /// How candidates tied in a vote are counted.
+    pub tie_scoring: TieScoring,
+}
+
+/// Bucklin voting. First every candidate is given one vote for every vote
+/// which ranks it first. If no candidate has a majority of the votes, the
+/// second choices are added, then the third, and so on until some candidate has
+/// a majority. The candidates with the most votes in that round win.
+///
+/// If no candidate ever gets a majority, e.g. because the votes are incomplete,
+/// the round where every ranked candidate is counted decides the result.
+pub struct Bucklin {
+    // The votes of each candidate in the deciding round.
+    score: Vec<usize>,
+}
+
+impl<'a> VotingMethod<'a> for Bucklin {
+    type Format = TiedOrdersIncomplete;
+    type Score = usize;
+
+    /// Count `data`, using [`TieScoring::Average`] for tied candidates.
+    fn count(data: &TiedOrdersIncomplete) -> Result<Self, &'static str> {
+        Bucklin::count_with(data, TieScoring::Average)
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl Bucklin {
+    /// Count `data`, where round `k` is the positional method giving one point
+    /// to each of the first `k` positions, with tied candidates scored
+    /// according to `tie_scoring`.
+    pub fn count_with(
+        data: &TiedOrdersIncomplete,
+        tie_scoring: TieScoring,
+    ) -> Result<Self, &'static str> {
+        let n = data.candidates();
+        let voters = data.voters();
+        let weights = vec![1; n];
+        for round in 1..=n {
+            let (score, scale) = positional_score(data, &weights[..round], tie_scoring)?;
+            let majority = voters.checked_mul(scale).ok_or("Integer overflow: Too many votes")?;
+            if round == n || score.iter().any(|&s| s > majority / 2) {
+                return Ok(Bucklin { score });
+            }
+        }
+        // Only reached without candidates
+        Ok(Bucklin { score: Vec::new() })
+    }
+
+    /// Count the votes in `data`, with the method configured by `config`.
+    pub fn from_config(
+        data: &TiedOrdersIncomplete,
+        config: &BucklinConfig,
+    ) -> Result<Self, &'static str> {
+        Bucklin::count_with(data, config.tie_scoring)
+    }
+
+    pub fn as_vote(&self) -> TiedRank {
+        let order = self.get_order();
+        order_to_vote(&order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_round() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,1,2", 4);
+        votes.add_from_str_i("1,2,0", 3);
+        votes.add_from_str_i("2,1,0", 2);
+        // No majority in the first round, in the second round 1 has 9 votes.
+        let res = Bucklin::count(&votes).unwrap();
+        assert_eq!(res.get_score(), &vec![4, 9, 5]);
+        assert_eq!(res.as_vote().as_ref().to_string(), "1,2,0");
+    }
+
+    #[test]
+    fn tie_scoring() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("{0,1},2", 3);
+        votes.add_from_str_i("2,0,1", 2);
+        // With `Max` both 0 and 1 have a majority in the first round.
+        let max = Bucklin::count_with(&votes, TieScoring::Max).unwrap();
+        assert_eq!(max.get_score(), &vec![3, 3, 2]);
+        assert_eq!(max.as_vote().as_ref().to_string(), "{0,1},2");
+        // With `Min` no one has a majority until the second round.
+        let min = Bucklin::count_with(&votes, TieScoring::Min).unwrap();
+        assert_eq!(min.get_score(), &vec![5, 3, 2]);
+        // With `Split` the scores are doubled, and 0 and 1 get half a vote each
+        // from "{0,1},2", in both rounds.
+        let split = Bucklin::count_with(&votes, TieScoring::Split).unwrap();
+        assert_eq!(split.get_score(), &vec![7, 3, 4]);
+        assert_eq!(split.as_vote().as_ref().to_string(), "0,2,1");
+    }
+}
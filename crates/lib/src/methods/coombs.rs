@@ -0,0 +1,118 @@
+use super::fptp::order_to_vote;
+use crate::{
+    formats::{orders::TiedRank, toi::TiedOrdersIncomplete, VoteFormat},
+    methods::VotingMethod,
+};
+
+/// Coombs' method. If a candidate is ranked first by a majority of the votes,
+/// then it wins. Otherwise the candidate ranked last by the most votes is
+/// eliminated, and we try again.
+///
+/// Candidates which are not ranked by a vote are treated as tied last in it.
+/// If multiple candidates are tied for the most last preferences, they are all
+/// eliminated at once and tied in the result. When a candidate wins, every
+/// other remaining candidate is tied right below it.
+pub struct Coombs {
+    // The round each candidate was eliminated in, the winners get the highest
+    // score.
+    score: Vec<usize>,
+}
+
+impl<'a> VotingMethod<'a> for Coombs {
+    type Format = TiedOrdersIncomplete;
+    type Score = usize;
+
+    fn count(data: &TiedOrdersIncomplete) -> Result<Self, &'static str> {
+        let n = data.candidates();
+        let mut score = vec![0; n];
+        let mut remaining: Vec<usize> = (0..n).collect();
+        let mut round = 0;
+        while !remaining.is_empty() {
+            round += 1;
+            let view = data.view(&remaining)?;
+            let r = view.candidates();
+            let mut firsts = vec![0; r];
+            let mut lasts = vec![0; r];
+            let mut unranked = vec![true; r];
+            let mut active = 0;
+            view.for_each_vote(|vote| {
+                // First preferences
+                if let Some(group) = vote.iter_groups().next() {
+                    active += 1;
+                    for &c in group {
+                        firsts[c] += 1;
+                    }
+                }
+
+                // Last preferences, which are unranked candidates if there are any.
+                if vote.len() < r {
+                    unranked.fill(true);
+                    for &c in vote.order() {
+                        unranked[c] = false;
+                    }
+                    for c in (0..r).filter(|&c| unranked[c]) {
+                        lasts[c] += 1;
+                    }
+                } else if let Some(group) = vote.iter_groups().last() {
+                    for &c in group {
+                        lasts[c] += 1;
+                    }
+                }
+            });
+
+            let max_first = *firsts.iter().max().unwrap();
+            if 2 * max_first > active {
+                for c in 0..r {
+                    let s = if firsts[c] == max_first { round + 1 } else { round };
+                    score[view.original(c)] = s;
+                }
+                break;
+            }
+
+            let max_last = *lasts.iter().max().unwrap();
+            for c in (0..r).filter(|&c| lasts[c] == max_last) {
+                score[view.original(c)] = round;
+            }
+            remaining =
+                (0..r).filter(|&c| lasts[c] != max_last).map(|c| view.original(c)).collect();
+        }
+        Ok(Coombs { score })
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl Coombs {
+    pub fn as_vote(&self) -> TiedRank {
+        let order = self.get_order();
+        order_to_vote(&order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn majority() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,1,2", 3);
+        votes.add_from_str_i("1,2,0", 2);
+        let res = Coombs::count(&votes).unwrap().as_vote();
+        assert_eq!(res.as_ref().winners(), &[0]);
+    }
+
+    #[test]
+    fn eliminate_last() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,1,2", 4);
+        votes.add_from_str_i("2,1,0", 3);
+        votes.add_from_str_i("1,2,0", 2);
+        // No majority, and 0 is ranked last most often, so it's eliminated.
+        // Then 1 has a majority.
+        let res = Coombs::count(&votes).unwrap().as_vote();
+        assert_eq!(res.as_ref().to_string(), "1,2,0");
+    }
+}
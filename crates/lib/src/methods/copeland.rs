@@ -0,0 +1,149 @@
+//! Copeland's method, which ranks candidates by their pairwise wins.
+
+use super::{fptp::order_to_vote, pairwise::PairwiseMatrix};
+use crate::{
+    election::Fraction,
+    formats::{orders::TiedRank, toi::TiedOrdersIncomplete},
+    methods::VotingMethod,
+};
+
+/// How many points a candidate gets for a pairwise tie, where a win gives
+/// one point and a loss none.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CopelandTies {
+    Zero,
+    /// Ranks the candidates by their wins minus their losses.
+    #[default]
+    Half,
+    One,
+}
+
+impl CopelandTies {
+    // The points of a tie, multiplied by 2.
+    fn points(self) -> u64 {
+        match self {
+            CopelandTies::Zero => 0,
+            CopelandTies::Half => 1,
+            CopelandTies::One => 2,
+        }
+    }
+}
+
+/// Configuration of [`Copeland`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CopelandConfig {
+    /// The points of a pairwise tie.
+    pub ties: CopelandTies,
+}
+
+/// Every candidate gets a point for every other candidate it beats pairwise,
+/// and a part of a point for every pairwise tie, see [`CopelandTies`].
+pub struct Copeland {
+    score: Vec<Fraction>,
+}
+
+impl<'a> VotingMethod<'a> for Copeland {
+    type Format = TiedOrdersIncomplete;
+    type Score = Fraction;
+
+    /// Count `data` with half a point for every pairwise tie.
+    fn count(data: &TiedOrdersIncomplete) -> Result<Self, &'static str> {
+        Copeland::count_with(data, CopelandTies::default())
+    }
+
+    fn get_score(&self) -> &[Fraction] {
+        &self.score
+    }
+}
+
+impl Copeland {
+    /// Count `data` with pairwise ties worth `ties` points.
+    pub fn count_with(
+        data: &TiedOrdersIncomplete,
+        ties: CopelandTies,
+    ) -> Result<Self, &'static str> {
+        Ok(Copeland::from_matrix(&data.pairwise_matrix()?, ties))
+    }
+
+    /// Count the pairwise comparisons in `matrix`, with pairwise ties worth
+    /// `ties` points.
+    pub fn from_matrix(matrix: &PairwiseMatrix, ties: CopelandTies) -> Self {
+        let n = matrix.candidates();
+        let score = (0..n)
+            .map(|a| {
+                (0..n)
+                    .filter(|&b| a != b)
+                    .map(|b| match matrix.margin(a, b) {
+                        0 => ties.points(),
+                        m if m > 0 => 2,
+                        _ => 0,
+                    })
+                    .sum::<u64>()
+            })
+            .map(|doubled| Fraction::new(doubled, 2))
+            .collect();
+        Copeland { score }
+    }
+
+    /// Count the votes in `data`, with the method configured by `config`.
+    pub fn from_config(
+        data: &TiedOrdersIncomplete,
+        config: &CopelandConfig,
+    ) -> Result<Self, &'static str> {
+        Copeland::count_with(data, config.ties)
+    }
+
+    pub fn as_vote(&self) -> TiedRank {
+        let order = self.get_order();
+        order_to_vote(&order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[test]
+    fn tie_points() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        // 0 ties with everyone, 1 beats 2 and 3, and 2 ties with 3.
+        votes.add_from_str("0,1,2,3");
+        votes.add_from_str("1,{0,2,3}");
+        votes.add_from_str("3,2");
+        // The scores multiplied by 2.
+        let score = |ties| -> Vec<u64> {
+            let copeland = Copeland::count_with(&votes, ties).unwrap();
+            copeland.get_score().iter().map(|s| s.numer() * 2 / s.denom()).collect()
+        };
+        assert_eq!(score(CopelandTies::Zero), [0, 4, 0, 0]);
+        assert_eq!(score(CopelandTies::Half), [3, 5, 2, 2]);
+        assert_eq!(score(CopelandTies::One), [6, 6, 4, 4]);
+        assert_eq!(Copeland::count(&votes).unwrap().get_score()[0], Fraction::new(3, 2));
+        let copeland = Copeland::count(&votes).unwrap();
+        assert_eq!(copeland.as_vote().as_ref().to_string(), "1,0,{2,3}");
+    }
+
+    #[quickcheck]
+    fn condorcet_winner_wins(votes: TiedOrdersIncomplete) -> bool {
+        let m = votes.pairwise_matrix().unwrap();
+        let copeland = Copeland::from_matrix(&m, CopelandTies::Half);
+        match m.condorcet_winner() {
+            Some(w) => copeland.as_vote().as_ref().winners() == [w],
+            None => true,
+        }
+    }
+
+    #[quickcheck]
+    fn points_sum(votes: TiedOrdersIncomplete) -> bool {
+        // With half a point per tie, every pair gives out exactly one point.
+        let n = votes.candidates();
+        let copeland = Copeland::count(&votes).unwrap();
+        let doubled: u64 = copeland.score.iter().map(|s| s.numer() * 2 / s.denom()).sum();
+        doubled == (n * n.saturating_sub(1)) as u64
+    }
+}
@@ -0,0 +1,59 @@
+//! Cumulative voting, where the candidate with the most points wins.
+
+use crate::{
+    formats::{orders::TiedRank, Cumulative},
+    methods::VotingMethod,
+};
+
+/// Every candidate gets the sum of the points it was given, and the candidate
+/// with the most points wins.
+pub struct CumulativeVoting {
+    score: Vec<usize>,
+}
+
+impl<'a> VotingMethod<'a> for CumulativeVoting {
+    type Format = Cumulative;
+    type Score = usize;
+
+    fn count(data: &Cumulative) -> Result<Self, &'static str> {
+        // The total can't overflow if every voter's budget fits.
+        data.voters.checked_mul(data.budget).ok_or("Integer overflow: Too many points")?;
+        Ok(CumulativeVoting { score: data.column_sums() })
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl CumulativeVoting {
+    pub fn as_vote(&self) -> TiedRank {
+        TiedRank::from_scores(self.score.len(), &self.score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[test]
+    fn points() {
+        let mut votes = Cumulative::new(3, 4);
+        votes.add(&[4, 0, 0]).unwrap();
+        votes.add(&[0, 2, 2]).unwrap();
+        votes.add(&[1, 0, 3]).unwrap();
+        let cumulative = CumulativeVoting::count(&votes).unwrap();
+        assert_eq!(cumulative.get_score(), &vec![5, 2, 5]);
+        assert_eq!(cumulative.as_vote().as_ref().to_string(), "{0,2},1");
+    }
+
+    #[quickcheck]
+    fn total_points(votes: Cumulative) -> bool {
+        let cumulative = CumulativeVoting::count(&votes).unwrap();
+        let total: usize = cumulative.get_score().iter().sum();
+        votes.candidates == 0 || total == votes.voters * votes.budget
+    }
+}
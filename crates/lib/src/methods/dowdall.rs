@@ -15,7 +15,7 @@ impl VotingMethod for Dowdall {
         Ok(Dowdall { score })
     }
 
-    fn get_score(&self) -> &Vec<usize> {
+    fn get_score(&self) -> &[usize] {
         &self.score
     }
 }
@@ -0,0 +1,228 @@
+//! The Method of Equal Shares, electing a committee where every voter
+//! controls an equal part of the budget.
+
+use super::{pav::check_seats, phragmen};
+use crate::formats::{Binary, Cardinal};
+
+// Amounts closer than this are treated as equal, to not depend on rounding
+// errors.
+const EPSILON: f64 = 1e-9;
+
+/// How [`EqualShares`] fills the seats left when no candidate can be afforded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Completion {
+    /// Continue with Phragmén's sequential method, see
+    /// [`Phragmen`](super::Phragmen), where every voter starts with the load
+    /// they have already paid. Every voter who gave a candidate more than the
+    /// lowest rating counts as approving of it.
+    #[default]
+    Phragmen,
+    /// Leave the seats empty, so the committee may be smaller than `seats`.
+    None,
+}
+
+/// Configuration of [`EqualShares`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EqualSharesConfig {
+    /// The number of candidates to elect.
+    pub seats: usize,
+    /// The total budget, split equally between the voters. Every candidate
+    /// costs 1.
+    pub budget: f64,
+    pub completion: Completion,
+}
+
+impl EqualSharesConfig {
+    /// Elect `seats` candidates with a budget of `seats`, completed by
+    /// [`Completion::Phragmen`].
+    pub fn new(seats: usize) -> Self {
+        EqualSharesConfig { seats, budget: seats as f64, completion: Completion::default() }
+    }
+}
+
+/// The Method of Equal Shares (MES), electing one candidate at a time.
+///
+/// Every voter gets an equal share of the budget, and every candidate costs 1.
+/// A candidate can be afforded if its supporters, the voters who gave it some
+/// utility, have 1 left together. The cost is then split such that every
+/// supporter pays in proportion to their utility, except for supporters who
+/// can't afford that and pay everything they have left. Each time, the
+/// candidate whose supporters pay the least per utility is elected. When no
+/// candidate can be afforded, the remaining seats are filled according to
+/// [`Completion`].
+///
+/// With approval votes, every approval is 1 utility. With cardinal votes,
+/// the utility is the rating above the lowest rating. Ties are broken by
+/// electing the candidate with the lowest index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EqualShares {
+    committee: Vec<usize>,
+    by_equal_shares: usize,
+    remaining: Vec<f64>,
+}
+
+impl EqualShares {
+    pub fn approval(data: &Binary, config: &EqualSharesConfig) -> Result<Self, &'static str> {
+        let utilities = data.votes.iter().map(|&v| v as usize).collect();
+        EqualShares::count(utilities, data.candidates, data.voters, config)
+    }
+
+    pub fn cardinal(data: &Cardinal, config: &EqualSharesConfig) -> Result<Self, &'static str> {
+        let utilities = data.votes.iter().map(|&v| v - data.min).collect();
+        EqualShares::count(utilities, data.candidates, data.voters, config)
+    }
+
+    // `utilities` has length `voters * candidates`.
+    fn count(
+        utilities: Vec<usize>,
+        candidates: usize,
+        voters: usize,
+        config: &EqualSharesConfig,
+    ) -> Result<Self, &'static str> {
+        let seats = config.seats;
+        let approvals =
+            Binary { votes: utilities.iter().map(|&u| u != 0).collect(), candidates, voters };
+        check_seats(&approvals, seats)?;
+        if !(config.budget > 0.0 && config.budget.is_finite()) {
+            return Err("Budget must be positive");
+        }
+        let share = config.budget / voters as f64;
+        let mut remaining = vec![share; voters];
+        let mut elected = vec![false; candidates];
+        let mut committee = Vec::with_capacity(seats);
+        while committee.len() < seats {
+            let mut winner: Option<(usize, f64)> = None;
+            for c in (0..candidates).filter(|&c| !elected[c]) {
+                let supporters = utilities
+                    .chunks_exact(candidates)
+                    .zip(&remaining)
+                    .filter(|(u, _)| u[c] != 0)
+                    .map(|(u, &b)| (b, u[c]));
+                let Some(rho) = payment_per_utility(supporters) else {
+                    continue;
+                };
+                if winner.is_none_or(|(_, best)| rho < best - EPSILON) {
+                    winner = Some((c, rho));
+                }
+            }
+            let Some((winner, rho)) = winner else {
+                break;
+            };
+            elected[winner] = true;
+            committee.push(winner);
+            for (u, b) in utilities.chunks_exact(candidates).zip(&mut remaining) {
+                *b -= b.min(rho * u[winner] as f64);
+            }
+        }
+        let by_equal_shares = committee.len();
+        if config.completion == Completion::Phragmen {
+            // The budget is counted in units of the cost of a candidate, so
+            // what a voter paid is their load.
+            let mut loads: Vec<f64> = remaining.iter().map(|b| share - b).collect();
+            phragmen::complete(&approvals, seats, &mut committee, &mut loads);
+        }
+        Ok(EqualShares { committee, by_equal_shares, remaining })
+    }
+
+    /// The elected candidates, in the order they were elected.
+    pub fn committee(&self) -> &[usize] {
+        &self.committee
+    }
+
+    /// The candidates elected before the completion.
+    pub fn by_equal_shares(&self) -> &[usize] {
+        &self.committee[..self.by_equal_shares]
+    }
+
+    /// The budget every voter had left before the completion.
+    pub fn remaining(&self) -> &[f64] {
+        &self.remaining
+    }
+}
+
+// The lowest payment per utility where `supporters`, given as their budget
+// left and utility, pay 1 together if every supporter pays that times their
+// utility, or everything they have left if it's less. Returns `None` if they
+// can't afford 1.
+fn payment_per_utility(supporters: impl Iterator<Item = (f64, usize)>) -> Option<f64> {
+    let mut supporters: Vec<(f64, f64)> = supporters.map(|(b, u)| (b, u as f64)).collect();
+    if supporters.iter().map(|(b, _)| b).sum::<f64>() < 1.0 - EPSILON {
+        return None;
+    }
+    // The supporters who can afford the least per utility run out first.
+    supporters.sort_by(|(b1, u1), (b2, u2)| (b1 / u1).total_cmp(&(b2 / u2)));
+    let mut cost = 1.0;
+    let mut utility: f64 = supporters.iter().map(|(_, u)| u).sum();
+    for (b, u) in supporters {
+        let rho = cost / utility;
+        if rho * u <= b + EPSILON {
+            return Some(rho);
+        }
+        cost -= b;
+        utility -= u;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[test]
+    fn approval() {
+        let mut data = Binary::new(4);
+        for _ in 0..6 {
+            data.add(&[true, true, true, false]).unwrap();
+        }
+        for _ in 0..3 {
+            data.add(&[false, false, false, true]).unwrap();
+        }
+        // Every voter has 1/3. 0 costs the first 6 voters 1/6 each, then 1
+        // costs them the rest. They can't afford 2, but the other 3 voters
+        // can afford 3.
+        let mes = EqualShares::approval(&data, &EqualSharesConfig::new(3)).unwrap();
+        assert_eq!(mes.committee(), [0, 1, 3]);
+        assert_eq!(mes.by_equal_shares(), [0, 1, 3]);
+        assert!(mes.remaining().iter().all(|b| b.abs() < EPSILON));
+        let config = EqualSharesConfig { budget: -1.0, ..EqualSharesConfig::new(3) };
+        assert!(EqualShares::approval(&data, &config).is_err());
+        assert!(EqualShares::approval(&data, &EqualSharesConfig::new(0)).is_err());
+    }
+
+    #[test]
+    fn completion() {
+        let mut data = Cardinal::new(3, 0, 2);
+        data.add(&[2, 1, 0]).unwrap();
+        data.add(&[2, 1, 0]).unwrap();
+        data.add(&[0, 0, 2]).unwrap();
+        // Every voter has 2/3. The first two voters pay 1/2 each for 0, and
+        // then nobody can afford another candidate.
+        let config =
+            EqualSharesConfig { completion: Completion::None, ..EqualSharesConfig::new(2) };
+        let mes = EqualShares::cardinal(&data, &config).unwrap();
+        assert_eq!(mes.committee(), [0]);
+        assert!((mes.remaining()[0] - 1.0 / 6.0).abs() < EPSILON);
+        // With Phragmén, electing 1 or 2 both give a load of 1.
+        let mes = EqualShares::cardinal(&data, &EqualSharesConfig::new(2)).unwrap();
+        assert_eq!(mes.committee(), [0, 1]);
+        assert_eq!(mes.by_equal_shares(), [0]);
+    }
+
+    #[quickcheck]
+    fn approval_as_cardinal(data: Binary, seats: usize) -> bool {
+        if data.candidates == 0 {
+            return true;
+        }
+        let config = EqualSharesConfig::new(seats % data.candidates + 1);
+        let mes = EqualShares::approval(&data, &config).unwrap();
+        let mut committee = mes.committee().to_vec();
+        committee.sort();
+        committee.dedup();
+        mes == EqualShares::cardinal(&data.to_cardinal().unwrap(), &config).unwrap()
+            && committee.len() == config.seats
+            && mes.remaining().iter().all(|&b| b > -EPSILON)
+    }
+}
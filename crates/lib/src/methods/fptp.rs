@@ -9,6 +9,7 @@ pub struct Fptp {
 
 impl<'a> VotingMethod<'a> for Fptp {
     type Format = Specific;
+    type Score = usize;
 
     fn count(data: &Specific) -> Result<Self, &'static str> {
         let mut score: Vec<usize> = vec![0; data.candidates];
@@ -21,7 +22,7 @@ impl<'a> VotingMethod<'a> for Fptp {
         Ok(Fptp { score })
     }
 
-    fn get_score(&self) -> &Vec<usize> {
+    fn get_score(&self) -> &[usize] {
         &self.score
     }
 }
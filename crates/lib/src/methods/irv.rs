@@ -0,0 +1,98 @@
+use super::fptp::order_to_vote;
+use crate::{
+    formats::{orders::TiedRank, toi::TiedOrdersIncomplete, VoteFormat},
+    methods::VotingMethod,
+    profile,
+};
+
+/// Instant-runoff voting (IRV). The candidate with the fewest first preferences
+/// is eliminated, and its votes are transferred to the next candidate on each
+/// vote, until only one candidate remains.
+///
+/// If multiple candidates are tied for the fewest first preferences, they are
+/// all eliminated at once and tied in the result. If multiple candidates are
+/// tied first in a vote, each of them receive a first preference from it.
+pub struct Irv {
+    // The round each candidate was eliminated in, the winners are eliminated
+    // last.
+    score: Vec<usize>,
+    // The first preferences of every candidate in every round.
+    rounds: Vec<Vec<usize>>,
+}
+
+impl<'a> VotingMethod<'a> for Irv {
+    type Format = TiedOrdersIncomplete;
+    type Score = usize;
+
+    fn count(data: &TiedOrdersIncomplete) -> Result<Self, &'static str> {
+        let n = data.candidates();
+        let mut score = vec![0; n];
+        let mut eliminated: Vec<usize> = Vec::with_capacity(n);
+        let mut rounds = Vec::new();
+        let mut round = 0;
+        while eliminated.len() < n {
+            profile::phase("round", || {
+                round += 1;
+                let firsts = data.majority_ignore(&eliminated);
+                let min = (0..n)
+                    .filter(|c| eliminated.binary_search(c).is_err())
+                    .map(|c| firsts[c])
+                    .min()
+                    .unwrap();
+                for c in 0..n {
+                    if firsts[c] == min && score[c] == 0 {
+                        score[c] = round;
+                        eliminated.push(c);
+                    }
+                }
+                eliminated.sort();
+                rounds.push(firsts);
+            });
+        }
+        Ok(Irv { score, rounds })
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl Irv {
+    pub fn as_vote(&self) -> TiedRank {
+        let order = self.get_order();
+        order_to_vote(&order)
+    }
+
+    /// The number of first preferences of every candidate in every round.
+    /// Candidates which were eliminated in an earlier round have none.
+    pub fn rounds(&self) -> &[Vec<usize>] {
+        &self.rounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfers() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,2,1", 8);
+        votes.add_from_str_i("1,0,2", 7);
+        votes.add_from_str_i("2,1,0", 6);
+        // 2 is eliminated first and its votes go to 1, which then wins.
+        let irv = Irv::count(&votes).unwrap();
+        assert_eq!(irv.as_vote().as_ref().to_string(), "1,0,2");
+        assert_eq!(irv.rounds(), [vec![8, 7, 6], vec![8, 13, 0], vec![0, 21, 0]]);
+    }
+
+    #[test]
+    fn tied_elimination() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,1,2", 2);
+        votes.add_from_str("1,0,2");
+        votes.add_from_str("2,1,0");
+        let res = Irv::count(&votes).unwrap().as_vote();
+        assert_eq!(res.as_ref().to_string(), "0,{1,2}");
+    }
+}
@@ -0,0 +1,196 @@
+//! Probabilistic voting methods, which elect a random winner.
+//!
+//! Instead of a ranking, these methods result in a [`Lottery`], the
+//! probability of each candidate winning.
+
+use std::collections::HashMap;
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use super::{Borda, TieScoring, VotingMethod};
+use crate::formats::{toi::TiedOrdersIncomplete, VoteFormat};
+
+/// The probability of each candidate being elected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lottery {
+    probabilities: Vec<f64>,
+}
+
+impl Lottery {
+    /// A lottery where every candidate is elected with probability
+    /// proportional to its weight. If every weight is zero, every candidate
+    /// is equally likely. Returns an error if there are no candidates.
+    pub fn from_weights(weights: &[usize]) -> Result<Self, &'static str> {
+        if weights.is_empty() {
+            return Err("No candidates");
+        }
+        let sum: usize = weights.iter().sum();
+        let probabilities = if sum == 0 {
+            vec![1.0 / weights.len() as f64; weights.len()]
+        } else {
+            weights.iter().map(|&w| w as f64 / sum as f64).collect()
+        };
+        Ok(Lottery { probabilities })
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.probabilities.len()
+    }
+
+    /// The probability of each candidate being elected, summing to 1.
+    pub fn probabilities(&self) -> &[f64] {
+        &self.probabilities
+    }
+
+    /// Draw a winner from the lottery.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        // Every lottery has a candidate, and the probabilities sum to 1.
+        WeightedIndex::new(&self.probabilities).unwrap().sample(rng)
+    }
+}
+
+/// Random serial dictatorship. The voters are put in a random order and, one at
+/// a time, keep only the candidates they rank highest among the remaining
+/// ones. When every voter has had their turn, a winner is chosen uniformly
+/// among the remaining candidates.
+///
+/// The probabilities are computed exactly, not by sampling. Unranked
+/// candidates are treated as tied below every ranked candidate.
+pub fn random_serial_dictatorship(data: &TiedOrdersIncomplete) -> Result<Lottery, &'static str> {
+    let n = data.candidates();
+    if n == 0 {
+        return Err("No candidates");
+    }
+    let mut memo = HashMap::new();
+    let probabilities = rsd(data, vec![true; n], &mut memo);
+    Ok(Lottery { probabilities })
+}
+
+// The probabilities of each candidate winning when only `remaining` are left.
+//
+// A voter who ranks every remaining candidate equally can be skipped, and will
+// never matter for any later subset either. That includes every voter who has
+// already had their turn, so the next voter to matter is uniformly random among
+// the ones who would remove some candidate, which only depends on `remaining`.
+fn rsd(
+    data: &TiedOrdersIncomplete,
+    remaining: Vec<bool>,
+    memo: &mut HashMap<Vec<bool>, Vec<f64>>,
+) -> Vec<f64> {
+    if let Some(p) = memo.get(&remaining) {
+        return p.clone();
+    }
+    let mut next: Vec<Vec<bool>> = Vec::new();
+    for vote in data {
+        let top = vote.iter_groups().find(|g| g.iter().any(|&c| remaining[c]));
+        if let Some(group) = top {
+            let mut kept = vec![false; remaining.len()];
+            for &c in group {
+                kept[c] = remaining[c];
+            }
+            if kept != remaining {
+                next.push(kept);
+            }
+        }
+    }
+    let mut probabilities = vec![0.0; remaining.len()];
+    if next.is_empty() {
+        let left = remaining.iter().filter(|&&r| r).count() as f64;
+        for (p, &r) in probabilities.iter_mut().zip(&remaining) {
+            if r {
+                *p = 1.0 / left;
+            }
+        }
+    } else {
+        let share = 1.0 / next.len() as f64;
+        for kept in next {
+            for (p, q) in probabilities.iter_mut().zip(rsd(data, kept, memo)) {
+                *p += share * q;
+            }
+        }
+    }
+    memo.insert(remaining, probabilities.clone());
+    probabilities
+}
+
+/// Elect a candidate with probability proportional to its Borda score, where
+/// tied candidates are scored according to `tie_scoring`.
+pub fn proportional_borda(
+    data: &TiedOrdersIncomplete,
+    tie_scoring: TieScoring,
+) -> Result<Lottery, &'static str> {
+    let borda = Borda::count_with(data, tie_scoring)?;
+    Lottery::from_weights(borda.get_score())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn rsd_strict() {
+        // With strict orders the first voter decides
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,1,2", 3);
+        votes.add_from_str("2,1,0");
+        let lottery = random_serial_dictatorship(&votes).unwrap();
+        assert_close(lottery.probabilities(), &[0.75, 0.0, 0.25]);
+    }
+
+    #[test]
+    fn rsd_ties() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("{0,1},2");
+        votes.add_from_str("1,0,2");
+        votes.add_from_str("2");
+        // If "2" goes first, 2 wins. Otherwise "{0,1},2" only matters if it is
+        // first, and then "1,0,2" picks 1, so 1 always wins.
+        let lottery = random_serial_dictatorship(&votes).unwrap();
+        assert_close(lottery.probabilities(), &[0.0, 2.0 / 3.0, 1.0 / 3.0]);
+    }
+
+    #[test]
+    fn rsd_indifferent() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("{0,1},2");
+        let lottery = random_serial_dictatorship(&votes).unwrap();
+        assert_close(lottery.probabilities(), &[0.5, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn no_candidates() {
+        assert!(Lottery::from_weights(&[]).is_err());
+        let votes = TiedOrdersIncomplete::new(0);
+        assert!(proportional_borda(&votes, TieScoring::Average).is_err());
+        assert!(random_serial_dictatorship(&votes).is_err());
+        // Without any weight, every candidate can still win.
+        let lottery = Lottery::from_weights(&[0, 0]).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(lottery.sample(&mut rng) < 2);
+        assert_close(lottery.probabilities(), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn borda_lottery() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("0,1,2");
+        votes.add_from_str("1,0,2");
+        votes.add_from_str("{0,2},1");
+        // Borda scores are (4.5, 3, 1.5)
+        let lottery = proportional_borda(&votes, TieScoring::Average).unwrap();
+        assert_close(lottery.probabilities(), &[0.5, 1.0 / 3.0, 1.0 / 6.0]);
+        // Borda scores are (5, 3, 2)
+        let lottery = proportional_borda(&votes, TieScoring::Max).unwrap();
+        assert_close(lottery.probabilities(), &[0.5, 0.3, 0.2]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Majority judgment, where the candidate with the highest median grade wins.
+
+use super::fptp::order_to_vote;
+use crate::{
+    formats::{orders::TiedRank, Cardinal, Validated},
+    methods::VotingMethod,
+};
+
+/// How many voters gave a candidate each grade.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GradeProfile {
+    min: usize,
+    counts: Vec<usize>,
+}
+
+impl GradeProfile {
+    /// The number of voters who gave the candidate `grade`.
+    pub fn count(&self, grade: usize) -> usize {
+        grade.checked_sub(self.min).and_then(|i| self.counts.get(i)).copied().unwrap_or(0)
+    }
+
+    pub fn voters(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The lower median grade, or `None` if nobody graded the candidate.
+    pub fn median(&self) -> Option<usize> {
+        self.medians().next()
+    }
+
+    /// The median grades which decide ties: the median, then the median
+    /// after removing one median grade, and so on until every grade is
+    /// removed.
+    pub fn medians(&self) -> impl Iterator<Item = usize> + '_ {
+        let grades: Vec<usize> = self
+            .counts
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &n)| std::iter::repeat_n(self.min + i, n))
+            .collect();
+        // The grades left are `grades[..below]` and `grades[above..]`, and
+        // the lower median is always next to the gap between them.
+        let n = grades.len();
+        let (mut below, mut above) = (n.div_ceil(2), n.div_ceil(2));
+        (0..n).map(move |removed| {
+            let i = (n - removed - 1) / 2;
+            if i < below {
+                below -= 1;
+                grades[below]
+            } else {
+                above += 1;
+                grades[above - 1]
+            }
+        })
+    }
+}
+
+/// Every candidate is ranked by its median grade. Ties are broken by
+/// repeatedly removing one median grade from the tied candidates, until
+/// their medians differ, see [`GradeProfile::medians`].
+///
+/// The score of a candidate is this sequence of medians, compared
+/// lexicographically.
+pub struct MajorityJudgment {
+    profiles: Vec<GradeProfile>,
+    score: Vec<Vec<usize>>,
+}
+
+impl<'a> VotingMethod<'a> for MajorityJudgment {
+    type Format = Cardinal;
+    type Score = Vec<usize>;
+
+    fn count(data: &Cardinal) -> Result<Self, &'static str> {
+        MajorityJudgment::count_grades(data, true)
+    }
+
+    // Every grade of a valid profile is allowed.
+    fn count_validated(data: &Validated<Cardinal>) -> Result<Self, &'static str> {
+        MajorityJudgment::count_grades(data, false)
+    }
+
+    fn get_score(&self) -> &[Vec<usize>] {
+        &self.score
+    }
+}
+
+impl MajorityJudgment {
+    // Count `data`, checking that every grade is allowed if `check` is true.
+    fn count_grades(data: &Cardinal, check: bool) -> Result<Self, &'static str> {
+        let mut profiles =
+            vec![
+                GradeProfile { min: data.min, counts: vec![0; data.max - data.min + 1] };
+                data.candidates
+            ];
+        for vote in data.votes.chunks_exact(data.candidates.max(1)) {
+            for (profile, &grade) in profiles.iter_mut().zip(vote) {
+                if check && (grade < data.min || grade > data.max) {
+                    return Err("Grade outside of the allowed grades");
+                }
+                profile.counts[grade - data.min] += 1;
+            }
+        }
+        let score = profiles.iter().map(|p| p.medians().collect()).collect();
+        Ok(MajorityJudgment { profiles, score })
+    }
+
+    /// The grades of every candidate.
+    pub fn profiles(&self) -> &[GradeProfile] {
+        &self.profiles
+    }
+
+    pub fn as_vote(&self) -> TiedRank {
+        order_to_vote(&self.get_order())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[test]
+    fn median_tiebreak() {
+        let mut votes = Cardinal::new(3, 0, 4);
+        votes.add(&[4, 2, 1]).unwrap();
+        votes.add(&[2, 2, 3]).unwrap();
+        votes.add(&[0, 3, 2]).unwrap();
+        votes.add(&[1, 2, 2]).unwrap();
+        let mj = MajorityJudgment::count(&votes).unwrap();
+        let medians: Vec<Option<usize>> = mj.profiles().iter().map(|p| p.median()).collect();
+        assert_eq!(medians, [Some(1), Some(2), Some(2)]);
+        // 1 and 2 have the same medians until the third one, where 1 has a 2
+        // and 2 only a 1.
+        let tiebreak: Vec<Vec<usize>> =
+            mj.profiles().iter().map(|p| p.medians().collect()).collect();
+        assert_eq!(tiebreak, [vec![1, 2, 0, 4], vec![2, 2, 2, 3], vec![2, 2, 1, 3]]);
+        assert_eq!(mj.as_vote().as_ref().to_string(), "1,2,0");
+        assert_eq!(mj.profiles()[1].count(2), 3);
+        assert_eq!(mj.profiles()[1].count(7), 0);
+    }
+
+    #[quickcheck]
+    fn ranked_by_median(votes: Cardinal) -> bool {
+        // A candidate with a higher median is never ranked below one with a
+        // lower median.
+        let mj = MajorityJudgment::count(&votes).unwrap();
+        let order = mj.get_order();
+        let p = mj.profiles();
+        (0..p.len())
+            .all(|a| (0..p.len()).all(|b| p[a].median() <= p[b].median() || order[a] <= order[b]))
+    }
+}
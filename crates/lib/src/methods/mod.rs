@@ -1,26 +1,67 @@
-use crate::formats::VoteFormat;
+#[cfg(feature = "profiling")]
+use crate::profile::{self, Timings};
+use crate::{
+    election::{Rank, Score},
+    formats::{
+        orders::{TiedRank, TiedRankRef},
+        toc::TiedOrdersComplete,
+        toi::TiedOrdersIncomplete,
+        TiePolicy, Validated, VoteFormat,
+    },
+};
 
 /// Trait shared by every voting method
 pub trait VotingMethod<'a> {
     /// Every voting method accepts some specific vote format as input.
     type Format: VoteFormat<'a> + Clone;
 
+    /// The score of a candidate, e.g. [`Fraction`](crate::election::Fraction)
+    /// for methods with fractional points.
+    type Score: Ord;
+
     /// Counts all the votes, into a format which makes it fast to compute other
     /// methods such as `get_order`.
     fn count(data: &Self::Format) -> Result<Self, &'static str>
     where
         Self: Sized;
 
+    /// Counts a profile which is known to be valid, like
+    /// [`count`](VotingMethod::count). Methods which check the votes while
+    /// counting, like [`ScoreVoting`], skip the checks a valid profile always
+    /// passes.
+    fn count_validated(data: &Validated<Self::Format>) -> Result<Self, &'static str>
+    where
+        Self: Sized,
+    {
+        Self::count(data)
+    }
+
     /// Internal score, e.g. the number of votes for each candidate for methods
     /// like first-past-the-post, but may not make sense for all methods.
     /// Return value should be able to be used by `get_order` to get the
     /// result of the voting method. Larger values are higher rank.
-    fn get_score(&self) -> &Vec<usize>;
+    fn get_score(&self) -> &[Self::Score];
 
     /// Gets a partial order of the candidates
     fn get_order(&self) -> Vec<usize> {
         get_order(self.get_score(), true)
     }
+
+    /// The rank of every candidate, like
+    /// [`get_order`](VotingMethod::get_order).
+    fn ranks(&self) -> Vec<Rank> {
+        self.get_order().into_iter().map(Rank).collect()
+    }
+
+    /// The score of every candidate, like
+    /// [`get_score`](VotingMethod::get_score), for methods with whole
+    /// numbers as scores.
+    fn scores(&self) -> Vec<Score>
+    where
+        Self::Score: Copy + Into<Score>,
+    {
+        self.get_score().iter().map(|&s| s.into()).collect()
+    }
 }
 
 /// A version of `VotingMethod`, but randomness can be used when calculating the
@@ -42,12 +83,225 @@ pub trait RandomVotingMethod<'a> {
     /// like first-past-the-post, but may not make sense for all methods.
     /// Return value should be able to be used by `get_order` to get the
     /// result of the voting method. Larger values are higher rank.
-    fn get_score(&self) -> &Vec<usize>;
+    fn get_score(&self) -> &[usize];
 
     /// Gets a partial order of the candidates
     fn get_order(&self) -> Vec<usize> {
         get_order(self.get_score(), true)
     }
+
+    /// The rank of every candidate, like
+    /// [`get_order`](RandomVotingMethod::get_order).
+    fn ranks(&self) -> Vec<Rank> {
+        self.get_order().into_iter().map(Rank).collect()
+    }
+
+    /// The score of every candidate, like
+    /// [`get_score`](RandomVotingMethod::get_score).
+    fn scores(&self) -> Vec<Score> {
+        self.get_score().iter().map(|&s| Score::from(s)).collect()
+    }
+}
+
+/// A single-winner voting method together with its configuration, making it
+/// possible to describe which method to use in a configuration file. Methods
+/// with options carry the `Config` of the method, e.g. [`StarConfig`], which
+/// is passed to its `from_config`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MethodConfig {
+    Borda,
+    Bucklin(BucklinConfig),
+    /// The Condorcet winner, if there is one, ranked above every other
+    /// candidate, see [`PairwiseMatrix`](pairwise::PairwiseMatrix).
+    Condorcet,
+    Coombs,
+    /// Copeland's method, with pairwise ties worth the configured points.
+    Copeland(CopelandConfig),
+    /// First-past-the-post, where votes with multiple candidates tied first
+    /// are handled according to the policy.
+    Fptp(TiePolicy),
+    Irv,
+    /// Majority judgment, see [`MajorityJudgment`], with grades converted
+    /// from the rankings.
+    MajorityJudgment,
+    Positional(PositionalConfig),
+    /// Score voting, see [`ScoreVoting`], with ratings converted from the
+    /// rankings.
+    Score,
+    /// IRV counted with only the candidates in the Smith set, see
+    /// [`SmithIrv`].
+    SmithIrv,
+    /// Minimax counted with only the candidates in the Smith set, see
+    /// [`SmithMinimax`].
+    SmithMinimax,
+    Star(StarConfig),
+}
+
+impl MethodConfig {
+    /// A short name of the method, without its configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MethodConfig::Borda => "borda",
+            MethodConfig::Bucklin(_) => "bucklin",
+            MethodConfig::Condorcet => "condorcet",
+            MethodConfig::Coombs => "coombs",
+            MethodConfig::Copeland(_) => "copeland",
+            MethodConfig::Fptp(_) => "fptp",
+            MethodConfig::Irv => "irv",
+            MethodConfig::MajorityJudgment => "majority_judgment",
+            MethodConfig::Positional(_) => "positional",
+            MethodConfig::Score => "score",
+            MethodConfig::SmithIrv => "smith_irv",
+            MethodConfig::SmithMinimax => "smith_minimax",
+            MethodConfig::Star(_) => "star",
+        }
+    }
+
+    /// Count `votes` using the configured method and return the resulting
+    /// ranking. The votes are first converted to the format used by the
+    /// method, using `rng` for conversions which need randomness.
+    pub fn rank<R: Rng>(
+        &self,
+        votes: &TiedOrdersComplete,
+        rng: &mut R,
+    ) -> Result<TiedRank, &'static str> {
+        match self {
+            MethodConfig::Borda => Ok(Borda::count(&votes.clone().to_toi()?)?.as_vote()),
+            MethodConfig::Bucklin(config) => {
+                Ok(Bucklin::from_config(&votes.clone().to_toi()?, config)?.as_vote())
+            }
+            MethodConfig::Condorcet => Ok(votes.pairwise_matrix()?.condorcet_ranking()),
+            MethodConfig::Coombs => Ok(Coombs::count(&votes.clone().to_toi()?)?.as_vote()),
+            MethodConfig::Copeland(config) => {
+                Ok(Copeland::from_matrix(&votes.pairwise_matrix()?, config.ties).as_vote())
+            }
+            MethodConfig::Fptp(policy) => {
+                Ok(Fptp::count(&votes.to_specific_with(*policy, rng)?)?.as_vote())
+            }
+            MethodConfig::Irv => Ok(Irv::count(&votes.clone().to_toi()?)?.as_vote()),
+            MethodConfig::MajorityJudgment => {
+                Ok(MajorityJudgment::count(&votes.to_cardinal()?)?.as_vote())
+            }
+            MethodConfig::Positional(config) => {
+                Ok(Positional::from_config(&votes.clone().to_toi()?, config)?.as_vote())
+            }
+            MethodConfig::Score => Ok(ScoreVoting::count(&votes.to_cardinal()?)?.as_vote()),
+            MethodConfig::SmithIrv => Ok(SmithIrv::count(&votes.clone().to_toi()?)?.as_vote()),
+            MethodConfig::SmithMinimax => {
+                Ok(SmithMinimax::count(&votes.clone().to_toi()?)?.as_vote())
+            }
+            MethodConfig::Star(config) => {
+                Ok(Star::from_config(&votes.to_cardinal()?, config)?.as_vote())
+            }
+        }
+    }
+
+    /// Like [`rank`](MethodConfig::rank), but for votes which are known to be
+    /// valid. The votes converted from them are valid too, so methods
+    /// counting ratings don't check every rating again, see
+    /// [`VotingMethod::count_validated`].
+    pub fn rank_validated<R: Rng>(
+        &self,
+        votes: &Validated<TiedOrdersComplete>,
+        rng: &mut R,
+    ) -> Result<TiedRank, &'static str> {
+        match self {
+            MethodConfig::MajorityJudgment => {
+                let ratings = Validated::assume_valid(votes.to_cardinal()?);
+                Ok(MajorityJudgment::count_validated(&ratings)?.as_vote())
+            }
+            MethodConfig::Score => {
+                let ratings = Validated::assume_valid(votes.to_cardinal()?);
+                Ok(ScoreVoting::count_validated(&ratings)?.as_vote())
+            }
+            _ => self.rank(votes, rng),
+        }
+    }
+
+    /// Like [`rank`](MethodConfig::rank), but also returns how long every
+    /// phase of the count took, see [`profile`](crate::profile). The whole
+    /// count is timed as the phase `rank`.
+    #[cfg(feature = "profiling")]
+    pub fn rank_profiled<R: Rng>(
+        &self,
+        votes: &TiedOrdersComplete,
+        rng: &mut R,
+    ) -> Result<(TiedRank, Timings), &'static str> {
+        let (ranking, timings) =
+            profile::record(|| profile::phase("rank", || self.rank(votes, rng)));
+        Ok((ranking?, timings))
+    }
+
+    /// Like [`rank`](MethodConfig::rank), but condensed into two levels: the
+    /// candidates in `contention` first, in the order given by the method,
+    /// and then every other candidate tied last, see [`sets::condense`]. This
+    /// makes it explicit which candidates can't win under any
+    /// Condorcet-compliant method.
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha8Rng;
+    /// use votery::{
+    ///     formats::{toc::TiedOrdersComplete, TiePolicy},
+    ///     methods::{sets::Contention, MethodConfig},
+    /// };
+    ///
+    /// let mut votes = TiedOrdersComplete::new(4);
+    /// for vote in ["3,0,1,2", "1,2,0,3", "2,0,1,3", "3,1,2,0", "0,1,2,3"] {
+    ///     assert!(votes.add_from_str(vote));
+    /// }
+    /// // 3 has the most first preferences, but 0, 1 and 2 form a cycle which
+    /// // beats it.
+    /// let fptp = MethodConfig::Fptp(TiePolicy::default());
+    /// let mut rng = ChaCha8Rng::seed_from_u64(0);
+    /// assert_eq!(fptp.rank(&votes, &mut rng).unwrap().as_ref().to_string(), "3,{0,1,2}");
+    /// let ranking = fptp.rank_condensed(&votes, Contention::Smith, &mut rng).unwrap();
+    /// assert_eq!(ranking.as_ref().to_string(), "{0,1,2},3");
+    /// ```
+    pub fn rank_condensed<R: Rng>(
+        &self,
+        votes: &TiedOrdersComplete,
+        contention: sets::Contention,
+        rng: &mut R,
+    ) -> Result<TiedRank, &'static str> {
+        let ranking = self.rank(votes, rng)?;
+        let contenders = contention.set(votes.pairwise_matrix()?.tournament());
+        Ok(sets::condense(ranking.as_ref(), &contenders))
+    }
+
+    /// Like [`rank`](MethodConfig::rank), but for votes which don't have to
+    /// rank every candidate. How unranked candidates are treated depends on
+    /// the method, e.g. [`Star`] gives them the lowest score.
+    pub fn rank_incomplete<R: Rng>(
+        &self,
+        votes: &TiedOrdersIncomplete,
+        rng: &mut R,
+    ) -> Result<TiedRank, &'static str> {
+        match self {
+            MethodConfig::Borda => Ok(Borda::count(votes)?.as_vote()),
+            MethodConfig::Bucklin(config) => Ok(Bucklin::from_config(votes, config)?.as_vote()),
+            MethodConfig::Condorcet => Ok(votes.pairwise_matrix()?.condorcet_ranking()),
+            MethodConfig::Coombs => Ok(Coombs::count(votes)?.as_vote()),
+            MethodConfig::Copeland(config) => Ok(Copeland::from_config(votes, config)?.as_vote()),
+            MethodConfig::Fptp(policy) => {
+                Ok(Fptp::count(&votes.to_specific_with(*policy, rng)?)?.as_vote())
+            }
+            MethodConfig::Irv => Ok(Irv::count(votes)?.as_vote()),
+            MethodConfig::MajorityJudgment => {
+                Ok(MajorityJudgment::count(&votes.clone().to_cardinal()?)?.as_vote())
+            }
+            MethodConfig::Positional(config) => {
+                Ok(Positional::from_config(votes, config)?.as_vote())
+            }
+            MethodConfig::Score => Ok(ScoreVoting::count(&votes.clone().to_cardinal()?)?.as_vote()),
+            MethodConfig::SmithIrv => Ok(SmithIrv::count(votes)?.as_vote()),
+            MethodConfig::SmithMinimax => Ok(SmithMinimax::count(votes)?.as_vote()),
+            MethodConfig::Star(config) => {
+                Ok(Star::from_config(&votes.clone().to_cardinal()?, config)?.as_vote())
+            }
+        }
+    }
 }
 
 // Convert a list of numbers to the partial order of the list. High numbers in
@@ -83,6 +337,20 @@ pub fn get_order<T: Ord>(v: &[T], reverse: bool) -> Vec<usize> {
     out
 }
 
+// The score of every candidate in `rank`, where the first group gets the most
+// points and unranked candidates get 0, so `get_order` gives back the groups
+// of `rank`.
+pub(crate) fn rank_score(rank: TiedRankRef) -> Vec<usize> {
+    let groups = rank.iter_groups().count();
+    let mut score = vec![0; rank.candidates];
+    for (i, group) in rank.iter_groups().enumerate() {
+        for &c in group {
+            score[c] = groups - i;
+        }
+    }
+    score
+}
+
 // TODO: This method makes no sense
 // Returns
 //     Ordering::Less    if i is ranked better than j
@@ -113,7 +381,11 @@ pub fn get_order<T: Ord>(v: &[T], reverse: bool) -> Vec<usize> {
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
     use super::*;
+    use crate::formats::Validate;
 
     #[test]
     fn get_order_ordered() {
@@ -133,7 +405,7 @@ mod tests {
     #[test]
     fn get_order_empty() {
         let a: Vec<usize> = vec![];
-        let b = vec![];
+        let b: Vec<usize> = vec![];
         assert_eq!(get_order(&a, true), b);
     }
 
@@ -144,6 +416,69 @@ mod tests {
         assert_eq!(get_order(&a, true), b);
     }
 
+    #[quickcheck]
+    fn ranks_follow_scores(votes: TiedOrdersIncomplete) -> bool {
+        let borda = Borda::count(&votes).unwrap();
+        let (ranks, scores) = (borda.ranks(), borda.scores());
+        (0..ranks.len()).all(|a| {
+            (0..ranks.len())
+                .all(|b| ranks[a].is_better_than(ranks[b]) == scores[a].is_better_than(scores[b]))
+        })
+    }
+
+    #[quickcheck]
+    fn rank_validated(candidates: u8, voters: u8, seed: u64) -> bool {
+        let mut votes = TiedOrdersComplete::new(candidates as usize % 8 + 1);
+        votes.generate_uniform(&mut ChaCha8Rng::seed_from_u64(seed), voters as usize);
+        let validated = votes.clone().validate().unwrap();
+        [MethodConfig::Irv, MethodConfig::MajorityJudgment, MethodConfig::Score].iter().all(|m| {
+            let rank = m.rank(&votes, &mut rand::thread_rng());
+            rank == m.rank_validated(&validated, &mut rand::thread_rng())
+        })
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn rank_profiled() {
+        let mut votes = TiedOrdersComplete::new(3);
+        for v in ["0,1,2", "1,0,2", "2,1,0", "0,2,1"] {
+            votes.add_from_str(v);
+        }
+        let mut rng = rand::thread_rng();
+        let (ranking, timings) = MethodConfig::Irv.rank_profiled(&votes, &mut rng).unwrap();
+        assert_eq!(ranking, MethodConfig::Irv.rank(&votes, &mut rng).unwrap());
+        assert_eq!(timings.count("rank"), 1);
+        assert_eq!(timings.count("convert"), 1);
+        // 1 and 2 are eliminated together in the first round.
+        assert_eq!(timings.count("round"), 2);
+        assert!(timings.total("round") <= timings.total("rank"));
+        let (_, timings) = MethodConfig::Condorcet.rank_profiled(&votes, &mut rng).unwrap();
+        assert_eq!(timings.count("pairwise matrix"), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_from_toml() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        for v in ["0,{1,2}", "1,0,2", "2,1"] {
+            votes.add_from_str(v);
+        }
+        let mut rng = rand::thread_rng();
+        let method: MethodConfig =
+            toml::from_str("Positional = { weights = [2, 1], tie_scoring = \"Max\" }").unwrap();
+        let config = PositionalConfig { weights: vec![2, 1], tie_scoring: TieScoring::Max };
+        assert_eq!(method, MethodConfig::Positional(config.clone()));
+        assert_eq!(
+            method.rank_incomplete(&votes, &mut rng).unwrap(),
+            Positional::from_config(&votes, &config).unwrap().as_vote()
+        );
+        let method: MethodConfig = toml::from_str("Copeland = { ties = \"Zero\" }").unwrap();
+        assert_eq!(method, MethodConfig::Copeland(CopelandConfig { ties: CopelandTies::Zero }));
+        let stv: StvConfig = toml::from_str("seats = 2\nquota = \"Hare\"").unwrap();
+        assert_eq!(stv, StvConfig { seats: 2, quota: Quota::Hare });
+        assert_eq!(Stv::from_config(&votes, &stv).unwrap().elected(), [1, 0]);
+    }
+
     #[quickcheck]
     fn qc_get_order_involution(xs: Vec<usize>) -> bool {
         let a = get_order(&xs, true);
@@ -184,9 +519,38 @@ mod approval;
 pub use approval::Approval;
 mod borda;
 pub use borda::Borda;
+mod bucklin;
+pub use bucklin::{Bucklin, BucklinConfig};
+mod coombs;
+pub use coombs::Coombs;
+mod copeland;
+pub use copeland::{Copeland, CopelandConfig, CopelandTies};
+mod cumulative;
+pub use cumulative::CumulativeVoting;
+mod equal_shares;
+pub use equal_shares::{Completion, EqualShares, EqualSharesConfig};
 mod fptp;
 pub use fptp::Fptp;
+mod irv;
+pub use irv::Irv;
+pub mod lottery;
+mod majority_judgment;
+pub use majority_judgment::{GradeProfile, MajorityJudgment};
+pub mod pairwise;
+mod pav;
+pub use pav::{Pav, SeqPav};
+mod phragmen;
+pub use phragmen::Phragmen;
+mod positional;
+pub use positional::{Positional, PositionalConfig, TieScoring};
 pub mod random_ballot;
 use rand::Rng;
+mod score;
+pub use score::{ScoreRange, ScoreVoting};
+pub mod sets;
+mod smith;
+pub use smith::{SmithIrv, SmithMinimax};
 mod star;
-pub use star::Star;
+pub use star::{Runoff, Star, StarConfig, TieBreaker};
+mod stv;
+pub use stv::{Quota, Stv, StvConfig, StvRound};
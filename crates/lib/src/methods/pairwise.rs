@@ -0,0 +1,222 @@
+//! Pairwise comparisons of candidates, the base of Condorcet methods.
+//!
+//! A [`PairwiseMatrix`] counts, for every pair of candidates, how many voters
+//! prefer one over the other. Unranked candidates are treated as tied below
+//! every ranked candidate.
+//!
+//! A [`HeadToHead`] instead breaks down how the voters compare a single pair
+//! of candidates, i.e. what an election between only those two would look
+//! like.
+
+use crate::{
+    formats::orders::{TiedRank, TiedRankRef},
+    profile,
+    tournament::Tournament,
+};
+
+/// How many voters prefer every candidate over every other candidate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairwiseMatrix {
+    tournament: Tournament,
+}
+
+impl PairwiseMatrix {
+    /// Count the ranked `votes` of `candidates`, e.g. from a
+    /// [`TiedOrdersComplete`](crate::formats::toc::TiedOrdersComplete) or a
+    /// [`TiedOrdersIncomplete`](crate::formats::toi::TiedOrdersIncomplete).
+    pub fn from_votes<'a, I>(candidates: usize, votes: I) -> Result<Self, &'static str>
+    where
+        I: IntoIterator<Item = TiedRankRef<'a>>,
+    {
+        profile::phase("pairwise matrix", || {
+            let mut weights: Vec<usize> = vec![0; candidates * candidates];
+            // The group of every candidate in the current vote, where unranked
+            // candidates are in a group after every other group.
+            let mut groups = vec![0; candidates];
+            for vote in votes {
+                groups.iter_mut().for_each(|g| *g = usize::MAX);
+                for (i, group) in vote.iter_groups().enumerate() {
+                    for &c in group {
+                        groups[c] = i;
+                    }
+                }
+                for (a, &ga) in groups.iter().enumerate() {
+                    for (b, &gb) in groups.iter().enumerate() {
+                        if ga < gb {
+                            let w = &mut weights[a * candidates + b];
+                            *w = w.checked_add(1).ok_or("Integer overflow: Too many votes")?;
+                        }
+                    }
+                }
+            }
+            Ok(PairwiseMatrix { tournament: Tournament::from_weights(candidates, weights) })
+        })
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.tournament.candidates()
+    }
+
+    /// The number of voters preferring `a` over `b`.
+    pub fn wins(&self, a: usize, b: usize) -> usize {
+        self.tournament.weight(a, b)
+    }
+
+    /// The number of voters preferring `a` over `b`, minus the number of
+    /// voters preferring `b` over `a`.
+    pub fn margin(&self, a: usize, b: usize) -> isize {
+        self.tournament.margin(a, b)
+    }
+
+    /// Returns true if more voters prefer `a` over `b` than the other way
+    /// around.
+    pub fn beats(&self, a: usize, b: usize) -> bool {
+        self.tournament.beats(a, b)
+    }
+
+    /// The candidate which beats every other candidate, if there is one.
+    pub fn condorcet_winner(&self) -> Option<usize> {
+        self.tournament.condorcet_winner()
+    }
+
+    /// The Condorcet winner ranked above every other candidate, which are
+    /// tied. Every candidate is tied if there is no Condorcet winner.
+    pub fn condorcet_ranking(&self) -> TiedRank {
+        let mut score = vec![0; self.candidates()];
+        if let Some(w) = self.condorcet_winner() {
+            score[w] = 1;
+        }
+        TiedRank::from_scores(self.candidates(), &score)
+    }
+
+    /// The pairwise wins as a weighted tournament.
+    pub fn tournament(&self) -> &Tournament {
+        &self.tournament
+    }
+}
+
+/// How the voters compare two candidates `a` and `b`. Every voter is counted
+/// exactly once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeadToHead {
+    /// Voters ranking `a` above `b`, including voters ranking `a` but not `b`.
+    pub a_over_b: usize,
+    /// Voters ranking `b` above `a`, including voters ranking `b` but not `a`.
+    pub b_over_a: usize,
+    /// Voters ranking both candidates equally.
+    pub tied: usize,
+    /// Voters ranking neither candidate.
+    pub unranked: usize,
+}
+
+impl HeadToHead {
+    /// Count how the ranked `votes` compare `a` and `b`, where unranked
+    /// candidates are treated like in a [`PairwiseMatrix`], so `a_over_b` is
+    /// the same as [`PairwiseMatrix::wins(a, b)`](PairwiseMatrix::wins).
+    pub fn from_votes<'a, I>(votes: I, a: usize, b: usize) -> Self
+    where
+        I: IntoIterator<Item = TiedRankRef<'a>>,
+    {
+        let mut res = HeadToHead::default();
+        for vote in votes {
+            let (mut group_a, mut group_b) = (None, None);
+            for (i, group) in vote.iter_groups().enumerate() {
+                if group.contains(&a) {
+                    group_a = Some(i);
+                }
+                if group.contains(&b) {
+                    group_b = Some(i);
+                }
+            }
+            let count = match (group_a, group_b) {
+                (None, None) => &mut res.unranked,
+                (Some(_), None) => &mut res.a_over_b,
+                (None, Some(_)) => &mut res.b_over_a,
+                (Some(ga), Some(gb)) if ga < gb => &mut res.a_over_b,
+                (Some(ga), Some(gb)) if ga > gb => &mut res.b_over_a,
+                _ => &mut res.tied,
+            };
+            *count += 1;
+        }
+        res
+    }
+
+    pub fn voters(&self) -> usize {
+        self.a_over_b + self.b_over_a + self.tied + self.unranked
+    }
+
+    /// The number of voters preferring `a` over `b`, minus the number of
+    /// voters preferring `b` over `a`.
+    pub fn margin(&self) -> isize {
+        self.a_over_b as isize - self.b_over_a as isize
+    }
+}
+
+impl std::iter::Sum for HeadToHead {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(HeadToHead::default(), |acc, h| HeadToHead {
+            a_over_b: acc.a_over_b + h.a_over_b,
+            b_over_a: acc.b_over_a + h.b_over_a,
+            tied: acc.tied + h.tied,
+            unranked: acc.unranked + h.unranked,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use crate::formats::{toi::TiedOrdersIncomplete, VoteFormat};
+
+    #[test]
+    fn incomplete() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("0,{1,2}");
+        votes.add_from_str("1");
+        votes.add_from_str("2,1,0");
+        let m = votes.pairwise_matrix().unwrap();
+        assert_eq!((m.wins(0, 1), m.wins(1, 0)), (1, 2));
+        assert_eq!((m.wins(1, 2), m.wins(2, 1)), (1, 1));
+        assert_eq!(m.margin(0, 2), 0);
+        assert_eq!(m.condorcet_winner(), None);
+        votes.add_from_str("1,2");
+        let m = votes.pairwise_matrix().unwrap();
+        assert_eq!(m.condorcet_winner(), Some(1));
+        assert_eq!(m.condorcet_ranking().as_ref().to_string(), "1,{0,2}");
+    }
+
+    #[quickcheck]
+    fn every_pair_compared(votes: TiedOrdersIncomplete) -> bool {
+        let m = votes.pairwise_matrix().unwrap();
+        let n = votes.candidates();
+        // The group of every candidate in every vote, `None` if unranked.
+        let mut groups = vec![vec![None; n]; votes.voters()];
+        for (vote, g) in votes.into_iter().zip(&mut groups) {
+            for (i, group) in vote.iter_groups().enumerate() {
+                group.iter().for_each(|&c| g[c] = Some(i));
+            }
+        }
+        (0..n).all(|a| {
+            (0..n).all(|b| {
+                let ties = groups.iter().filter(|g| g[a] == g[b]).count();
+                m.wins(a, b) + m.wins(b, a) + ties == votes.voters()
+            })
+        })
+    }
+
+    #[quickcheck]
+    fn head_to_head_matches_matrix(votes: TiedOrdersIncomplete, a: usize, b: usize) -> bool {
+        let n = votes.candidates();
+        if n == 0 {
+            return votes.head_to_head(0, 0).is_err();
+        }
+        let (a, b) = (a % n, b % n);
+        let m = votes.pairwise_matrix().unwrap();
+        let h = votes.head_to_head(a, b).unwrap();
+        h.a_over_b == m.wins(a, b)
+            && h.b_over_a == m.wins(b, a)
+            && h.voters() == votes.voters()
+            && votes.head_to_head(n, a).is_err()
+    }
+}
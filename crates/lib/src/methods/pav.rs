@@ -0,0 +1,215 @@
+//! Proportional approval voting (PAV), electing a committee of approval
+//! votes.
+
+use crate::formats::Binary;
+
+// Scores closer than this are treated as equal, to not depend on rounding
+// errors of the harmonic numbers.
+const EPSILON: f64 = 1e-9;
+
+// The most committees `Pav` will compare.
+const MAX_COMMITTEES: u64 = 1_000_000;
+
+// The PAV score of `committee`: every voter contributes `1 + 1/2 + ... + 1/j`
+// where `j` is the number of members of `committee` they approve of.
+fn pav_score(data: &Binary, committee: &[usize]) -> f64 {
+    if data.candidates == 0 {
+        return 0.0;
+    }
+    let mut approved = vec![0; committee.len() + 1];
+    for vote in data.votes.chunks_exact(data.candidates) {
+        approved[committee.iter().filter(|&&c| vote[c]).count()] += 1;
+    }
+    let mut score = 0.0;
+    let mut harmonic = 0.0;
+    for (j, &voters) in approved.iter().enumerate().skip(1) {
+        harmonic += 1.0 / j as f64;
+        score += voters as f64 * harmonic;
+    }
+    score
+}
+
+pub(super) fn check_seats(data: &Binary, seats: usize) -> Result<(), &'static str> {
+    if seats == 0 {
+        return Err("Need at least one seat");
+    }
+    if seats > data.candidates {
+        return Err("More seats than candidates");
+    }
+    Ok(())
+}
+
+/// Proportional approval voting, electing the committee of `seats` candidates
+/// with the highest PAV score, where a voter who approves of `j` members of
+/// the committee adds `1 + 1/2 + ... + 1/j` to it.
+///
+/// Every committee is compared, so this is only possible for small elections,
+/// see [`SeqPav`] for an approximation. Ties are broken by electing the
+/// committee which comes first lexicographically.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pav {
+    committee: Vec<usize>,
+    score: f64,
+}
+
+impl Pav {
+    /// Returns an error if there are more than a million committees.
+    pub fn count(data: &Binary, seats: usize) -> Result<Self, &'static str> {
+        check_seats(data, seats)?;
+        let n = data.candidates;
+        // The number of committees, `n` choose `seats`.
+        let mut committees: u64 = 1;
+        for i in 0..seats.min(n - seats) {
+            committees = committees * (n - i) as u64 / (i + 1) as u64;
+            if committees > MAX_COMMITTEES {
+                return Err("Too many committees to compare");
+            }
+        }
+        let mut committee: Vec<usize> = (0..seats).collect();
+        let mut best = Pav { score: pav_score(data, &committee), committee: committee.clone() };
+        // Go through the committees in lexicographic order.
+        while let Some(i) = (0..seats).rev().find(|&i| committee[i] < n - seats + i) {
+            committee[i] += 1;
+            for j in i + 1..seats {
+                committee[j] = committee[j - 1] + 1;
+            }
+            let score = pav_score(data, &committee);
+            if score > best.score + EPSILON {
+                best = Pav { committee: committee.clone(), score };
+            }
+        }
+        Ok(best)
+    }
+
+    /// The elected candidates, in increasing order.
+    pub fn committee(&self) -> &[usize] {
+        &self.committee
+    }
+
+    /// The PAV score of the committee.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+/// Sequential proportional approval voting, electing one candidate at a time
+/// until `seats` candidates are elected. Each time, the candidate which
+/// increases the PAV score of the committee the most is elected, i.e. a
+/// voter who approves of `j` elected candidates adds `1 / (j + 1)` to each
+/// candidate they approve of.
+///
+/// Ties are broken by electing the candidate with the lowest index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeqPav {
+    committee: Vec<usize>,
+    score: f64,
+}
+
+impl SeqPav {
+    pub fn count(data: &Binary, seats: usize) -> Result<Self, &'static str> {
+        check_seats(data, seats)?;
+        let n = data.candidates;
+        let mut elected = vec![false; n];
+        // The number of elected candidates every voter approves of.
+        let mut approved = vec![0; data.voters];
+        let mut committee = Vec::with_capacity(seats);
+        let mut score = 0.0;
+        while committee.len() < seats {
+            let mut gains = vec![0.0; n];
+            for (vote, &j) in data.votes.chunks_exact(n).zip(&approved) {
+                let gain = 1.0 / (j + 1) as f64;
+                for (g, &v) in gains.iter_mut().zip(vote) {
+                    if v {
+                        *g += gain;
+                    }
+                }
+            }
+            let mut winner = None;
+            for c in (0..n).filter(|&c| !elected[c]) {
+                if winner.is_none_or(|w: usize| gains[c] > gains[w] + EPSILON) {
+                    winner = Some(c);
+                }
+            }
+            let winner = winner.unwrap();
+            elected[winner] = true;
+            committee.push(winner);
+            score += gains[winner];
+            for (vote, j) in data.votes.chunks_exact(n).zip(&mut approved) {
+                *j += vote[winner] as usize;
+            }
+        }
+        Ok(SeqPav { committee, score })
+    }
+
+    /// The elected candidates, in the order they were elected.
+    pub fn committee(&self) -> &[usize] {
+        &self.committee
+    }
+
+    /// The PAV score of the committee.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    fn votes(candidates: usize, votes: &[(&[usize], usize)]) -> Binary {
+        let mut data = Binary::new(candidates);
+        for &(approved, n) in votes {
+            let vote: Vec<bool> = (0..candidates).map(|c| approved.contains(&c)).collect();
+            for _ in 0..n {
+                data.add(&vote).unwrap();
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn proportional() {
+        // A majority of 6 approves of 0, 1 and 2, and a minority of 4 of 3.
+        // Approval voting would elect 0 and 1, but PAV gives the minority a
+        // seat: 6 * 1 + 4 * 1 = 10 against 6 * 1.5 = 9.
+        let data = votes(4, &[(&[0, 1, 2], 6), (&[3], 4)]);
+        let pav = Pav::count(&data, 2).unwrap();
+        assert_eq!(pav.committee(), [0, 3]);
+        assert!((pav.score() - 10.0).abs() < EPSILON);
+        let seq = SeqPav::count(&data, 2).unwrap();
+        assert_eq!(seq.committee(), [0, 3]);
+        assert!((seq.score() - 10.0).abs() < EPSILON);
+        assert!(Pav::count(&data, 0).is_err());
+        assert!(SeqPav::count(&data, 5).is_err());
+    }
+
+    #[test]
+    fn sequential_is_not_optimal() {
+        // 0 is elected first by sequential PAV, but 1 and 2 together are
+        // approved by more voters.
+        let data = votes(3, &[(&[0, 1], 3), (&[0, 2], 3), (&[1], 2), (&[2], 2)]);
+        let pav = Pav::count(&data, 2).unwrap();
+        assert_eq!(pav.committee(), [1, 2]);
+        assert!((pav.score() - 10.0).abs() < EPSILON);
+        let seq = SeqPav::count(&data, 2).unwrap();
+        assert_eq!(seq.committee(), [0, 1]);
+        assert!((seq.score() - 9.5).abs() < EPSILON);
+    }
+
+    #[quickcheck]
+    fn exact_beats_sequential(data: Binary, seats: usize) -> bool {
+        // Comparing every committee is slow with many candidates.
+        if data.candidates == 0 || data.candidates > 10 {
+            return true;
+        }
+        let seats = seats % data.candidates + 1;
+        let (Ok(pav), Ok(seq)) = (Pav::count(&data, seats), SeqPav::count(&data, seats)) else {
+            return true;
+        };
+        let mut committee = seq.committee().to_vec();
+        committee.sort();
+        (pav_score(&data, &committee) - seq.score()).abs() < EPSILON
+            && pav.score() >= seq.score() - EPSILON
+    }
+}
@@ -0,0 +1,139 @@
+//! Phragmén's sequential method, electing a committee of approval votes.
+
+use super::pav::check_seats;
+use crate::formats::Binary;
+
+// Loads closer than this are treated as equal, to not depend on rounding
+// errors.
+const EPSILON: f64 = 1e-9;
+
+/// Phragmén's sequential method, electing one candidate at a time until
+/// `seats` candidates are elected.
+///
+/// Electing a candidate costs one unit, which is shared by the voters who
+/// approve of it such that they all end up with the same load, the total
+/// cost they have paid. Each time, the candidate which gives the lowest
+/// such load is elected. Candidates which nobody approves of are only
+/// elected when no other candidates remain.
+///
+/// Ties are broken by electing the candidate with the lowest index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phragmen {
+    committee: Vec<usize>,
+    loads: Vec<f64>,
+}
+
+impl Phragmen {
+    pub fn count(data: &Binary, seats: usize) -> Result<Self, &'static str> {
+        check_seats(data, seats)?;
+        let mut committee = Vec::with_capacity(seats);
+        let mut loads = vec![0.0; data.voters];
+        complete(data, seats, &mut committee, &mut loads);
+        Ok(Phragmen { committee, loads })
+    }
+
+    /// The elected candidates, in the order they were elected.
+    pub fn committee(&self) -> &[usize] {
+        &self.committee
+    }
+
+    /// The load of every voter after the last candidate was elected.
+    pub fn loads(&self) -> &[f64] {
+        &self.loads
+    }
+
+    /// The highest load of any voter.
+    pub fn max_load(&self) -> f64 {
+        self.loads.iter().copied().fold(0.0, f64::max)
+    }
+}
+
+// Continue electing candidates using Phragmén's sequential method until
+// `committee` has `seats` candidates, where every voter starts with the load
+// in `loads`.
+pub(super) fn complete(data: &Binary, seats: usize, committee: &mut Vec<usize>, loads: &mut [f64]) {
+    let n = data.candidates;
+    let mut elected = vec![false; n];
+    for &c in committee.iter() {
+        elected[c] = true;
+    }
+    while committee.len() < seats {
+        // The number of voters approving of every candidate and their total
+        // load.
+        let mut approvers = vec![0; n];
+        let mut total = vec![0.0; n];
+        for (vote, &load) in data.votes.chunks_exact(n).zip(loads.iter()) {
+            for ((a, t), &v) in approvers.iter_mut().zip(&mut total).zip(vote) {
+                if v {
+                    *a += 1;
+                    *t += load;
+                }
+            }
+        }
+        let new_load = |c: usize| match approvers[c] {
+            0 => f64::INFINITY,
+            a => (1.0 + total[c]) / a as f64,
+        };
+        let mut winner: Option<usize> = None;
+        for c in (0..n).filter(|&c| !elected[c]) {
+            if winner.is_none_or(|w| new_load(c) < new_load(w) - EPSILON) {
+                winner = Some(c);
+            }
+        }
+        let winner = winner.unwrap();
+        let load = new_load(winner);
+        elected[winner] = true;
+        committee.push(winner);
+        if load.is_finite() {
+            for (vote, l) in data.votes.chunks_exact(n).zip(loads.iter_mut()) {
+                if vote[winner] {
+                    *l = load;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::VoteFormat;
+
+    #[test]
+    fn loads() {
+        let mut data = Binary::new(4);
+        for _ in 0..6 {
+            data.add(&[true, true, true, false]).unwrap();
+        }
+        for _ in 0..3 {
+            data.add(&[false, false, true, true]).unwrap();
+        }
+        // 2 is approved by everyone, so it is elected first with a load of
+        // 1/9. Then 0 would give (1 + 6/9) / 6 = 5/18 and 3 would give
+        // (1 + 3/9) / 3 = 4/9, so 0 is elected. Then 1 would give (1 + 6 * 5/18) / 6 =
+        // 4/9 as well, and the tie is broken by index.
+        let p = Phragmen::count(&data, 3).unwrap();
+        assert_eq!(p.committee(), [2, 0, 1]);
+        assert!((p.loads()[0] - 4.0 / 9.0).abs() < EPSILON);
+        assert!((p.loads()[8] - 1.0 / 9.0).abs() < EPSILON);
+        assert!((p.max_load() - 4.0 / 9.0).abs() < EPSILON);
+        assert!(Phragmen::count(&data, 5).is_err());
+    }
+
+    #[quickcheck]
+    fn loads_pay_for_committee(data: Binary, seats: usize) -> bool {
+        if data.candidates == 0 {
+            return true;
+        }
+        let seats = seats % data.candidates + 1;
+        let p = Phragmen::count(&data, seats).unwrap();
+        // Every elected candidate somebody approves of costs one unit.
+        let approved = p
+            .committee()
+            .iter()
+            .filter(|&&c| data.votes.chunks_exact(data.candidates).any(|v| v[c]))
+            .count();
+        let paid: f64 = p.loads().iter().sum();
+        (paid - approved as f64).abs() < 1e-6
+    }
+}
@@ -0,0 +1,197 @@
+use super::fptp::order_to_vote;
+use crate::formats::{
+    orders::{TiedRank, TiedRankRef},
+    toi::TiedOrdersIncomplete,
+    VoteFormat,
+};
+
+/// How positional methods score candidates which are tied in a vote.
+///
+/// A group of `t` tied candidates, directly after `s` higher ranked
+/// candidates, spans the positions `s..(s + t)`. With the points
+/// `w = [3, 2, 1, 0]` and the vote `0,{1,2},3`, the candidates 1 and 2 span
+/// the positions with 2 and 1 points and are given
+///
+/// | `TieScoring` | Points                 |
+/// |--------------|------------------------|
+/// | `Average`    | `(2 + 1) / 2 = 1.5`    |
+/// | `Max`        | `2`                    |
+/// | `Min`        | `1`                    |
+/// | `Split`      | `2 / 2 = 1`            |
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieScoring {
+    /// Every candidate gets the average of the points of the positions the
+    /// group spans. The group gets the same total as if it was not tied.
+    #[default]
+    Average,
+    /// Every candidate gets the most points of any position the group spans.
+    Max,
+    /// Every candidate gets the least points of any position the group spans.
+    Min,
+    /// The points of the first position the group spans are split equally
+    /// between the candidates.
+    Split,
+}
+
+impl TieScoring {
+    /// The points of a group of `len` candidates starting at position `start`,
+    /// multiplied by `scale`. Positions outside `weights` are worth 0 points.
+    /// `scale` has to be divisible by `len` for `Average` and `Split`.
+    fn points(self, weights: &[usize], start: usize, len: usize, scale: usize) -> Option<usize> {
+        let w = |p: usize| weights.get(p).copied().unwrap_or(0);
+        let span = start..(start + len);
+        match self {
+            TieScoring::Average => span.map(w).sum::<usize>().checked_mul(scale / len),
+            TieScoring::Max => span.map(w).max().unwrap().checked_mul(scale),
+            TieScoring::Min => span.map(w).min().unwrap().checked_mul(scale),
+            TieScoring::Split => w(start).checked_mul(scale / len),
+        }
+    }
+
+    /// The number every score has to be multiplied with to stay integers,
+    /// when counting `votes`.
+    pub(crate) fn scale<'a, I>(self, votes: I) -> Result<usize, &'static str>
+    where
+        I: IntoIterator<Item = TiedRankRef<'a>>,
+    {
+        let mut scale: usize = 1;
+        if matches!(self, TieScoring::Average | TieScoring::Split) {
+            for vote in votes {
+                for group in vote.iter_groups() {
+                    let len = group.len();
+                    scale = (scale / gcd(scale, len))
+                        .checked_mul(len)
+                        .ok_or("Integer overflow: Too many different sizes of ties")?;
+                }
+            }
+        }
+        Ok(scale)
+    }
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Count `data` with the points `weights`, returning the score of each
+// candidate and the factor the scores were multiplied with.
+pub(crate) fn positional_score(
+    data: &TiedOrdersIncomplete,
+    weights: &[usize],
+    tie_scoring: TieScoring,
+) -> Result<(Vec<usize>, usize), &'static str> {
+    let scale = tie_scoring.scale(data)?;
+    let mut score: Vec<usize> = vec![0; data.candidates()];
+    for vote in data {
+        let mut seen = 0;
+        for group in vote.iter_groups() {
+            let points = tie_scoring
+                .points(weights, seen, group.len(), scale)
+                .ok_or("Integer overflow: Too many points")?;
+            for &c in group {
+                score[c] =
+                    score[c].checked_add(points).ok_or("Integer overflow: Too many points")?;
+            }
+            seen += group.len();
+        }
+    }
+    Ok((score, scale))
+}
+
+/// Configuration of [`Positional`].
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionalConfig {
+    /// The points of every position, e.g. `[2, 1, 0]` for Borda count with
+    /// three candidates. Positions after `weights` give no points.
+    pub weights: Vec<usize>,
+    /// How candidates tied in a vote are scored.
+    pub tie_scoring: TieScoring,
+}
+
+/// A general positional method, where every position in a vote gives some
+/// number of points and the candidates with the most points win. Unranked
+/// candidates get no points.
+///
+/// Borda count, first-past-the-post and approval of the top `k` candidates
+/// are all special cases.
+pub struct Positional {
+    score: Vec<usize>,
+}
+
+impl Positional {
+    /// Count `data`, where `weights[i]` is the number of points for position
+    /// `i`, and positions after `weights` give no points. Tied candidates are
+    /// scored according to `tie_scoring`.
+    pub fn count(
+        data: &TiedOrdersIncomplete,
+        weights: &[usize],
+        tie_scoring: TieScoring,
+    ) -> Result<Self, &'static str> {
+        let (score, _) = positional_score(data, weights, tie_scoring)?;
+        Ok(Positional { score })
+    }
+
+    /// Count the votes in `data`, with the method configured by `config`.
+    pub fn from_config(
+        data: &TiedOrdersIncomplete,
+        config: &PositionalConfig,
+    ) -> Result<Self, &'static str> {
+        Positional::count(data, &config.weights, config.tie_scoring)
+    }
+
+    /// The score of every candidate. They may be multiplied by some constant
+    /// to avoid fractions.
+    pub fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+
+    pub fn get_order(&self) -> Vec<usize> {
+        super::get_order(&self.score, true)
+    }
+
+    pub fn as_vote(&self) -> TiedRank {
+        order_to_vote(&self.get_order())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> TiedOrdersIncomplete {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str("0,{1,2},3");
+        votes.add_from_str("{2,3},1");
+        votes
+    }
+
+    #[test]
+    fn tie_scoring() {
+        // Scores computed by hand, with the points [3, 2, 1, 0]. In the second
+        // vote 2 and 3 span the positions worth 3 and 2, while 0 is unranked.
+        let votes = example();
+        let weights = [3, 2, 1, 0];
+        let score = |t| positional_score(&votes, &weights, t).unwrap();
+        // Average: [3, 1.5 + 1, 1.5 + 2.5, 2.5]
+        assert_eq!(score(TieScoring::Average), (vec![6, 5, 8, 5], 2));
+        assert_eq!(score(TieScoring::Max), (vec![3, 3, 5, 3], 1));
+        assert_eq!(score(TieScoring::Min), (vec![3, 2, 3, 2], 1));
+        // Split: [3, 1 + 1, 1 + 1.5, 1.5]
+        assert_eq!(score(TieScoring::Split), (vec![6, 4, 5, 3], 2));
+    }
+
+    #[test]
+    fn lcm_scale() {
+        let mut votes = TiedOrdersIncomplete::new(5);
+        votes.add_from_str("{0,1},{2,3,4}");
+        let (score, scale) =
+            positional_score(&votes, &[4, 3, 2, 1, 0], TieScoring::Average).unwrap();
+        assert_eq!(scale, 6);
+        assert_eq!(score, vec![21, 21, 6, 6, 6]);
+    }
+}
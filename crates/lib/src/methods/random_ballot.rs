@@ -1,11 +1,14 @@
 use rand::{prelude::SliceRandom, Rng};
 use rand_distr::Uniform;
 
-use super::{get_order, RandomVotingMethod};
-use crate::formats::{
-    orders::{Rank, TiedRank},
-    soi::StrictOrdersIncomplete,
-    toi::TiedOrdersIncomplete,
+use super::{rank_score, RandomVotingMethod};
+use crate::{
+    election::Voter,
+    formats::{
+        orders::{Rank, RankRef, TiedRank},
+        soi::StrictOrdersIncomplete,
+        toi::TiedOrdersIncomplete,
+    },
 };
 
 /// Draw random votes until they create a ranking
@@ -15,6 +18,7 @@ use crate::formats::{
 /// until it has a total order of the top `positions`.
 pub struct RandomBallot {
     ranking: Rank,
+    score: Vec<usize>,
 }
 
 impl<'a> RandomVotingMethod<'a> for RandomBallot {
@@ -31,10 +35,10 @@ impl<'a> RandomVotingMethod<'a> for RandomBallot {
         debug_assert!(positions <= data.candidates);
         let mut left = positions;
         let mut order: Vec<usize> = Vec::new();
-        let mut values: Vec<usize> = (0..data.voters()).collect();
-        values.shuffle(rng);
-        'outer: for i in values {
-            let vote = data.vote_i(i);
+        let mut voters: Vec<Voter> = Voter::all(data.voters()).collect();
+        voters.shuffle(rng);
+        'outer: for voter in voters {
+            let vote = data.vote(voter);
             for v in vote {
                 let l = order.len();
                 // Quadratic, maybe bad
@@ -47,21 +51,26 @@ impl<'a> RandomVotingMethod<'a> for RandomBallot {
                 }
             }
         }
-        Ok(RandomBallot { ranking: Rank::new(data.candidates, order) })
+        let tied = vec![false; order.len().saturating_sub(1)];
+        let score = rank_score(RankRef::new(data.candidates, &order).to_tied(&tied));
+        Ok(RandomBallot { ranking: Rank::new(data.candidates, order), score })
     }
 
-    fn get_score(&self) -> &Vec<usize> {
-        unimplemented!();
+    fn get_score(&self) -> &[usize] {
+        &self.score
     }
+}
 
-    fn get_order(&self) -> Vec<usize> {
-        get_order(self.get_score(), true)
+impl RandomBallot {
+    pub fn as_vote(&self) -> Rank {
+        self.ranking.clone()
     }
 }
 
 /// Draw a single random vote
 pub struct RandomBallotSingle {
     ranking: TiedRank,
+    score: Vec<usize>,
 }
 
 impl<'a> RandomVotingMethod<'a> for RandomBallotSingle {
@@ -73,17 +82,13 @@ impl<'a> RandomVotingMethod<'a> for RandomBallotSingle {
         Self: Sized,
     {
         let _ = positions;
-        let i: usize = rng.sample(Uniform::new(0, data.voters()));
-        let vote = data.vote_i(i);
-        Ok(RandomBallotSingle { ranking: vote.owned() })
+        let voter = Voter(rng.sample(Uniform::new(0, data.voters())));
+        let vote = data.vote(voter);
+        Ok(RandomBallotSingle { ranking: vote.owned(), score: rank_score(vote) })
     }
 
-    fn get_score(&self) -> &Vec<usize> {
-        unimplemented!();
-    }
-
-    fn get_order(&self) -> Vec<usize> {
-        get_order(self.get_score(), true)
+    fn get_score(&self) -> &[usize] {
+        &self.score
     }
 }
 
@@ -92,3 +97,28 @@ impl RandomBallotSingle {
         self.ranking.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::election::Rank as Position;
+
+    #[test]
+    fn ranks_follow_ballot() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut votes = StrictOrdersIncomplete::new(4);
+        votes.add_from_str("2,0");
+        votes.add_from_str("2,0,3");
+        let ballot = RandomBallot::count(&votes, &mut rng, 3).unwrap();
+        assert_eq!(ballot.as_vote().as_ref().order(), [2, 0, 3]);
+        assert_eq!(ballot.get_order(), [1, 3, 0, 2]);
+
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str("{0,2}");
+        let single = RandomBallotSingle::count(&votes, &mut rng, 1).unwrap();
+        assert_eq!(single.ranks(), [Position(0), Position(1), Position(0)]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Score voting, also called range voting, where the candidate with the
+//! highest total rating wins.
+
+use crate::{
+    formats::{orders::TiedRank, Cardinal, Validated},
+    methods::VotingMethod,
+};
+
+/// The ratings allowed by [`ScoreVoting`], from `min` to `max` inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreRange {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl ScoreRange {
+    pub fn new(min: usize, max: usize) -> Result<Self, &'static str> {
+        if min > max {
+            return Err("Minimum score is larger than maximum score");
+        }
+        Ok(ScoreRange { min, max })
+    }
+
+    /// The range of the votes in `data`.
+    pub fn of(data: &Cardinal) -> Self {
+        ScoreRange { min: data.min, max: data.max }
+    }
+
+    pub fn contains(&self, rating: usize) -> bool {
+        self.min <= rating && rating <= self.max
+    }
+}
+
+/// Every candidate gets the sum of its ratings, counted from the lowest
+/// allowed rating, and the candidate with the highest total wins.
+pub struct ScoreVoting {
+    score: Vec<usize>,
+}
+
+impl<'a> VotingMethod<'a> for ScoreVoting {
+    type Format = Cardinal;
+    type Score = usize;
+
+    /// Count `data`, allowing every rating between `data.min` and `data.max`.
+    fn count(data: &Cardinal) -> Result<Self, &'static str> {
+        ScoreVoting::count_with(data, ScoreRange::of(data))
+    }
+
+    // Every rating of a valid profile is in its range.
+    fn count_validated(data: &Validated<Cardinal>) -> Result<Self, &'static str> {
+        ScoreVoting::sum(data, ScoreRange::of(data))
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl ScoreVoting {
+    /// Count `data`, returning an error if any rating is outside of `range`.
+    /// The ratings are counted from `range.min`, so it's worth no points.
+    pub fn count_with(data: &Cardinal, range: ScoreRange) -> Result<Self, &'static str> {
+        if range.min > range.max {
+            return Err("Minimum score is larger than maximum score");
+        }
+        if data.votes.iter().any(|&r| !range.contains(r)) {
+            return Err("Rating outside of the score range");
+        }
+        ScoreVoting::sum(data, range)
+    }
+
+    /// Score voting for `data`, where `sums` is the total rating of every
+    /// candidate, like [`Cardinal::column_sums`] but e.g. computed on the GPU.
+    pub fn from_sums(data: &Cardinal, mut sums: Vec<usize>) -> Result<Self, &'static str> {
+        if sums.len() != data.candidates {
+            return Err("Wrong number of sums");
+        }
+        let offset = data.voters * data.min;
+        for s in &mut sums {
+            *s = s.checked_sub(offset).ok_or("Rating outside of the score range")?;
+        }
+        Ok(ScoreVoting { score: sums })
+    }
+
+    // Count `data`, where every rating is in `range`.
+    fn sum(data: &Cardinal, range: ScoreRange) -> Result<Self, &'static str> {
+        let mut score = data.column_sums()?;
+        let offset = data.voters * range.min;
+        score.iter_mut().for_each(|s| *s -= offset);
+        Ok(ScoreVoting { score })
+    }
+
+    /// The candidates ranked by their total scores.
+    pub fn as_vote(&self) -> TiedRank {
+        TiedRank::from_scores(self.score.len(), &self.score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::formats::{Validate, VoteFormat};
+
+    #[test]
+    fn ranges() {
+        let mut votes = Cardinal::new(3, 1, 5);
+        votes.add(&[5, 1, 3]).unwrap();
+        votes.add(&[2, 4, 4]).unwrap();
+        let score = ScoreVoting::count(&votes).unwrap();
+        assert_eq!(score.get_score(), &vec![5, 3, 5]);
+        assert_eq!(score.as_vote().as_ref().to_string(), "{0,2},1");
+        let wider = ScoreVoting::count_with(&votes, ScoreRange::new(0, 10).unwrap()).unwrap();
+        assert_eq!(wider.get_score(), &vec![7, 5, 7]);
+        assert!(ScoreVoting::count_with(&votes, ScoreRange::new(2, 5).unwrap()).is_err());
+        assert!(ScoreRange::new(3, 2).is_err());
+    }
+
+    #[quickcheck]
+    fn shifting_ratings(votes: Cardinal) -> bool {
+        // Adding a constant to every rating, and to the range, changes
+        // nothing.
+        let mut shifted = votes.clone();
+        shifted.add_constant(3);
+        let a = ScoreVoting::count(&votes).unwrap();
+        let b = ScoreVoting::count(&shifted).unwrap();
+        a.get_score() == b.get_score()
+    }
+
+    #[quickcheck]
+    fn validated_same_score(votes: Cardinal) -> bool {
+        let a = ScoreVoting::count(&votes).unwrap();
+        let b = ScoreVoting::count_validated(&votes.validate().unwrap()).unwrap();
+        a.get_score() == b.get_score()
+    }
+}
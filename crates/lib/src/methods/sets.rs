@@ -0,0 +1,192 @@
+//! Sets of candidates which can't be beaten by anyone outside the set.
+//!
+//! Both sets are computed from the strongly connected components of a graph of
+//! pairwise wins, found with [`tarjan`](crate::tarjan::tarjan). A component
+//! which no candidate outside it has an edge to dominates every other
+//! candidate. If there is a Condorcet winner, both sets contain only it.
+//!
+//! ```
+//! use votery::{
+//!     methods::sets::{condorcet_cycles, schwartz_set, smith_set},
+//!     tournament::Tournament,
+//! };
+//!
+//! // 0, 1 and 2 form a cycle, and each of them beats 3.
+//! let edges = [(0, 1, 2), (1, 2, 2), (2, 0, 2), (0, 3, 1), (1, 3, 1), (2, 3, 1)];
+//! let t = Tournament::from_edges(&edges).unwrap();
+//! assert_eq!(smith_set(&t), vec![0, 1, 2]);
+//! assert_eq!(schwartz_set(&t), vec![0, 1, 2]);
+//! assert_eq!(condorcet_cycles(&t), vec![vec![0, 1, 2]]);
+//! ```
+
+use crate::{
+    formats::orders::{TiedRank, TiedRankRef},
+    tarjan::tarjan,
+    tournament::Tournament,
+};
+
+/// Which set of candidates is still in contention, e.g. when condensing a
+/// ranking with [`condense`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Contention {
+    /// The candidates in the [`smith_set`].
+    #[default]
+    Smith,
+    /// The candidates in the [`schwartz_set`].
+    Schwartz,
+}
+
+impl Contention {
+    /// The candidates in contention in `t`, sorted by candidate.
+    pub fn set(&self, t: &Tournament) -> Vec<usize> {
+        match self {
+            Contention::Smith => smith_set(t),
+            Contention::Schwartz => schwartz_set(t),
+        }
+    }
+}
+
+/// The Smith set: the smallest set of candidates where every candidate in the
+/// set beats every candidate outside it. Sorted by candidate.
+pub fn smith_set(t: &Tournament) -> Vec<usize> {
+    // `a` has an edge to `b` if `a` isn't beaten by `b`, so every pair of
+    // candidates is connected and exactly one component is undominated.
+    let c = t.candidates();
+    let mut edges = vec![false; c * c];
+    for a in 0..c {
+        for b in 0..c {
+            edges[a * c + b] = a != b && !t.beats(b, a);
+        }
+    }
+    undominated(c, &edges)
+}
+
+/// The Schwartz set: the union of every smallest set of candidates where no
+/// candidate in the set is beaten by a candidate outside it. A subset of the
+/// [`smith_set`], which only differs from it when there are pairwise ties.
+/// Sorted by candidate.
+pub fn schwartz_set(t: &Tournament) -> Vec<usize> {
+    undominated(t.candidates(), &t.beats_matrix())
+}
+
+/// Every Condorcet cycle: the strongly connected components of more than one
+/// candidate in the graph of which candidates beat each other. Each cycle is
+/// sorted by candidate, and the cycles by their first candidate.
+pub fn condorcet_cycles(t: &Tournament) -> Vec<Vec<usize>> {
+    let mut cycles: Vec<Vec<usize>> = tarjan(t.candidates(), &t.beats_matrix())
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .collect();
+    cycles.iter_mut().for_each(|cycle| cycle.sort_unstable());
+    cycles.sort_unstable();
+    cycles
+}
+
+/// Condense `ranking` into two levels: the candidates in `contenders` first,
+/// in the same order as in `ranking`, and then every other candidate tied
+/// last. `ranking` has to rank every candidate.
+///
+/// ```
+/// use votery::{formats::orders::TiedRank, methods::sets::condense};
+///
+/// let ranking = TiedRank::parse_vote(5, "3,2,{0,4},1").unwrap();
+/// let condensed = condense(ranking.as_ref(), &[0, 2, 4]);
+/// assert_eq!(condensed.as_ref().to_string(), "2,{0,4},{1,3}");
+/// ```
+pub fn condense(ranking: TiedRankRef, contenders: &[usize]) -> TiedRank {
+    debug_assert!(ranking.len() == ranking.candidates);
+    let mut order = Vec::with_capacity(ranking.candidates);
+    let mut tied = Vec::with_capacity(ranking.candidates);
+    let mut add_group = |group: &mut dyn Iterator<Item = usize>| {
+        let start = order.len();
+        order.extend(group);
+        if order.len() > start {
+            tied.extend((start + 1..order.len()).map(|_| true));
+            tied.push(false);
+        }
+    };
+    for group in ranking.iter_groups() {
+        add_group(&mut group.iter().copied().filter(|c| contenders.contains(c)));
+    }
+    add_group(&mut (0..ranking.candidates).filter(|c| !contenders.contains(c)));
+    tied.pop();
+    TiedRank::new(ranking.candidates, order, tied)
+}
+
+// The union of the components of `edges` which no vertex outside the
+// component has an edge to.
+fn undominated(vertices: usize, edges: &Vec<bool>) -> Vec<usize> {
+    let components = tarjan(vertices, edges);
+    let mut component_of = vec![0; vertices];
+    for (i, component) in components.iter().enumerate() {
+        component.iter().for_each(|&v| component_of[v] = i);
+    }
+    let mut set: Vec<usize> = components
+        .iter()
+        .filter(|component| {
+            component.iter().all(|&b| {
+                (0..vertices)
+                    .all(|a| component_of[a] == component_of[b] || !edges[a * vertices + b])
+            })
+        })
+        .flatten()
+        .copied()
+        .collect();
+    set.sort_unstable();
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+    use crate::formats::toi::TiedOrdersIncomplete;
+
+    #[test]
+    fn condorcet_winner() {
+        let t = Tournament::from_edges(&[(0, 1, 2), (2, 0, 3), (2, 1, 2)]).unwrap();
+        assert_eq!(smith_set(&t), vec![2]);
+        assert_eq!(schwartz_set(&t), vec![2]);
+        assert!(condorcet_cycles(&t).is_empty());
+    }
+
+    #[test]
+    fn ties() {
+        // 0 and 1 are tied and both beat 2, 2 and 3 are tied.
+        let t = Tournament::from_edges(&[(0, 2, 1), (1, 2, 1), (0, 3, 1), (1, 3, 1)]).unwrap();
+        assert_eq!(smith_set(&t), vec![0, 1]);
+        assert_eq!(schwartz_set(&t), vec![0, 1]);
+        // 0 beats 1, which is tied with 2, which beats 0.
+        let t = Tournament::from_edges(&[(0, 1, 1), (2, 0, 1)]).unwrap();
+        assert_eq!(smith_set(&t), vec![0, 1, 2]);
+        assert_eq!(schwartz_set(&t), vec![2]);
+        assert!(condorcet_cycles(&t).is_empty());
+        assert!(smith_set(&Tournament::new(0)).is_empty());
+    }
+
+    #[test]
+    fn condense_every_candidate() {
+        let ranking = TiedRank::parse_vote(3, "{1,2},0").unwrap();
+        assert_eq!(condense(ranking.as_ref(), &[0, 1, 2]), ranking);
+        assert_eq!(condense(ranking.as_ref(), &[]).as_ref().to_string(), "{0,1,2}");
+        assert_eq!(condense(ranking.as_ref(), &[0]).as_ref().to_string(), "0,{1,2}");
+    }
+
+    #[quickcheck]
+    fn dominant(votes: TiedOrdersIncomplete) -> bool {
+        let t = votes.pairwise_matrix().unwrap().tournament().clone();
+        let smith = smith_set(&t);
+        let schwartz = schwartz_set(&t);
+        let n = t.candidates();
+        let outside = |set: &[usize], b: usize| !set.contains(&b);
+        (n == 0 || !smith.is_empty())
+            && schwartz.iter().all(|c| smith.contains(c))
+            && smith.iter().all(|&a| (0..n).filter(|&b| outside(&smith, b)).all(|b| t.beats(a, b)))
+            && schwartz
+                .iter()
+                .all(|&a| (0..n).filter(|&b| outside(&schwartz, b)).all(|b| !t.beats(b, a)))
+            && t.condorcet_winner().is_none_or(|w| smith == [w])
+    }
+}
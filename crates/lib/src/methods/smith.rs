@@ -0,0 +1,145 @@
+//! Condorcet-compliant variants of other methods, which only count the
+//! candidates in the [Smith set](super::sets::smith_set).
+//!
+//! Every candidate outside the Smith set is removed from the votes before the
+//! base method counts them, so the winner is always in the Smith set, and
+//! always the Condorcet winner if there is one. The candidates outside the
+//! Smith set are tied below every candidate in it.
+
+use super::{fptp::order_to_vote, sets::smith_set, Irv};
+use crate::{
+    formats::{orders::TiedRank, toi::TiedOrdersIncomplete, VoteFormat},
+    methods::VotingMethod,
+};
+
+// The candidates in the Smith set of `data`, and `data` with every other
+// candidate removed. Candidate `i` of the votes is `smith[i]`.
+fn restrict_to_smith(
+    data: &TiedOrdersIncomplete,
+) -> Result<(Vec<usize>, TiedOrdersIncomplete), &'static str> {
+    let smith = smith_set(data.pairwise_matrix()?.tournament());
+    let votes = data.restrict(&smith)?;
+    Ok((smith, votes))
+}
+
+// The score of every candidate of `candidates`, where the candidates in
+// `smith` get their score in `restricted` plus one and the others get 0.
+fn smith_score(candidates: usize, smith: &[usize], restricted: &[usize]) -> Vec<usize> {
+    let mut score = vec![0; candidates];
+    for (&c, &s) in smith.iter().zip(restricted) {
+        score[c] = s + 1;
+    }
+    score
+}
+
+/// Smith//IRV: [`Irv`] counted with only the candidates in the Smith set.
+pub struct SmithIrv {
+    score: Vec<usize>,
+}
+
+impl<'a> VotingMethod<'a> for SmithIrv {
+    type Format = TiedOrdersIncomplete;
+    type Score = usize;
+
+    fn count(data: &TiedOrdersIncomplete) -> Result<Self, &'static str> {
+        let (smith, votes) = restrict_to_smith(data)?;
+        let irv = Irv::count(&votes)?;
+        Ok(SmithIrv { score: smith_score(data.candidates(), &smith, irv.get_score()) })
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl SmithIrv {
+    pub fn as_vote(&self) -> TiedRank {
+        let order = self.get_order();
+        order_to_vote(&order)
+    }
+}
+
+/// Smith//Minimax: the candidates in the Smith set are ranked by their largest
+/// pairwise defeat against another candidate in the Smith set, measured in
+/// margins, where a smaller defeat is better.
+pub struct SmithMinimax {
+    score: Vec<usize>,
+}
+
+impl<'a> VotingMethod<'a> for SmithMinimax {
+    type Format = TiedOrdersIncomplete;
+    type Score = usize;
+
+    fn count(data: &TiedOrdersIncomplete) -> Result<Self, &'static str> {
+        let (smith, votes) = restrict_to_smith(data)?;
+        let matrix = votes.pairwise_matrix()?;
+        let n = matrix.candidates();
+        // No defeat can be larger than the number of voters, so a larger score
+        // is a smaller defeat.
+        let restricted: Vec<usize> = (0..n)
+            .map(|a| {
+                let defeat = (0..n).map(|b| matrix.margin(b, a).max(0) as usize).max();
+                votes.voters() - defeat.unwrap_or(0)
+            })
+            .collect();
+        Ok(SmithMinimax { score: smith_score(data.candidates(), &smith, &restricted) })
+    }
+
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl SmithMinimax {
+    pub fn as_vote(&self) -> TiedRank {
+        let order = self.get_order();
+        order_to_vote(&order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    // 0, 1 and 2 form a cycle which beats 3, and IRV elects 1.
+    fn cycle() -> TiedOrdersIncomplete {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str_i("3,0,1,2", 4);
+        votes.add_from_str_i("1,2,0,3", 4);
+        votes.add_from_str_i("2,0,1,3", 3);
+        votes.add_from_str_i("0,1,2,3", 2);
+        votes
+    }
+
+    #[test]
+    fn smith_irv() {
+        let votes = cycle();
+        assert_eq!(Irv::count(&votes).unwrap().as_vote().as_ref().winners(), [1]);
+        // Without 3, 2 is eliminated first and its votes go to 0.
+        let smith_irv = SmithIrv::count(&votes).unwrap();
+        assert_eq!(smith_irv.get_score()[3], 0);
+        assert_eq!(smith_irv.as_vote().as_ref().to_string(), "0,1,2,3");
+    }
+
+    #[test]
+    fn smith_minimax() {
+        let votes = cycle();
+        // 0 loses to 2 by 1, 1 loses to 0 by 5 and 2 loses to 1 by 7.
+        let minimax = SmithMinimax::count(&votes).unwrap();
+        assert_eq!(minimax.get_score(), [13, 9, 7, 0]);
+        assert_eq!(minimax.as_vote().as_ref().to_string(), "0,1,2,3");
+    }
+
+    #[quickcheck]
+    fn wins_in_smith_set(votes: TiedOrdersIncomplete) -> bool {
+        let smith = smith_set(votes.pairwise_matrix().unwrap().tournament());
+        let in_smith = |v: TiedRank| v.as_ref().winners().iter().all(|w| smith.contains(w));
+        if votes.candidates() == 0 {
+            return true;
+        }
+        in_smith(SmithIrv::count(&votes).unwrap().as_vote())
+            && in_smith(SmithMinimax::count(&votes).unwrap().as_vote())
+    }
+}
@@ -1,13 +1,33 @@
 use std::cmp::Ordering;
 
+use super::{rank_score, ScoreVoting};
 use crate::{
-    formats::{orders::TiedRank, Cardinal, VoteFormat},
+    formats::{orders::TiedRank, Cardinal},
     methods::VotingMethod,
+    profile,
 };
 /// STAR (Score Then Automatic Runoff) voting is a single winner protocol.
 /// Ties are resolved according to the "Official Tiebreaker Protocol" described at https://www.starvoting.org/ties
+///
+/// The scoring round is counted like [`ScoreVoting`].
 pub struct Star {
-    score: TiedRank,
+    rank: TiedRank,
+    // Derived from `rank`, so the winner of the runoff has the highest score.
+    score: Vec<usize>,
+    totals: Vec<usize>,
+    runoff: Option<Runoff>,
+}
+
+/// The runoff round of [`Star`], between the two finalists of the scoring
+/// round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Runoff {
+    /// The two candidates with the highest total scores, after tiebreaking.
+    pub finalists: [usize; 2],
+    /// The number of voters who rated each finalist higher than the other.
+    pub preferred: [usize; 2],
+    /// The number of voters who rated both finalists the same.
+    pub tied: usize,
 }
 
 // We can break ties by...
@@ -65,19 +85,52 @@ fn rank_by_specific(v: &[usize], data: &Cardinal, rating: usize) -> TiedRank {
     TiedRank::from_score(data.candidates, v.to_vec(), &mut count)
 }
 
-enum TieBreaker {
+/// A way to break ties between candidates in the scoring round of [`Star`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieBreaker {
+    /// Keep those that won the most pairwise matchups against the other tied
+    /// candidates.
     Matchups,
+    /// Keep those that got the most max ratings.
     Max,
+    /// Remove those that got the most min ratings.
     Min,
+    /// Choose randomly. This is not handled by [`Star`], so the tie remains.
     Random,
 }
 
-// The "Official Tiebreaker Protocol" for the scoring round of star voting.
+/// Configuration of [`Star`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StarConfig {
+    /// The tiebreakers used in the scoring round, tried in order. When a
+    /// tiebreaker eliminates a candidate we start over from the first one.
+    pub tiebreakers: Vec<TieBreaker>,
+}
+
+impl Default for StarConfig {
+    /// The "Official Tiebreaker Protocol".
+    fn default() -> Self {
+        StarConfig { tiebreakers: vec![TieBreaker::Matchups, TieBreaker::Max, TieBreaker::Min] }
+    }
+}
+
+// Tiebreak the scoring round of star voting using `tiebreakers`.
 // We tiebreak `ranking` until it is well defined which ones are ranked better
 // than `goal_len`. Returns `true` if it manages to tiebreak, else `false`.
-fn tiebreak_scoring_official(ranking: &mut TiedRank, goal_len: usize, data: &Cardinal) -> bool {
-    let mut tiebreaker = TieBreaker::Matchups;
+fn tiebreak_scoring(
+    ranking: &mut TiedRank,
+    goal_len: usize,
+    data: &Cardinal,
+    tiebreakers: &[TieBreaker],
+) -> bool {
+    let mut i = 0;
     loop {
+        let tiebreaker = match tiebreakers.get(i) {
+            Some(t) => *t,
+            None => return false,
+        };
         // We will only tiebreak those that are tied, who would change
         // which candidates are ranked better than `goal_len`.
         let (order_slice, tied_slice) = ranking.top_n_threshold(goal_len);
@@ -108,106 +161,141 @@ fn tiebreak_scoring_official(ranking: &mut TiedRank, goal_len: usize, data: &Car
         }
         let change = ranking.len().cmp(&before_len);
 
-        // We see in this "transition diagram" of `tiebreaker` that this loop
-        // is finite, as `change` can only be "Less" a finite number of times.
-        tiebreaker = match (change, tiebreaker) {
-            (Ordering::Equal, TieBreaker::Matchups) => TieBreaker::Max,
-            (Ordering::Equal, TieBreaker::Max) => TieBreaker::Min,
-            (Ordering::Equal, TieBreaker::Min) => TieBreaker::Random,
-            (Ordering::Equal, TieBreaker::Random) => unreachable!(),
-            (Ordering::Less, _) => TieBreaker::Matchups,
-            (Ordering::Greater, _) => unreachable!(),
+        // This loop is finite, as `change` can only be "Less" a finite number
+        // of times.
+        i = match change {
+            Ordering::Equal => i + 1,
+            Ordering::Less => 0,
+            Ordering::Greater => unreachable!(),
         }
     }
 }
 
-// Get a ranking of the candidates sorted by their total score
-fn score_ranking(data: &Cardinal) -> TiedRank {
-    if data.candidates < 2 {
-        return TiedRank::new_tied(data.candidates);
-    }
-    let mut sum = vec![0; data.candidates()];
-    for vote in data.iter() {
-        for i in 0..data.candidates {
-            sum[i] += vote[i];
-        }
-    }
-    TiedRank::from_scores(data.candidates, &sum)
-}
-
-// Return a comparison between `a` and `b`, a "greater" result means `a` has a
-// better rank.
-fn runoff_round(a: usize, b: usize, data: &Cardinal) -> Ordering {
+// Count the runoff between `a` and `b`, and compare them. A "greater" result
+// means `a` has a better rank.
+fn runoff_round(a: usize, b: usize, data: &Cardinal) -> (Runoff, Ordering) {
     let mut matrix = [0; 4];
     data.fill_preference_matrix(&[a, b], &mut matrix);
     let a_v = matrix[1];
     let b_v = matrix[2];
-    a_v.cmp(&b_v)
+    let runoff = Runoff { finalists: [a, b], preferred: [a_v, b_v], tied: data.voters - a_v - b_v };
+    let order = a_v
+        .cmp(&b_v)
         .then_with(|| data.compare(a, b))
-        .then_with(|| data.compare_specific(a, b, data.max))
+        .then_with(|| data.compare_specific(a, b, data.max));
+    (runoff, order)
 }
 
 impl<'a> VotingMethod<'a> for Star {
     type Format = Cardinal;
+    type Score = usize;
 
     fn count(data: &Cardinal) -> Result<Self, &'static str> {
-        if data.candidates < 2 {
-            return Ok(Star { score: TiedRank::new_tied(data.candidates) });
-        }
+        Star::from_config(data, &StarConfig::default())
+    }
 
+    fn get_score(&self) -> &[usize] {
+        &self.score
+    }
+}
+
+impl Star {
+    /// Count the votes in `data`, with the method configured by `config`.
+    pub fn from_config(data: &Cardinal, config: &StarConfig) -> Result<Self, &'static str> {
         // The Scoring Round
-        let mut v = score_ranking(data);
-        let found_top_two = tiebreak_scoring_official(&mut v, 2, data);
+        let totals = ScoreVoting::count(data)?.get_score().to_vec();
+        if data.candidates < 2 {
+            return Ok(Star::new(TiedRank::new_tied(data.candidates), totals, None));
+        }
+        let mut v = TiedRank::from_scores(data.candidates, &totals);
+        let found_top_two =
+            profile::phase("tie-break", || tiebreak_scoring(&mut v, 2, data, &config.tiebreakers));
 
         // We return if the scoring round didn't find top 2.
         if !found_top_two {
             v.make_complete(false);
-            return Ok(Star { score: v });
+            return Ok(Star::new(v, totals, None));
         }
         let a = v.order[0];
         let b = v.order[1];
 
         // The Runoff Round
-        let mut rank = match runoff_round(a, b, data) {
+        let (runoff, order) = runoff_round(a, b, data);
+        let mut rank = match order {
             Ordering::Less => TiedRank::new(data.candidates, vec![b, a], vec![false]),
             Ordering::Equal => TiedRank::new(data.candidates, vec![a, b], vec![true]),
             Ordering::Greater => TiedRank::new(data.candidates, vec![a, b], vec![false]),
         };
         rank.make_complete(false);
 
-        Ok(Star { score: rank })
+        Ok(Star::new(rank, totals, Some(runoff)))
     }
 
-    fn get_score(&self) -> &Vec<usize> {
-        // TODO: fix
-        &self.score.order
+    fn new(rank: TiedRank, totals: Vec<usize>, runoff: Option<Runoff>) -> Self {
+        let score = rank_score(rank.as_ref());
+        Star { rank, score, totals, runoff }
+    }
+
+    /// The total score of every candidate in the scoring round, counted like
+    /// [`ScoreVoting`].
+    pub fn totals(&self) -> &[usize] {
+        &self.totals
+    }
+
+    /// The runoff round, if the scoring round found two finalists.
+    pub fn runoff(&self) -> Option<&Runoff> {
+        self.runoff.as_ref()
     }
-}
 
-impl Star {
     pub fn as_vote(&self) -> TiedRank {
-        self.score.clone()
+        self.rank.clone()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::formats::VoteFormat;
 
     #[test]
     fn simple_example() {
-        let mut votes = Cardinal::new(4,0,4);
+        let mut votes = Cardinal::new(4, 0, 4);
         votes.add(&[1, 3, 2, 4]).unwrap();
         votes.add(&[3, 1, 1, 3]).unwrap();
         votes.add(&[0, 2, 1, 2]).unwrap();
         votes.add(&[2, 4, 2, 2]).unwrap();
         // Scoring round should have 1 and 3 as the candidates.
-        // Then 3 is preferred on two ballots, tied on one and not preferred on one, so it should win.
-        let res = Star::count(&votes).unwrap().as_vote();
-        let correct_winner = match res.as_ref().winners() {
+        // Then 3 is preferred on two ballots, tied on one and not preferred on one, so
+        // it should win.
+        let star = Star::count(&votes).unwrap();
+        let correct_winner = match star.as_vote().as_ref().winners() {
             &[win] => win == 3,
             _ => false,
         };
         assert!(correct_winner);
+        assert_eq!(star.totals(), [6, 10, 6, 11]);
+        let runoff = Runoff { finalists: [3, 1], preferred: [2, 1], tied: 1 };
+        assert_eq!(star.runoff(), Some(&runoff));
+        // The score follows the result of the runoff, not the scoring round.
+        assert_eq!(star.get_order(), [2, 1, 2, 0]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn no_tiebreakers() {
+        let mut votes = Cardinal::new(3, 0, 2);
+        votes.add(&[2, 0, 1]).unwrap();
+        votes.add(&[0, 2, 0]).unwrap();
+        votes.add(&[0, 0, 1]).unwrap();
+        // Every candidate has the same score, so without tiebreakers we can't
+        // find a winner.
+        let config = StarConfig { tiebreakers: Vec::new() };
+        let star = Star::from_config(&votes, &config).unwrap();
+        assert_eq!(star.as_vote().as_ref().winners().len(), 3);
+        assert_eq!(star.runoff(), None);
+
+        // The matchups break the tie
+        let res = Star::count(&votes).unwrap().as_vote();
+        assert!(res.as_ref().winners().len() < 3);
+        assert_eq!(star.get_order(), [0, 0, 0]);
+    }
+}
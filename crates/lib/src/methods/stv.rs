@@ -0,0 +1,299 @@
+use crate::{
+    formats::{orders::TiedRankRef, toi::TiedOrdersIncomplete, VoteFormat},
+    profile,
+};
+
+// Tallies closer than this are treated as equal, to not depend on rounding
+// errors of the fractional transfers.
+const EPSILON: f64 = 1e-9;
+
+/// The number of votes a candidate needs to be elected in [`Stv`], where `V`
+/// is the number of votes and `S` the number of seats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Quota {
+    /// `floor(V / (S + 1)) + 1`, the smallest quota which at most `S`
+    /// candidates can reach.
+    #[default]
+    Droop,
+    /// `V / S`
+    Hare,
+}
+
+impl Quota {
+    /// The quota of `votes` votes and `seats` seats.
+    pub fn value(&self, votes: usize, seats: usize) -> f64 {
+        debug_assert!(seats != 0);
+        match self {
+            Quota::Droop => (votes / (seats + 1) + 1) as f64,
+            Quota::Hare => votes as f64 / seats as f64,
+        }
+    }
+}
+
+/// Configuration of [`Stv`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StvConfig {
+    /// The number of candidates to elect.
+    pub seats: usize,
+    pub quota: Quota,
+}
+
+impl StvConfig {
+    /// Elect `seats` candidates with the [`Quota::Droop`] quota.
+    pub fn new(seats: usize) -> Self {
+        StvConfig { seats, quota: Quota::default() }
+    }
+}
+
+/// A round of [`Stv`], where either some candidates reached the quota, or a
+/// candidate was eliminated.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StvRound {
+    /// The votes of every candidate at the start of the round. Candidates
+    /// which were elected or eliminated in an earlier round have none.
+    pub tallies: Vec<f64>,
+    /// The votes which no longer count for any candidate, because every
+    /// candidate they rank was elected or eliminated, or because they were
+    /// spent on electing a candidate.
+    pub exhausted: f64,
+    /// The candidates elected this round, with the most votes first. Their
+    /// surplus is transferred before the next round.
+    pub elected: Vec<usize>,
+    /// The candidate eliminated this round, whose votes are transferred
+    /// before the next round.
+    pub eliminated: Option<usize>,
+}
+
+/// Single transferable vote (STV), electing `seats` candidates.
+///
+/// Every round, the candidates who reach the [`Quota`] are elected, and the
+/// votes they have beyond it, their surplus, are transferred to the next
+/// candidate on each vote using the Gregory method: every vote counting for an
+/// elected candidate continues with the fraction `surplus / tally` of its
+/// value. If no candidate reaches the quota, the candidate with the fewest
+/// votes is eliminated and its votes are transferred at their current value.
+/// When only as many candidates remain as there are seats left, they are all
+/// elected.
+///
+/// If multiple candidates are tied first in a vote, its value is split equally
+/// between them. Ties for the fewest votes are broken by the tallies of the
+/// earliest round where they differ, and then by eliminating the candidate
+/// with the highest index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stv {
+    elected: Vec<usize>,
+    rounds: Vec<StvRound>,
+    quota: f64,
+}
+
+impl Stv {
+    pub fn count(
+        data: &TiedOrdersIncomplete,
+        seats: usize,
+        quota: Quota,
+    ) -> Result<Self, &'static str> {
+        let n = data.candidates();
+        if seats == 0 {
+            return Err("Need at least one seat");
+        }
+        if seats > n {
+            return Err("More seats than candidates");
+        }
+        let quota = quota.value(data.voters(), seats);
+        let mut weights = vec![1.0; data.voters()];
+        let mut continuing = vec![true; n];
+        let mut elected = Vec::with_capacity(seats);
+        let mut rounds: Vec<StvRound> = Vec::new();
+        while elected.len() < seats {
+            profile::phase("round", || {
+                let mut tallies = vec![0.0; n];
+                let mut exhausted = data.voters() as f64;
+                for (vote, &w) in data.into_iter().zip(&weights) {
+                    if let Some(top) = top_group(vote, &continuing) {
+                        let share = w / top.len() as f64;
+                        for c in top {
+                            tallies[c] += share;
+                        }
+                        exhausted -= w;
+                    }
+                }
+                let mut remaining: Vec<usize> = (0..n).filter(|&c| continuing[c]).collect();
+                remaining.sort_by(|&a, &b| tallies[b].total_cmp(&tallies[a]));
+                let mut round =
+                    StvRound { tallies, exhausted, elected: Vec::new(), eliminated: None };
+
+                if remaining.len() <= seats - elected.len() {
+                    round.elected = remaining.clone();
+                } else {
+                    round.elected = remaining
+                        .iter()
+                        .copied()
+                        .filter(|&c| round.tallies[c] >= quota - EPSILON)
+                        .take(seats - elected.len())
+                        .collect();
+                }
+                if round.elected.is_empty() {
+                    let loser = *remaining
+                        .iter()
+                        .min_by(|&&a, &&b| {
+                            let difference = |r: &StvRound| r.tallies[a] - r.tallies[b];
+                            let mut d = difference(&round);
+                            if d.abs() <= EPSILON {
+                                let mut history = rounds.iter().map(difference);
+                                d = history.find(|d| d.abs() > EPSILON).unwrap_or(0.0);
+                            }
+                            d.total_cmp(&0.0).then(b.cmp(&a))
+                        })
+                        .unwrap();
+                    continuing[loser] = false;
+                    round.eliminated = Some(loser);
+                } else {
+                    // Every vote counting for an elected candidate keeps the
+                    // fraction of its share which is surplus.
+                    let mut factors = vec![1.0; n];
+                    for &c in &round.elected {
+                        let tally = round.tallies[c];
+                        factors[c] =
+                            if tally > 0.0 { ((tally - quota) / tally).max(0.0) } else { 0.0 };
+                    }
+                    for (vote, w) in data.into_iter().zip(&mut weights) {
+                        if let Some(top) = top_group(vote, &continuing) {
+                            let share = *w / top.len() as f64;
+                            *w = top.iter().map(|&c| share * factors[c]).sum();
+                        }
+                    }
+                    for &c in &round.elected {
+                        continuing[c] = false;
+                    }
+                    elected.extend_from_slice(&round.elected);
+                }
+                rounds.push(round);
+            });
+        }
+        Ok(Stv { elected, rounds, quota })
+    }
+
+    /// Count the votes in `data`, with the method configured by `config`.
+    pub fn from_config(
+        data: &TiedOrdersIncomplete,
+        config: &StvConfig,
+    ) -> Result<Self, &'static str> {
+        Stv::count(data, config.seats, config.quota)
+    }
+
+    /// The elected candidates, in the order they were elected.
+    pub fn elected(&self) -> &[usize] {
+        &self.elected
+    }
+
+    /// Every round of the count, showing how the votes were transferred.
+    pub fn rounds(&self) -> &[StvRound] {
+        &self.rounds
+    }
+
+    /// The number of votes needed to be elected.
+    pub fn quota(&self) -> f64 {
+        self.quota
+    }
+}
+
+// The highest ranked group of `vote` which has a continuing candidate, only
+// listing the continuing candidates.
+fn top_group(vote: TiedRankRef, continuing: &[bool]) -> Option<Vec<usize>> {
+    let group = vote.iter_groups().find(|g| g.iter().any(|&c| continuing[c]))?;
+    Some(group.iter().copied().filter(|&c| continuing[c]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surplus_transfer() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str_i("0,1", 12);
+        votes.add_from_str_i("2", 5);
+        votes.add_from_str_i("3,2", 4);
+        // Droop quota: 21 / 3 + 1 = 8.
+        let stv = Stv::count(&votes, 2, Quota::Droop).unwrap();
+        assert_eq!(stv.quota(), 8.0);
+        let rounds = stv.rounds();
+        assert_eq!(rounds[0].tallies, [12.0, 0.0, 5.0, 4.0]);
+        assert_eq!(rounds[0].elected, [0]);
+        // The surplus of 4 goes to 1, at a value of 1/3 per vote.
+        assert!((rounds[1].tallies[1] - 4.0).abs() < 1e-9);
+        assert_eq!(rounds[1].eliminated, Some(1));
+        // 1 has no further preferences, so its votes are exhausted, and then
+        // 3's votes elect 2.
+        assert!((rounds[2].exhausted - 8.0 - 4.0).abs() < 1e-9);
+        assert_eq!(rounds[2].eliminated, Some(3));
+        assert_eq!(stv.elected(), [0, 2]);
+    }
+
+    #[test]
+    fn hare() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,1,2", 6);
+        votes.add_from_str_i("1,2,0", 3);
+        votes.add_from_str_i("2,1,0", 3);
+        // With the Hare quota 6 only 0 is elected directly. The tie between 1
+        // and 2 is broken by index.
+        let stv = Stv::count(&votes, 2, Quota::Hare).unwrap();
+        assert_eq!(stv.quota(), 6.0);
+        assert_eq!(stv.rounds()[1].eliminated, Some(2));
+        assert_eq!(stv.elected(), [0, 1]);
+        let droop = Stv::count(&votes, 2, Quota::Droop).unwrap();
+        assert_eq!(droop.elected(), [0, 1]);
+    }
+
+    #[test]
+    fn split_ties() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("{0,1},2", 4);
+        votes.add_from_str("2");
+        let stv = Stv::count(&votes, 1, Quota::Droop).unwrap();
+        assert_eq!(stv.rounds()[0].tallies, [2.0, 2.0, 1.0]);
+        assert_eq!(stv.rounds()[0].eliminated, Some(2));
+        assert!(Stv::count(&votes, 0, Quota::Droop).is_err());
+        assert!(Stv::count(&votes, 4, Quota::Droop).is_err());
+    }
+
+    #[test]
+    fn earliest_round_breaks_ties() {
+        let mut votes = TiedOrdersIncomplete::new(5);
+        votes.add_from_str_i("0", 5);
+        votes.add_from_str_i("1", 4);
+        votes.add_from_str_i("2,1", 2);
+        votes.add_from_str_i("3,0", 1);
+        votes.add_from_str_i("3", 2);
+        votes.add_from_str_i("4", 7);
+        let stv = Stv::count(&votes, 1, Quota::Droop).unwrap();
+        let rounds = stv.rounds();
+        assert_eq!(rounds[2].tallies[..2], [6.0, 6.0]);
+        // 1 had fewer votes than 0 in the first round, but more in the second.
+        assert_eq!(rounds[0].tallies[..2], [5.0, 4.0]);
+        assert_eq!(rounds[1].tallies[..2], [5.0, 6.0]);
+        assert_eq!(rounds[2].eliminated, Some(1));
+        assert_eq!(stv.elected(), [4]);
+    }
+
+    #[test]
+    fn fewest_votes_eliminated() {
+        let mut votes = TiedOrdersIncomplete::new(4);
+        votes.add_from_str_i("0", 10);
+        votes.add_from_str_i("1", 5);
+        votes.add_from_str_i("2", 6);
+        votes.add_from_str_i("3,1", 4);
+        let stv = Stv::count(&votes, 1, Quota::Droop).unwrap();
+        let rounds = stv.rounds();
+        assert_eq!(rounds[0].eliminated, Some(3));
+        // 1 had fewer votes than 2 in the first round, but only the current
+        // tallies matter when they differ.
+        assert_eq!(rounds[1].tallies, [10.0, 9.0, 6.0, 0.0]);
+        assert_eq!(rounds[1].eliminated, Some(2));
+        assert_eq!(stv.elected(), [0]);
+    }
+}
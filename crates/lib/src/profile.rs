@@ -0,0 +1,88 @@
+//! Timings of the phases of counting an election, such as building the
+//! pairwise matrix or the rounds of a runoff.
+//!
+//! Phases are only timed with the `profiling` feature, see
+//! [`MethodConfig::rank_profiled`](crate::methods::MethodConfig::rank_profiled).
+//! Without it, timing a phase does nothing.
+
+use std::time::Duration;
+
+/// How long every timed phase took, in the order the phases ended. A phase
+/// which is repeated, e.g. every round of a runoff, is listed every time, and
+/// phases may be timed inside other phases.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    /// The total time of every phase called `name`.
+    pub fn total(&self, name: &str) -> Duration {
+        self.phases.iter().filter(|(n, _)| *n == name).map(|(_, d)| *d).sum()
+    }
+
+    /// How many times a phase called `name` was timed.
+    pub fn count(&self, name: &str) -> usize {
+        self.phases.iter().filter(|(n, _)| *n == name).count()
+    }
+}
+
+#[cfg(feature = "profiling")]
+thread_local! {
+    static CURRENT: std::cell::RefCell<Option<Timings>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Time `f` as the phase `name`, if it is called inside [`record`].
+#[cfg(feature = "profiling")]
+pub(crate) fn phase<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    if CURRENT.with_borrow(Option::is_none) {
+        return f();
+    }
+    let start = std::time::Instant::now();
+    let res = f();
+    let elapsed = start.elapsed();
+    CURRENT.with_borrow_mut(|t| {
+        if let Some(t) = t {
+            t.phases.push((name, elapsed));
+        }
+    });
+    res
+}
+
+#[cfg(not(feature = "profiling"))]
+#[inline(always)]
+pub(crate) fn phase<T>(_name: &'static str, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/// Call `f`, timing every phase it goes through on this thread.
+#[cfg(feature = "profiling")]
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Timings) {
+    let outer = CURRENT.replace(Some(Timings::default()));
+    let res = f();
+    let timings = CURRENT.replace(outer).unwrap();
+    (res, timings)
+}
+
+#[cfg(all(test, feature = "profiling"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        assert_eq!(phase("outside", || 1), 1);
+        let (res, timings) = record(|| {
+            let inner = record(|| phase("inner", || 2)).1;
+            assert_eq!(inner.count("inner"), 1);
+            phase("a", || phase("b", || 3)) + phase("b", || 4)
+        });
+        assert_eq!(res, 7);
+        let names: Vec<&str> = timings.phases().iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, ["b", "a", "b"]);
+        assert_eq!(timings.count("outside"), 0);
+    }
+}
@@ -0,0 +1,83 @@
+//! Reproducible random numbers for simulations.
+//!
+//! An [`ElectionSeed`] is a single number from which independent random
+//! number generators are derived, one [`Stream`] for every kind of randomness.
+//! Then, e.g., adding a tie-breaking method to a simulation doesn't change
+//! which profiles are generated. Seeds of sub-elections, like the trials of an
+//! experiment, are derived with [`ElectionSeed::derive`], so they can be
+//! counted in parallel and in any order.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// What the random numbers of an [`ElectionSeed`] are used for. Every stream
+/// is independent of the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// Generating votes and candidates.
+    Generation,
+    /// Breaking ties in voting methods.
+    TieBreaking,
+    /// Sampling, e.g. voters' behavior or estimating results.
+    Sampling,
+}
+
+/// A master seed, from which reproducible random number generators are
+/// derived.
+///
+/// ```
+/// use rand::Rng;
+/// use votery::seed::{ElectionSeed, Stream};
+///
+/// let seed = ElectionSeed::new(7);
+/// let a: u64 = seed.rng(Stream::Generation).gen();
+/// assert_eq!(a, seed.rng(Stream::Generation).gen::<u64>());
+/// assert_ne!(a, seed.rng(Stream::TieBreaking).gen::<u64>());
+/// assert_ne!(a, seed.derive(0).rng(Stream::Generation).gen::<u64>());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ElectionSeed(u64);
+
+impl ElectionSeed {
+    pub fn new(seed: u64) -> Self {
+        ElectionSeed(seed)
+    }
+
+    /// A seed drawn from `rng`.
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        ElectionSeed(rng.gen())
+    }
+
+    /// A seed from the operating system's source of randomness.
+    pub fn from_entropy() -> Self {
+        ElectionSeed(rand::random())
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// The seed of the sub-election `index`, e.g. a trial of an experiment.
+    /// Different indices give unrelated seeds.
+    pub fn derive(&self, index: u64) -> ElectionSeed {
+        ElectionSeed(mix(self.0 ^ mix(index)))
+    }
+
+    /// A random number generator for `stream`, always starting from the same
+    /// state for the same seed and stream.
+    pub fn rng(&self, stream: Stream) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.0);
+        rng.set_stream(stream as u64);
+        rng
+    }
+}
+
+// The SplitMix64 finalizer, which spreads every bit of `z` over the result.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
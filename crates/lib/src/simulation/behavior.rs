@@ -0,0 +1,76 @@
+use rand::Rng;
+
+use crate::formats::{
+    orders::TiedRankRef, toc::TiedOrdersComplete, toi::TiedOrdersIncomplete, VoteFormat,
+};
+
+/// How voters turn their sincere preferences into the ballots they cast.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Behavior {
+    /// Every voter ranks every candidate sincerely.
+    #[default]
+    Sincere,
+    /// Every voter only ranks its favorite candidates, bullet voting, with
+    /// `probability`, and otherwise votes sincerely. Candidates tied first are
+    /// all kept.
+    Truncate { probability: f64 },
+}
+
+impl Behavior {
+    /// The ballots cast by voters with the sincere preferences `votes`.
+    /// Returns an error if there are no candidates.
+    pub fn apply<R: Rng>(
+        &self,
+        votes: &TiedOrdersComplete,
+        rng: &mut R,
+    ) -> Result<TiedOrdersIncomplete, &'static str> {
+        if votes.candidates == 0 {
+            return Err("No candidates");
+        }
+        match *self {
+            Behavior::Sincere => votes.clone().to_toi(),
+            Behavior::Truncate { probability } => {
+                if !(0.0..=1.0).contains(&probability) {
+                    return Err("Probability has to be between 0 and 1");
+                }
+                let mut ballots = TiedOrdersIncomplete::new(votes.candidates);
+                for vote in votes {
+                    if rng.gen_bool(probability) {
+                        let top = vote.winners();
+                        let tied = &vote.tied()[..(top.len() - 1)];
+                        ballots.add(TiedRankRef::new(votes.candidates, top, tied))?;
+                    } else {
+                        ballots.add(vote)?;
+                    }
+                }
+                Ok(ballots)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn truncate() {
+        let mut votes = TiedOrdersComplete::new(3);
+        votes.add_from_str("0,1,2");
+        votes.add_from_str("{1,2},0");
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let always = Behavior::Truncate { probability: 1.0 }.apply(&votes, &mut rng).unwrap();
+        let ballots: Vec<String> = always.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(ballots, ["0", "{1,2}"]);
+        let never = Behavior::Truncate { probability: 0.0 }.apply(&votes, &mut rng).unwrap();
+        assert_eq!(never, Behavior::Sincere.apply(&votes, &mut rng).unwrap());
+        assert!(Behavior::Truncate { probability: 2.0 }.apply(&votes, &mut rng).is_err());
+        let empty = TiedOrdersComplete::new(0);
+        assert!(Behavior::Truncate { probability: 1.0 }.apply(&empty, &mut rng).is_err());
+        assert!(Behavior::Sincere.apply(&empty, &mut rng).is_err());
+    }
+}
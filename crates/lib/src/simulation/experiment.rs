@@ -0,0 +1,858 @@
+use std::io::{self, Write};
+#[cfg(feature = "serde")]
+use std::{fs, path::Path};
+
+use rand::{distributions::Uniform, prelude::Distribution, Rng};
+
+use super::behavior::Behavior;
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuTally;
+use crate::{
+    criteria::spoilers,
+    formats::{orders::TiedRank, toc::TiedOrdersComplete, toi::TiedOrdersIncomplete, Cardinal},
+    generators::spatial::{FuzzyType, Spatial, UtilityModel},
+    methods::{MethodConfig, ScoreVoting},
+    seed::{ElectionSeed, Stream},
+    spatial::{DistanceMetric, Point},
+};
+
+/// A description of a simulation: every method is run on `trials` profiles for
+/// every combination of generator and size, and measured by every metric.
+///
+/// With the `serde` feature it can be read from a TOML file:
+/// ```toml
+/// seed = 1
+/// trials = 100
+/// methods = ["Borda", { Fptp = "Random" }, { Star = { tiebreakers = ["Matchups", "Max"] } }]
+/// metrics = ["UniqueWinner", "MajorityEfficiency"]
+/// generators = ["Uniform", { Gaussian = { dimensions = 2, variance = 0.2, fuzzy = "Equal" } }]
+/// sizes = [{ voters = 100, candidates = 3 }, { voters = 1000, candidates = 5 }]
+/// behavior = { Truncate = { probability = 0.3 } }
+/// sampling = "Antithetic"
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Experiment {
+    pub generators: Vec<GeneratorConfig>,
+    pub sizes: Vec<Size>,
+    pub methods: Vec<MethodConfig>,
+    pub metrics: Vec<Metric>,
+    pub trials: usize,
+    /// How voters cast their ballots. Defaults to [`Behavior::Sincere`] if
+    /// left out. Otherwise every method also counts the sincere votes, so
+    /// that [`Metric::SincereAgreement`] can compare the two outcomes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub behavior: Behavior,
+    /// Seed of the random numbers, so that experiments are reproducible.
+    /// Every trial derives its own seed from it, and every method breaks
+    /// ties with a fresh copy of the trial's tie-breaking stream, so adding a
+    /// method doesn't change the results of the others.
+    pub seed: ElectionSeed,
+    /// How random numbers are shared between methods and trials. Defaults to
+    /// [`Sampling::CommonRandomNumbers`] if left out.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sampling: Sampling,
+}
+
+/// How the trials of an [`Experiment`] are sampled, to reduce the variance
+/// of its estimates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sampling {
+    /// Every method counts its own profiles, so the results of different
+    /// methods are independent.
+    Independent,
+    /// Every method counts the same profiles, so differences between methods
+    /// aren't hidden by differences between the profiles, see
+    /// [`ExperimentResults::differences`].
+    #[default]
+    CommonRandomNumbers,
+    /// Like [`Sampling::CommonRandomNumbers`], but trials come in pairs where
+    /// the second profile mirrors the first, see
+    /// [`GeneratorConfig::generate_antithetic`]. Every pair is one unit of
+    /// the estimates. Both profiles of a pair have the same candidates, so
+    /// this only reduces the variance which comes from where the voters are.
+    Antithetic,
+}
+
+/// The number of voters and candidates of generated profiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Size {
+    pub voters: usize,
+    pub candidates: usize,
+}
+
+/// How profiles are generated in an [`Experiment`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeneratorConfig {
+    /// Every vote is a uniformly random complete order with ties.
+    Uniform,
+    /// The [`Spatial`] model with normally distributed voters, candidates
+    /// placed uniformly at random in `[0.0, 1.0]^dimensions` and voters
+    /// centered in the middle.
+    /// Supports between 1 and
+    /// [`MAX_DIMENSIONS`](crate::spatial::MAX_DIMENSIONS) dimensions.
+    Gaussian {
+        dimensions: usize,
+        variance: f64,
+        fuzzy: FuzzyType,
+        /// Defaults to [`DistanceMetric::Euclidean`] if left out.
+        #[cfg_attr(feature = "serde", serde(default))]
+        metric: DistanceMetric,
+        /// Defaults to [`UtilityModel::Proximity`] if left out. The neutral
+        /// point of [`UtilityModel::Directional`] is the center of the voters.
+        #[cfg_attr(feature = "serde", serde(default))]
+        model: UtilityModel,
+    },
+}
+
+impl GeneratorConfig {
+    /// A short name of the generator, without its configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GeneratorConfig::Uniform => "uniform",
+            GeneratorConfig::Gaussian { .. } => "gaussian",
+        }
+    }
+
+    /// Generate a profile of `size`, using random numbers from `rng`.
+    pub fn generate<R: Rng>(
+        &self,
+        rng: &mut R,
+        size: Size,
+    ) -> Result<TiedOrdersComplete, &'static str> {
+        self.generate_with(rng, size, false)
+    }
+
+    /// The antithetic counterpart of [`generate`](GeneratorConfig::generate)
+    /// with the same random numbers: every Gaussian voter is mirrored through
+    /// the center of the voters, negating every normal draw, and every
+    /// uniform vote is reversed. The candidates are the same. Either profile
+    /// is as likely as the other, but results which depend on where the
+    /// voters are tend to be negatively correlated between the two.
+    pub fn generate_antithetic<R: Rng>(
+        &self,
+        rng: &mut R,
+        size: Size,
+    ) -> Result<TiedOrdersComplete, &'static str> {
+        self.generate_with(rng, size, true)
+    }
+
+    fn generate_with<R: Rng>(
+        &self,
+        rng: &mut R,
+        size: Size,
+        antithetic: bool,
+    ) -> Result<TiedOrdersComplete, &'static str> {
+        match *self {
+            GeneratorConfig::Uniform => {
+                let mut votes = TiedOrdersComplete::new(size.candidates);
+                votes.generate_uniform(rng, size.voters);
+                if !antithetic {
+                    return Ok(votes);
+                }
+                let mut reversed = TiedOrdersComplete::new(size.candidates);
+                for vote in &votes {
+                    let mut vote = vote.owned();
+                    vote.reverse();
+                    reversed.add(vote.as_ref());
+                }
+                Ok(reversed)
+            }
+            GeneratorConfig::Gaussian { dimensions, variance, fuzzy, metric, model } => {
+                let gaussian = match dimensions {
+                    1 => gaussian::<1, R>,
+                    2 => gaussian::<2, R>,
+                    3 => gaussian::<3, R>,
+                    4 => gaussian::<4, R>,
+                    5 => gaussian::<5, R>,
+                    6 => gaussian::<6, R>,
+                    7 => gaussian::<7, R>,
+                    8 => gaussian::<8, R>,
+                    9 => gaussian::<9, R>,
+                    10 => gaussian::<10, R>,
+                    _ => return Err("Unsupported number of dimensions"),
+                };
+                Ok(gaussian(rng, size, variance, fuzzy, metric, model, antithetic))
+            }
+        }
+    }
+}
+
+fn gaussian<const D: usize, R: Rng>(
+    rng: &mut R,
+    size: Size,
+    variance: f64,
+    fuzzy: FuzzyType,
+    metric: DistanceMetric,
+    model: UtilityModel,
+    antithetic: bool,
+) -> TiedOrdersComplete {
+    let center = Point::new([0.5; D]);
+    let mut g: Spatial<D> = Spatial::new(variance, size.voters, fuzzy);
+    g.set_metric(metric);
+    g.set_model(model);
+    g.set_neutral(center);
+    let dist = Uniform::new_inclusive(0.0, 1.0);
+    for _ in 0..size.candidates {
+        let c: [f64; D] = std::array::from_fn(|_| dist.sample(rng));
+        g.add_candidate(c);
+    }
+    let mut electorate = g.sample_electorate(rng);
+    if antithetic {
+        mirror(&mut electorate);
+    }
+    g.set_electorate(Some(electorate));
+    g.sample(rng, &center)
+}
+
+// Mirror every voter of `electorate`, placed relative to the center of the
+// voters, through that center.
+fn mirror<const D: usize>(electorate: &mut [Point<D>]) {
+    for voter in electorate {
+        voter.coords_mut().iter_mut().for_each(|x| *x = -*x);
+    }
+}
+
+/// Something measured for every method in every trial of an [`Experiment`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Metric {
+    /// 1.0 if the method has a single winner, otherwise 0.0.
+    UniqueWinner,
+    /// If a candidate is ranked first by a majority of voters, then 1.0 if the
+    /// method elects that candidate as the single winner, otherwise 0.0.
+    /// Trials without a majority winner are not counted.
+    MajorityEfficiency,
+    /// 1.0 if the method has the same winners when voters follow the
+    /// [`Behavior`] of the experiment as when they vote sincerely, otherwise
+    /// 0.0.
+    SincereAgreement,
+    /// The spoiler index of the sincere votes, i.e. the share of the
+    /// candidates which didn't win whose removal changes the winners, see
+    /// [`spoilers`]. Trials where every candidate wins are not counted.
+    SpoilerIndex,
+}
+
+impl Metric {
+    /// A short name of the metric.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::UniqueWinner => "unique_winner",
+            Metric::MajorityEfficiency => "majority_efficiency",
+            Metric::SincereAgreement => "sincere_agreement",
+            Metric::SpoilerIndex => "spoiler_index",
+        }
+    }
+
+    /// Measure the result `ranking` of counting the ballots cast by voters
+    /// with the sincere preferences `votes`, where `sincere` is the result of
+    /// counting `votes` directly with `method`. Returns `None` if the metric
+    /// is not applicable. Metrics which count other profiles use `rng`, like
+    /// [`MethodConfig::rank`].
+    pub fn measure<R: Rng>(
+        &self,
+        votes: &TiedOrdersComplete,
+        ranking: &TiedRank,
+        sincere: &TiedRank,
+        method: &MethodConfig,
+        rng: &mut R,
+    ) -> Result<Option<f64>, &'static str> {
+        let winners = ranking.as_ref().winners();
+        match self {
+            Metric::UniqueWinner => Ok(Some(if winners.len() == 1 { 1.0 } else { 0.0 })),
+            Metric::MajorityEfficiency => {
+                let Ok(votes) = votes.clone().to_toi() else { return Ok(None) };
+                let majority = votes.majority();
+                match (&majority[..], winners) {
+                    ([m], [w]) if m == w => Ok(Some(1.0)),
+                    ([_], _) => Ok(Some(0.0)),
+                    _ => Ok(None),
+                }
+            }
+            Metric::SincereAgreement => {
+                let mut a = winners.to_vec();
+                let mut b = sincere.as_ref().winners().to_vec();
+                a.sort_unstable();
+                b.sort_unstable();
+                Ok(Some(if a == b { 1.0 } else { 0.0 }))
+            }
+            Metric::SpoilerIndex => Ok(spoilers(votes, method, rng)?.index()),
+        }
+    }
+}
+
+/// How far an [`Experiment`] has come.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of finished trials
+    pub done: usize,
+    /// Total number of trials
+    pub total: usize,
+}
+
+/// The measured value of one metric for one method, generator and size.
+///
+/// The values are measured in independent units, which are single trials,
+/// or pairs of trials with [`Sampling::Antithetic`] where the unit's value is
+/// the average of the pair.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricResult {
+    /// Index of the generator in [`Experiment::generators`]
+    pub generator: usize,
+    pub size: Size,
+    /// Index of the method in [`Experiment::methods`]
+    pub method: usize,
+    pub metric: Metric,
+    /// The number of trials where the metric was applicable
+    pub samples: usize,
+    /// The number of units where the metric was applicable
+    pub units: usize,
+    /// The sum of the measured values of the units
+    pub sum: f64,
+    /// The sum of the squared measured values of the units
+    pub sum_squares: f64,
+}
+
+impl MetricResult {
+    /// The average measured value, `None` if there were no samples.
+    pub fn mean(&self) -> Option<f64> {
+        mean(self.units, self.sum)
+    }
+
+    /// The estimated standard error of [`mean`](MetricResult::mean), `None`
+    /// if there were less than two units.
+    pub fn standard_error(&self) -> Option<f64> {
+        standard_error(self.units, self.sum, self.sum_squares)
+    }
+}
+
+/// The difference between the measured values of one method and the first
+/// method of an [`Experiment`], for one metric, generator and size. Only
+/// units where the metric was applicable for both methods are counted.
+///
+/// With [`Sampling::CommonRandomNumbers`] both methods count the same
+/// profiles, so the standard error of the difference is usually much smaller
+/// than that of each method.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Difference {
+    /// Index of the generator in [`Experiment::generators`]
+    pub generator: usize,
+    pub size: Size,
+    /// Index of the method in [`Experiment::methods`], compared with the
+    /// first method.
+    pub method: usize,
+    pub metric: Metric,
+    pub units: usize,
+    /// The sum of the differences of the units
+    pub sum: f64,
+    /// The sum of the squared differences of the units
+    pub sum_squares: f64,
+}
+
+impl Difference {
+    /// The average difference, `None` if there were no units.
+    pub fn mean(&self) -> Option<f64> {
+        mean(self.units, self.sum)
+    }
+
+    /// The estimated standard error of [`mean`](Difference::mean), `None` if
+    /// there were less than two units.
+    pub fn standard_error(&self) -> Option<f64> {
+        standard_error(self.units, self.sum, self.sum_squares)
+    }
+}
+
+fn mean(units: usize, sum: f64) -> Option<f64> {
+    if units == 0 {
+        None
+    } else {
+        Some(sum / units as f64)
+    }
+}
+
+// The standard error of the mean, using the sample variance.
+fn standard_error(units: usize, sum: f64, sum_squares: f64) -> Option<f64> {
+    if units < 2 {
+        return None;
+    }
+    let n = units as f64;
+    let variance = (sum_squares - sum * sum / n) / (n - 1.0);
+    Some((variance.max(0.0) / n).sqrt())
+}
+
+/// Results of running an [`Experiment`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExperimentResults {
+    pub experiment: Experiment,
+    pub results: Vec<MetricResult>,
+    /// The difference between every other method and the first one, ordered
+    /// like `results`.
+    pub differences: Vec<Difference>,
+}
+
+impl Experiment {
+    /// Parse an experiment from a TOML string.
+    #[cfg(feature = "serde")]
+    pub fn from_toml(s: &str) -> Result<Self, &'static str> {
+        toml::from_str(s).or(Err("Could not parse experiment"))
+    }
+
+    /// Total number of trials
+    pub fn total_trials(&self) -> usize {
+        self.generators.len() * self.sizes.len() * self.trials
+    }
+
+    /// Run the experiment.
+    pub fn run(&self) -> Result<ExperimentResults, &'static str> {
+        self.run_with_progress(|_| {})
+    }
+
+    /// Run the experiment, calling `progress` after every finished trial.
+    pub fn run_with_progress<F>(&self, mut progress: F) -> Result<ExperimentResults, &'static str>
+    where
+        F: FnMut(Progress),
+    {
+        let mut results = Vec::new();
+        let mut differences = Vec::new();
+        let mut done = 0;
+        let total = self.total_trials();
+        let metrics = self.metrics.len();
+        let per_unit = if self.sampling == Sampling::Antithetic { 2 } else { 1 };
+        let tally = ScoreTally::new();
+        for (gi, generator) in self.generators.iter().enumerate() {
+            for &size in &self.sizes {
+                let first = results.len();
+                let first_difference = differences.len();
+                for method in 0..self.methods.len() {
+                    for &metric in &self.metrics {
+                        results.push(MetricResult {
+                            generator: gi,
+                            size,
+                            method,
+                            metric,
+                            samples: 0,
+                            units: 0,
+                            sum: 0.0,
+                            sum_squares: 0.0,
+                        });
+                        if method != 0 {
+                            differences.push(Difference {
+                                generator: gi,
+                                size,
+                                method,
+                                metric,
+                                units: 0,
+                                sum: 0.0,
+                                sum_squares: 0.0,
+                            });
+                        }
+                    }
+                }
+                let mut left = self.trials;
+                while left != 0 {
+                    let trials = per_unit.min(left);
+                    let seed = self.seed.derive(done as u64);
+                    // The sum and number of the applicable values of every
+                    // method and metric in this unit.
+                    let mut unit = vec![(0.0, 0); self.methods.len() * metrics];
+                    for k in 0..trials {
+                        let values = self.measure_trial(generator, size, seed, k == 1, &tally)?;
+                        for ((sum, samples), value) in unit.iter_mut().zip(values) {
+                            if let Some(v) = value {
+                                *sum += v;
+                                *samples += 1;
+                            }
+                        }
+                        done += 1;
+                        progress(Progress { done, total });
+                    }
+                    let value =
+                        |(sum, samples): (f64, usize)| (samples != 0).then(|| sum / samples as f64);
+                    for (i, &(sum, samples)) in unit.iter().enumerate() {
+                        let Some(v) = value((sum, samples)) else { continue };
+                        let result = &mut results[first + i];
+                        result.samples += samples;
+                        result.units += 1;
+                        result.sum += v;
+                        result.sum_squares += v * v;
+                        if i < metrics {
+                            continue;
+                        }
+                        if let Some(base) = value(unit[i % metrics]) {
+                            let d = v - base;
+                            let difference = &mut differences[first_difference + i - metrics];
+                            difference.units += 1;
+                            difference.sum += d;
+                            difference.sum_squares += d * d;
+                        }
+                    }
+                    left -= trials;
+                }
+            }
+        }
+        Ok(ExperimentResults { experiment: self.clone(), results, differences })
+    }
+
+    // Count a trial with every method, and measure every metric. The values
+    // are ordered by method and then by metric. With `antithetic`, the
+    // profile is the antithetic counterpart of the one `seed` gives.
+    fn measure_trial(
+        &self,
+        generator: &GeneratorConfig,
+        size: Size,
+        seed: ElectionSeed,
+        antithetic: bool,
+        tally: &ScoreTally,
+    ) -> Result<Vec<Option<f64>>, &'static str> {
+        let mut values = Vec::with_capacity(self.methods.len() * self.metrics.len());
+        let mut profile = None;
+        for (mi, method) in self.methods.iter().enumerate() {
+            let seed = match self.sampling {
+                Sampling::Independent => seed.derive(mi as u64),
+                _ => seed,
+            };
+            if profile.is_none() || self.sampling == Sampling::Independent {
+                profile = Some(self.profile(generator, size, seed, antithetic)?);
+            }
+            let (votes, ballots) = profile.as_ref().unwrap();
+            let mut rng = seed.rng(Stream::TieBreaking);
+            let sincere = match method {
+                MethodConfig::Score => tally.rank(votes.to_cardinal()?)?,
+                method => method.rank(votes, &mut rng)?,
+            };
+            let ranking = match (ballots, method) {
+                (Some(ballots), MethodConfig::Score) => {
+                    tally.rank(ballots.clone().to_cardinal()?)?
+                }
+                (Some(ballots), method) => method.rank_incomplete(ballots, &mut rng)?,
+                (None, _) => sincere.clone(),
+            };
+            for metric in &self.metrics {
+                values.push(metric.measure(votes, &ranking, &sincere, method, &mut rng)?);
+            }
+        }
+        Ok(values)
+    }
+
+    // Generate the sincere votes of a trial, and the ballots cast according to
+    // `self.behavior` if the voters aren't sincere.
+    fn profile(
+        &self,
+        generator: &GeneratorConfig,
+        size: Size,
+        seed: ElectionSeed,
+        antithetic: bool,
+    ) -> Result<(TiedOrdersComplete, Option<TiedOrdersIncomplete>), &'static str> {
+        let mut rng = seed.rng(Stream::Generation);
+        let votes = if antithetic {
+            generator.generate_antithetic(&mut rng, size)?
+        } else {
+            generator.generate(&mut rng, size)?
+        };
+        let ballots = match self.behavior {
+            Behavior::Sincere => None,
+            behavior => Some(behavior.apply(&votes, &mut seed.rng(Stream::Sampling))?),
+        };
+        Ok((votes, ballots))
+    }
+}
+
+// Counts score voting in the trials of an experiment. With the `gpu` feature
+// the ratings are summed on the GPU if there is one, as that's where most of
+// the time goes for large electorates, and otherwise on the CPU.
+struct ScoreTally {
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuTally>,
+}
+
+impl ScoreTally {
+    fn new() -> Self {
+        ScoreTally {
+            #[cfg(feature = "gpu")]
+            gpu: GpuTally::new().ok(),
+        }
+    }
+
+    // The ranking of score voting, like `MethodConfig::Score`.
+    fn rank(&self, ratings: Cardinal) -> Result<TiedRank, &'static str> {
+        #[cfg(feature = "gpu")]
+        if let Some(sums) = self.gpu.as_ref().and_then(|gpu| gpu.cardinal_sums(&ratings).ok()) {
+            return Ok(ScoreVoting::from_sums(&ratings, sums)?.as_vote());
+        }
+        Ok(ScoreVoting::from_sums(&ratings, ratings.column_sums()?)?.as_vote())
+    }
+}
+
+impl ExperimentResults {
+    /// Write the results to the directory `path` as `results.json`, and also as
+    /// `results.csv` if `csv` is true. The directory is created if it does not
+    /// exist.
+    #[cfg(feature = "serde")]
+    pub fn write_dir(&self, path: &Path, csv: bool) -> Result<(), &'static str> {
+        fs::create_dir_all(path).or(Err("Could not create results directory"))?;
+        let json =
+            fs::File::create(path.join("results.json")).or(Err("Could not create results file"))?;
+        serde_json::to_writer_pretty(json, self).or(Err("Could not write results"))?;
+        if csv {
+            let mut f = fs::File::create(path.join("results.csv"))
+                .or(Err("Could not create results file"))?;
+            self.write_csv(&mut f).or(Err("Could not write results"))?;
+        }
+        Ok(())
+    }
+
+    /// Write the results as CSV, one row per result.
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "generator,voters,candidates,method,metric,samples,mean,standard_error")?;
+        for r in &self.results {
+            let mean = r.mean().map_or(String::new(), |m| m.to_string());
+            let error = r.standard_error().map_or(String::new(), |e| e.to_string());
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{}",
+                self.experiment.generators[r.generator].name(),
+                r.size.voters,
+                r.size.candidates,
+                self.experiment.methods[r.method].name(),
+                r.metric.name(),
+                r.samples,
+                mean,
+                error
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{formats::TiePolicy, methods::StarConfig};
+
+    fn example() -> Experiment {
+        Experiment {
+            generators: vec![
+                GeneratorConfig::Uniform,
+                GeneratorConfig::Gaussian {
+                    dimensions: 2,
+                    variance: 0.2,
+                    fuzzy: FuzzyType::Equal,
+                    metric: DistanceMetric::Euclidean,
+                    model: UtilityModel::Proximity,
+                },
+            ],
+            sizes: vec![Size { voters: 11, candidates: 3 }],
+            methods: vec![
+                MethodConfig::Borda,
+                MethodConfig::Fptp(TiePolicy::Random),
+                MethodConfig::Star(StarConfig::default()),
+            ],
+            metrics: vec![Metric::UniqueWinner, Metric::MajorityEfficiency],
+            trials: 5,
+            behavior: Behavior::Sincere,
+            seed: ElectionSeed::new(1),
+            sampling: Sampling::CommonRandomNumbers,
+        }
+    }
+
+    #[test]
+    fn reproducible() {
+        let experiment = example();
+        let mut calls = 0;
+        let a = experiment.run_with_progress(|_| calls += 1).unwrap();
+        let b = experiment.run().unwrap();
+        assert_eq!(calls, experiment.total_trials());
+        assert_eq!(a, b);
+        assert_eq!(a.results.len(), 2 * 3 * 2);
+        for r in &a.results {
+            assert!(r.samples <= experiment.trials);
+        }
+    }
+
+    #[test]
+    fn independent_methods() {
+        let experiment = example();
+        let mut more = example();
+        more.methods.insert(0, MethodConfig::Irv);
+        let a = experiment.run().unwrap();
+        let b = more.run().unwrap();
+        // The same profiles are generated, and every method breaks ties the
+        // same way, so only the new method's results are added.
+        let old: Vec<_> = b.results.iter().filter(|r| r.method != 0).collect();
+        assert_eq!(old.len(), a.results.len());
+        for (r, s) in a.results.iter().zip(old) {
+            assert_eq!(MetricResult { method: r.method + 1, ..r.clone() }, *s);
+        }
+    }
+
+    #[test]
+    fn sampling() {
+        let mut experiment = example();
+        experiment.trials = 40;
+        experiment.metrics = vec![Metric::UniqueWinner];
+        experiment.generators.truncate(1);
+        let common = experiment.run().unwrap();
+        for r in &common.results {
+            assert_eq!((r.samples, r.units), (40, 40));
+            let se = r.standard_error().unwrap();
+            assert!(0.0 <= se && se <= 0.5 / 39f64.sqrt());
+        }
+        // Every method but the first is compared with it.
+        assert_eq!(common.differences.len(), 2);
+        for d in &common.differences {
+            assert_eq!(d.units, 40);
+            let r = &common.results[d.method];
+            let base = &common.results[0];
+            assert!((d.mean().unwrap() - (r.mean().unwrap() - base.mean().unwrap())).abs() < 1e-9);
+        }
+
+        experiment.sampling = Sampling::Antithetic;
+        let antithetic = experiment.run().unwrap();
+        for r in &antithetic.results {
+            assert_eq!((r.samples, r.units), (40, 20));
+        }
+        experiment.sampling = Sampling::Independent;
+        let independent = experiment.run().unwrap();
+        assert_eq!(independent, experiment.run().unwrap());
+        for d in &independent.differences {
+            assert_eq!(d.units, 40);
+        }
+    }
+
+    #[test]
+    fn antithetic_variance() {
+        // With the candidates fixed, how many voters prefer candidate 0
+        // depends on which way the voters lean, so the average over a pair
+        // varies less when the second electorate mirrors the first.
+        let new = || {
+            let mut g: Spatial<1> = Spatial::new(0.2, 9, FuzzyType::Equal);
+            g.add_candidate([0.4]);
+            g.add_candidate([0.7]);
+            g
+        };
+        let g = new();
+        let center = Point::new([0.5]);
+        let share = |electorate: Vec<Point<1>>| {
+            let mut g = new();
+            g.set_electorate(Some(electorate));
+            let votes = g.sample(&mut ChaCha8Rng::seed_from_u64(0), &center);
+            let firsts = votes.into_iter().filter(|v| v.winners() == [0]).count();
+            firsts as f64 / votes.voters() as f64
+        };
+        let pair_variance = |antithetic: bool| {
+            let pairs: Vec<f64> = (0..200)
+                .map(|i| {
+                    let a = g.sample_electorate(&mut ChaCha8Rng::seed_from_u64(i));
+                    let b = if antithetic {
+                        let mut b = a.clone();
+                        mirror(&mut b);
+                        b
+                    } else {
+                        g.sample_electorate(&mut ChaCha8Rng::seed_from_u64(i + 200))
+                    };
+                    (share(a) + share(b)) / 2.0
+                })
+                .collect();
+            let n = pairs.len() as f64;
+            let mean = pairs.iter().sum::<f64>() / n;
+            pairs.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        };
+        let (antithetic, independent) = (pair_variance(true), pair_variance(false));
+        assert!(antithetic < independent / 2.0);
+    }
+
+    #[test]
+    fn score_tally() {
+        let tally = ScoreTally::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for candidates in 1..5 {
+            let size = Size { voters: 200, candidates };
+            let votes = GeneratorConfig::Uniform.generate(&mut rng, size).unwrap();
+            let ranking = tally.rank(votes.to_cardinal().unwrap()).unwrap();
+            assert_eq!(ranking, MethodConfig::Score.rank(&votes, &mut rng).unwrap());
+        }
+    }
+
+    #[test]
+    fn truncation() {
+        let mut experiment = example();
+        experiment.metrics.push(Metric::SincereAgreement);
+        let sincere = experiment.run().unwrap();
+        for r in sincere.results.iter().filter(|r| r.metric == Metric::SincereAgreement) {
+            assert_eq!(r.mean(), Some(1.0));
+        }
+        experiment.methods[1] = MethodConfig::Fptp(TiePolicy::FirstListed);
+        experiment.behavior = Behavior::Truncate { probability: 1.0 };
+        let truncated = experiment.run().unwrap();
+        let agreement = |method: usize| -> f64 {
+            let rs = truncated
+                .results
+                .iter()
+                .filter(|r| r.method == method && r.metric == Metric::SincereAgreement);
+            rs.map(|r| r.mean().unwrap()).sum()
+        };
+        // Plurality only looks at the top choices, which bullet voters keep, as
+        // long as ties are broken the same way.
+        assert_eq!(agreement(1), 2.0);
+        assert!(agreement(0) < 2.0);
+    }
+
+    #[test]
+    fn spoiler_index() {
+        let mut experiment = example();
+        experiment.trials = 20;
+        experiment.metrics = vec![Metric::SpoilerIndex];
+        experiment.methods =
+            vec![MethodConfig::Fptp(TiePolicy::Random), MethodConfig::Irv, MethodConfig::Condorcet];
+        let results = experiment.run().unwrap();
+        for r in &results.results {
+            let mean = r.mean().unwrap();
+            assert!((0.0..=1.0).contains(&mean));
+            // A Condorcet winner still wins without any other candidate, and
+            // without one every candidate is tied.
+            if r.method == 2 {
+                assert_eq!(mean, 0.0);
+            }
+        }
+        assert!(results.results.iter().any(|r| r.method == 0 && r.mean() > Some(0.0)));
+    }
+
+    #[test]
+    fn dimensions() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let size = Size { voters: 7, candidates: 4 };
+        let generator = |dimensions| GeneratorConfig::Gaussian {
+            dimensions,
+            variance: 0.2,
+            fuzzy: FuzzyType::Equal,
+            metric: DistanceMetric::Manhattan,
+            model: UtilityModel::Directional,
+        };
+        assert_eq!(generator(10).generate(&mut rng, size).unwrap().voters(), 7);
+        assert!(generator(0).generate(&mut rng, size).is_err());
+        assert!(generator(11).generate(&mut rng, size).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn from_toml() {
+        let s = r#"
+            seed = 1
+            trials = 5
+            methods = ["Borda", { Fptp = "Random" }, { Star = { tiebreakers = ["Matchups", "Max", "Min"] } }]
+            metrics = ["UniqueWinner", "MajorityEfficiency"]
+            generators = ["Uniform", { Gaussian = { dimensions = 2, variance = 0.2, fuzzy = "Equal" } }]
+            sizes = [{ voters = 11, candidates = 3 }]
+        "#;
+        assert_eq!(Experiment::from_toml(s).unwrap(), example());
+    }
+}
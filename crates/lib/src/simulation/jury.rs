@@ -0,0 +1,111 @@
+use rand::{seq::SliceRandom, Rng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    formats::toc::TiedOrdersComplete,
+    generators::mallows::mallows,
+    methods::MethodConfig,
+    seed::{ElectionSeed, Stream},
+};
+
+/// How often a method found the truth in [`jury_accuracy`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Accuracy {
+    /// The dispersion of the Mallows noise.
+    pub phi: f64,
+    /// The number of voters in every trial.
+    pub voters: usize,
+    /// The probability that the true winner won. When several candidates tie
+    /// and the true winner is one of them, it counts as a fraction of a win.
+    pub winner: f64,
+    /// The probability that the result was exactly the true ranking, without
+    /// any ties.
+    pub ranking: f64,
+}
+
+/// Estimate how well `method` recovers a hidden true ranking of `candidates`
+/// candidates, for every combination of noise in `phis` and electorate size in
+/// `electorates`, like Condorcet's jury theorem.
+///
+/// Every trial picks a uniformly random true ranking, and lets every voter
+/// vote according to the Mallows model around it, see
+/// [`mallows`](crate::generators::mallows::mallows). The results are ordered
+/// by `phis` and then by `electorates`.
+pub fn jury_accuracy<R: Rng>(
+    method: &MethodConfig,
+    candidates: usize,
+    phis: &[f64],
+    electorates: &[usize],
+    trials: usize,
+    rng: &mut R,
+) -> Result<Vec<Accuracy>, &'static str> {
+    if candidates == 0 {
+        return Err("Need at least one candidate");
+    }
+    if trials == 0 {
+        return Err("Need at least one trial");
+    }
+    let mut results = Vec::with_capacity(phis.len() * electorates.len());
+    for &phi in phis {
+        for &voters in electorates {
+            let seed = ElectionSeed::random(rng);
+            let trial = |i: u64| -> Result<(f64, bool), &'static str> {
+                let seed = seed.derive(i);
+                let mut rng = seed.rng(Stream::Generation);
+                let mut truth: Vec<usize> = (0..candidates).collect();
+                truth.shuffle(&mut rng);
+                let votes = TiedOrdersComplete::from(mallows(&mut rng, &truth, phi, voters)?);
+                let ranking = method.rank(&votes, &mut seed.rng(Stream::TieBreaking))?;
+                let ranking = ranking.as_ref();
+                let winners = ranking.winners();
+                let winner =
+                    if winners.contains(&truth[0]) { 1.0 / winners.len() as f64 } else { 0.0 };
+                let exact = ranking.order() == truth && ranking.tied().iter().all(|t| !t);
+                Ok((winner, exact))
+            };
+
+            #[cfg(feature = "rayon")]
+            let outcomes: Vec<_> = (0..trials as u64).into_par_iter().map(trial).collect();
+            #[cfg(not(feature = "rayon"))]
+            let outcomes: Vec<_> = (0..trials as u64).map(trial).collect();
+
+            let (mut winner, mut ranking) = (0.0, 0);
+            for outcome in outcomes {
+                let (w, exact) = outcome?;
+                winner += w;
+                ranking += usize::from(exact);
+            }
+            results.push(Accuracy {
+                phi,
+                voters,
+                winner: winner / trials as f64,
+                ranking: ranking as f64 / trials as f64,
+            });
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn more_voters_more_accurate() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let res =
+            jury_accuracy(&MethodConfig::Borda, 4, &[0.0, 0.8], &[1, 51], 200, &mut rng).unwrap();
+        assert_eq!(res.len(), 4);
+        // Without noise every voter knows the truth.
+        assert_eq!((res[0].winner, res[0].ranking), (1.0, 1.0));
+        assert_eq!((res[1].winner, res[1].ranking), (1.0, 1.0));
+        let (few, many) = (&res[2], &res[3]);
+        assert_eq!((few.phi, few.voters, many.voters), (0.8, 1, 51));
+        assert!(few.winner < many.winner && few.ranking < many.ranking);
+    }
+}
@@ -0,0 +1,21 @@
+//! Simulations of elections, used to compare voting methods over many
+//! randomly generated profiles.
+//!
+//! A simulation is described by an [`Experiment`], which lists which
+//! generators, sizes, methods and metrics to use. With the `serde` feature an
+//! experiment can be read from a TOML file and its results written to a
+//! directory as JSON and CSV.
+
+mod behavior;
+mod experiment;
+mod jury;
+mod nomination;
+pub use behavior::Behavior;
+pub use experiment::{
+    Difference, Experiment, ExperimentResults, GeneratorConfig, Metric, MetricResult, Progress,
+    Sampling, Size,
+};
+pub use jury::{jury_accuracy, Accuracy};
+pub use nomination::{simulate_nomination, NominationConfig, Round};
+mod ternary;
+pub use ternary::{ternary_winners, TernaryPoint};
@@ -0,0 +1,224 @@
+use rand::{distributions::Uniform, prelude::Distribution, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::StandardNormal;
+
+use crate::{
+    formats::orders::TiedRank, generators::spatial::Spatial, methods::MethodConfig, spatial::Point,
+};
+
+/// How candidates react to the polls in [`simulate_nomination`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NominationConfig {
+    /// The method used to count the polls.
+    pub method: MethodConfig,
+    /// Number of polls.
+    pub rounds: usize,
+    /// The furthest a candidate can move after a poll.
+    pub step: f64,
+    /// Number of random positions each candidate tries after a poll. A
+    /// candidate only moves if one of them would give it a better rank, or the
+    /// same rank with more voters ranking it first.
+    pub trials: usize,
+    /// A candidate ranked last in this many polls in a row drops out. With
+    /// `None` no candidate drops out.
+    pub dropout_after: Option<usize>,
+    /// Candidates stop dropping out when this many remain.
+    pub min_candidates: usize,
+}
+
+/// One poll in a simulation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round<const D: usize> {
+    /// The result of the poll. Candidates which have dropped out are not
+    /// ranked.
+    pub poll: TiedRank,
+    /// The position of every candidate after reacting to the poll, or `None`
+    /// if it has dropped out.
+    pub positions: Vec<Option<Point<D>>>,
+}
+
+// The result of a poll
+struct Poll {
+    rank: TiedRank,
+    // The number of votes ranking each candidate first
+    firsts: Vec<usize>,
+}
+
+impl Poll {
+    // How well candidate `c` did, where smaller is better: the number of
+    // candidates ranked above it, and then the number of votes not ranking it
+    // first.
+    fn standing(&self, c: usize) -> (usize, usize) {
+        let mut above = 0;
+        for group in self.rank.as_ref().iter_groups() {
+            if group.contains(&c) {
+                break;
+            }
+            above += group.len();
+        }
+        (above, usize::MAX - self.firsts[c])
+    }
+}
+
+// Poll the voters around `center`, using `seed` so that polls of different
+// positions can be compared fairly.
+fn poll<const D: usize>(
+    generator: &Spatial<D>,
+    center: &Point<D>,
+    method: &MethodConfig,
+    seed: u64,
+) -> Result<Poll, &'static str> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let votes = generator.sample(&mut rng, center);
+    let mut firsts = vec![0; generator.candidates()];
+    for vote in &votes {
+        for &c in vote.winners() {
+            firsts[c] += 1;
+        }
+    }
+    let rank = method.rank(&votes, &mut rng)?;
+    Ok(Poll { rank, firsts })
+}
+
+/// Simulate candidates repositioning themselves, and dropping out, based on
+/// polls of the voters around `center`.
+///
+/// The candidates start at their positions in `generator`, which is also used
+/// to generate the voters of every poll. After each poll every candidate tries
+/// `trials` random positions at most `step` away, keeping the one which would
+/// give it the best rank, assuming the other candidates stay where they are.
+/// Then every candidate moves at once.
+///
+/// Returns every round, so the trajectories of the candidates can be
+/// analyzed.
+pub fn simulate_nomination<const D: usize, R: Rng>(
+    mut generator: Spatial<D>,
+    center: &Point<D>,
+    config: &NominationConfig,
+    rng: &mut R,
+) -> Result<Vec<Round<D>>, &'static str> {
+    let n = generator.candidates();
+    // The original index of every remaining candidate
+    let mut alive: Vec<usize> = (0..n).collect();
+    let mut last_streak = vec![0; n];
+    let mut rounds = Vec::with_capacity(config.rounds);
+    let offset = Uniform::new_inclusive(0.0, config.step);
+
+    for _ in 0..config.rounds {
+        let seed: u64 = rng.gen();
+        let current = poll(&generator, center, &config.method, seed)?;
+        let order = current.rank.order.iter().map(|&c| alive[c]).collect();
+        let original_poll = TiedRank::new(n, order, current.rank.tied.clone());
+
+        // Every candidate looks for a better position.
+        let mut new_positions = Vec::with_capacity(alive.len());
+        for c in 0..alive.len() {
+            let start = *generator.candidate(c).unwrap();
+            let mut best = start;
+            let mut best_standing = current.standing(c);
+            for _ in 0..config.trials {
+                // A uniformly random direction, scaled to a random length.
+                let dir: [f64; D] = std::array::from_fn(|_| StandardNormal.sample(rng));
+                let norm = dir.iter().map(|d| d * d).sum::<f64>().sqrt();
+                let len = offset.sample(rng);
+                if norm == 0.0 {
+                    continue;
+                }
+                let mut position = start;
+                for (p, d) in position.coords_mut().iter_mut().zip(dir) {
+                    *p += d / norm * len;
+                }
+
+                generator.move_candidate(c, position)?;
+                let trial = poll(&generator, center, &config.method, seed)?;
+                let standing = trial.standing(c);
+                if standing < best_standing {
+                    best_standing = standing;
+                    best = position;
+                }
+            }
+            generator.move_candidate(c, start)?;
+            new_positions.push(best);
+        }
+        for (c, position) in new_positions.into_iter().enumerate() {
+            generator.move_candidate(c, position)?;
+        }
+
+        // Candidates which keep coming last drop out.
+        let last = current.rank.as_ref().iter_groups().last().map(<[usize]>::to_vec);
+        for c in 0..alive.len() {
+            let is_last = alive.len() > 1 && last.as_ref().is_some_and(|l| l.contains(&c));
+            last_streak[alive[c]] = if is_last { last_streak[alive[c]] + 1 } else { 0 };
+        }
+        if let Some(after) = config.dropout_after {
+            let mut c = alive.len();
+            while c > 0 {
+                c -= 1;
+                if alive.len() > config.min_candidates && last_streak[alive[c]] >= after {
+                    generator.remove_candidate(c)?;
+                    alive.remove(c);
+                }
+            }
+        }
+
+        let mut positions = vec![None; n];
+        for (c, &orig) in alive.iter().enumerate() {
+            positions[orig] = generator.candidate(c).copied();
+        }
+        rounds.push(Round { poll: original_poll, positions });
+    }
+    Ok(rounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formats::TiePolicy, generators::spatial::FuzzyType};
+
+    fn config(method: MethodConfig) -> NominationConfig {
+        NominationConfig {
+            method,
+            rounds: 20,
+            step: 0.1,
+            trials: 5,
+            dropout_after: None,
+            min_candidates: 1,
+        }
+    }
+
+    #[test]
+    fn converge_to_median() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut generator = Spatial::new(0.2, 200, FuzzyType::Equal);
+        generator.add_candidate([-0.8]);
+        generator.add_candidate([0.9]);
+        let config = config(MethodConfig::Fptp(TiePolicy::Random));
+        let rounds = simulate_nomination(generator, &Point::new([0.0]), &config, &mut rng).unwrap();
+        assert_eq!(rounds.len(), 20);
+        let last = &rounds.last().unwrap().positions;
+        for p in last {
+            assert!(p.as_ref().unwrap()[0].abs() < 0.5, "{:?}", last);
+        }
+    }
+
+    #[test]
+    fn dropout() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        let mut generator = Spatial::new(0.1, 100, FuzzyType::Equal);
+        generator.add_candidate([0.0, 0.0]);
+        generator.add_candidate([3.0, 3.0]);
+        generator.add_candidate([-3.0, 3.0]);
+        let config = NominationConfig {
+            step: 0.0,
+            dropout_after: Some(2),
+            min_candidates: 2,
+            ..config(MethodConfig::Borda)
+        };
+        let rounds = simulate_nomination(generator, &Point::default(), &config, &mut rng).unwrap();
+        let remaining = rounds.last().unwrap().positions.iter().filter(|p| p.is_some()).count();
+        assert_eq!(remaining, 2);
+        assert_eq!(rounds[0].poll.as_ref().winners(), &[0]);
+        assert!(rounds.last().unwrap().positions[0].is_some());
+    }
+}
@@ -0,0 +1,101 @@
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    formats::toc::TiedOrdersComplete,
+    methods::MethodConfig,
+    seed::{ElectionSeed, Stream},
+};
+
+/// A point in a ternary diagram, see [`ternary_winners`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TernaryPoint {
+    /// How many copies of each corner the profile contains. They always sum
+    /// to the resolution of the diagram.
+    pub weights: [usize; 3],
+    /// The sorted winners of the profile.
+    pub winners: Vec<usize>,
+}
+
+/// Count every profile on a simplex grid between the three profiles
+/// `corners`, like a Yee diagram of profiles instead of voter positions.
+///
+/// The point `[i, j, k]`, with `i + j + k = resolution`, is the profile with
+/// `i` copies of the votes in `corners[0]`, `j` of `corners[1]` and `k` of
+/// `corners[2]`. With a single vote in each corner, every anonymous profile
+/// of `resolution` voters using those three votes is counted. The points are
+/// ordered by `i` and then by `j`.
+///
+/// Every count uses the same random numbers, seeded from `rng`, so random tie
+/// breaking doesn't make neighbouring points differ.
+pub fn ternary_winners<R: Rng>(
+    corners: &[TiedOrdersComplete; 3],
+    resolution: usize,
+    method: &MethodConfig,
+    rng: &mut R,
+) -> Result<Vec<TernaryPoint>, &'static str> {
+    if resolution == 0 {
+        return Err("Resolution must be positive");
+    }
+    let candidates = corners[0].candidates;
+    if corners.iter().any(|c| c.candidates != candidates) {
+        return Err("Corners have different numbers of candidates");
+    }
+    let seed = ElectionSeed::random(rng);
+    let mut grid = Vec::new();
+    for i in 0..=resolution {
+        for j in 0..=(resolution - i) {
+            grid.push([i, j, resolution - i - j]);
+        }
+    }
+    let count = |weights: [usize; 3]| -> Result<TernaryPoint, &'static str> {
+        let mut votes = TiedOrdersComplete::new(candidates);
+        for (corner, &w) in corners.iter().zip(&weights) {
+            for _ in 0..w {
+                for vote in corner {
+                    votes.add(vote);
+                }
+            }
+        }
+        let ranking = method.rank(&votes, &mut seed.rng(Stream::TieBreaking))?;
+        let mut winners = ranking.as_ref().winners().to_vec();
+        winners.sort();
+        Ok(TernaryPoint { weights, winners })
+    };
+
+    #[cfg(feature = "rayon")]
+    let points = grid.into_par_iter().map(count).collect();
+    #[cfg(not(feature = "rayon"))]
+    let points = grid.into_iter().map(count).collect();
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn corner(s: &str) -> TiedOrdersComplete {
+        let mut votes = TiedOrdersComplete::new(3);
+        assert!(votes.add_from_str(s));
+        votes
+    }
+
+    #[test]
+    fn cycle() {
+        let corners = [corner("0,1,2"), corner("1,2,0"), corner("2,0,1")];
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let points = ternary_winners(&corners, 3, &MethodConfig::Borda, &mut rng).unwrap();
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[0], TernaryPoint { weights: [0, 0, 3], winners: vec![2] });
+        assert_eq!(points[9], TernaryPoint { weights: [3, 0, 0], winners: vec![0] });
+        // The middle of the cycle is a three-way tie.
+        let middle = points.iter().find(|p| p.weights == [1, 1, 1]).unwrap();
+        assert_eq!(middle.winners, [0, 1, 2]);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(ternary_winners(&corners, 0, &MethodConfig::Borda, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Points in an issue space, used by spatial models of voting like
+//! [`Spatial`](crate::generators::spatial::Spatial).
+//!
+//! The number of dimensions is part of the type, so candidates and voters in
+//! different spaces can't be mixed up.
+use std::ops::{Index, IndexMut};
+
+/// The most dimensions supported when the number of dimensions is only known
+/// at runtime, like in a
+/// [`GeneratorConfig`](crate::simulation::GeneratorConfig).
+pub const MAX_DIMENSIONS: usize = 10;
+
+/// A point in `D` dimensional space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point<const D: usize> {
+    coords: [f64; D],
+}
+
+impl<const D: usize> Point<D> {
+    pub fn new(coords: [f64; D]) -> Self {
+        Point { coords }
+    }
+
+    pub fn coords(&self) -> &[f64; D] {
+        &self.coords
+    }
+
+    pub fn coords_mut(&mut self) -> &mut [f64; D] {
+        &mut self.coords
+    }
+
+    /// The Euclidean distance between `self` and `other`.
+    pub fn distance(&self, other: &Self) -> f64 {
+        let mut sum = 0.0;
+        for (a, b) in self.coords.iter().zip(&other.coords) {
+            sum += (a - b) * (a - b);
+        }
+        sum.sqrt()
+    }
+}
+
+/// The origin.
+impl<const D: usize> Default for Point<D> {
+    fn default() -> Self {
+        Point { coords: [0.0; D] }
+    }
+}
+
+impl<const D: usize> From<[f64; D]> for Point<D> {
+    fn from(coords: [f64; D]) -> Self {
+        Point { coords }
+    }
+}
+
+impl<const D: usize> From<&[f64; D]> for Point<D> {
+    fn from(coords: &[f64; D]) -> Self {
+        Point { coords: *coords }
+    }
+}
+
+// Serde only implements its traits for arrays of a fixed length, so a point is
+// written as a sequence of its coordinates.
+#[cfg(feature = "serde")]
+impl<const D: usize> serde::Serialize for Point<D> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.coords.as_slice().serialize(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const D: usize> serde::Deserialize<'de> for Point<D> {
+    fn deserialize<De: serde::Deserializer<'de>>(d: De) -> Result<Self, De::Error> {
+        let coords: Vec<f64> = serde::Deserialize::deserialize(d)?;
+        let len = coords.len();
+        match coords.try_into() {
+            Ok(coords) => Ok(Point { coords }),
+            Err(_) => Err(serde::de::Error::invalid_length(len, &"one coordinate per dimension")),
+        }
+    }
+}
+
+impl<const D: usize> TryFrom<&[f64]> for Point<D> {
+    type Error = &'static str;
+
+    fn try_from(coords: &[f64]) -> Result<Self, Self::Error> {
+        match coords.try_into() {
+            Ok(coords) => Ok(Point { coords }),
+            Err(_) => Err("Point has the wrong number of dimensions"),
+        }
+    }
+}
+
+impl<const D: usize> Index<usize> for Point<D> {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.coords[i]
+    }
+}
+
+impl<const D: usize> IndexMut<usize> for Point<D> {
+    fn index_mut(&mut self, i: usize) -> &mut f64 {
+        &mut self.coords[i]
+    }
+}
+
+/// How distances between voters and candidates are measured.
+///
+/// Every metric takes a salience weight per dimension, so voters can care more
+/// about some issues than others. With every weight set to 1.0 they are the
+/// ordinary, unweighted metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistanceMetric {
+    /// The square root of the weighted sum of squared differences.
+    #[default]
+    Euclidean,
+    /// The weighted sum of absolute differences, also called taxicab distance.
+    Manhattan,
+    /// The largest weighted absolute difference in any dimension.
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    /// The distance between `a` and `b`, where the difference in dimension `i`
+    /// is weighted by `weights[i]`.
+    pub fn distance<const D: usize>(self, a: &Point<D>, b: &Point<D>, weights: &[f64; D]) -> f64 {
+        let diffs = a.coords.iter().zip(&b.coords).map(|(a, b)| (a - b).abs()).zip(weights);
+        match self {
+            DistanceMetric::Euclidean => diffs.map(|(d, w)| w * d * d).sum::<f64>().sqrt(),
+            DistanceMetric::Manhattan => diffs.map(|(d, w)| w * d).sum(),
+            DistanceMetric::Chebyshev => diffs.map(|(d, w)| w * d).fold(0.0, f64::max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance() {
+        let a = Point::new([0.0, 0.0, 0.0]);
+        let b = Point::new([1.0, 2.0, 2.0]);
+        assert_eq!(a.distance(&b), 3.0);
+        assert_eq!(b.distance(&a), 3.0);
+        assert_eq!(Point::<1>::default().distance(&Point::from([-2.0])), 2.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_point() {
+        let p = Point::new([1.0, -2.5]);
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(json, "[1.0,-2.5]");
+        assert_eq!(serde_json::from_str::<Point<2>>(&json).unwrap(), p);
+        assert!(serde_json::from_str::<Point<3>>(&json).is_err());
+    }
+
+    #[test]
+    fn metrics() {
+        let a = Point::new([0.0, 0.0]);
+        let b = Point::new([3.0, -4.0]);
+        let ones = [1.0, 1.0];
+        assert_eq!(DistanceMetric::Euclidean.distance(&a, &b, &ones), 5.0);
+        assert_eq!(DistanceMetric::Manhattan.distance(&a, &b, &ones), 7.0);
+        assert_eq!(DistanceMetric::Chebyshev.distance(&a, &b, &ones), 4.0);
+
+        let weights = [4.0, 0.0];
+        assert_eq!(DistanceMetric::Euclidean.distance(&a, &b, &weights), 6.0);
+        assert_eq!(DistanceMetric::Manhattan.distance(&a, &b, &weights), 12.0);
+        assert_eq!(DistanceMetric::Chebyshev.distance(&a, &b, &weights), 12.0);
+    }
+
+    #[test]
+    fn from_slice() {
+        let p: Point<2> = Point::try_from(&[0.5, 0.25][..]).unwrap();
+        assert_eq!(p[1], 0.25);
+        assert!(Point::<2>::try_from(&[0.5][..]).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Standalone SVG images of results, which can be embedded in reports without
+//! any plotting tools. Every function returns a complete `<svg>` element.
+//!
+//! Requires the `svg` feature.
+use std::fmt::Write;
+
+use crate::{
+    methods::{Irv, VotingMethod},
+    tournament::Tournament,
+};
+
+// Colors for the candidates, repeated if there are more candidates.
+const PALETTE: [&str; 10] = [
+    "#4878d0", "#ee854a", "#6acc64", "#d65f5f", "#956cb4", "#8c613c", "#dc7ec0", "#797979",
+    "#d5bb67", "#82c6e2",
+];
+
+const ROW_HEIGHT: f64 = 24.0;
+const FONT_SIZE: f64 = 14.0;
+// A rough width of a character, used to make room for labels.
+const CHAR_WIDTH: f64 = 8.0;
+
+/// A horizontal bar chart of `values`, with one bar per candidate. The largest
+/// value is drawn `width` pixels wide. Negative values and NaN are drawn as
+/// empty bars.
+///
+/// # Panics
+///
+/// Panics if `labels` and `values` have different lengths.
+pub fn bar_chart<L: AsRef<str>>(labels: &[L], values: &[f64], width: f64) -> String {
+    assert_eq!(labels.len(), values.len(), "every value needs a label");
+    let label_width = text_width(labels) + CHAR_WIDTH;
+    let max = values.iter().copied().filter(|v| *v > 0.0).fold(0.0, f64::max);
+    let value_width = CHAR_WIDTH * 10.0;
+    let mut svg = start(label_width + width + value_width, ROW_HEIGHT * values.len() as f64);
+    for (i, (label, &value)) in labels.iter().zip(values).enumerate() {
+        let y = ROW_HEIGHT * i as f64;
+        let len = if value > 0.0 { value / max * width } else { 0.0 };
+        text(&mut svg, label_width - CHAR_WIDTH / 2.0, y, "end", label.as_ref());
+        writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+            label_width,
+            y + 2.0,
+            len,
+            ROW_HEIGHT - 4.0,
+            color(i)
+        )
+        .unwrap();
+        text(&mut svg, label_width + len + CHAR_WIDTH / 2.0, y, "start", &value.to_string());
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A sankey-style diagram of the rounds of `irv`, with a column of stacked
+/// bars per round. Every bar is as tall as the first preferences of its
+/// candidate, and bands between the columns show which votes a candidate kept
+/// and which it got from the candidates eliminated in the previous round.
+///
+/// When several candidates are eliminated in the same round, the transferred
+/// votes are drawn as coming from all of them together, as `Irv` doesn't
+/// record where each vote came from.
+///
+/// # Panics
+///
+/// Panics if there isn't a label for every candidate.
+pub fn irv_transfers<L: AsRef<str>>(irv: &Irv, labels: &[L], height: f64) -> String {
+    let eliminated = irv.get_score();
+    let n = eliminated.len();
+    assert_eq!(labels.len(), n, "every candidate needs a label");
+    let rounds = irv.rounds();
+    let bar_width = 20.0;
+    let column = text_width(labels) + bar_width + 80.0;
+    let gap = 4.0;
+    let total = rounds.iter().map(|r| r.iter().sum::<usize>()).max().unwrap_or(0).max(1);
+    let scale = (height - gap * n as f64) / total as f64;
+
+    // The top of every candidate's bar in every round.
+    let tops: Vec<Vec<f64>> = rounds
+        .iter()
+        .map(|firsts| {
+            let mut y = ROW_HEIGHT;
+            firsts
+                .iter()
+                .map(|&f| {
+                    let top = y;
+                    y += f as f64 * scale + gap;
+                    top
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut svg = start(column * rounds.len() as f64, height + ROW_HEIGHT);
+    for (r, firsts) in rounds.iter().enumerate() {
+        let x = column * r as f64;
+        text(&mut svg, x, 0.0, "start", &format!("Round {}", r + 1));
+        if r > 0 {
+            let prev = &rounds[r - 1];
+            // Transferred votes are taken from the eliminated bars in order.
+            let mut sources =
+                (0..n).filter(|&c| eliminated[c] == r).map(|c| (tops[r - 1][c], prev[c]));
+            let mut source = sources.next();
+            let mut taken = 0;
+            for c in (0..n).filter(|&c| eliminated[c] > r) {
+                let kept = prev[c].min(firsts[c]);
+                let from = x - column + bar_width;
+                band(&mut svg, from, tops[r - 1][c], x, tops[r][c], kept as f64 * scale, c);
+                let mut gained = firsts[c] - kept;
+                let mut y = tops[r][c] + kept as f64 * scale;
+                while gained > 0 {
+                    let Some((top, votes)) = source else { break };
+                    let amount = gained.min(votes - taken);
+                    let h = amount as f64 * scale;
+                    band(&mut svg, from, top + taken as f64 * scale, x, y, h, c);
+                    y += h;
+                    gained -= amount;
+                    taken += amount;
+                    if taken == votes {
+                        source = sources.next();
+                        taken = 0;
+                    }
+                }
+            }
+        }
+        for c in (0..n).filter(|&c| eliminated[c] > r) {
+            let h = firsts[c] as f64 * scale;
+            writeln!(
+                svg,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+                x,
+                tops[r][c],
+                bar_width,
+                h,
+                color(c)
+            )
+            .unwrap();
+            let label = format!("{} {}", labels[c].as_ref(), firsts[c]);
+            let y = tops[r][c] + h / 2.0 - ROW_HEIGHT / 2.0;
+            text(&mut svg, x + bar_width + CHAR_WIDTH / 2.0, y, "start", &label);
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A table of the weights of `tournament`, where the cell in row `a` and
+/// column `b` holds the weight of `a` over `b`. Cells are green if `a` beats
+/// `b`, red if `b` beats `a`, and gray if they are tied.
+///
+/// # Panics
+///
+/// Panics if there isn't a label for every candidate.
+pub fn pairwise_matrix<L: AsRef<str>>(tournament: &Tournament, labels: &[L]) -> String {
+    let n = tournament.candidates();
+    assert_eq!(labels.len(), n, "every candidate needs a label");
+    let cell = 40.0;
+    let label_width = text_width(labels) + CHAR_WIDTH;
+    let mut svg = start(label_width + cell * n as f64, ROW_HEIGHT + cell * n as f64);
+    for (b, label) in labels.iter().enumerate() {
+        let x = label_width + cell * (b as f64 + 0.5);
+        text(&mut svg, x, 0.0, "middle", label.as_ref());
+    }
+    for (a, label) in labels.iter().enumerate() {
+        let y = ROW_HEIGHT + cell * a as f64;
+        let middle = y + (cell - ROW_HEIGHT) / 2.0;
+        text(&mut svg, label_width - CHAR_WIDTH / 2.0, middle, "end", label.as_ref());
+        for b in 0..n {
+            let x = label_width + cell * b as f64;
+            let fill = match tournament.margin(a, b) {
+                _ if a == b => "#ffffff",
+                m if m > 0 => "#b7e4b0",
+                m if m < 0 => "#f2b8b5",
+                _ => "#dddddd",
+            };
+            writeln!(
+                svg,
+                r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" stroke="#999999"/>"##,
+                x, y, cell, cell, fill
+            )
+            .unwrap();
+            if a != b {
+                let weight = tournament.weight(a, b).to_string();
+                text(&mut svg, x + cell / 2.0, middle, "middle", &weight);
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn start(width: f64, height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" \
+         viewBox=\"0 0 {w:.0} {h:.0}\" font-family=\"sans-serif\" font-size=\"{}\">\n",
+        FONT_SIZE,
+        w = width.ceil(),
+        h = height.ceil()
+    )
+}
+
+// Text vertically centered in the row starting at `y`.
+fn text(svg: &mut String, x: f64, y: f64, anchor: &str, s: &str) {
+    writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" text-anchor="{}" dominant-baseline="middle">{}</text>"#,
+        x,
+        y + ROW_HEIGHT / 2.0,
+        anchor,
+        escape(s)
+    )
+    .unwrap();
+}
+
+// A band of height `h` from `(x0, y0)` to `(x1, y1)`, curving between them.
+fn band(svg: &mut String, x0: f64, y0: f64, x1: f64, y1: f64, h: f64, candidate: usize) {
+    if h <= 0.0 {
+        return;
+    }
+    let xm = (x0 + x1) / 2.0;
+    writeln!(
+        svg,
+        r#"<path d="M{x0:.1},{y0:.1} C{xm:.1},{y0:.1} {xm:.1},{y1:.1} {x1:.1},{y1:.1} V{:.1} C{xm:.1},{:.1} {xm:.1},{:.1} {x0:.1},{:.1} Z" fill="{}" fill-opacity="0.4"/>"#,
+        y1 + h,
+        y1 + h,
+        y0 + h,
+        y0 + h,
+        color(candidate)
+    )
+    .unwrap();
+}
+
+fn color(candidate: usize) -> &'static str {
+    PALETTE[candidate % PALETTE.len()]
+}
+
+fn text_width<L: AsRef<str>>(labels: &[L]) -> f64 {
+    labels.iter().map(|l| l.as_ref().chars().count()).max().unwrap_or(0) as f64 * CHAR_WIDTH
+}
+
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::toi::TiedOrdersIncomplete;
+
+    #[test]
+    fn bars() {
+        let svg = bar_chart(&["a<b", "c"], &[2.0, 1.0], 100.0);
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">a&lt;b</text>"));
+        assert!(svg.contains(r#"width="100.0""#) && svg.contains(r#"width="50.0""#));
+    }
+
+    #[test]
+    fn transfers() {
+        let mut votes = TiedOrdersIncomplete::new(3);
+        votes.add_from_str_i("0,2,1", 4);
+        votes.add_from_str_i("1,0,2", 3);
+        votes.add_from_str_i("2,1,0", 2);
+        let irv = Irv::count(&votes).unwrap();
+        let svg = irv_transfers(&irv, &["A", "B", "C"], 200.0);
+        // 3 + 2 + 1 bars. Every remaining candidate keeps its votes, and B
+        // gets the votes of C and then A.
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert_eq!(svg.matches("<path").count(), 5);
+        assert!(svg.contains(">B 9</text>"));
+    }
+
+    #[test]
+    fn matrix() {
+        let t = Tournament::from_edges(&[(0, 1, 3), (1, 0, 1), (1, 2, 2), (2, 1, 2)]).unwrap();
+        let svg = pairwise_matrix(&t, &["x", "y", "z"]);
+        assert_eq!(svg.matches("<rect").count(), 9);
+        assert_eq!(svg.matches("#b7e4b0").count(), 1);
+        assert_eq!(svg.matches("#f2b8b5").count(), 1);
+        // Two ties between 1 and 2, and two between 0 and 2 without any edges.
+        assert_eq!(svg.matches("#dddddd").count(), 4);
+    }
+}
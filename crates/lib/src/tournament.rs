@@ -0,0 +1,131 @@
+//! Weighted tournaments between candidates.
+//!
+//! A [`Tournament`] stores a weight for every ordered pair of candidates, where
+//! the weight of `(a, b)` says how strongly `a` beats `b`, e.g. the number of
+//! voters preferring `a` over `b`. It can be constructed directly from edges,
+//! so code working on pairwise comparisons can be used without any votes.
+
+/// A complete weighted directed graph of candidates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tournament {
+    candidates: usize,
+    // Has length candidates * candidates, where `weights[a * candidates + b]` is
+    // the weight of the edge from `a` to `b`.
+    weights: Vec<usize>,
+}
+
+impl Tournament {
+    /// Create a tournament of `candidates` without any edges.
+    pub fn new(candidates: usize) -> Self {
+        Tournament { candidates, weights: vec![0; candidates * candidates] }
+    }
+
+    // Create a tournament from every weight, where `weights[a * candidates + b]`
+    // is the weight of the edge from `a` to `b`.
+    pub(crate) fn from_weights(candidates: usize, weights: Vec<usize>) -> Self {
+        debug_assert!(weights.len() == candidates * candidates);
+        debug_assert!((0..candidates).all(|c| weights[c * candidates + c] == 0));
+        Tournament { candidates, weights }
+    }
+
+    /// Create a tournament from a list of edges `(a, b, weight)`, where `a`
+    /// beats `b` with `weight`. The number of candidates is one more than the
+    /// largest candidate listed. Weights of repeated edges are added together.
+    ///
+    /// ```
+    /// use votery::tournament::Tournament;
+    ///
+    /// let t = Tournament::from_edges(&[(0, 1, 3), (1, 2, 2), (2, 0, 1)]).unwrap();
+    /// assert_eq!(t.candidates(), 3);
+    /// assert!(t.beats(0, 1) && t.beats(1, 2) && t.beats(2, 0));
+    /// assert_eq!(t.condorcet_winner(), None);
+    /// ```
+    pub fn from_edges(edges: &[(usize, usize, usize)]) -> Result<Self, &'static str> {
+        let candidates = edges.iter().map(|&(a, b, _)| a.max(b) + 1).max().unwrap_or(0);
+        let mut t = Tournament::new(candidates);
+        for &(a, b, weight) in edges {
+            t.add_edge(a, b, weight)?;
+        }
+        Ok(t)
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    /// Add `weight` to the edge from `a` to `b`.
+    pub fn add_edge(&mut self, a: usize, b: usize, weight: usize) -> Result<(), &'static str> {
+        if a >= self.candidates || b >= self.candidates {
+            return Err("Edge to non-existing candidate");
+        }
+        if a == b {
+            return Err("Edge from candidate to itself");
+        }
+        let w = &mut self.weights[a * self.candidates + b];
+        *w = w.checked_add(weight).ok_or("Integer overflow: Too large weight")?;
+        Ok(())
+    }
+
+    /// The weight of the edge from `a` to `b`.
+    pub fn weight(&self, a: usize, b: usize) -> usize {
+        debug_assert!(a < self.candidates && b < self.candidates);
+        self.weights[a * self.candidates + b]
+    }
+
+    /// The weight from `a` to `b` minus the weight from `b` to `a`.
+    pub fn margin(&self, a: usize, b: usize) -> isize {
+        self.weight(a, b) as isize - self.weight(b, a) as isize
+    }
+
+    /// Returns true if `a` beats `b`, i.e. the weight from `a` to `b` is larger
+    /// than the weight from `b` to `a`.
+    pub fn beats(&self, a: usize, b: usize) -> bool {
+        self.weight(a, b) > self.weight(b, a)
+    }
+
+    /// The candidate which beats every other candidate, if there is one.
+    pub fn condorcet_winner(&self) -> Option<usize> {
+        (0..self.candidates).find(|&a| (0..self.candidates).all(|b| a == b || self.beats(a, b)))
+    }
+
+    /// The graph of which candidates beat each other, as an adjacency matrix.
+    /// Can be used with [`tarjan`](crate::tarjan::tarjan).
+    pub fn beats_matrix(&self) -> Vec<bool> {
+        let c = self.candidates;
+        let mut edges = vec![false; c * c];
+        for a in 0..c {
+            for b in 0..c {
+                edges[a * c + b] = a != b && self.beats(a, b);
+            }
+        }
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_edges_empty() {
+        let t = Tournament::from_edges(&[]).unwrap();
+        assert_eq!(t.candidates(), 0);
+        assert_eq!(t.condorcet_winner(), None);
+    }
+
+    #[test]
+    fn from_edges_invalid() {
+        assert!(Tournament::from_edges(&[(1, 1, 1)]).is_err());
+    }
+
+    #[test]
+    fn condorcet_winner() {
+        let t = Tournament::from_edges(&[(0, 1, 2), (1, 0, 1), (2, 0, 3), (2, 1, 1), (1, 2, 1)])
+            .unwrap();
+        assert_eq!(t.margin(0, 1), 1);
+        assert_eq!(t.margin(1, 2), 0);
+        assert_eq!(t.condorcet_winner(), None);
+        let t = Tournament::from_edges(&[(0, 1, 2), (2, 0, 3), (2, 1, 2)]).unwrap();
+        assert_eq!(t.condorcet_winner(), Some(2));
+    }
+}
@@ -0,0 +1,57 @@
+//! Small helpers which are useful when analyzing the results of simulations.
+
+/// The most common element of `values`, or `None` if it's empty. If several
+/// elements are equally common, the smallest of them is returned.
+///
+/// `values` is sorted in the process.
+///
+/// ```
+/// use votery::util::most_common;
+///
+/// let mut values = vec![3, 1, 2, 3, 1];
+/// assert_eq!(most_common(&mut values), Some(1));
+/// assert_eq!(most_common::<u8>(&mut []), None);
+/// ```
+///
+/// # Panics
+///
+/// Panics if two elements can't be compared, like `f64::NAN`.
+pub fn most_common<T: PartialOrd + Clone>(values: &mut [T]) -> Option<T> {
+    values.sort_by(|a, b| a.partial_cmp(b).expect("elements could not be compared"));
+    let mut best: Option<(&T, usize)> = None;
+    let mut rest = &*values;
+    while let Some(first) = rest.first() {
+        let run = rest.iter().take_while(|x| *x == first).count();
+        if best.is_none_or(|(_, count)| run > count) {
+            best = Some((first, run));
+        }
+        rest = &rest[run..];
+    }
+    best.map(|(x, _)| x.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[quickcheck]
+    fn matches_naive_count(values: Vec<u8>) -> bool {
+        let mut counts: HashMap<u8, usize> = HashMap::new();
+        for &v in &values {
+            *counts.entry(v).or_default() += 1;
+        }
+        let max = counts.values().copied().max();
+        let expected = counts.iter().filter(|(_, &c)| Some(c) == max).map(|(&v, _)| v).min();
+        most_common(&mut values.clone()) == expected
+    }
+
+    #[test]
+    fn later_runs() {
+        // A longer run after shorter ones, and ties with an earlier run.
+        assert_eq!(most_common(&mut [1, 2, 2, 3, 3, 3]), Some(3));
+        assert_eq!(most_common(&mut [5, 4, 5, 4]), Some(4));
+        assert_eq!(most_common(&mut [0.5, 0.25, 0.5]), Some(0.5));
+    }
+}
@@ -0,0 +1,116 @@
+//! Golden-file tests: every method counts every profile in `golden/fixtures`
+//! and the results are compared to the snapshots in `golden/snapshots`, so
+//! that changes of behavior don't go unnoticed.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the current results as the new
+//! snapshots, e.g. when adding a fixture or a method, and review the diff.
+#![cfg(feature = "serde")]
+
+use std::{collections::BTreeMap, env, fs, path::Path};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use votery::{
+    formats::{toc::TiedOrdersComplete, toi::TiedOrdersIncomplete, TiePolicy},
+    methods::{
+        BucklinConfig, CopelandConfig, CopelandTies, MethodConfig, PositionalConfig, StarConfig,
+        TieScoring,
+    },
+};
+
+// A profile, where every ballot is repeated `count` times.
+#[derive(Deserialize)]
+struct Fixture {
+    candidates: usize,
+    votes: Vec<Ballot>,
+}
+
+#[derive(Deserialize)]
+struct Ballot {
+    #[serde(default = "one")]
+    count: usize,
+    order: String,
+}
+
+fn one() -> usize {
+    1
+}
+
+// Every method with a snapshot. Every method needs a unique name.
+fn methods() -> Vec<MethodConfig> {
+    vec![
+        MethodConfig::Borda,
+        MethodConfig::Bucklin(BucklinConfig { tie_scoring: TieScoring::Average }),
+        MethodConfig::Condorcet,
+        MethodConfig::Coombs,
+        MethodConfig::Copeland(CopelandConfig { ties: CopelandTies::Half }),
+        MethodConfig::Fptp(TiePolicy::FirstListed),
+        MethodConfig::Irv,
+        MethodConfig::MajorityJudgment,
+        MethodConfig::Positional(PositionalConfig {
+            weights: vec![3, 1],
+            tie_scoring: TieScoring::Average,
+        }),
+        MethodConfig::Score,
+        MethodConfig::Star(StarConfig::default()),
+    ]
+}
+
+// The result of every method on `fixture`, by method name. Profiles where
+// every vote ranks every candidate are counted as complete votes.
+fn results(fixture: &Fixture) -> Result<BTreeMap<&'static str, String>, &'static str> {
+    let mut votes = TiedOrdersIncomplete::new(fixture.candidates);
+    let mut complete = Some(TiedOrdersComplete::new(fixture.candidates));
+    for ballot in &fixture.votes {
+        for _ in 0..ballot.count {
+            if !votes.add_from_str(&ballot.order) {
+                return Err("Invalid vote in fixture");
+            }
+            if complete.as_mut().is_some_and(|c| !c.add_from_str(&ballot.order)) {
+                complete = None;
+            }
+        }
+    }
+    let mut results = BTreeMap::new();
+    for method in methods() {
+        // Every method gets its own random numbers, so adding a method
+        // doesn't change the others.
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let ranking = match &complete {
+            Some(complete) => method.rank(complete, &mut rng)?,
+            None => method.rank_incomplete(&votes, &mut rng)?,
+        };
+        results.insert(method.name(), ranking.as_ref().to_string());
+    }
+    Ok(results)
+}
+
+#[test]
+fn golden() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut fixtures: Vec<_> = fs::read_dir(dir.join("fixtures"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "toml"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty());
+
+    let mut failed = Vec::new();
+    for path in fixtures {
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let fixture: Fixture = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let results = results(&fixture).unwrap();
+        let json = serde_json::to_string_pretty(&results).unwrap() + "\n";
+        let snapshot = dir.join("snapshots").join(format!("{}.json", name));
+        if update {
+            fs::write(&snapshot, json).unwrap();
+        } else if fs::read_to_string(&snapshot).ok().as_deref() != Some(&json) {
+            eprintln!("{} differs from its snapshot, got:\n{}", name, json);
+            failed.push(name);
+        }
+    }
+    assert!(failed.is_empty(), "run with UPDATE_GOLDEN=1 to update {:?}", failed);
+}
@@ -0,0 +1,173 @@
+// Text and legends drawn on top of the images, so a frame of an animation
+// describes itself. We use a tiny built-in bitmap font to avoid depending on
+// any font files.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    candidates::Candidate,
+    color::{Color, BLACK},
+};
+
+// Which annotations to draw on each image.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Annotations {
+    // The color and name of every candidate
+    pub legend: bool,
+    // The index of the frame in the animation
+    pub frame_counter: bool,
+    // The name of the voting method
+    pub method_label: bool,
+}
+
+impl Annotations {
+    pub const ALL: Annotations =
+        Annotations { legend: true, frame_counter: true, method_label: true };
+}
+
+const WHITE: Color = Color::from_rgb([255, 255, 255]);
+
+// Every glyph is 3 pixels wide and 5 pixels high, with one pixel between
+// glyphs and lines.
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const MARGIN: usize = 1;
+
+// The rows of a glyph, where the three lowest bits of each row are the pixels
+// from left to right.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+// The width in pixels of `text` drawn with `scale`.
+fn text_width(text: &str, scale: usize) -> usize {
+    let n = text.chars().count();
+    (n * (GLYPH_WIDTH + MARGIN)).saturating_sub(MARGIN) * scale
+}
+
+fn line_height(scale: usize) -> usize {
+    (GLYPH_HEIGHT + MARGIN) * scale
+}
+
+// Fill a rectangle, ignoring any part outside of the image.
+fn fill_rect(
+    image: &mut [Vec<[u8; 3]>],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    color: Color,
+) {
+    let rgb = color.quantize();
+    for row in image.iter_mut().skip(y).take(height) {
+        for pixel in row.iter_mut().skip(x).take(width) {
+            *pixel = rgb;
+        }
+    }
+}
+
+// Draw `text` with its top left corner at (x, y), on a white background.
+fn draw_text(image: &mut [Vec<[u8; 3]>], x: usize, y: usize, text: &str, scale: usize) {
+    let width = text_width(text, scale);
+    let height = GLYPH_HEIGHT * scale;
+    fill_rect(
+        image,
+        x.saturating_sub(scale),
+        y.saturating_sub(scale),
+        width + 2 * scale,
+        height + 2 * scale,
+        WHITE,
+    );
+    for (i, c) in text.chars().enumerate() {
+        let gx = x + i * (GLYPH_WIDTH + MARGIN) * scale;
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    fill_rect(image, gx + col * scale, y + row * scale, scale, scale, BLACK);
+                }
+            }
+        }
+    }
+}
+
+// Draw a swatch of each candidate's color followed by its name, in the top
+// left corner.
+fn draw_legend(image: &mut [Vec<[u8; 3]>], candidates: &[Candidate], scale: usize) {
+    let swatch = GLYPH_HEIGHT * scale;
+    for (i, c) in candidates.iter().enumerate() {
+        let y = MARGIN * scale + i * (line_height(scale) + scale);
+        fill_rect(image, MARGIN * scale, y, swatch, swatch, c.color);
+        draw_text(image, (2 * MARGIN + 1) * scale + swatch, y, &c.name, scale);
+    }
+}
+
+// Draw the annotations enabled in `annotations` on `image`. `frame` is the
+// index of the image in an animation, if it is part of one.
+pub fn annotate(
+    image: &mut [Vec<[u8; 3]>],
+    annotations: Annotations,
+    candidates: &[Candidate],
+    method: &str,
+    frame: Option<usize>,
+) {
+    let resolution = image.len();
+    // Make the text readable on larger images
+    let scale = (resolution / 200).max(1);
+    let bottom = resolution.saturating_sub((GLYPH_HEIGHT + MARGIN) * scale);
+    if annotations.legend {
+        draw_legend(image, candidates, scale);
+    }
+    if annotations.method_label {
+        let x = resolution.saturating_sub(text_width(method, scale) + MARGIN * scale);
+        draw_text(image, x, MARGIN * scale, method, scale);
+    }
+    if let (true, Some(frame)) = (annotations.frame_counter, frame) {
+        draw_text(image, MARGIN * scale, bottom, &frame.to_string(), scale);
+    }
+}
@@ -1,7 +1,107 @@
 use rand::{distributions::Uniform, prelude::Distribution, Rng};
-use votery::formats::orders::TiedRankRef;
+use serde::{Deserialize, Serialize};
+use votery::formats::{orders::TiedRankRef, parse::CandidateRegistry};
 
-use crate::{MAX, MIN, vector::Vector};
+use crate::{
+    color::{distinct_colors, Color},
+    optimize::Search,
+    vector::Vector,
+    ImageConfig, MAX, MIN,
+};
+
+// The name and color of a candidate, used when drawing it.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub name: String,
+    pub color: Color,
+}
+
+impl Candidate {
+    // Create the candidates of `registry`. Candidates without a color get one
+    // from `distinct_colors`.
+    pub fn from_registry(registry: &CandidateRegistry) -> Vec<Candidate> {
+        let palette = distinct_colors(registry.len());
+        registry
+            .candidates()
+            .map(|c| Candidate {
+                name: registry.name(c).unwrap().to_string(),
+                color: match registry.color(c) {
+                    Some(rgb) => Color::from_rgb(rgb),
+                    None => palette[c.index()],
+                },
+            })
+            .collect()
+    }
+}
+
+// A legend of the candidates, with one line per candidate like "#e60049 alice".
+pub fn legend(candidates: &[Candidate]) -> String {
+    let mut s = String::new();
+    for c in candidates {
+        s.push_str(&c.color.hex());
+        s.push(' ');
+        s.push_str(&c.name);
+        s.push('\n');
+    }
+    s
+}
+
+// How the candidates move between the frames of an animation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Movement {
+    // Move according to the ranking of the previous frame, see
+    // `OptimizingCandidates`. Every frame depends on the one before it, so
+    // they have to be rendered one at a time.
+    Optimizing { speed: f64 },
+    // Every candidate searches for the position maximizing an objective,
+    // like its share of the wins, see `Search`.
+    Searching(Search),
+    // Stay in the same place.
+    Static,
+    // Bounce around in random directions, moving `speed` every frame.
+    Bouncing { speed: f64 },
+    // The position of every candidate in every frame. The candidates stay at
+    // their last positions once the script runs out.
+    Scripted(Vec<Vec<[f64; 2]>>),
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Movement::Optimizing { speed: 0.1 }
+    }
+}
+
+impl Movement {
+    // The positions of the candidates in each of `config.frames` frames,
+    // starting at `start`. Returns `None` if the positions depend on the
+    // rendered frames.
+    pub fn precompute<R: Rng>(
+        &self,
+        rng: &mut R,
+        start: Vec<[f64; 2]>,
+        config: &ImageConfig,
+    ) -> Option<Vec<Vec<[f64; 2]>>> {
+        let frames = config.frames;
+        match self {
+            Movement::Optimizing { .. } => None,
+            Movement::Searching(search) => Some(search.positions(start, config)),
+            Movement::Static => Some(vec![start; frames]),
+            Movement::Bouncing { speed } => {
+                let mut bouncing = BouncingCandidates::new_random_direction(rng, *speed, start);
+                let mut positions = Vec::with_capacity(frames);
+                for _ in 0..frames {
+                    positions.push(bouncing.candidates.clone());
+                    bouncing.step();
+                }
+                Some(positions)
+            }
+            Movement::Scripted(script) => {
+                let last = script.last().unwrap_or(&start);
+                Some((0..frames).map(|i| script.get(i).unwrap_or(last).clone()).collect())
+            }
+        }
+    }
+}
 
 // A struct to represent a set of candidates which "bounce around" in the yee
 // diagram.
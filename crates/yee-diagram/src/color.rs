@@ -1,4 +1,5 @@
-use votery::formats::orders::TiedRankRef;
+use serde::{Deserialize, Serialize};
+use votery::formats::{orders::TiedRankRef, toc::TiedOrdersComplete};
 
 // Normal RGB color
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
@@ -7,17 +8,27 @@ pub struct Color {
 }
 
 ///
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub enum VoteColorBlending {
     /// The average of the winners of a vote
     Winners,
     /// The average of all ranked candidates, weighted according to it's group.
     /// The winners get the weight 1/1, second place gets 1/2, etc.
     Harmonic,
+    /// The average of the winners, where the saturation shows the margin of
+    /// victory: the share of voters preferring the winner over the runner-up,
+    /// minus the share preferring the runner-up. Close races are gray.
+    Margin,
+    /// Grayscale showing how many winners are tied, from white for a single
+    /// winner to black if every candidate is tied.
+    Ties,
 }
 
 pub const BLACK: Color = Color { values: [0.0, 0.0, 0.0] };
 
+// Number of colors in `Color::dutch_field`.
+pub const DUTCH_FIELD_LEN: usize = 9;
+
 impl Color {
     pub fn new(r: f64, g: f64, b: f64) -> Self {
         let c = Color { values: [r, g, b] };
@@ -34,6 +45,36 @@ impl Color {
             && self.b() <= 255.0
     }
 
+    // Convert a color in OKLCh, with the hue in degrees, to RGB. Colors outside
+    // of the RGB gamut are clamped.
+    fn from_oklch(lightness: f64, chroma: f64, hue: f64) -> Self {
+        let (sin, cos) = hue.to_radians().sin_cos();
+        let (a, b) = (chroma * cos, chroma * sin);
+        let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        let linear = [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ];
+        let [r, g, b] = linear.map(|u| {
+            let u = u.clamp(0.0, 1.0);
+            let gamma = if u <= 0.0031308 { 12.92 * u } else { 1.055 * u.powf(1.0 / 2.4) - 0.055 };
+            gamma * 255.0
+        });
+        Color::new(r, g, b)
+    }
+
+    // Mix the color with a gray of the same luma, where `saturation` 1.0 keeps
+    // the color and 0.0 gives the gray.
+    fn with_saturation(&self, saturation: f64) -> Self {
+        let luma = 0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b();
+        let gray = Color::new(luma, luma, luma);
+        let s = saturation.clamp(0.0, 1.0);
+        blend_colors_weighted([*self, gray].iter(), Some(&[s, 1.0 - s]))
+    }
+
     pub fn bw(x: usize, max: usize) -> Self {
         let v = 255.0 * x as f64 / max as f64;
         Color::new(v, v, v)
@@ -64,6 +105,16 @@ impl Color {
         [self.r() as u8, self.g() as u8, self.b() as u8]
     }
 
+    pub const fn from_rgb([r, g, b]: [u8; 3]) -> Self {
+        Color { values: [r as f64, g as f64, b as f64] }
+    }
+
+    // Hexadecimal color code, like "#e60049".
+    pub fn hex(&self) -> String {
+        let [r, g, b] = self.quantize();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
     fn to_srgb(&self) -> [f64; 3] {
         fn f(u: f64) -> f64 {
             ((u + 0.055) / 1.055).powf(2.4)
@@ -111,7 +162,6 @@ impl Color {
     }
 
     pub const fn dutch_field(n: usize) -> Color {
-        const DUTCH_FIELD_LEN: usize = 9;
         assert!(n < DUTCH_FIELD_LEN);
         const DUTCH_FIELD: [&'static str; DUTCH_FIELD_LEN] = [
             "#e60049", "#0bb4ff", "#50e991", "#e6d800", "#9b19f5", "#ffa300", "#dc0ab4", "#b3d4ff",
@@ -132,8 +182,14 @@ impl Color {
         DUTCH_FIELD_COLORS[n]
     }
 
-    /// Turn a vote into a color.
-    pub fn from_vote(vote_color: VoteColorBlending, vote: TiedRankRef, colors: &[Color]) -> Color {
+    /// Turn a vote into a color. `votes` are the votes which were counted to
+    /// get `vote`.
+    pub fn from_vote(
+        vote_color: VoteColorBlending,
+        vote: TiedRankRef,
+        votes: &TiedOrdersComplete,
+        colors: &[Color],
+    ) -> Color {
         match vote_color {
             VoteColorBlending::Harmonic => {
                 let mut mixes: Vec<Color> = Vec::new();
@@ -154,8 +210,77 @@ impl Color {
                 let i_colors = vote.winners().iter().map(|&i| &colors[i]);
                 blend_colors(i_colors)
             }
+            VoteColorBlending::Margin => {
+                let i_colors = vote.winners().iter().map(|&i| &colors[i]);
+                blend_colors(i_colors).with_saturation(margin_of_victory(vote, votes))
+            }
+            VoteColorBlending::Ties => {
+                let n = colors.len();
+                if n <= 1 {
+                    return Color::bw(1, 1);
+                }
+                Color::bw(n - vote.winners().len().clamp(1, n), n - 1)
+            }
+        }
+    }
+}
+
+// The smallest margin, as a share of `votes`, by which the winner of `vote` is
+// preferred over a runner-up. If there are several winners the margin is 0.0,
+// and if there is no runner-up it's 1.0.
+fn margin_of_victory(vote: TiedRankRef, votes: &TiedOrdersComplete) -> f64 {
+    let (&[winner], rest) = vote.split_winner_group() else {
+        return 0.0;
+    };
+    let Some(runners_up) = rest.group(0) else {
+        return 1.0;
+    };
+    if votes.voters() == 0 {
+        return 0.0;
+    }
+    let mut smallest = f64::INFINITY;
+    for &r in runners_up {
+        let mut margin: isize = 0;
+        for v in votes {
+            match v.group_of(winner).cmp(&v.group_of(r)) {
+                std::cmp::Ordering::Less => margin += 1,
+                std::cmp::Ordering::Greater => margin -= 1,
+                std::cmp::Ordering::Equal => {}
+            }
+        }
+        smallest = smallest.min(margin as f64 / votes.voters() as f64);
+    }
+    smallest.max(0.0)
+}
+
+// `n` colors which are easy to tell apart. The first ones are from
+// `Color::dutch_field`, then we step the hue by the golden angle in OKLCh, and
+// skip colors too close to the ones we already have. The same `n` always gives
+// the same colors.
+pub fn distinct_colors(n: usize) -> Vec<Color> {
+    const GOLDEN_ANGLE: f64 = 137.50776405003785;
+    const LIGHTNESS: [f64; 3] = [0.7, 0.55, 0.85];
+    // The smallest allowed distance between two colors, which we lower if we
+    // fail to find a new color for a long time.
+    const MIN_DISTANCE: f64 = 60.0;
+
+    let mut colors: Vec<Color> = (0..n.min(DUTCH_FIELD_LEN)).map(Color::dutch_field).collect();
+    let mut hue = 0.0;
+    let mut attempts = 0;
+    let mut failures = 0;
+    while colors.len() < n {
+        hue = (hue + GOLDEN_ANGLE) % 360.0;
+        let c = Color::from_oklch(LIGHTNESS[attempts % LIGHTNESS.len()], 0.13, hue);
+        attempts += 1;
+        let required = MIN_DISTANCE / (1 + failures / 64) as f64;
+        if colors.iter().all(|o| o.dist(&c) >= required) {
+            colors.push(c);
+            failures = 0;
+        } else {
+            failures += 1;
         }
     }
+    colors
 }
 
 // Used instead of Option::unwrap in const contexts
@@ -200,8 +325,59 @@ where
     Color::from_srgb(res)
 }
 
+// The median of every color channel of `cs` on its own, so the result doesn't
+// have to be one of the colors.
+pub fn median_color(cs: &[Color]) -> Color {
+    if cs.is_empty() {
+        return Color::default();
+    }
+    let mut channel = Vec::with_capacity(cs.len());
+    let mut median = [0.0; 3];
+    for (i, m) in median.iter_mut().enumerate() {
+        channel.clear();
+        channel.extend(cs.iter().map(|c| c.values[i]));
+        channel.sort_by(f64::total_cmp);
+        let mid = channel.len() / 2;
+        *m = if channel.len().is_multiple_of(2) {
+            (channel[mid - 1] + channel[mid]) / 2.0
+        } else {
+            channel[mid]
+        };
+    }
+    Color::new(median[0], median[1], median[2])
+}
+
 impl Default for Color {
     fn default() -> Self {
         Color::new(0.0, 0.0, 0.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn many_distinct_colors() {
+        for n in [0, 3, DUTCH_FIELD_LEN, 10, 40] {
+            let colors = distinct_colors(n);
+            assert_eq!(colors.len(), n);
+            assert_eq!(colors, distinct_colors(n));
+            for (i, c) in colors.iter().enumerate().take(DUTCH_FIELD_LEN) {
+                assert_eq!(*c, Color::dutch_field(i));
+            }
+            for (i, a) in colors.iter().enumerate() {
+                assert!(colors[..i].iter().all(|b| a.dist(b) > 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn median() {
+        let cs = [Color::new(0.1, 0.9, 0.5), Color::new(0.3, 0.2, 0.5), Color::new(0.2, 0.4, 0.0)];
+        assert_eq!(median_color(&cs), Color::new(0.2, 0.4, 0.5));
+        // With an even number of colors, the middle two are averaged.
+        assert_eq!(median_color(&cs[..2]), Color::new(0.2, 0.55, 0.5));
+        assert_eq!(median_color(&[]), Color::default());
+    }
+}
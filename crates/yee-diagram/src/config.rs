@@ -0,0 +1,139 @@
+// Reading the `ImageConfig` from a config file and the command line, so
+// different diagrams can be rendered without recompiling.
+
+use std::{fs, path::Path};
+
+use crate::{candidates::Movement, vector, ImageConfig};
+
+const USAGE: &str = "usage: yee-diagram [CONFIG] [--resolution N] [--frames N] [--output DIR] \
+                     [--winner-grid] [--ternary] [--sweep]";
+
+pub struct Args {
+    pub config: ImageConfig,
+    // Print the winner at every pixel instead of rendering an animation.
+    pub winner_grid: bool,
+    // Render a ternary diagram of `ImageConfig::ternary_corners` instead.
+    pub ternary: bool,
+    // Render every combination of `ImageConfig::sweep` instead.
+    pub sweep: bool,
+}
+
+// Parse the command line arguments, without the name of the program. The
+// config file is TOML, or JSON if it ends with `.json`, and the options given
+// after it override its values.
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut config = None;
+    let mut resolution = None;
+    let mut frames = None;
+    let mut output_dir = None;
+    let mut winner_grid = false;
+    let mut ternary = false;
+    let mut sweep = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--resolution" => resolution = Some(parse_number(&arg, args.next())?),
+            "--frames" => frames = Some(parse_number(&arg, args.next())?),
+            "--output" => output_dir = Some(args.next().ok_or(USAGE)?),
+            "--winner-grid" => winner_grid = true,
+            "--ternary" => ternary = true,
+            "--sweep" => sweep = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') || config.is_some() => {
+                return Err(format!("unexpected argument '{}'\n{}", arg, USAGE));
+            }
+            _ => config = Some(arg),
+        }
+    }
+
+    let mut config = match config {
+        Some(path) => load_config(Path::new(&path))?,
+        None => ImageConfig::default(),
+    };
+    if let Some(resolution) = resolution {
+        config.resolution = resolution;
+    }
+    if let Some(frames) = frames {
+        config.frames = frames;
+    }
+    if let Some(output_dir) = output_dir {
+        config.output_dir = output_dir.into();
+    }
+    if let Movement::Scripted(script) = &config.movement {
+        // The script decides how many candidates there are.
+        config.candidates = check_script(script)?;
+    }
+    if config.outputs.rank_of.is_some_and(|c| c >= config.candidates) {
+        return Err("outputs.rank_of is not one of the candidates".to_string());
+    }
+    config.voters.distribution().check()?;
+    config.turnout.check()?;
+    if let Movement::Searching(search) = &config.movement {
+        search.check()?;
+    }
+    Ok(Args { config, winner_grid, ternary, sweep })
+}
+
+fn parse_number(option: &str, value: Option<String>) -> Result<usize, String> {
+    let value = value.ok_or(USAGE)?;
+    value.parse().map_err(|_| format!("{} expects a number, not '{}'", option, value))
+}
+
+// Check that every frame of a script has the same number of candidates, all
+// inside the image, and return that number.
+fn check_script(script: &[Vec<[f64; 2]>]) -> Result<usize, String> {
+    let candidates = script.first().ok_or("the script has no frames")?.len();
+    for (i, frame) in script.iter().enumerate() {
+        if frame.len() != candidates {
+            return Err(format!("frame {} of the script has the wrong number of candidates", i));
+        }
+        if !frame.iter().all(|c| vector(c)) {
+            return Err(format!("frame {} of the script has candidates outside the image", i));
+        }
+    }
+    Ok(candidates)
+}
+
+pub fn load_config(path: &Path) -> Result<ImageConfig, String> {
+    let s = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let config = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&s).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&s).map_err(|e| e.to_string())
+    };
+    config.map_err(|e| format!("invalid config {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn overrides() {
+        let path = std::env::temp_dir().join(format!("yee-config-{}.toml", std::process::id()));
+        fs::write(&path, "resolution = 40\nframes = 3\n").unwrap();
+        let path = path.to_str().unwrap();
+        let parsed = args(&[path, "--frames", "7", "--sweep"]).unwrap();
+        // The options override the file, and the rest keeps its defaults.
+        assert_eq!((parsed.config.resolution, parsed.config.frames), (40, 7));
+        assert_eq!(parsed.config.output_dir, ImageConfig::default().output_dir);
+        assert!(parsed.sweep && !parsed.ternary && !parsed.winner_grid);
+        let parsed = args(&["--output", "out", path]).unwrap();
+        assert_eq!((parsed.config.frames, parsed.config.output_dir), (3, "out".into()));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid() {
+        let err = args(&["--bogus"]).err().unwrap();
+        assert!(err.starts_with("unexpected argument '--bogus'"));
+        assert!(args(&["a.toml", "b.toml"]).is_err());
+        assert!(args(&["--frames", "many"]).err().unwrap().contains("'many'"));
+        assert!(args(&["--resolution"]).is_err());
+        assert!(args(&["missing.toml"]).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+// When a pixel has been sampled enough. We treat the samples of a pixel as
+// independent draws and stop sampling once the confidence interval of its
+// estimate is narrow enough.
+
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Convergence {
+    // The confidence level of the interval, like 0.95.
+    pub confidence: f64,
+    // The largest allowed half-width of the confidence interval, as a
+    // fraction in [0, 1]. For `Blending::Max` this is the share of the most
+    // common color, for `Blending::Average` it's each color channel divided by
+    // 255.
+    pub tolerance: f64,
+    // Every pixel gets at least this many samples before we test it, as the
+    // estimates are unreliable for few samples.
+    pub min_samples: usize,
+}
+
+impl Default for Convergence {
+    fn default() -> Self {
+        Convergence { confidence: 0.95, tolerance: 0.05, min_samples: 10 }
+    }
+}
+
+impl Convergence {
+    // The z-score of a two-sided interval with the confidence level.
+    pub fn z(&self) -> f64 {
+        debug_assert!(0.0 < self.confidence && self.confidence < 1.0);
+        upper_quantile((1.0 - self.confidence) / 2.0)
+    }
+}
+
+// The `x` where P(Z > x) = p for a standard normal Z, for 0 < p <= 0.5. Uses
+// the rational approximation 26.2.23 from Abramowitz and Stegun, which has an
+// error below 4.5e-4.
+fn upper_quantile(p: f64) -> f64 {
+    let t = (-2.0 * p.ln()).sqrt();
+    let num = 2.515517 + 0.802853 * t + 0.010328 * t * t;
+    let den = 1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t;
+    t - num / den
+}
+
+// Half-width of the Wilson score interval of the share of samples which are
+// `mode`, the most common color. Unlike the normal approximation, it doesn't
+// shrink to nothing when every sample has the same color.
+pub fn winner_margin(samples: &[Color], mode: &Color, z: f64) -> f64 {
+    let n = samples.len() as f64;
+    let p = samples.iter().filter(|c| *c == mode).count() as f64 / n;
+    let z2 = z * z;
+    z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt()
+}
+
+// Half-width of the confidence interval of the average color, for the color
+// channel with the highest variance.
+pub fn color_margin(samples: &[Color], z: f64) -> f64 {
+    if samples.len() < 2 {
+        return f64::INFINITY;
+    }
+    let n = samples.len() as f64;
+    let mut worst: f64 = 0.0;
+    for channel in [Color::r, Color::g, Color::b] {
+        let mean = samples.iter().map(channel).sum::<f64>() / n;
+        let var = samples.iter().map(|c| (channel(c) - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        worst = worst.max((var / n).sqrt() / 255.0);
+    }
+    z * worst
+}
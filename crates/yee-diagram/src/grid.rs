@@ -0,0 +1,74 @@
+// Computing the result at every pixel without drawing anything, for when we
+// want to analyze the results ourselves instead of looking at an image.
+
+use std::fmt;
+
+use rayon::prelude::*;
+use votery::{formats::orders::TiedRank, seed::Stream, util::most_common};
+
+use crate::{sample_ranking, vector, ImageConfig};
+
+// A square grid of values, one per pixel.
+pub struct Grid<T> {
+    resolution: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.resolution.max(1))
+    }
+}
+
+impl Grid<TiedRank> {
+    // The number of pixels every candidate wins, where a tie counts for all of
+    // the tied winners.
+    pub fn wins(&self, candidates: usize) -> Vec<usize> {
+        let mut wins = vec![0; candidates];
+        for cell in &self.cells {
+            for &c in cell.as_ref().winners() {
+                wins[c] += 1;
+            }
+        }
+        wins
+    }
+}
+
+// One row per line, with the rankings separated by spaces.
+impl fmt::Display for Grid<TiedRank> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for (i, cell) in row.iter().enumerate() {
+                if i != 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", cell.as_ref())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// The most common ranking of the candidates at every pixel, using
+// `Convergence::min_samples` samples per pixel. No colors are computed.
+pub fn compute_winner_grid(candidates: &[[f64; 2]], config: &ImageConfig) -> Grid<TiedRank> {
+    let mut g = config.generator();
+    for c in candidates {
+        assert!(vector(c));
+        g.add_candidate(c);
+    }
+    let resolution = config.resolution;
+    let samples = config.convergence.min_samples.max(1);
+    let cells = (0..resolution * resolution)
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = config.seed.derive(i as u64).rng(Stream::Sampling);
+            let (xi, yi) = (i % resolution, i / resolution);
+            let mut rankings: Vec<TiedRank> =
+                (0..samples).map(|_| sample_ranking(&g, xi, yi, &mut rng, config)).collect();
+            most_common(&mut rankings).unwrap_or_default()
+        })
+        .collect();
+    Grid { resolution, cells }
+}
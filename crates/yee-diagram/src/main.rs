@@ -1,68 +1,212 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::BufWriter,
-    path::Path,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-use candidates::{BouncingCandidates, OptimizingCandidates};
-use color::{blend_colors, blend_colors_weighted, Color, VoteColorBlending};
+use annotate::{annotate, Annotations};
+use candidates::{legend, Candidate, Movement, OptimizingCandidates};
+use color::{blend_colors_weighted, Color, VoteColorBlending};
+use config::parse_args;
+use convergence::Convergence;
+use grid::compute_winner_grid;
+use marker::Marker;
 use png::Writer;
-use rand::{distributions::Uniform, prelude::Distribution, seq::SliceRandom, thread_rng, Rng};
-use rayon::{
-    iter::ParallelIterator,
-    prelude::{IntoParallelIterator, ParallelDrainRange},
-};
+use rand::{distributions::Uniform, prelude::Distribution, seq::SliceRandom, Rng};
+use rayon::prelude::*;
+use render::{Outputs, Renderer, SampleResult};
+use serde::{Deserialize, Serialize};
+use sweep::{render_sweep, Sweep};
+use ternary::render_ternary;
 use votery::{
+    chart::bar_chart,
     formats::{
         orders::{TiedRank, TiedRankRef},
+        parse::CandidateRegistry,
+        toc::TiedOrdersComplete,
         toi::TiedOrdersIncomplete,
         Specific,
     },
-    generators::gaussian::{FuzzyType, Gaussian},
+    generators::spatial::{
+        FuzzyType, MixtureComponent, Spatial, Turnout, UtilityModel, VoterDistribution,
+    },
     methods::{
         random_ballot::{RandomBallot, RandomBallotSingle},
-        Borda, Fptp, RandomVotingMethod,
+        Fptp, MethodConfig, RandomVotingMethod,
     },
-    prelude::VotingMethod,
+    seed::{ElectionSeed, Stream},
+    spatial::{DistanceMetric, Point},
+    util::most_common,
 };
 
+mod annotate;
 mod candidates;
 mod color;
+mod config;
+mod convergence;
+mod grid;
+mod marker;
+mod optimize;
+mod render;
+mod sweep;
+mod ternary;
 mod vector;
 
-#[derive(PartialEq, Eq)]
+// How many samples every pixel gets.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
 enum Adaptive {
+    // Every pixel gets `Convergence::min_samples` samples, rounded up to a
+    // multiple of `ImageConfig::sample_size`.
     Disable,
+    // Pixels are sampled until they, and every pixel around them, have
+    // converged according to `ImageConfig::convergence`.
     Enable,
+    // Like `Enable`, but also write an image showing how many times each pixel
+    // was sampled.
     Display,
 }
 
-// We only support 2 dimensional images right now
+// Images are 2 dimensional, even though the spatial model supports more
+// dimensions
 const DIMENSIONS: usize = 2;
 
 // Each image is contained in a box [0.0, 1.0] x [0.0, 1.0]
 const MIN: f64 = 0.0;
 const MAX: f64 = 1.0;
 
+// Every field can be left out of a config file, and then gets its default
+// value.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 struct ImageConfig {
     points: usize,
     resolution: usize,
     frames: usize,
     candidates: usize,
     sample_size: usize,
-    max_noise: f64,
+    convergence: Convergence,
     variance: f64,
     adapt_mode: Adaptive,
     around_size: usize,
+    // How the samples of a pixel are combined, see `Blending`.
     blending: Blending,
     vote_color: VoteColorBlending,
     fuzzy: FuzzyType,
+    turnout: Turnout,
+    metric: DistanceMetric,
+    model: UtilityModel,
+    method: MethodConfig,
+    annotations: Annotations,
+    marker: Marker,
+    outputs: Outputs,
+    movement: Movement,
+    // The directory where the images are written.
+    output_dir: PathBuf,
+    // The three profiles mixed in a ternary diagram, with the votes of each
+    // separated by `;`.
+    ternary_corners: [String; 3],
+    // The seed of every random choice, so that a diagram can be rendered
+    // again. A new one is picked for every run if it's left out.
+    seed: ElectionSeed,
+    // The values rendered with `--sweep`.
+    sweep: Sweep,
+    // When frames are rendered one after another, keep the samples of the
+    // previous frame at pixels further than this from every candidate which
+    // moved, and not on a border. Disabled if it's left out.
+    warm_start: Option<f64>,
+    // With a fixed electorate every pixel describes the same voters, instead
+    // of a new sample of voters around it.
+    electorate: Electorate,
+    // How the voters of a sample are distributed around the pixel.
+    voters: Voters,
+}
+
+// Which voters are around every pixel.
+#[derive(Clone, Default, Deserialize, Serialize)]
+enum Electorate {
+    // New voters are drawn for every sample.
+    #[default]
+    Resampled,
+    // `points` voters are drawn once from the seed, and the same voters are
+    // moved to every pixel.
+    Fixed,
+    // These voters, relative to the pixel, are moved to every pixel.
+    Given(Vec<[f64; DIMENSIONS]>),
+}
+
+// How the voters of a sample are distributed around the pixel, see
+// `VoterDistribution`.
+#[derive(Clone, Default, Deserialize, Serialize)]
+enum Voters {
+    // Normally distributed with `variance`.
+    #[default]
+    Gaussian,
+    // Uniformly distributed in a box which is `2 * half_size[i]` wide in
+    // dimension `i`.
+    UniformBox {
+        half_size: [f64; DIMENSIONS],
+    },
+    // A mixture of normal distributions, e.g. two groups of voters on either
+    // side of the pixel.
+    Mixture(Vec<Component>),
+    // `clusters` centers normally distributed with `variance` every sample,
+    // with voters normally distributed around them with `spread`.
+    Clustered {
+        clusters: usize,
+        spread: f64,
+    },
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Component {
+    weight: f64,
+    // The center of the component, relative to the pixel.
+    offset: [f64; DIMENSIONS],
+    variance: f64,
+}
+
+impl Voters {
+    fn distribution(&self) -> VoterDistribution<DIMENSIONS> {
+        match self {
+            Voters::Gaussian => VoterDistribution::Gaussian,
+            Voters::UniformBox { half_size } => {
+                VoterDistribution::UniformBox { half_size: Point::from(half_size) }
+            }
+            Voters::Mixture(components) => VoterDistribution::Mixture(
+                components
+                    .iter()
+                    .map(|c| MixtureComponent {
+                        weight: c.weight,
+                        offset: Point::from(c.offset),
+                        variance: c.variance,
+                    })
+                    .collect(),
+            ),
+            Voters::Clustered { clusters, spread } => {
+                VoterDistribution::Clustered { clusters: *clusters, variance: *spread }
+            }
+        }
+    }
 }
 
+// How the colors of the samples of a pixel are combined into its color.
+#[derive(Clone, Deserialize, Serialize)]
 enum Blending {
+    // The average color, where a pixel has converged once the share of its
+    // most common color is known.
     Max,
+    // The average color, where a pixel has converged once every color
+    // channel of the average is known.
     Average,
+    // The median of every color channel on its own, which ignores a few
+    // outlying samples. Converges like `Average`.
+    Median,
+    // Every sampled ranking gives the candidates Borda points, and the pixel
+    // gets the color of the candidates with the most points in total. This
+    // shows the consensus of the rankings instead of mixing the colors of
+    // different winners. Converges like `Max`.
+    Consensus,
 }
 
 impl Default for ImageConfig {
@@ -73,17 +217,53 @@ impl Default for ImageConfig {
             frames: 1000,
             candidates: 4,
             sample_size: 5,
-            max_noise: 0.2,
+            convergence: Convergence::default(),
             variance: 0.2,
             adapt_mode: Adaptive::Enable,
             around_size: 3,
             blending: Blending::Average,
             vote_color: VoteColorBlending::Harmonic,
             fuzzy: FuzzyType::Scaling(0.4),
+            turnout: Turnout::Full,
+            metric: DistanceMetric::Euclidean,
+            model: UtilityModel::Proximity,
+            method: MethodConfig::Borda,
+            annotations: Annotations::ALL,
+            marker: Marker::default(),
+            outputs: Outputs::default(),
+            movement: Movement::default(),
+            output_dir: PathBuf::from("animation"),
+            ternary_corners: ["0,1,2".into(), "1,2,0".into(), "2,0,1".into()],
+            seed: ElectionSeed::from_entropy(),
+            sweep: Sweep::default(),
+            warm_start: None,
+            electorate: Electorate::Resampled,
+            voters: Voters::Gaussian,
         }
     }
 }
 
+impl ImageConfig {
+    // A generator of votes around the pixels, without any candidates.
+    fn generator(&self) -> Spatial<DIMENSIONS> {
+        let mut g = Spatial::new(self.variance, self.points, self.fuzzy);
+        g.set_metric(self.metric);
+        g.set_model(self.model);
+        // Checked when the config is parsed.
+        g.set_turnout(self.turnout).unwrap();
+        g.set_distribution(self.voters.distribution()).unwrap();
+        // The center of the image
+        g.set_neutral([(MIN + MAX) / 2.0; DIMENSIONS]);
+        let electorate = match &self.electorate {
+            Electorate::Resampled => None,
+            Electorate::Fixed => Some(g.sample_electorate(&mut self.seed.rng(Stream::Sampling))),
+            Electorate::Given(voters) => Some(voters.iter().map(Point::from).collect()),
+        };
+        g.set_electorate(electorate);
+        g
+    }
+}
+
 fn create_png_writer(filename: &str, resolution: usize) -> Writer<BufWriter<File>> {
     let path = Path::new(filename);
     let file = File::create(path).unwrap();
@@ -94,19 +274,42 @@ fn create_png_writer(filename: &str, resolution: usize) -> Writer<BufWriter<File
     encoder.write_header().unwrap()
 }
 
+// Sample votes around pixel (xi, yi).
+fn sample_votes<R: Rng>(
+    g: &Spatial<DIMENSIONS>,
+    xi: usize,
+    yi: usize,
+    rng: &mut R,
+    config: &ImageConfig,
+) -> TiedOrdersComplete {
+    let x: f64 = (xi as f64) / (config.resolution as f64) * (MAX - MIN) + MIN;
+    let y: f64 = (yi as f64) / (config.resolution as f64) * (MAX - MIN) + MIN;
+    g.sample(rng, &Point::new([x, y]))
+}
+
+// Sample votes around pixel (xi, yi) and rank the candidates using them.
+fn sample_ranking<R: Rng>(
+    g: &Spatial<DIMENSIONS>,
+    xi: usize,
+    yi: usize,
+    rng: &mut R,
+    config: &ImageConfig,
+) -> TiedRank {
+    let votes = sample_votes(g, xi, yi, rng, config);
+    config.method.rank(&votes, rng).unwrap()
+}
+
 fn sample_pixel<R: Rng>(
-    g: &Gaussian,
+    g: &Spatial<DIMENSIONS>,
     xi: usize,
     yi: usize,
     rng: &mut R,
     colors: &[Color],
     config: &ImageConfig,
 ) -> (Color, TiedRank) {
-    let x: f64 = (xi as f64) / (config.resolution as f64) * (MAX - MIN) + MIN;
-    let y: f64 = (yi as f64) / (config.resolution as f64) * (MAX - MIN) + MIN;
-    let votes = g.sample(rng, &[x, y]).to_toi().unwrap();
-    let vote: TiedRank = Borda::count(&votes).unwrap().as_vote();
-    let color = Color::from_vote(config.vote_color, vote.as_ref(), colors);
+    let votes = sample_votes(g, xi, yi, rng, config);
+    let vote = config.method.rank(&votes, rng).unwrap();
+    let color = Color::from_vote(config.vote_color, vote.as_ref(), &votes, colors);
     (color, vote)
 }
 
@@ -122,153 +325,133 @@ fn random_candidates<R: Rng>(rng: &mut R, n: usize) -> Vec<[f64; DIMENSIONS]> {
 }
 
 fn main() {
-    let config = ImageConfig::default();
-    let candidates = random_candidates(&mut thread_rng(), config.candidates);
-    let mut directions = Vec::new();
-    for [x, y] in &candidates {
-        directions.push([y / 100.0, x / 100.0]);
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut config = args.config;
+    eprintln!("seed: {}", config.seed.value());
+    if args.ternary {
+        fs::create_dir_all(&config.output_dir).unwrap();
+        write_config(&config);
+        let name = config.output_dir.join(format!("{}_ternary", config.method.name()));
+        if let Err(e) = render_ternary(name.to_str().unwrap(), &config) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut rng = config.seed.rng(Stream::Generation);
+    let candidates = match &config.movement {
+        Movement::Scripted(script) => script[0].clone(),
+        _ => random_candidates(&mut rng, config.candidates),
+    };
+    if args.winner_grid {
+        let grid = compute_winner_grid(&candidates, &config);
+        print!("{}", grid);
+        // A summary for the terminal, kept out of the grid itself.
+        let labels: Vec<String> = (0..candidates.len()).map(|i| format!("c{}", i)).collect();
+        let wins: Vec<f64> = grid.wins(candidates.len()).into_iter().map(|w| w as f64).collect();
+        eprint!("{}", bar_chart(&labels, &wins, 40));
+        return;
+    }
+    let names = (0..candidates.len()).map(|i| format!("c{}", i)).collect();
+    let registry = CandidateRegistry::new(names).unwrap();
+    let metadata = Candidate::from_registry(&registry);
+    fs::create_dir_all(&config.output_dir).unwrap();
+    write_config(&config);
+    if args.sweep {
+        if let Err(e) = render_sweep(&candidates, &metadata, &config) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    match config.movement.precompute(&mut rng, candidates.clone(), &config) {
+        Some(positions) => render_frames(&positions, &metadata, &config),
+        None => {
+            // The animation moves the candidates according to the rankings.
+            config.outputs.rankings = true;
+            render_animation(candidates, &metadata, &config);
+        }
     }
-    let colors: Vec<Color> =
-        (0..candidates.len()).into_iter().map(|i| Color::dutch_field(i)).collect();
-    render_animation(candidates, directions, &colors, &config);
 }
 
-fn render_animation(
-    candidates: Vec<[f64; 2]>,
-    directions: Vec<[f64; 2]>,
-    colors: &[Color],
-    config: &ImageConfig,
-) {
-    let mut moving_candidates = OptimizingCandidates::new(candidates, 0.1);
+// Write the whole config, including the seed, to `config.json` in the output
+// directory. Giving it as the config renders the same images again.
+fn write_config(config: &ImageConfig) {
+    let json = serde_json::to_string_pretty(config).unwrap();
+    fs::write(config.output_dir.join("config.json"), json + "\n").unwrap();
+}
+
+fn frame_name(config: &ImageConfig, frame: usize) -> String {
+    let name = config.output_dir.join(format!("{}_{}", config.method.name(), frame));
+    name.to_str().unwrap().to_string()
+}
+
+// Render a frame for every element of `positions` in parallel. Each thread
+// keeps its own `Renderer`, and every frame is written to its own file, so
+// the output doesn't depend on the order in which frames finish.
+fn render_frames(positions: &[Vec<[f64; 2]>], metadata: &[Candidate], config: &ImageConfig) {
+    positions.par_iter().enumerate().for_each_init(
+        || (Renderer::new(config), SampleResult::default()),
+        |(renderer, result), (i, candidates)| {
+            let name = frame_name(config, i);
+            render_image(&name, renderer, candidates, metadata, Some(i), result, false);
+        },
+    );
+}
+
+// Render an animation where the candidates move according to
+// `Movement::Optimizing`, one frame at a time.
+fn render_animation(candidates: Vec<[f64; 2]>, metadata: &[Candidate], config: &ImageConfig) {
+    let speed = match config.movement {
+        Movement::Optimizing { speed } => speed,
+        _ => unreachable!("only optimizing candidates depend on the previous frame"),
+    };
+    let mut moving_candidates = OptimizingCandidates::new(candidates, speed);
+    let mut renderer = Renderer::new(config);
+    let mut result = SampleResult::default();
     for i in 0..config.frames {
-        let SampleResult { mut all_rankings, .. } = render_image(
-            &format!("animation/slow_borda_{}", i),
+        render_image(
+            &frame_name(config, i),
+            &mut renderer,
             &moving_candidates.candidates,
-            colors,
-            config,
+            metadata,
+            Some(i),
+            &mut result,
+            true,
         );
+        if let Some(margins) = result.margins() {
+            let max = margins.iter().flatten().fold(0.0, |a: f64, &b| a.max(b));
+            println!("largest margin of error: {}", max);
+        }
         let x = config.resolution / 4;
         let y = config.resolution / 2;
-        let v = most_common(&mut all_rankings[y][x]);
+        let rankings = result.rankings_mut(x, y).expect("rankings are needed for the animation");
+        let v = most_common(rankings).unwrap_or_default();
         println!("{:?}, {:?}", moving_candidates.candidates, v);
         moving_candidates.step(v.as_ref());
         println!("{:?}", moving_candidates.candidates);
     }
 }
 
-// We have this big struct to store results from sampling an image, but we
-// should use `Option`.
-struct SampleResult {
-    image: Vec<Vec<[u8; 3]>>,
-    sample_count: Vec<Vec<usize>>,
-    all_rankings: Vec<Vec<Vec<TiedRank>>>,
-}
-
-fn get_image(candidates: &[[f64; 2]], colors: &[Color], config: &ImageConfig) -> SampleResult {
-    let mut g = Gaussian::new(DIMENSIONS, config.variance, config.points, config.fuzzy);
-    for c in candidates {
-        assert!(vector(c));
-        g.add_candidate(c);
-    }
-    let mut iterations = 0;
-    let mut all_samples: Vec<Vec<Vec<Color>>> =
-        vec![vec![Vec::new(); config.resolution]; config.resolution];
-    let mut needs_samples = vec![vec![true; config.resolution]; config.resolution];
-    let mut queue = Vec::with_capacity(config.resolution * config.resolution);
-    let mut sample_count: Vec<Vec<usize>> = vec![vec![0; config.resolution]; config.resolution];
-    let mut all_rankings: Vec<Vec<Vec<TiedRank>>> =
-        vec![vec![Vec::new(); config.resolution]; config.resolution];
-    loop {
-        iterations += 1;
-        // First we'll add every pixel that needs samples to the queue
-        queue.clear();
-        for yi in 0..config.resolution {
-            for xi in 0..config.resolution {
-                if needs_samples[yi][xi] {
-                    queue.push((xi, yi));
-                    needs_samples[yi][xi] = false;
-                }
-            }
-        }
-        println!("{}: pixels to sample: {}", iterations, queue.len());
-        // Then we actually get some samples
-        let new_samples: Vec<(usize, usize, Vec<Color>, Vec<TiedRank>)> = queue
-            .par_drain(..)
-            .map(|(xi, yi)| {
-                let mut rng = thread_rng();
-                let mut new_samples1 = Vec::with_capacity(config.sample_size);
-                let mut new_samples2 = Vec::with_capacity(config.sample_size);
-                for _ in 0..config.sample_size {
-                    let (color, vote) = sample_pixel(&g, xi, yi, &mut rng, &colors, &config);
-                    new_samples1.push(color);
-                    new_samples2.push(vote);
-                }
-                (xi, yi, new_samples1, new_samples2)
-            })
-            .collect();
-        // Then we need to decide which pixels need more samples. We say that a pixel
-        // needs more samples if it hasn't converged, or if any of its neighbours
-        // haven't converged yet
-        let mut done = true;
-        for (xi, yi, new_colors, new_votes) in new_samples {
-            all_rankings[yi][xi].extend(new_votes);
-            sample_count[yi][xi] += 1;
-            let old = &mut all_samples[yi][xi];
-            if old.len() == 0 || needs_samples[yi][xi] {
-                needs_samples[yi][xi] = true;
-                old.extend(new_colors);
-                done = false;
-                continue;
-            }
-            let more_samples = match config.blending {
-                Blending::Max => {
-                    let old_color = most_common(old);
-                    old.extend(new_colors);
-                    let new_color = most_common(old);
-                    old_color != new_color
-                }
-                Blending::Average => {
-                    let old_color = blend_colors(old.iter());
-                    old.extend(new_colors);
-                    let new_color = blend_colors(old.iter());
-                    let d = old_color.dist(&new_color);
-                    d > config.max_noise
-                }
-            };
-            if more_samples {
-                done = false;
-                let max_xi = xi.saturating_add(config.around_size).min(config.resolution - 1);
-                let min_xi = xi.saturating_sub(config.around_size);
-                let max_yi = yi.saturating_add(config.around_size).min(config.resolution - 1);
-                let min_yi = yi.saturating_sub(config.around_size);
-                for y in min_yi..=max_yi {
-                    for x in min_xi..=max_xi {
-                        needs_samples[y][x] = true;
-                    }
-                }
-            }
-        }
-        if done {
-            break;
-        }
-    }
-    let mut image = vec![vec![[0, 0, 0]; config.resolution]; config.resolution];
-    for yi in 0..config.resolution {
-        for xi in 0..config.resolution {
-            image[yi][xi] = blend_colors(all_samples[yi][xi].iter()).quantize();
-        }
-    }
-    SampleResult { image, sample_count, all_rankings }
-}
-
-// TODO: This should return the image and all calculated votes (if they are
-// needed for other parts later)
+// Render an image to `name`.png, and store the results in `result`. With
+// `warm_start`, the image starts from the previous one of `renderer`, see
+// `Renderer::render_next_into`.
 fn render_image(
     name: &str,
+    renderer: &mut Renderer,
     candidates: &[[f64; 2]],
-    colors: &[Color],
-    config: &ImageConfig,
-) -> SampleResult {
+    metadata: &[Candidate],
+    frame: Option<usize>,
+    result: &mut SampleResult,
+    warm_start: bool,
+) {
+    let config = renderer.config();
     debug_assert!(candidates.len() == config.candidates);
     // Output file
     let mut writer = create_png_writer(&format!("{}.png", name), config.resolution);
@@ -278,10 +461,15 @@ fn render_image(
         None
     };
 
-    debug_assert!(colors.len() == config.candidates);
-    let SampleResult { mut image, sample_count, all_rankings } =
-        get_image(candidates, colors, config);
+    debug_assert!(metadata.len() == config.candidates);
+    let seed = config.seed.derive(frame.unwrap_or(0) as u64);
+    if warm_start {
+        renderer.render_next_into(candidates, metadata, seed, result);
+    } else {
+        renderer.render_into(candidates, metadata, seed, result);
+    }
     if config.adapt_mode == Adaptive::Display {
+        let sample_count = result.sample_count().unwrap();
         let max_samples = sample_count.iter().map(|c| c.iter().max().unwrap()).max().unwrap();
         let adaptive_image: Vec<Vec<[u8; 3]>> = sample_count
             .iter()
@@ -290,113 +478,50 @@ fn render_image(
         let image_bytes: Vec<u8> = adaptive_image.iter().flatten().flatten().copied().collect();
         writer_adaptive.unwrap().write_image_data(&image_bytes).unwrap();
     }
+    let metadata = result.candidates().to_vec();
+    let image = result.image_mut();
     for c in 0..config.candidates {
-        add_circle(&mut image, colors[c], &candidates[c], config.resolution);
+        config.marker.draw(image, metadata[c].color, &candidates[c]);
     }
-    let image_bytes: Vec<u8> = image.iter().flatten().flatten().copied().collect();
+    annotate(image, config.annotations, &metadata, config.method.name(), frame);
+    let image_bytes: Vec<u8> = result.image().iter().flatten().flatten().copied().collect();
     writer.write_image_data(&image_bytes).unwrap();
-    SampleResult { image, sample_count, all_rankings }
-}
-
-fn most_common<T>(v: &mut Vec<T>) -> T
-where
-    T: Default + PartialOrd + Clone,
-{
-    if v.len() == 0 {
-        return T::default();
+    std::fs::write(format!("{}_legend.txt", name), legend(&metadata)).unwrap();
+    if config.outputs.heatmaps {
+        write_heatmaps(name, result, config.resolution);
     }
-    v.sort_by(|a, b| a.partial_cmp(&b).unwrap());
-    let mut most_common = None;
-    let mut current_count = 0;
-    let mut max_count = 0;
-    let mut prev = None;
-    for o in v.iter() {
-        match most_common {
-            Some(_) => {
-                if prev.unwrap() == o {
-                    current_count += 1;
-                    if current_count > max_count {
-                        max_count = current_count;
-                        most_common = Some(o)
-                    }
-                } else {
-                    current_count = 1;
-                }
-            }
-            None => {
-                most_common = Some(o);
-                current_count = 1;
-            }
-        }
-        prev = Some(o);
+    if let Some(c) = config.outputs.rank_of {
+        write_rank_map(&format!("{}_rank_{}.png", name, c), result, config.resolution);
     }
-
-    most_common.unwrap().clone()
 }
 
-fn add_circle(
-    image: &mut Vec<Vec<[u8; 3]>>,
-    color: Color,
-    pos: &[f64; DIMENSIONS],
-    resolution: usize,
-) {
-    let r = 0.02;
-    let pi = std::f64::consts::PI;
-    let mut angle: f64 = 0.0;
-    while angle < 360.0 {
-        let mut r_in = 0.0;
-        while r_in < r {
-            let x1 = r_in * f64::cos(angle * pi / 180.0);
-            let y1 = r_in * f64::sin(angle * pi / 180.0);
-            let x = pos[0] + x1;
-            let y = pos[1] + y1;
-            put_pixel(image, x, y, color, resolution);
-            r_in += 0.001
-        }
-        angle += 0.1;
-    }
-
-    let mut angle: f64 = 0.0;
-    while angle < 360.0 {
-        let x1 = r * f64::cos(angle * pi / 180.0);
-        let y1 = r * f64::sin(angle * pi / 180.0);
-        let x = pos[0] + x1;
-        let y = pos[1] + y1;
-        put_pixel(image, x, y, color::BLACK, resolution);
-        angle += 0.1;
-    }
+// Write the average rank of `Outputs::rank_of` at every pixel to `name`,
+// from white when it's always ranked first to black when it's always ranked
+// last.
+fn write_rank_map(name: &str, result: &SampleResult, resolution: usize) {
+    let average_rank = result.average_rank().expect("ranks were kept");
+    let last = result.candidates().len().max(2) as f64;
+    let image_bytes: Vec<u8> = average_rank
+        .iter()
+        .flatten()
+        .flat_map(|&r| [(255.0 * (last - r) / (last - 1.0)).round() as u8; 3])
+        .collect();
+    let mut writer = create_png_writer(name, resolution);
+    writer.write_image_data(&image_bytes).unwrap();
 }
 
-// maps [MIN, MAX) -> [0, RESOLUTION)
-fn f64_to_coord(u: f64, resolution: usize) -> usize {
-    let s = ((u - MIN) / (MAX - MIN) * resolution as f64) as usize;
-    if s >= resolution {
-        resolution - 1
-    } else {
-        s
+// Write how often every candidate won at every pixel to `name`_heat_`c`.png,
+// where white means that candidate `c` always won.
+fn write_heatmaps(name: &str, result: &SampleResult, resolution: usize) {
+    for c in 0..result.candidates().len() {
+        let heatmap = result.heatmap(c).expect("heatmaps were kept");
+        let image_bytes: Vec<u8> =
+            heatmap.iter().flatten().flat_map(|&f| [(255.0 * f).round() as u8; 3]).collect();
+        let mut writer = create_png_writer(&format!("{}_heat_{}.png", name, c), resolution);
+        writer.write_image_data(&image_bytes).unwrap();
     }
 }
 
-fn put_pixel(image: &mut Vec<Vec<[u8; 3]>>, x: f64, y: f64, color: Color, resolution: usize) {
-    let xx = f64_to_coord(x, resolution);
-    let yy = f64_to_coord(y, resolution);
-    image[yy][xx] = color.quantize();
-}
-
-// void DrawCircle(int x, int y, int r, int color)
-// {
-//       static const double PI = 3.1415926535;
-//       double i, angle, x1, y1;
-
-//       for(i = 0; i < 360; i += 0.1)
-//       {
-//             angle = i;
-//             x1 = r * cos(angle * PI / 180);
-//             y1 = r * sin(angle * PI / 180);
-//             putpixel(x + x1, y + y1, color);
-//       }
-// }
-
 fn vector(n: &[f64]) -> bool {
     if n.len() != DIMENSIONS {
         return false;
@@ -0,0 +1,102 @@
+// Anti-aliased markers showing where the candidates are. Every pixel is
+// supersampled, so the markers are smooth and can be placed with subpixel
+// accuracy.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::{Color, BLACK},
+    MAX, MIN,
+};
+
+// Number of samples per pixel, along each axis.
+const SUPERSAMPLING: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MarkerShape {
+    Circle,
+    Square,
+    Cross,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Marker {
+    pub shape: MarkerShape,
+    // Radius of the marker, in the same units as the candidates' positions.
+    pub radius: f64,
+    // Draw a black ring, one pixel wide, around the marker.
+    pub outline: bool,
+}
+
+impl Default for Marker {
+    fn default() -> Self {
+        Marker { shape: MarkerShape::Circle, radius: 0.02, outline: true }
+    }
+}
+
+impl MarkerShape {
+    // If the offset (dx, dy) from the center is inside the shape with radius
+    // `r`.
+    fn contains(self, dx: f64, dy: f64, r: f64) -> bool {
+        if r <= 0.0 {
+            return false;
+        }
+        let (dx, dy) = (dx.abs(), dy.abs());
+        match self {
+            MarkerShape::Circle => dx * dx + dy * dy <= r * r,
+            MarkerShape::Square => dx <= r && dy <= r,
+            MarkerShape::Cross => (dx <= r && dy <= r / 4.0) || (dy <= r && dx <= r / 4.0),
+        }
+    }
+}
+
+// Cover `t` of the pixel `a` with `b` and `s` of it with `c`.
+fn mix(a: [u8; 3], b: Color, t: f64, c: Color, s: f64) -> [u8; 3] {
+    let (b, c) = (b.quantize(), c.quantize());
+    let mut res = [0; 3];
+    for (i, r) in res.iter_mut().enumerate() {
+        let v = a[i] as f64 * (1.0 - t - s) + b[i] as f64 * t + c[i] as f64 * s;
+        *r = v.round() as u8;
+    }
+    res
+}
+
+impl Marker {
+    // Draw the marker centered at `pos` with `color` on `image`, which covers
+    // [MIN, MAX] x [MIN, MAX].
+    pub fn draw(&self, image: &mut [Vec<[u8; 3]>], color: Color, pos: &[f64; 2]) {
+        let resolution = image.len();
+        let pixel = (MAX - MIN) / resolution as f64;
+        let ring_width = if self.outline { pixel } else { 0.0 };
+        let to_pixel = |u: f64| ((u - MIN) / pixel).floor();
+        let (x0, x1) = (to_pixel(pos[0] - self.radius), to_pixel(pos[0] + self.radius));
+        let (y0, y1) = (to_pixel(pos[1] - self.radius), to_pixel(pos[1] + self.radius));
+        let clamp = |p: f64| p.clamp(0.0, (resolution - 1) as f64) as usize;
+
+        let step = pixel / SUPERSAMPLING as f64;
+        let total = (SUPERSAMPLING * SUPERSAMPLING) as f64;
+        let (y0, y1, x0, x1) = (clamp(y0), clamp(y1), clamp(x0), clamp(x1));
+        for (yi, row) in image.iter_mut().enumerate().take(y1 + 1).skip(y0) {
+            for (xi, p) in row.iter_mut().enumerate().take(x1 + 1).skip(x0) {
+                let mut inside = 0;
+                let mut ring = 0;
+                for sy in 0..SUPERSAMPLING {
+                    for sx in 0..SUPERSAMPLING {
+                        let x = MIN + xi as f64 * pixel + (sx as f64 + 0.5) * step;
+                        let y = MIN + yi as f64 * pixel + (sy as f64 + 0.5) * step;
+                        let (dx, dy) = (x - pos[0], y - pos[1]);
+                        if self.shape.contains(dx, dy, self.radius - ring_width) {
+                            inside += 1;
+                        } else if self.shape.contains(dx, dy, self.radius) {
+                            ring += 1;
+                        }
+                    }
+                }
+                if inside + ring != 0 {
+                    *p = mix(*p, color, inside as f64 / total, BLACK, ring as f64 / total);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,427 @@
+// Moving the candidates with gradient-free optimizers. Every candidate looks
+// for the position which maximizes its `Objective`, given where the other
+// candidates are, and takes one step of its `Optimizer` per frame.
+//
+// The objective is estimated from a fixed set of sampled electorates, with
+// their centers spread uniformly over the image, so it's a deterministic
+// function of the positions, which the optimizers rely on.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, StandardNormal};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use votery::{formats::orders::TiedRankRef, seed::Stream, spatial::Point};
+
+use crate::{vector, ImageConfig, DIMENSIONS, MAX, MIN};
+
+type Position = [f64; DIMENSIONS];
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum Optimizer {
+    // The Nelder-Mead simplex method.
+    NelderMead,
+    // The covariance matrix adaptation evolution strategy.
+    CmaEs,
+}
+
+// What every candidate maximizes, averaged over the electorates.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum Objective {
+    // The share of the electorates the candidate wins, where a tie is split
+    // between the tied winners.
+    WinShare,
+    // How high the candidate is ranked, from 1 when nobody is ranked above
+    // it to 0 when everyone else is.
+    Placement,
+}
+
+impl Objective {
+    fn value(self, ranking: TiedRankRef, c: usize) -> f64 {
+        match self {
+            Objective::WinShare => {
+                let winners = ranking.winners();
+                if winners.contains(&c) {
+                    1.0 / winners.len() as f64
+                } else {
+                    0.0
+                }
+            }
+            Objective::Placement => {
+                let others = ranking.len().saturating_sub(1);
+                if others == 0 {
+                    return 1.0;
+                }
+                let above: usize =
+                    ranking.iter_groups().take_while(|g| !g.contains(&c)).map(|g| g.len()).sum();
+                1.0 - above as f64 / others as f64
+            }
+        }
+    }
+}
+
+// The settings of `Movement::Searching`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Search {
+    pub optimizer: Optimizer,
+    pub objective: Objective,
+    // The initial size of the simplex of `NelderMead`, or the initial
+    // standard deviation of `CmaEs`.
+    pub step: f64,
+    // How many electorates the objective is estimated from.
+    pub electorates: usize,
+    // The candidates stop moving once the spread of every optimizer, i.e.
+    // how far it still searches, is below this.
+    pub tolerance: f64,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Search {
+            optimizer: Optimizer::CmaEs,
+            objective: Objective::WinShare,
+            step: 0.1,
+            electorates: 200,
+            tolerance: 1e-3,
+        }
+    }
+}
+
+impl Search {
+    pub fn check(&self) -> Result<(), String> {
+        if self.step.is_nan() || self.step <= 0.0 {
+            return Err("the step of the search must be positive".to_string());
+        }
+        if self.electorates == 0 {
+            return Err("the search needs at least one electorate".to_string());
+        }
+        Ok(())
+    }
+
+    // The objective of candidate `c` with the candidates at `positions`.
+    fn evaluate(&self, positions: &[Position], c: usize, config: &ImageConfig) -> f64 {
+        let mut g = config.generator();
+        for p in positions {
+            g.add_candidate(p);
+        }
+        let total: f64 = (0..self.electorates)
+            .into_par_iter()
+            .map(|k| {
+                let seed = config.seed.derive(k as u64);
+                let mut rng = seed.rng(Stream::Sampling);
+                let center = [rng.gen_range(MIN..=MAX), rng.gen_range(MIN..=MAX)];
+                let votes = g.sample(&mut rng, &Point::new(center));
+                let ranking = config.method.rank(&votes, &mut seed.rng(Stream::TieBreaking));
+                self.objective.value(ranking.unwrap().as_ref(), c)
+            })
+            .sum();
+        total / self.electorates as f64
+    }
+
+    // The positions of the candidates in each of `config.frames` frames,
+    // starting at `start`. Between two frames every candidate takes a step,
+    // with the other candidates where they were in the previous frame. The
+    // progress is printed and written to `search.csv` in `config.output_dir`.
+    pub fn positions(&self, start: Vec<Position>, config: &ImageConfig) -> Vec<Vec<Position>> {
+        let mut optimizers: Vec<Box<dyn Optimize>> = start
+            .iter()
+            .map(|&p| -> Box<dyn Optimize> {
+                match self.optimizer {
+                    Optimizer::NelderMead => Box::new(NelderMead::new(p, self.step)),
+                    Optimizer::CmaEs => Box::new(CmaEs::new(p, self.step)),
+                }
+            })
+            .collect();
+        let mut rngs: Vec<_> = (0..start.len())
+            .map(|c| config.seed.derive(c as u64).rng(Stream::Generation))
+            .collect();
+        let file = File::create(config.output_dir.join("search.csv")).unwrap();
+        let mut report = BufWriter::new(file);
+        writeln!(report, "round,candidate,x,y,objective,spread").unwrap();
+
+        let mut positions = Vec::with_capacity(config.frames);
+        let mut current = start;
+        let mut converged = false;
+        for round in 0..config.frames {
+            if round != 0 && !converged {
+                for (c, (optimizer, rng)) in optimizers.iter_mut().zip(&mut rngs).enumerate() {
+                    let mut moved = current.clone();
+                    let mut f = |p: Position| {
+                        moved[c] = p;
+                        self.evaluate(&moved, c, config)
+                    };
+                    optimizer.step(&mut f, rng);
+                }
+                current = optimizers.iter().map(|o| o.position()).collect();
+                let objectives: Vec<f64> =
+                    (0..current.len()).map(|c| self.evaluate(&current, c, config)).collect();
+                let spreads: Vec<f64> = optimizers.iter().map(|o| o.spread()).collect();
+                println!("round {}: objectives {:?}, spreads {:?}", round, objectives, spreads);
+                for (c, p) in current.iter().enumerate() {
+                    let (objective, spread) = (objectives[c], spreads[c]);
+                    writeln!(report, "{},{},{},{},{},{}", round, c, p[0], p[1], objective, spread)
+                        .unwrap();
+                }
+                if spreads.iter().all(|&s| s < self.tolerance) {
+                    converged = true;
+                    println!("converged after {} rounds", round);
+                }
+            }
+            positions.push(current.clone());
+        }
+        report.flush().unwrap();
+        positions
+    }
+}
+
+// An optimizer of the position of a single candidate.
+trait Optimize {
+    // The best position found so far.
+    fn position(&self) -> Position;
+    // How far from `position` the optimizer still searches.
+    fn spread(&self) -> f64;
+    // Take a step towards a maximum of `f`. The other candidates may have
+    // moved since the last step, so earlier values of `f` can't be reused.
+    fn step(&mut self, f: &mut dyn FnMut(Position) -> f64, rng: &mut dyn RngCore);
+}
+
+fn clamp(p: Position) -> Position {
+    p.map(|x| x.clamp(MIN, MAX))
+}
+
+// `a + s * (b - a)`, clamped to the image.
+fn towards(a: Position, b: Position, s: f64) -> Position {
+    clamp([a[0] + s * (b[0] - a[0]), a[1] + s * (b[1] - a[1])])
+}
+
+fn distance(a: Position, b: Position) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+// A triangle of positions, sorted from the best to the worst.
+struct NelderMead {
+    simplex: [Position; 3],
+}
+
+impl NelderMead {
+    fn new(start: Position, step: f64) -> Self {
+        // Go away from the nearest edge, so the simplex isn't flattened
+        // against it.
+        let away = |x: f64| if x + step <= MAX { x + step } else { x - step };
+        let simplex = [start, clamp([away(start[0]), start[1]]), clamp([start[0], away(start[1])])];
+        NelderMead { simplex }
+    }
+}
+
+impl Optimize for NelderMead {
+    fn position(&self) -> Position {
+        self.simplex[0]
+    }
+
+    fn spread(&self) -> f64 {
+        let best = self.simplex[0];
+        self.simplex[1..].iter().map(|&p| distance(best, p)).fold(0.0, f64::max)
+    }
+
+    fn step(&mut self, f: &mut dyn FnMut(Position) -> f64, _rng: &mut dyn RngCore) {
+        let mut v = self.simplex.map(|p| (p, f(p)));
+        v.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let (worst, f_worst) = v[2];
+        let centroid = towards(v[0].0, v[1].0, 0.5);
+        let reflected = towards(centroid, worst, -1.0);
+        let f_reflected = f(reflected);
+        v[2] = if f_reflected > v[0].1 {
+            let expanded = towards(centroid, worst, -2.0);
+            let f_expanded = f(expanded);
+            if f_expanded > f_reflected {
+                (expanded, f_expanded)
+            } else {
+                (reflected, f_reflected)
+            }
+        } else if f_reflected > v[1].1 {
+            (reflected, f_reflected)
+        } else {
+            let (contracted, bound) = if f_reflected > f_worst {
+                (towards(centroid, reflected, 0.5), f_reflected)
+            } else {
+                (towards(centroid, worst, 0.5), f_worst)
+            };
+            let f_contracted = f(contracted);
+            if f_contracted > bound {
+                (contracted, f_contracted)
+            } else {
+                // Shrink the simplex towards the best position.
+                let best = v[0].0;
+                for vertex in &mut v[1..] {
+                    let p = towards(best, vertex.0, 0.5);
+                    *vertex = (p, f(p));
+                }
+                v[2]
+            }
+        };
+        v.sort_by(|a, b| b.1.total_cmp(&a.1));
+        self.simplex = v.map(|(p, _)| p);
+    }
+}
+
+// A symmetric 2x2 matrix [[a, b], [b, c]].
+#[derive(Clone, Copy)]
+struct Symmetric {
+    a: f64,
+    b: f64,
+    c: f64,
+}
+
+impl Symmetric {
+    const IDENTITY: Symmetric = Symmetric { a: 1.0, b: 0.0, c: 1.0 };
+
+    fn outer(y: Position) -> Symmetric {
+        Symmetric { a: y[0] * y[0], b: y[0] * y[1], c: y[1] * y[1] }
+    }
+
+    fn scaled(self, s: f64) -> Symmetric {
+        Symmetric { a: self.a * s, b: self.b * s, c: self.c * s }
+    }
+
+    fn add(self, m: Symmetric) -> Symmetric {
+        Symmetric { a: self.a + m.a, b: self.b + m.b, c: self.c + m.c }
+    }
+
+    fn mul(self, y: Position) -> Position {
+        [self.a * y[0] + self.b * y[1], self.b * y[0] + self.c * y[1]]
+    }
+
+    fn largest_eigenvalue(self) -> f64 {
+        (self.a + self.c) / 2.0 + (((self.a - self.c) / 2.0).powi(2) + self.b * self.b).sqrt()
+    }
+
+    // The square root of a positive definite matrix, using that
+    // sqrt(M) = (M + sqrt(det M) I) / sqrt(tr M + 2 sqrt(det M)) for 2x2
+    // matrices.
+    fn sqrt(self) -> Symmetric {
+        let s = (self.a * self.c - self.b * self.b).max(0.0).sqrt();
+        let t = (self.a + self.c + 2.0 * s).sqrt();
+        Symmetric { a: (self.a + s) / t, b: self.b / t, c: (self.c + s) / t }
+    }
+
+    fn inverse(self) -> Symmetric {
+        let det = self.a * self.c - self.b * self.b;
+        Symmetric { a: self.c / det, b: -self.b / det, c: self.a / det }
+    }
+}
+
+// The CMA-ES of Hansen, with the default parameters for two dimensions. Every
+// step samples a population of positions around `mean`, and moves `mean`
+// towards the best of them while adapting the covariance `cov` and the step
+// size `sigma` to how the search has been going.
+struct CmaEs {
+    mean: Position,
+    sigma: f64,
+    cov: Symmetric,
+    // The evolution paths of the covariance and of the step size.
+    path_c: Position,
+    path_sigma: Position,
+    generation: i32,
+}
+
+// The size of the population, 4 + floor(3 ln 2).
+const POPULATION: usize = 6;
+// How many of the best positions the mean moves towards.
+const PARENTS: usize = POPULATION / 2;
+
+impl CmaEs {
+    fn new(start: Position, sigma: f64) -> Self {
+        CmaEs {
+            mean: start,
+            sigma,
+            cov: Symmetric::IDENTITY,
+            path_c: [0.0; 2],
+            path_sigma: [0.0; 2],
+            generation: 0,
+        }
+    }
+}
+
+impl Optimize for CmaEs {
+    fn position(&self) -> Position {
+        self.mean
+    }
+
+    fn spread(&self) -> f64 {
+        self.sigma * self.cov.largest_eigenvalue().sqrt()
+    }
+
+    fn step(&mut self, f: &mut dyn FnMut(Position) -> f64, rng: &mut dyn RngCore) {
+        let n = DIMENSIONS as f64;
+        let weights: Vec<f64> = {
+            let w: Vec<f64> =
+                (1..=PARENTS).map(|i| (PARENTS as f64 + 0.5).ln() - (i as f64).ln()).collect();
+            let sum: f64 = w.iter().sum();
+            w.iter().map(|x| x / sum).collect()
+        };
+        let mu_eff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+        let c_c = (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n);
+        let c_sigma = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+        let c_1 = 2.0 / ((n + 1.3).powi(2) + mu_eff);
+        let c_mu =
+            (1.0 - c_1).min(2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff));
+        let damping = 1.0 + 2.0 * (((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + c_sigma;
+        let chi_n = n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n));
+
+        // Sample the population, and keep the steps `y` which actually lead
+        // to each position after clamping it to the image.
+        let root = self.cov.sqrt();
+        let mut population: Vec<(Position, f64)> = (0..POPULATION)
+            .map(|_| {
+                let z: Position = [StandardNormal.sample(rng), StandardNormal.sample(rng)];
+                let y = root.mul(z);
+                let x = clamp([self.mean[0] + self.sigma * y[0], self.mean[1] + self.sigma * y[1]]);
+                let y = [(x[0] - self.mean[0]) / self.sigma, (x[1] - self.mean[1]) / self.sigma];
+                (y, f(x))
+            })
+            .collect();
+        population.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let parents = &population[..PARENTS];
+
+        let mut y_w = [0.0; 2];
+        for ((y, _), w) in parents.iter().zip(&weights) {
+            y_w[0] += w * y[0];
+            y_w[1] += w * y[1];
+        }
+        self.mean = clamp([self.mean[0] + self.sigma * y_w[0], self.mean[1] + self.sigma * y_w[1]]);
+
+        let whitened = root.inverse().mul(y_w);
+        let s = (c_sigma * (2.0 - c_sigma) * mu_eff).sqrt();
+        for (p, w) in self.path_sigma.iter_mut().zip(whitened) {
+            *p = (1.0 - c_sigma) * *p + s * w;
+        }
+        self.generation += 1;
+        let norm = (self.path_sigma[0].powi(2) + self.path_sigma[1].powi(2)).sqrt();
+        let correction = (1.0 - (1.0 - c_sigma).powi(2 * self.generation)).sqrt();
+        // Stop the covariance path from growing while the step size is too
+        // small.
+        let h_sigma = if norm / correction / chi_n < 1.4 + 2.0 / (n + 1.0) { 1.0 } else { 0.0 };
+        let s = (c_c * (2.0 - c_c) * mu_eff).sqrt();
+        for (p, y) in self.path_c.iter_mut().zip(y_w) {
+            *p = (1.0 - c_c) * *p + h_sigma * s * y;
+        }
+
+        let mut rank_mu = Symmetric { a: 0.0, b: 0.0, c: 0.0 };
+        for ((y, _), w) in parents.iter().zip(&weights) {
+            rank_mu = rank_mu.add(Symmetric::outer(*y).scaled(*w));
+        }
+        let lost = (1.0 - h_sigma) * c_c * (2.0 - c_c);
+        let rank_one = Symmetric::outer(self.path_c).add(self.cov.scaled(lost));
+        self.cov =
+            self.cov.scaled(1.0 - c_1 - c_mu).add(rank_one.scaled(c_1)).add(rank_mu.scaled(c_mu));
+        self.sigma *= ((c_sigma / damping) * (norm / chi_n - 1.0)).exp();
+        // Nothing is gained by searching much further than the image.
+        self.sigma = self.sigma.min(MAX - MIN);
+        debug_assert!(vector(&self.mean));
+    }
+}
@@ -0,0 +1,461 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use votery::{
+    formats::orders::{TiedRank, TiedRankRef},
+    generators::spatial::Spatial,
+    seed::{ElectionSeed, Stream},
+    util::most_common,
+};
+
+use crate::{
+    candidates::Candidate,
+    color::{blend_colors, median_color, Color},
+    convergence::{color_margin, winner_margin},
+    sample_pixel, vector, Adaptive, Blending, ImageConfig, DIMENSIONS, MAX, MIN,
+};
+
+// Which results, other than the image itself, to keep after sampling an
+// image. Keeping every ranking takes a lot of memory at high resolutions, so
+// everything is disabled by default.
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Outputs {
+    // Every ranking sampled at every pixel.
+    pub rankings: bool,
+    // How many times every pixel was sampled.
+    pub sample_count: bool,
+    // The half-width of the confidence interval of every pixel.
+    pub margins: bool,
+    // How often every candidate won at every pixel.
+    pub heatmaps: bool,
+    // The average rank of this candidate at every pixel.
+    pub rank_of: Option<usize>,
+}
+
+// The results from sampling an image. Indexed like `image[y][x]`.
+#[derive(Default)]
+pub struct SampleResult {
+    image: Vec<Vec<[u8; 3]>>,
+    sample_count: Option<Vec<Vec<usize>>>,
+    rankings: Option<Vec<Vec<Vec<TiedRank>>>>,
+    margins: Option<Vec<Vec<f64>>>,
+    // How many samples every candidate won at every pixel, with ties split
+    // evenly between the winners.
+    wins: Option<Vec<Vec<Vec<f64>>>>,
+    // The sum of the ranks of `Outputs::rank_of` at every pixel, and how many
+    // rankings were summed.
+    ranks: Option<Vec<Vec<(usize, usize)>>>,
+    // The names and colors of the candidates in the image
+    candidates: Vec<Candidate>,
+}
+
+impl SampleResult {
+    pub fn image(&self) -> &[Vec<[u8; 3]>] {
+        &self.image
+    }
+
+    pub fn image_mut(&mut self) -> &mut [Vec<[u8; 3]>] {
+        &mut self.image
+    }
+
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    // Only kept if `Outputs::sample_count` was set.
+    pub fn sample_count(&self) -> Option<&[Vec<usize>]> {
+        self.sample_count.as_deref()
+    }
+
+    // The rankings sampled at pixel (x, y). Only kept if `Outputs::rankings`
+    // was set.
+    pub fn rankings_mut(&mut self, x: usize, y: usize) -> Option<&mut Vec<TiedRank>> {
+        self.rankings.as_mut().map(|r| &mut r[y][x])
+    }
+
+    // Only kept if `Outputs::margins` was set.
+    pub fn margins(&self) -> Option<&[Vec<f64>]> {
+        self.margins.as_deref()
+    }
+
+    // How often `candidate` won at every pixel, between 0 and 1. Only kept if
+    // `Outputs::heatmaps` was set.
+    pub fn heatmap(&self, candidate: usize) -> Option<Vec<Vec<f64>>> {
+        let wins = self.wins.as_ref()?;
+        let frequency = |w: &Vec<f64>| {
+            let total: f64 = w.iter().sum();
+            if total > 0.0 {
+                w.get(candidate).copied().unwrap_or(0.0) / total
+            } else {
+                0.0
+            }
+        };
+        Some(wins.iter().map(|row| row.iter().map(frequency).collect()).collect())
+    }
+
+    // The average rank of `Outputs::rank_of` at every pixel, where 1 is the
+    // highest rank and tied candidates have the same rank. Only kept if
+    // `Outputs::rank_of` was set.
+    pub fn average_rank(&self) -> Option<Vec<Vec<f64>>> {
+        let ranks = self.ranks.as_ref()?;
+        let average = |&(sum, count): &(usize, usize)| sum as f64 / count.max(1) as f64;
+        Some(ranks.iter().map(|row| row.iter().map(average).collect()).collect())
+    }
+}
+
+// The rank of candidate `c` in `vote`, where 1 is the highest rank. Unranked
+// candidates are ranked below every ranked candidate.
+fn rank_of(vote: TiedRankRef, c: usize) -> usize {
+    vote.group_of(c).unwrap_or_else(|| vote.iter_groups().count()) + 1
+}
+
+// Make `grid` a `resolution` x `resolution` grid and `reset` every cell where
+// `only` is true, while keeping as many of its allocations as possible. New
+// cells always get their default value.
+fn reset_grid<T: Default>(
+    grid: &mut Vec<Vec<T>>,
+    resolution: usize,
+    only: &[Vec<bool>],
+    reset: impl Fn(&mut T),
+) {
+    grid.resize_with(resolution, Vec::new);
+    for (row, only) in grid.iter_mut().zip(only) {
+        row.resize_with(resolution, T::default);
+        row.iter_mut().zip(only).filter(|(_, &o)| o).for_each(|(cell, _)| reset(cell));
+    }
+}
+
+// Like `reset_grid`, but for an optional output which is removed if it isn't
+// `kept`.
+fn reset_output<T: Default>(
+    output: &mut Option<Vec<Vec<T>>>,
+    kept: bool,
+    resolution: usize,
+    only: &[Vec<bool>],
+    reset: impl Fn(&mut T),
+) {
+    if kept {
+        reset_grid(output.get_or_insert_with(Vec::new), resolution, only, reset);
+    } else {
+        *output = None;
+    }
+}
+
+// A new sample of pixel (x, y). Only the colors are always kept, the other
+// fields are empty unless they are needed.
+struct PixelSamples {
+    x: usize,
+    y: usize,
+    colors: Vec<Color>,
+    // The ranking of every vote.
+    votes: Vec<TiedRank>,
+    // How many of the votes every candidate won.
+    wins: Vec<f64>,
+    // The sum of the ranks of `Outputs::rank_of`.
+    ranks: usize,
+    // The Borda points of every candidate, summed over the rankings.
+    borda: Vec<usize>,
+}
+
+// The Borda points of every candidate in `vote`: the number of candidates
+// ranked below it. Unranked candidates get no points.
+fn add_borda_points(vote: TiedRankRef, points: &mut [usize]) {
+    let mut below = points.len() - vote.len();
+    let groups: Vec<&[usize]> = vote.iter_groups().collect();
+    for group in groups.into_iter().rev() {
+        group.iter().for_each(|&c| points[c] += below);
+        below += group.len();
+    }
+}
+
+// The color of a pixel from its `samples`, see `Blending`. `borda` is only
+// used by `Blending::Consensus`.
+fn blend(blending: &Blending, samples: &[Color], borda: &[usize], colors: &[Color]) -> Color {
+    match blending {
+        Blending::Max | Blending::Average => blend_colors(samples.iter()),
+        Blending::Median => median_color(samples),
+        Blending::Consensus => match borda.iter().max() {
+            Some(max) => {
+                blend_colors(borda.iter().zip(colors).filter(|(b, _)| *b == max).map(|(_, c)| c))
+            }
+            None => Color::default(),
+        },
+    }
+}
+
+// Renders images with the same configuration. The buffers used while
+// sampling are kept between images, so rendering every frame of an animation
+// doesn't have to allocate them again.
+pub struct Renderer<'a> {
+    config: &'a ImageConfig,
+    generator: Spatial<DIMENSIONS>,
+    samples: Vec<Vec<Vec<Color>>>,
+    // The Borda points of every candidate at every pixel, for
+    // `Blending::Consensus`.
+    borda: Vec<Vec<Vec<usize>>>,
+    needs_samples: Vec<Vec<bool>>,
+    queue: Vec<(usize, usize)>,
+    new_samples: Vec<PixelSamples>,
+    // The candidates and the image of the previous render, to warm start the
+    // next one.
+    previous: Vec<[f64; 2]>,
+    previous_image: Vec<Vec<[u8; 3]>>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(config: &'a ImageConfig) -> Self {
+        Renderer {
+            config,
+            generator: config.generator(),
+            samples: Vec::new(),
+            borda: Vec::new(),
+            needs_samples: Vec::new(),
+            queue: Vec::with_capacity(config.resolution * config.resolution),
+            new_samples: Vec::new(),
+            previous: Vec::new(),
+            previous_image: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &'a ImageConfig {
+        self.config
+    }
+
+    // Sample an image with candidates at `candidates`, described by
+    // `metadata`, and store it in `result`. The buffers of `result` are
+    // reused. Every pixel of every iteration gets its own random numbers
+    // derived from `seed`, so the image doesn't depend on the thread count.
+    pub fn render_into(
+        &mut self,
+        candidates: &[[f64; 2]],
+        metadata: &[Candidate],
+        seed: ElectionSeed,
+        result: &mut SampleResult,
+    ) {
+        self.render(candidates, metadata, seed, result, false);
+    }
+
+    // Like `render_into`, but start from the samples of the previous render
+    // if `ImageConfig::warm_start` is set. Only pixels near a candidate which
+    // moved, or on a border between colors of the previous image, are
+    // sampled from scratch, so an animation where the candidates move slowly
+    // renders much faster. `result` must be the one given to the previous
+    // render, and the image depends on the previous one, so frames rendered
+    // this way have to be rendered in order.
+    pub fn render_next_into(
+        &mut self,
+        candidates: &[[f64; 2]],
+        metadata: &[Candidate],
+        seed: ElectionSeed,
+        result: &mut SampleResult,
+    ) {
+        self.render(candidates, metadata, seed, result, true);
+    }
+
+    // Mark the pixels which can't keep their samples from the previous
+    // render: those within `radius` of where a candidate which moved was or
+    // is, and those with a neighbour of another color in the previous image.
+    fn mark_changed(&mut self, candidates: &[[f64; 2]], radius: f64) {
+        let resolution = self.config.resolution;
+        let moved: Vec<[f64; 2]> = self
+            .previous
+            .iter()
+            .zip(candidates)
+            .filter(|(a, b)| a != b)
+            .flat_map(|(&a, &b)| [a, b])
+            .collect();
+        let image = &self.previous_image;
+        for (yi, row) in self.needs_samples.iter_mut().enumerate() {
+            for (xi, needs) in row.iter_mut().enumerate() {
+                let x = (xi as f64) / (resolution as f64) * (MAX - MIN) + MIN;
+                let y = (yi as f64) / (resolution as f64) * (MAX - MIN) + MIN;
+                let near = moved.iter().any(|c| (c[0] - x).hypot(c[1] - y) <= radius);
+                let border = (yi.saturating_sub(1)..=(yi + 1).min(resolution - 1)).any(|ny| {
+                    (xi.saturating_sub(1)..=(xi + 1).min(resolution - 1))
+                        .any(|nx| image[ny][nx] != image[yi][xi])
+                });
+                *needs = near || border;
+            }
+        }
+    }
+
+    fn render(
+        &mut self,
+        candidates: &[[f64; 2]],
+        metadata: &[Candidate],
+        seed: ElectionSeed,
+        result: &mut SampleResult,
+        warm: bool,
+    ) {
+        let config = self.config;
+        let resolution = config.resolution;
+        let colors: Vec<Color> = metadata.iter().map(|c| c.color).collect();
+        self.generator.clear();
+        for c in candidates {
+            assert!(vector(c));
+            self.generator.add_candidate(c);
+        }
+
+        let warm_start = config.warm_start.filter(|_| {
+            warm && self.previous.len() == candidates.len()
+                && self.previous_image.len() == resolution
+                && result.image.len() == resolution
+        });
+        match warm_start {
+            Some(radius) => self.mark_changed(candidates, radius),
+            None => {
+                self.needs_samples.resize_with(resolution, Vec::new);
+                for row in &mut self.needs_samples {
+                    row.clear();
+                    row.resize(resolution, true);
+                }
+            }
+        }
+        let only = &self.needs_samples;
+        reset_grid(&mut self.samples, resolution, only, Vec::clear);
+        reset_grid(&mut self.borda, resolution, only, Vec::clear);
+        reset_grid(&mut result.image, resolution, only, |_| {});
+        let keep_count = config.outputs.sample_count || config.adapt_mode == Adaptive::Display;
+        reset_output(&mut result.sample_count, keep_count, resolution, only, |c| *c = 0);
+        reset_output(&mut result.rankings, config.outputs.rankings, resolution, only, Vec::clear);
+        reset_output(&mut result.margins, config.outputs.margins, resolution, only, |m| {
+            *m = f64::INFINITY
+        });
+        reset_output(&mut result.wins, config.outputs.heatmaps, resolution, only, Vec::clear);
+        let keep_ranks = config.outputs.rank_of.is_some();
+        reset_output(&mut result.ranks, keep_ranks, resolution, only, |r| *r = (0, 0));
+        result.candidates.clear();
+        result.candidates.extend_from_slice(metadata);
+
+        let Renderer { generator: g, samples, borda, needs_samples, queue, new_samples, .. } = self;
+        let consensus = matches!(config.blending, Blending::Consensus);
+        let z = config.convergence.z();
+        let mut iterations = 0;
+        loop {
+            iterations += 1;
+            // First we'll add every pixel that needs samples to the queue
+            queue.clear();
+            for (yi, row) in needs_samples.iter_mut().enumerate() {
+                for (xi, needs) in row.iter_mut().enumerate() {
+                    if *needs {
+                        queue.push((xi, yi));
+                        *needs = false;
+                    }
+                }
+            }
+            println!("{}: pixels to sample: {}", iterations, queue.len());
+            // Then we actually get some samples
+            new_samples.par_extend(queue.par_drain(..).map(|(xi, yi)| {
+                let pixel = (yi * resolution + xi) as u64;
+                let mut rng = seed.derive(iterations).derive(pixel).rng(Stream::Sampling);
+                let mut new = PixelSamples {
+                    x: xi,
+                    y: yi,
+                    colors: Vec::with_capacity(config.sample_size),
+                    votes: Vec::new(),
+                    wins: Vec::new(),
+                    ranks: 0,
+                    borda: Vec::new(),
+                };
+                if config.outputs.heatmaps {
+                    new.wins.resize(colors.len(), 0.0);
+                }
+                if consensus {
+                    new.borda.resize(colors.len(), 0);
+                }
+                for _ in 0..config.sample_size {
+                    let (color, vote) = sample_pixel(g, xi, yi, &mut rng, &colors, config);
+                    new.colors.push(color);
+                    if config.outputs.heatmaps {
+                        let winners = vote.as_ref().winners();
+                        for &w in winners {
+                            new.wins[w] += 1.0 / winners.len() as f64;
+                        }
+                    }
+                    if let Some(c) = config.outputs.rank_of {
+                        new.ranks += rank_of(vote.as_ref(), c);
+                    }
+                    if consensus {
+                        add_borda_points(vote.as_ref(), &mut new.borda);
+                    }
+                    if config.outputs.rankings {
+                        new.votes.push(vote);
+                    }
+                }
+                new
+            }));
+            // Then we need to decide which pixels need more samples. We say that a pixel
+            // needs more samples if it hasn't converged, or if any of its neighbours
+            // haven't converged yet
+            let mut done = true;
+            for new in new_samples.drain(..) {
+                let (xi, yi) = (new.x, new.y);
+                if let Some(rankings) = &mut result.rankings {
+                    rankings[yi][xi].extend(new.votes);
+                }
+                if let Some(wins) = &mut result.wins {
+                    let old = &mut wins[yi][xi];
+                    old.resize(new.wins.len(), 0.0);
+                    old.iter_mut().zip(new.wins).for_each(|(o, n)| *o += n);
+                }
+                if let Some(ranks) = &mut result.ranks {
+                    ranks[yi][xi].0 += new.ranks;
+                    ranks[yi][xi].1 += config.sample_size;
+                }
+                if consensus {
+                    let old = &mut borda[yi][xi];
+                    old.resize(new.borda.len(), 0);
+                    old.iter_mut().zip(new.borda).for_each(|(o, n)| *o += n);
+                }
+                if let Some(sample_count) = &mut result.sample_count {
+                    sample_count[yi][xi] += 1;
+                }
+                let old = &mut samples[yi][xi];
+                old.extend(new.colors);
+                let margin = match config.blending {
+                    Blending::Max | Blending::Consensus => {
+                        let mode = most_common(old).unwrap_or_default();
+                        winner_margin(old, &mode, z)
+                    }
+                    Blending::Average | Blending::Median => color_margin(old, z),
+                };
+                if let Some(margins) = &mut result.margins {
+                    margins[yi][xi] = margin;
+                }
+                if old.len() < config.convergence.min_samples || needs_samples[yi][xi] {
+                    needs_samples[yi][xi] = true;
+                    done = false;
+                    continue;
+                }
+                if config.adapt_mode == Adaptive::Disable {
+                    continue;
+                }
+                if margin > config.convergence.tolerance {
+                    done = false;
+                    let max_xi = xi.saturating_add(config.around_size).min(resolution - 1);
+                    let min_xi = xi.saturating_sub(config.around_size);
+                    let max_yi = yi.saturating_add(config.around_size).min(resolution - 1);
+                    let min_yi = yi.saturating_sub(config.around_size);
+                    for row in &mut needs_samples[min_yi..=max_yi] {
+                        row[min_xi..=max_xi].fill(true);
+                    }
+                }
+            }
+            if done {
+                break;
+            }
+        }
+        for ((image_row, sample_row), borda_row) in
+            result.image.iter_mut().zip(&*samples).zip(&*borda)
+        {
+            for ((pixel, pixel_samples), points) in
+                image_row.iter_mut().zip(sample_row).zip(borda_row)
+            {
+                *pixel = blend(&config.blending, pixel_samples, points, &colors).quantize();
+            }
+        }
+        self.previous.clear();
+        self.previous.extend_from_slice(candidates);
+        self.previous_image.clone_from(&result.image);
+    }
+}
@@ -0,0 +1,130 @@
+// Parameter sweeps: render a diagram for every combination of a few
+// parameters, with the same candidates, and put them side by side in a
+// contact sheet so they can be compared at a glance.
+
+use std::{fs::File, io::BufWriter};
+
+use serde::{Deserialize, Serialize};
+use votery::methods::MethodConfig;
+
+use crate::{candidates::Candidate, render::SampleResult, render_image, ImageConfig, Renderer};
+
+// White space between the diagrams of the contact sheet, in pixels.
+const GAP: usize = 4;
+
+// The values to sweep over. An empty list keeps the value of the config.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Sweep {
+    pub variances: Vec<f64>,
+    pub points: Vec<usize>,
+    pub methods: Vec<MethodConfig>,
+}
+
+// The manifest written next to the contact sheet, describing where every
+// diagram is.
+#[derive(Serialize)]
+struct Manifest {
+    sheet: String,
+    columns: usize,
+    rows: usize,
+    resolution: usize,
+    gap: usize,
+    cells: Vec<Cell>,
+}
+
+#[derive(Serialize)]
+struct Cell {
+    file: String,
+    row: usize,
+    column: usize,
+    variance: f64,
+    points: usize,
+    method: MethodConfig,
+}
+
+fn or_config<T: Clone>(values: &[T], value: &T) -> Vec<T> {
+    if values.is_empty() {
+        vec![value.clone()]
+    } else {
+        values.to_vec()
+    }
+}
+
+// Render every combination of `config.sweep` to `sweep_{i}.png` in
+// `config.output_dir`, and the contact sheet `sweep.png` with the manifest
+// `sweep.json`. Every row of the sheet has one method and number of points,
+// and every column one variance.
+pub fn render_sweep(
+    candidates: &[[f64; 2]],
+    metadata: &[Candidate],
+    config: &ImageConfig,
+) -> Result<(), String> {
+    let sweep = &config.sweep;
+    let variances = or_config(&sweep.variances, &config.variance);
+    let points = or_config(&sweep.points, &config.points);
+    let methods = or_config(&sweep.methods, &config.method);
+
+    let n = config.resolution;
+    let columns = variances.len();
+    let rows = methods.len() * points.len();
+    let width = columns * n + (columns - 1) * GAP;
+    let height = rows * n + (rows - 1) * GAP;
+    let mut sheet = vec![[255; 3]; width * height];
+    let mut cells = Vec::with_capacity(rows * columns);
+    let mut result = SampleResult::default();
+    for method in &methods {
+        for &p in &points {
+            for &variance in &variances {
+                let (row, column) = (cells.len() / columns, cells.len() % columns);
+                let mut cell_config = config.clone();
+                cell_config.method = method.clone();
+                cell_config.points = p;
+                cell_config.variance = variance;
+                let file = format!("sweep_{}", cells.len());
+                let name = config.output_dir.join(&file);
+                let mut renderer = Renderer::new(&cell_config);
+                render_image(
+                    name.to_str().unwrap(),
+                    &mut renderer,
+                    candidates,
+                    metadata,
+                    None,
+                    &mut result,
+                    false,
+                );
+                for (y, image_row) in result.image().iter().enumerate() {
+                    let start = (row * (n + GAP) + y) * width + column * (n + GAP);
+                    sheet[start..start + n].copy_from_slice(image_row);
+                }
+                println!("sweep: {} / {}", cells.len() + 1, rows * columns);
+                let file = format!("{}.png", file);
+                cells.push(Cell { file, row, column, variance, points: p, method: method.clone() });
+            }
+        }
+    }
+
+    let path = config.output_dir.join("sweep.png");
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(sheet.as_flattened()).map_err(|e| e.to_string())?;
+
+    let manifest =
+        Manifest { sheet: "sweep.png".to_string(), columns, rows, resolution: n, gap: GAP, cells };
+    let file = File::create(config.output_dir.join("sweep.json")).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &manifest).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_keeps_config() {
+        assert_eq!(or_config(&[], &0.2), [0.2]);
+        assert_eq!(or_config(&[1, 2], &3), [1, 2]);
+    }
+}
@@ -0,0 +1,61 @@
+// Ternary diagrams: instead of moving the voters, every pixel is a mix of
+// three fixed profiles of 3 candidates, colored by who wins it.
+
+use votery::{formats::toc::TiedOrdersComplete, seed::Stream, simulation::ternary_winners};
+
+use crate::{
+    color::{blend_colors, distinct_colors, Color},
+    create_png_writer, ImageConfig,
+};
+
+const CANDIDATES: usize = 3;
+
+// Render the ternary diagram of `config.ternary_corners` to `name`.png. The
+// top corner is the first profile, bottom left the second and bottom right the
+// third. The simplex grid has `config.resolution` voters per point.
+pub fn render_ternary(name: &str, config: &ImageConfig) -> Result<(), String> {
+    let mut corners = Vec::with_capacity(3);
+    for s in &config.ternary_corners {
+        let mut votes = TiedOrdersComplete::new(CANDIDATES);
+        for vote in s.split(';') {
+            if !votes.add_from_str(vote.trim()) {
+                return Err(format!("invalid ternary corner '{}'", s));
+            }
+        }
+        corners.push(votes);
+    }
+    let corners: [TiedOrdersComplete; 3] = corners.try_into().unwrap();
+    let n = config.resolution;
+    let points =
+        ternary_winners(&corners, n, &config.method, &mut config.seed.rng(Stream::TieBreaking))?;
+    // The points are ordered by `i` and then `j`, so we find the index of
+    // `[i, j, _]` by skipping the rows of every smaller `i`.
+    let index = |i: usize, j: usize| i * (n + 1) - i * (i.saturating_sub(1)) / 2 + j;
+
+    let colors = distinct_colors(CANDIDATES);
+    let white = Color::new(1.0, 1.0, 1.0);
+    let height = (n as f64) * 3f64.sqrt() / 2.0;
+    let top = (n as f64 - height) / 2.0;
+    let mut image = Vec::with_capacity(n * n * 3);
+    for yi in 0..n {
+        for xi in 0..n {
+            let (x, y) = (xi as f64 + 0.5, yi as f64 + 0.5 - top);
+            // Barycentric coordinates of the triangle with corners at
+            // (n / 2, 0), (0, height) and (n, height).
+            let a = 1.0 - y / height;
+            let c = (x - a * n as f64 / 2.0) / n as f64;
+            let b = 1.0 - a - c;
+            let color = if a < 0.0 || b < 0.0 || c < 0.0 {
+                white
+            } else {
+                let i = (a * n as f64).round() as usize;
+                let j = ((b * n as f64).round() as usize).min(n - i);
+                let winners = &points[index(i, j)].winners;
+                blend_colors(winners.iter().map(|&w| &colors[w]))
+            };
+            image.extend_from_slice(&color.quantize());
+        }
+    }
+    let mut writer = create_png_writer(&format!("{}.png", name), n);
+    writer.write_image_data(&image).map_err(|e| e.to_string())
+}
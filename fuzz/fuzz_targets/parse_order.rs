@@ -0,0 +1,26 @@
+// Parse arbitrary orders, and check that formatting a parsed order in either
+// dialect gives a string which parses to the same order.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use votery::formats::{
+    orders::{Rank, TiedRank},
+    parse::{format_order, Dialect},
+};
+
+fuzz_target!(|input: (u8, &str)| {
+    let (candidates, s) = input;
+    let candidates = usize::from(candidates);
+    if let Ok(rank) = TiedRank::parse_vote(candidates, s) {
+        assert!(rank.len() <= candidates);
+        assert_eq!(rank.tied.len(), rank.len().saturating_sub(1));
+        for dialect in [Dialect::Braces, Dialect::Relations] {
+            let formatted = format_order(rank.as_ref(), None, dialect);
+            let again = TiedRank::parse_vote(candidates, &formatted).unwrap();
+            assert_eq!(again.as_ref().to_string(), rank.as_ref().to_string());
+        }
+    }
+    if let Ok(rank) = Rank::parse_vote(candidates, s) {
+        assert!(rank.len() <= candidates);
+    }
+});
@@ -0,0 +1,34 @@
+// Read arbitrary PrefLib files, and check that writing a file which was read
+// gives a file which reads to the same votes.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use votery::formats::{
+    preflib::{read_preflib_limited, write_preflib, PrefLib},
+    soc::StrictOrdersComplete,
+    soi::StrictOrdersIncomplete,
+    toc::TiedOrdersComplete,
+    toi::TiedOrdersIncomplete,
+};
+
+// Small enough that a short input can't make us run out of memory.
+const MAX_RANKED: usize = 1 << 16;
+
+fn roundtrip<P: PrefLib>(data: &[u8]) {
+    let Ok((votes, header)) = read_preflib_limited::<_, P>(data, MAX_RANKED) else {
+        return;
+    };
+    let registry = header.registry().ok();
+    let mut buf = Vec::new();
+    write_preflib(&mut buf, &votes, registry.as_ref()).unwrap();
+    let (again, again_header) = read_preflib_limited::<_, P>(&buf[..], MAX_RANKED).unwrap();
+    assert_eq!(again.preflib_candidates(), votes.preflib_candidates());
+    assert_eq!(again_header.voters, header.voters);
+}
+
+fuzz_target!(|data: &[u8]| {
+    roundtrip::<StrictOrdersComplete>(data);
+    roundtrip::<StrictOrdersIncomplete>(data);
+    roundtrip::<TiedOrdersComplete>(data);
+    roundtrip::<TiedOrdersIncomplete>(data);
+});
@@ -0,0 +1,63 @@
+// Apply arbitrary sequences of operations to a `TiedOrdersComplete`, and
+// check after every operation that every vote still ranks every candidate.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use votery::{election::Voter, formats::toc::TiedOrdersComplete};
+
+#[derive(Arbitrary, Debug)]
+enum Op<'a> {
+    Add(&'a str),
+    RemoveCandidate(u8),
+    RemoveVoter(u16),
+    ToToi,
+    ToCardinal,
+}
+
+fn check(votes: &TiedOrdersComplete) {
+    let c = votes.candidates;
+    let mut voters = 0;
+    for vote in votes {
+        assert_eq!(vote.order().len(), c);
+        assert_eq!(vote.tied().len(), c.saturating_sub(1));
+        let mut seen = vec![false; c];
+        for &i in vote.order() {
+            assert!(i < c && !seen[i]);
+            seen[i] = true;
+        }
+        voters += 1;
+    }
+    assert_eq!(voters, votes.voters());
+}
+
+fuzz_target!(|input: (u8, Vec<Op>)| {
+    let (candidates, ops) = input;
+    let mut votes = TiedOrdersComplete::new(usize::from(candidates % 16) + 1);
+    for op in ops {
+        match op {
+            Op::Add(s) => {
+                votes.add_from_str(s);
+            }
+            Op::RemoveCandidate(n) => {
+                let c = votes.candidates;
+                let removed = votes.remove_candidate(usize::from(n));
+                // The last candidate can't be removed.
+                assert_eq!(removed.is_ok(), usize::from(n) < c && c > 1);
+            }
+            Op::RemoveVoter(i) if usize::from(i) < votes.voters() => {
+                votes.remove_voter(Voter(usize::from(i)))
+            }
+            Op::RemoveVoter(_) => {}
+            Op::ToToi => {
+                let toi = votes.clone().to_toi().unwrap();
+                assert_eq!(toi.voters(), votes.voters());
+            }
+            Op::ToCardinal => {
+                let cardinal = votes.to_cardinal().unwrap();
+                assert_eq!(cardinal.iter().count(), votes.voters());
+            }
+        }
+        check(&votes);
+    }
+});
@@ -0,0 +1,71 @@
+// Apply arbitrary sequences of operations to a `TiedOrdersIncomplete`, and
+// check after every operation that every vote is still a valid order.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use votery::formats::{toi::TiedOrdersIncomplete, VoteFormat};
+
+#[derive(Arbitrary, Debug)]
+enum Op<'a> {
+    Add(&'a str),
+    RemoveCandidate(u8),
+    AddClone(u8),
+    AddCandidates(u8),
+    ToCardinal,
+    PairwiseMatrix,
+}
+
+fn check(votes: &TiedOrdersIncomplete) {
+    let c = votes.candidates();
+    let mut voters = 0;
+    for vote in votes {
+        assert!(!vote.order().is_empty() && vote.order().len() <= c);
+        assert_eq!(vote.tied().len(), vote.order().len() - 1);
+        let mut seen = vec![false; c];
+        for &i in vote.order() {
+            assert!(i < c && !seen[i]);
+            seen[i] = true;
+        }
+        voters += 1;
+    }
+    assert_eq!(voters, votes.voters());
+}
+
+fuzz_target!(|input: (u8, Vec<Op>)| {
+    let (candidates, ops) = input;
+    let mut votes = TiedOrdersIncomplete::new(usize::from(candidates % 16));
+    for op in ops {
+        let c = votes.candidates();
+        match op {
+            Op::Add(s) => {
+                votes.add_from_str(s);
+            }
+            Op::RemoveCandidate(n) => {
+                let voters = votes.voters();
+                let removed = votes.remove_candidate(usize::from(n));
+                assert_eq!(removed.is_ok(), usize::from(n) < c);
+                assert!(votes.voters() <= voters);
+            }
+            Op::AddClone(n) if usize::from(n) < c => votes.add_clone(usize::from(n)),
+            Op::AddClone(_) => {}
+            Op::AddCandidates(n) => votes.set_candidates(c + usize::from(n % 4)),
+            Op::ToCardinal => {
+                if let Ok(cardinal) = votes.clone().to_cardinal() {
+                    if c != 0 {
+                        assert_eq!(cardinal.iter().count(), votes.voters());
+                    }
+                }
+            }
+            Op::PairwiseMatrix => {
+                let m = votes.pairwise_matrix().unwrap();
+                for a in 0..c {
+                    for b in 0..c {
+                        assert!(m.wins(a, b) + m.wins(b, a) <= votes.voters());
+                    }
+                }
+            }
+        }
+        check(&votes);
+    }
+});